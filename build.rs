@@ -5,5 +5,5 @@ use clap_complete::{generate_to, shells::Bash};
 fn main() {
   let mut cold_turkey = ColdTurkey::command_for_update();
   let outdir = env!("CARGO_MANIFEST_DIR");
-  generate_to(Bash, &mut cold_turkey, "ctk", outdir); 
+  generate_to(Bash, &mut cold_turkey, "ctk", outdir).expect("cannot write the bash completions");
 }
@@ -0,0 +1,66 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ctk_common::fuzzy::{self, Matcher};
+use rayon::prelude::*;
+
+const PATH_COUNT: usize = 100_000;
+const RESULT_LIMIT: usize = 1000;
+
+const VENDORS: [&str; 8] = [
+  "Google", "Mozilla", "Microsoft", "Valve", "Adobe", "JetBrains", "Discord", "Spotify",
+];
+const FOLDERS: [&str; 6] = ["bin", "lib", "resources", "locales", "plugins", "updater"];
+const NAMES: [&str; 10] = [
+  "chrome", "firefox", "steam", "helper", "setup", "crashpad", "launcher", "update", "game",
+  "service",
+];
+
+/// Paths laid out like a well used Program Files folder, the same every run
+fn synthetic_tree() -> Vec<String> {
+  (0..PATH_COUNT)
+    .map(|i| {
+      let vendor = VENDORS[i % VENDORS.len()];
+      let folder = FOLDERS[(i / 7) % FOLDERS.len()];
+      let name = NAMES[(i / 3) % NAMES.len()];
+      if i % 4 == 0 {
+        format!("C:\\Program Files\\{vendor}\\App{}\\{folder}\\{name}{i}", i % 97)
+      } else {
+        format!("C:\\Program Files\\{vendor}\\App{}\\{folder}\\{name}{i}.exe", i % 97)
+      }
+    })
+    .collect()
+}
+
+fn best_match(c: &mut Criterion) {
+  let target = "C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe";
+  let mut group = c.benchmark_group("best_match");
+  group.bench_function("matching", |b| {
+    b.iter(|| fuzzy::best_match(black_box("chrome"), black_box(target)))
+  });
+  group.bench_function("not matching", |b| {
+    b.iter(|| fuzzy::best_match(black_box("zoom"), black_box(target)))
+  });
+  group.finish();
+}
+
+/// The matching and ranking half of `fuzzy_search_paths_by_keyword`, without walking a disk
+fn fuzzy_search_paths_by_keyword(c: &mut Criterion) {
+  let paths = synthetic_tree();
+  let mut group = c.benchmark_group("fuzzy_search_paths_by_keyword");
+  group.sample_size(20);
+  // A narrow keyword, a broad one matching most paths, and one matching nothing
+  for keyword in ["chrome", "e", "zoom"] {
+    group.bench_with_input(BenchmarkId::from_parameter(keyword), keyword, |b, keyword| {
+      b.iter(|| {
+        let matcher = Matcher::new(keyword);
+        let matches = paths
+          .par_iter()
+          .filter_map(|path| matcher.match_string(path.as_str()));
+        fuzzy::top_matches(matches, RESULT_LIMIT).into_sorted_vec()
+      })
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, best_match, fuzzy_search_paths_by_keyword);
+criterion_main!(benches);
@@ -0,0 +1,906 @@
+use chrono::{NaiveTime, Timelike};
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A block as it is written in a .ctbbl file. Settings left out of a file are taken from
+/// `BlockSettings::new`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BlockSettings {
+  #[serde(rename = "type")]
+  pub sched_type: SchedType,
+  pub lock: LockMethod,
+  #[serde(
+    serialize_with = "bool_str_serialize",
+    deserialize_with = "from_str_deserialize"
+  )]
+  pub lock_unblock: bool,
+  #[serde(
+    serialize_with = "bool_str_serialize",
+    deserialize_with = "from_str_deserialize"
+  )]
+  pub restart_unblock: bool,
+  pub password: String,
+  #[serde(
+    serialize_with = "u16_str_serialize",
+    deserialize_with = "from_str_deserialize"
+  )]
+  pub random_text_length: u16,
+  #[serde(rename = "break")]
+  pub break_type: BreakMethod,
+  pub window: RangeWindow,
+  pub users: String,
+  pub web: Vec<String>,
+  pub exceptions: Vec<String>,
+  pub apps: Vec<AppString>,
+  pub schedule: Vec<ScheduleBlock>,
+  pub custom_users: Vec<String>,
+  /// Settings from newer versions of Cold Turkey, kept when read and written back out
+  /// untouched
+  #[serde(flatten)]
+  pub unknown_fields: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BreakMethod {
+  None,
+  Allowance(u8),
+  Pomodoro(u8, u8),
+}
+
+impl Serialize for BreakMethod {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    match self {
+      Self::None => serializer.serialize_str("none"),
+      Self::Allowance(allow) => {
+        let allow_str = allow.to_string();
+        serializer.serialize_str(&allow_str)
+      }
+      Self::Pomodoro(block_min, break_min) => {
+        let pomodoro_str = format!("{},{}", block_min, break_min);
+        serializer.serialize_str(&pomodoro_str)
+      }
+    }
+  }
+}
+
+impl FromStr for BreakMethod {
+  type Err = String;
+
+  fn from_str(text: &str) -> Result<Self, Self::Err> {
+    let minutes = |minutes: &str| {
+      minutes
+        .trim()
+        .parse::<u8>()
+        .map_err(|_| format!("{minutes} is not a number of minutes"))
+    };
+    if text == "none" {
+      return Ok(Self::None);
+    }
+    // Newer releases write pomodoro breaks with "pomodoro" in front of the minutes
+    let pomodoro = text.strip_prefix("pomodoro");
+    let minutes_text = pomodoro.map_or(text, |rest| rest.trim_start_matches([',', ':', '@']));
+    match minutes_text.split_once(',') {
+      Some((block_min, break_min)) => Ok(Self::Pomodoro(minutes(block_min)?, minutes(break_min)?)),
+      None if pomodoro.is_none() => Ok(Self::Allowance(minutes(text)?)),
+      None => Err(format!(
+        "{text} is missing the minutes of its pomodoro break"
+      )),
+    }
+  }
+}
+
+impl<'de> Deserialize<'de> for BreakMethod {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    from_str_deserialize(deserializer)
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleBlock {
+  #[serde(
+    serialize_with = "usize_str_serialize",
+    deserialize_with = "from_str_deserialize"
+  )]
+  pub id: usize,
+  pub start_time: ScheduleTimeTuple,
+  pub end_time: ScheduleTimeTuple,
+  #[serde(rename = "break")]
+  pub break_type: BreakMethod,
+  /// Kept by ctk in its sidecar file, since Cold Turkey has nowhere to store it
+  #[serde(skip)]
+  pub label: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduleTimeTuple(usize, u32, u32);
+
+impl ScheduleTimeTuple {
+  // It's a tuple, why bother with new? It's for better communication.
+  pub const fn new(day_of_week: usize, hour: u32, minute: u32) -> Self {
+    Self(day_of_week, hour, minute)
+  }
+
+  /// Days from the Sunday the schedule starts on, which can be 7 for midnight at the end of
+  /// Saturday
+  pub const fn day(&self) -> usize {
+    self.0
+  }
+
+  pub const fn hour(&self) -> u32 {
+    self.1
+  }
+
+  pub const fn minute(&self) -> u32 {
+    self.2
+  }
+}
+
+impl fmt::Display for ScheduleTimeTuple {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{},{},{}", self.0, self.1, self.2)
+  }
+}
+
+impl Serialize for ScheduleTimeTuple {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl FromStr for ScheduleTimeTuple {
+  type Err = String;
+
+  fn from_str(text: &str) -> Result<Self, Self::Err> {
+    let invalid = || format!("{text} is not a time like \"day,hour,minute\"");
+    let parts: Vec<&str> = text.split(',').map(str::trim).collect();
+    let [day, hour, minute] = parts.as_slice() else {
+      return Err(invalid());
+    };
+    Ok(Self(
+      day.parse().map_err(|_| invalid())?,
+      hour.parse().map_err(|_| invalid())?,
+      minute.parse().map_err(|_| invalid())?,
+    ))
+  }
+}
+
+impl<'de> Deserialize<'de> for ScheduleTimeTuple {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    from_str_deserialize(deserializer)
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LockMethod {
+  None,
+  RandomText,
+  Window,
+  Restart,
+  Password,
+  Schedule,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedType {
+  Continuous,
+  Scheduled,
+}
+
+#[derive(Debug, Clone)]
+pub enum AppString {
+  File(String),
+  Folder(String),
+  Win10(String),
+  Title(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct RangeWindow {
+  pub lock_range: bool,
+  pub start_time: NaiveTime,
+  pub end_time: NaiveTime,
+}
+
+impl Serialize for RangeWindow {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let range_str = format!(
+      "{}lock@{},{}@{},{}",
+      if self.lock_range { "" } else { "un" },
+      self.start_time.hour(),
+      self.start_time.minute(),
+      self.end_time.hour(),
+      self.end_time.minute()
+    );
+
+    serializer.serialize_str(&range_str)
+  }
+}
+
+impl FromStr for RangeWindow {
+  type Err = String;
+
+  fn from_str(text: &str) -> Result<Self, Self::Err> {
+    let invalid = || format!("{text} is not a window like \"lock@h,m@h,m\"");
+    let (lock_range, times) = match (text.strip_prefix("lock@"), text.strip_prefix("unlock@")) {
+      (Some(times), _) => (true, times),
+      (_, Some(times)) => (false, times),
+      _ => return Err(invalid()),
+    };
+    let time = |time: &str| {
+      let (hour, minute) = time.split_once(',')?;
+      NaiveTime::from_hms_opt(hour.trim().parse().ok()?, minute.trim().parse().ok()?, 0)
+    };
+    let (start_time, end_time) = times.split_once('@').ok_or_else(invalid)?;
+    Ok(Self {
+      lock_range,
+      start_time: time(start_time).ok_or_else(invalid)?,
+      end_time: time(end_time).ok_or_else(invalid)?,
+    })
+  }
+}
+
+impl<'de> Deserialize<'de> for RangeWindow {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    from_str_deserialize(deserializer)
+  }
+}
+
+impl Serialize for AppString {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let app_string: String = match self {
+      AppString::File(path) => {
+        format!("file:{}", path.replace('\\', "/"))
+      }
+      AppString::Folder(path) => {
+        format!("folder:{}", path.replace('\\', "/"))
+      }
+      AppString::Win10(string) => {
+        format!("win10:{}", string)
+      }
+      AppString::Title(string) => {
+        format!("title:{}", string)
+      }
+    };
+
+    serializer.serialize_str(&app_string)
+  }
+}
+
+impl FromStr for AppString {
+  type Err = String;
+
+  fn from_str(text: &str) -> Result<Self, Self::Err> {
+    let app = match text.split_once(':') {
+      Some(("file", path)) => Self::File(path.to_string()),
+      Some(("folder", path)) => Self::Folder(path.to_string()),
+      Some(("win10", string)) => Self::Win10(string.to_string()),
+      Some(("title", string)) => Self::Title(string.to_string()),
+      _ => {
+        return Err(format!(
+          "{text} does not start with file:, folder:, win10: or title:"
+        ))
+      }
+    };
+    Ok(app)
+  }
+}
+
+impl<'de> Deserialize<'de> for AppString {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    from_str_deserialize(deserializer)
+  }
+}
+
+impl BlockSettings {
+  pub fn new() -> Self {
+    Self {
+      sched_type: SchedType::Continuous,
+      lock: LockMethod::None,
+      lock_unblock: true,
+      restart_unblock: true,
+      password: String::new(),
+      random_text_length: 30,
+      break_type: BreakMethod::None,
+      window: RangeWindow {
+        lock_range: true,
+        start_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        end_time: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+      },
+      users: String::new(),
+      web: Vec::new(),
+      exceptions: vec!["file://*".to_string()],
+      apps: Vec::new(),
+      schedule: Vec::new(),
+      custom_users: Vec::new(),
+      unknown_fields: Map::new(),
+    }
+  }
+}
+
+impl Default for BlockSettings {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Reads the blocks of a .ctbbl file, by name, so they can be changed and written back
+pub fn from_ctbbl(contents: &str) -> serde_json::Result<HashMap<String, BlockSettings>> {
+  serde_json::from_str(contents)
+}
+
+/// A part of the .ctbbl format that older Cold Turkey releases do not understand
+struct FormatChange {
+  /// The first release, as (major, minor), that understands it
+  since: (u32, u32),
+  key: &'static str,
+  /// Only the values this picks out are affected, if set
+  affects: Option<fn(&str) -> bool>,
+  /// Whether schedule entries have the key too
+  in_schedule: bool,
+  /// What older releases are given instead. None leaves the key out
+  fallback: Option<&'static str>,
+  description: &'static str,
+}
+
+impl FormatChange {
+  /// Gives the settings the fallback, or leaves the key out, if they are affected. Whether
+  /// that changed anything worth a warning
+  fn adapt(&self, settings: &mut Map<String, Value>) -> bool {
+    let affected = match (settings.get(self.key), self.affects) {
+      (None, _) => false,
+      (Some(_), None) => true,
+      (Some(value), Some(affects)) => value.as_str().is_some_and(affects),
+    };
+    if !affected {
+      return false;
+    }
+    match self.fallback {
+      Some(fallback) => {
+        settings.insert(self.key.to_string(), Value::String(fallback.to_string()));
+        true
+      }
+      // Leaving out an empty setting loses nothing, so it is not worth a warning
+      None => match settings.remove(self.key) {
+        Some(Value::String(value)) => !value.is_empty(),
+        Some(Value::Array(values)) => !values.is_empty(),
+        _ => true,
+      },
+    }
+  }
+}
+
+fn is_restart_lock(lock: &str) -> bool {
+  lock.starts_with("restart")
+}
+
+/// ctk writes pomodoro breaks as "25,5", and newer releases with "pomodoro" in front
+fn is_pomodoro_break(break_type: &str) -> bool {
+  break_type.starts_with("pomodoro") || break_type.contains(',')
+}
+
+// Newer releases read everything older ones write, so only older targets need changes
+const FORMAT_CHANGES: [FormatChange; 4] = [
+  FormatChange {
+    since: (4, 1),
+    key: "users",
+    affects: None,
+    in_schedule: false,
+    fallback: None,
+    description: "blocking for some users only",
+  },
+  FormatChange {
+    since: (4, 1),
+    key: "customUsers",
+    affects: None,
+    in_schedule: false,
+    fallback: None,
+    description: "blocking for some users only",
+  },
+  FormatChange {
+    since: (4, 2),
+    key: "lock",
+    affects: Some(is_restart_lock),
+    in_schedule: false,
+    fallback: Some("none"),
+    description: "restart locks",
+  },
+  FormatChange {
+    since: (4, 2),
+    key: "break",
+    affects: Some(is_pomodoro_break),
+    in_schedule: true,
+    fallback: Some("none"),
+    description: "pomodoro breaks",
+  },
+];
+
+/// Rewrites the blocks of a .ctbbl file for the given Cold Turkey release, returning a
+/// warning for each setting the release does not understand
+pub fn adapt_to_version(blocks: &mut Value, version: (u32, u32)) -> Vec<String> {
+  let mut warnings = Vec::new();
+  let Some(blocks) = blocks.as_object_mut() else {
+    return warnings;
+  };
+  let (major, minor) = version;
+
+  for (name, block) in blocks.iter_mut() {
+    let Some(block) = block.as_object_mut() else {
+      continue;
+    };
+    for change in FORMAT_CHANGES
+      .iter()
+      .filter(|change| version < change.since)
+    {
+      if change.adapt(block) {
+        warnings.push(match change.fallback {
+          Some(fallback) => format!(
+            "Cold Turkey {major}.{minor} does not support {}, so block {name} has {} set to {fallback}",
+            change.description, change.key
+          ),
+          None => format!(
+            "Cold Turkey {major}.{minor} does not support {}, so {} is left out of block {name}",
+            change.description, change.key
+          ),
+        });
+      }
+      if !change.in_schedule {
+        continue;
+      }
+      let adapted = block
+        .get_mut("schedule")
+        .and_then(Value::as_array_mut)
+        .map_or(0, |entries| {
+          entries
+            .iter_mut()
+            .filter_map(Value::as_object_mut)
+            .map(|entry| change.adapt(entry))
+            .filter(|adapted| *adapted)
+            .count()
+        });
+      if adapted > 0 {
+        warnings.push(match change.fallback {
+          Some(fallback) => format!(
+            "Cold Turkey {major}.{minor} does not support {}, so {adapted} schedule entries of block {name} have {} set to {fallback}",
+            change.description, change.key
+          ),
+          None => format!(
+            "Cold Turkey {major}.{minor} does not support {}, so {} is left out of {adapted} schedule entries of block {name}",
+            change.description, change.key
+          ),
+        });
+      }
+    }
+  }
+  warnings
+}
+
+/// What is wrong with the known settings of a block in a .ctbbl file, going by what
+/// `BlockSettings` writes. Settings ctk does not know about are left alone
+pub fn schema_problems(block: &Map<String, Value>) -> Vec<String> {
+  let mut problems = Vec::new();
+  for (key, value) in block {
+    let expected = match key.as_str() {
+      "type" => one_of(value, &["continuous", "scheduled"]),
+      "lock" => one_of(
+        value,
+        &[
+          "none",
+          "randomText",
+          "window",
+          "restart",
+          "password",
+          "schedule",
+        ],
+      ),
+      "lockUnblock" | "restartUnblock" => one_of(value, &["true", "false"]),
+      "password" | "users" => text(value, |_| true, "text"),
+      "randomTextLength" => text(
+        value,
+        |text| text.parse::<u16>().is_ok(),
+        "a whole number as text, like \"30\"",
+      ),
+      "break" => break_problem(value),
+      "window" => text(value, is_window, "\"lock@h,m@h,m\" or \"unlock@h,m@h,m\""),
+      "web" | "exceptions" | "customUsers" => list(value, |_| true, "text"),
+      "apps" => list(
+        value,
+        |app| {
+          ["file:", "folder:", "win10:", "title:"]
+            .iter()
+            .any(|prefix| app.starts_with(prefix))
+        },
+        "text starting with file:, folder:, win10: or title:",
+      ),
+      "schedule" => {
+        schedule_problems(value, &mut problems);
+        None
+      }
+      _ => None,
+    };
+    if let Some(expected) = expected {
+      problems.push(format!(
+        "has {key} set to {value}, but it must be {expected}"
+      ));
+    }
+  }
+  problems
+}
+
+fn schedule_problems(value: &Value, problems: &mut Vec<String>) {
+  let Some(entries) = value.as_array() else {
+    problems.push(format!(
+      "has schedule set to {value}, but it must be a list of times"
+    ));
+    return;
+  };
+  for (i, entry) in entries.iter().enumerate() {
+    let Some(entry) = entry.as_object() else {
+      problems.push(format!(
+        "has {entry} as schedule entry {}, but it must be an object",
+        i + 1
+      ));
+      continue;
+    };
+    for (key, value) in entry {
+      let expected = match key.as_str() {
+        "id" => text(
+          value,
+          |text| text.parse::<usize>().is_ok(),
+          "a whole number as text",
+        ),
+        "startTime" | "endTime" => text(value, is_schedule_time, "\"day,hour,minute\""),
+        "break" => break_problem(value),
+        _ => None,
+      };
+      if let Some(expected) = expected {
+        problems.push(format!(
+          "has {key} set to {value} in schedule entry {}, but it must be {expected}",
+          i + 1
+        ));
+      }
+    }
+  }
+}
+
+fn one_of(value: &Value, allowed: &[&str]) -> Option<String> {
+  if value.as_str().is_some_and(|value| allowed.contains(&value)) {
+    None
+  } else {
+    let allowed: Vec<String> = allowed
+      .iter()
+      .map(|allowed| format!("\"{allowed}\""))
+      .collect();
+    Some(format!("one of {}", allowed.join(", ")))
+  }
+}
+
+fn text(value: &Value, is_valid: fn(&str) -> bool, expected: &str) -> Option<String> {
+  match value.as_str() {
+    Some(text) if is_valid(text) => None,
+    _ => Some(expected.to_string()),
+  }
+}
+
+fn list(value: &Value, is_valid: fn(&str) -> bool, expected: &str) -> Option<String> {
+  let is_valid_list = value.as_array().is_some_and(|entries| {
+    entries
+      .iter()
+      .all(|entry| entry.as_str().is_some_and(is_valid))
+  });
+  if is_valid_list {
+    None
+  } else {
+    Some(format!("a list of {expected}"))
+  }
+}
+
+fn break_problem(value: &Value) -> Option<String> {
+  // Newer releases write pomodoro breaks in their own way, so those are taken as they are
+  let is_break = |text: &str| {
+    text == "none"
+      || text.starts_with("pomodoro")
+      || text.split(',').count() <= 2 && text.split(',').all(|part| part.parse::<u8>().is_ok())
+  };
+  text(
+    value,
+    is_break,
+    "\"none\", allowance minutes like \"10\", or pomodoro minutes like \"25,5\"",
+  )
+}
+
+fn is_window(text: &str) -> bool {
+  let Some(times) = text
+    .strip_prefix("lock@")
+    .or_else(|| text.strip_prefix("unlock@"))
+  else {
+    return false;
+  };
+  let times: Vec<&str> = times.split('@').collect();
+  times.len() == 2
+    && times.iter().all(|time| {
+      let parts: Vec<&str> = time.split(',').collect();
+      parts.len() == 2 && is_hour(parts[0]) && is_minute(parts[1])
+    })
+}
+
+// Day 7 is midnight at the end of the last day, for times that run to the end of it
+fn is_schedule_time(text: &str) -> bool {
+  let parts: Vec<&str> = text.split(',').collect();
+  parts.len() == 3
+    && parts[0].parse::<usize>().is_ok_and(|day| day <= 7)
+    && is_hour(parts[1])
+    && is_minute(parts[2])
+}
+
+fn is_hour(text: &str) -> bool {
+  text.parse::<u32>().is_ok_and(|hour| hour < 24)
+}
+
+fn is_minute(text: &str) -> bool {
+  text.parse::<u32>().is_ok_and(|minute| minute < 60)
+}
+
+/// Reads a setting Cold Turkey writes as text, like "true", "30" or "25,5". JSON booleans
+/// and numbers are taken too, for files written by hand
+fn from_str_deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+  D: Deserializer<'de>,
+  T: FromStr,
+  T::Err: fmt::Display,
+{
+  let text = match Value::deserialize(deserializer)? {
+    Value::String(text) => text,
+    value @ (Value::Bool(_) | Value::Number(_)) => value.to_string(),
+    value => return Err(de::Error::custom(format!("expected text, found {value}"))),
+  };
+  text.parse().map_err(de::Error::custom)
+}
+
+// Note for the following three functions: serializing requires that the types are passed by reference
+
+fn bool_str_serialize<S: Serializer>(my_bool: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+  let bool_str = my_bool.to_string();
+  serializer.serialize_str(&bool_str)
+}
+
+fn u16_str_serialize<S: Serializer>(num: &u16, serializer: S) -> Result<S::Ok, S::Error> {
+  let u16_str = num.to_string();
+  serializer.serialize_str(&u16_str)
+}
+
+fn usize_str_serialize<S: Serializer>(num: &usize, serializer: S) -> Result<S::Ok, S::Error> {
+  let usize_str = num.to_string();
+  serializer.serialize_str(&usize_str)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+  }
+
+  fn round_trip(blocks: &HashMap<String, BlockSettings>) -> HashMap<String, BlockSettings> {
+    from_ctbbl(&serde_json::to_string(blocks).unwrap()).unwrap()
+  }
+
+  #[test]
+  fn every_setting_survives_a_round_trip() {
+    let mut block = BlockSettings::new();
+    block.sched_type = SchedType::Scheduled;
+    block.lock = LockMethod::RandomText;
+    block.lock_unblock = false;
+    block.restart_unblock = false;
+    block.password = "hunter2".to_string();
+    block.random_text_length = 45;
+    block.break_type = BreakMethod::Pomodoro(25, 5);
+    block.window = RangeWindow {
+      lock_range: false,
+      start_time: time(8, 30),
+      end_time: time(18, 5),
+    };
+    block.users = "some".to_string();
+    block.web = vec!["example.com".to_string(), "*.example.org".to_string()];
+    block.exceptions = vec!["file://*".to_string(), "example.com/allowed".to_string()];
+    block.apps = vec![
+      AppString::File("C:/Games/game.exe".to_string()),
+      AppString::Folder("C:/Games".to_string()),
+      AppString::Win10("Microsoft.MinecraftUWP".to_string()),
+      AppString::Title("Netflix".to_string()),
+    ];
+    block.schedule = vec![
+      ScheduleBlock {
+        id: 0,
+        start_time: ScheduleTimeTuple::new(1, 9, 0),
+        end_time: ScheduleTimeTuple::new(1, 17, 30),
+        break_type: BreakMethod::Allowance(10),
+        label: None,
+      },
+      ScheduleBlock {
+        id: 1,
+        start_time: ScheduleTimeTuple::new(6, 22, 0),
+        end_time: ScheduleTimeTuple::new(7, 0, 0),
+        break_type: BreakMethod::None,
+        label: None,
+      },
+    ];
+    block.custom_users = vec!["student".to_string()];
+    let blocks = HashMap::from([("Work".to_string(), block)]);
+
+    let written = serde_json::to_value(&blocks).unwrap();
+    let read = round_trip(&blocks);
+    assert_eq!(serde_json::to_value(&read).unwrap(), written);
+
+    let block = &read["Work"];
+    assert!(matches!(block.sched_type, SchedType::Scheduled));
+    assert!(matches!(block.lock, LockMethod::RandomText));
+    assert!(!block.lock_unblock && !block.restart_unblock);
+    assert_eq!(block.password, "hunter2");
+    assert_eq!(block.random_text_length, 45);
+    assert_eq!(block.break_type, BreakMethod::Pomodoro(25, 5));
+    assert!(!block.window.lock_range);
+    assert_eq!(
+      (block.window.start_time, block.window.end_time),
+      (time(8, 30), time(18, 5))
+    );
+    assert!(matches!(&block.apps[..], [
+      AppString::File(file),
+      AppString::Folder(folder),
+      AppString::Win10(win10),
+      AppString::Title(title),
+    ] if file == "C:/Games/game.exe"
+      && folder == "C:/Games"
+      && win10 == "Microsoft.MinecraftUWP"
+      && title == "Netflix"));
+    let schedule: Vec<String> = block
+      .schedule
+      .iter()
+      .map(|entry| format!("{} {}-{}", entry.id, entry.start_time, entry.end_time))
+      .collect();
+    assert_eq!(schedule, ["0 1,9,0-1,17,30", "1 6,22,0-7,0,0"]);
+    assert_eq!(block.schedule[0].break_type, BreakMethod::Allowance(10));
+    assert_eq!(block.custom_users, ["student"]);
+  }
+
+  #[test]
+  fn settings_are_written_as_cold_turkey_writes_them() {
+    let mut block = BlockSettings::new();
+    block.break_type = BreakMethod::Pomodoro(25, 5);
+    block.apps = vec![AppString::File(r"C:\Games\game.exe".to_string())];
+    let written = serde_json::to_value(&block).unwrap();
+    assert_eq!(written["type"], "continuous");
+    assert_eq!(written["lock"], "none");
+    assert_eq!(written["lockUnblock"], "true");
+    assert_eq!(written["randomTextLength"], "30");
+    assert_eq!(written["break"], "25,5");
+    assert_eq!(written["window"], "lock@9,0@17,0");
+    assert_eq!(written["apps"], json!(["file:C:/Games/game.exe"]));
+  }
+
+  #[test]
+  fn unknown_settings_are_kept() {
+    let blocks = from_ctbbl(
+      r#"{"Work": {
+        "web": ["example.com"],
+        "allowanceWarning": "5",
+        "futureOptions": {"strict": true, "levels": [1, 2]}
+      }}"#,
+    )
+    .unwrap();
+    let block = &blocks["Work"];
+    assert_eq!(block.web, ["example.com"]);
+    assert_eq!(block.unknown_fields.len(), 2);
+    assert_eq!(block.unknown_fields["allowanceWarning"], "5");
+
+    let written = serde_json::to_value(round_trip(&blocks)).unwrap();
+    assert_eq!(written["Work"]["allowanceWarning"], "5");
+    assert_eq!(
+      written["Work"]["futureOptions"],
+      json!({"strict": true, "levels": [1, 2]})
+    );
+  }
+
+  #[test]
+  fn settings_left_out_are_the_defaults() {
+    let blocks = from_ctbbl(r#"{"Work": {"web": ["example.com"]}}"#).unwrap();
+    let block = &blocks["Work"];
+    assert!(matches!(block.sched_type, SchedType::Continuous));
+    assert!(matches!(block.lock, LockMethod::None));
+    assert_eq!(block.random_text_length, 30);
+    assert_eq!(block.break_type, BreakMethod::None);
+    assert_eq!(block.exceptions, ["file://*"]);
+    assert!(block.unknown_fields.is_empty());
+  }
+
+  #[test]
+  fn numbers_and_booleans_are_read_as_text() {
+    let blocks =
+      from_ctbbl(r#"{"Work": {"randomTextLength": 40, "lockUnblock": false, "break": 10}}"#)
+        .unwrap();
+    let block = &blocks["Work"];
+    assert_eq!(block.random_text_length, 40);
+    assert!(!block.lock_unblock);
+    assert_eq!(block.break_type, BreakMethod::Allowance(10));
+  }
+
+  #[test]
+  fn breaks_in_every_form() {
+    assert_eq!("none".parse(), Ok(BreakMethod::None));
+    assert_eq!("10".parse(), Ok(BreakMethod::Allowance(10)));
+    assert_eq!("25,5".parse(), Ok(BreakMethod::Pomodoro(25, 5)));
+    assert_eq!("pomodoro,25,5".parse(), Ok(BreakMethod::Pomodoro(25, 5)));
+    assert!("pomodoro".parse::<BreakMethod>().is_err());
+    assert!("lots".parse::<BreakMethod>().is_err());
+  }
+
+  #[test]
+  fn bad_settings_are_refused() {
+    for block in [
+      r#"{"Work": {"break": "lots"}}"#,
+      r#"{"Work": {"window": "lock@25,0@17,0"}}"#,
+      r#"{"Work": {"apps": ["game.exe"]}}"#,
+      r#"{"Work": {"schedule": [{"id": "0", "startTime": "1,9", "endTime": "1,17,0", "break": "none"}]}}"#,
+      r#"{"Work": {"lock": "forever"}}"#,
+    ] {
+      assert!(from_ctbbl(block).is_err(), "{block} was read");
+    }
+  }
+
+  fn blocks_with_break(break_type: BreakMethod) -> Value {
+    let mut block = BlockSettings::new();
+    block.sched_type = SchedType::Scheduled;
+    block.break_type = break_type.clone();
+    block.schedule = vec![ScheduleBlock {
+      id: 0,
+      start_time: ScheduleTimeTuple::new(1, 9, 0),
+      end_time: ScheduleTimeTuple::new(1, 17, 0),
+      break_type,
+      label: None,
+    }];
+    serde_json::to_value(HashMap::from([("Work".to_string(), block)])).unwrap()
+  }
+
+  #[test]
+  fn pomodoro_breaks_are_taken_out_before_4_2() {
+    let mut blocks = blocks_with_break(BreakMethod::Pomodoro(25, 5));
+    assert_eq!(blocks["Work"]["break"], "25,5");
+    let warnings = adapt_to_version(&mut blocks, (4, 1));
+    assert_eq!(blocks["Work"]["break"], "none");
+    assert_eq!(blocks["Work"]["schedule"][0]["break"], "none");
+    assert_eq!(warnings.len(), 2);
+  }
+
+  #[test]
+  fn pomodoro_breaks_are_kept_from_4_2() {
+    let mut blocks = blocks_with_break(BreakMethod::Pomodoro(25, 5));
+    assert!(adapt_to_version(&mut blocks, (4, 2)).is_empty());
+    assert_eq!(blocks["Work"]["break"], "25,5");
+    assert_eq!(blocks["Work"]["schedule"][0]["break"], "25,5");
+  }
+
+  #[test]
+  fn pomodoro_breaks_written_by_newer_releases_are_taken_out_too() {
+    let mut blocks = json!({"Work": {"break": "pomodoro,25,5"}});
+    adapt_to_version(&mut blocks, (4, 1));
+    assert_eq!(blocks["Work"]["break"], "none");
+  }
+
+  #[test]
+  fn older_releases_keep_allowances_without_warnings() {
+    // The empty users settings of a new block are left out without a warning
+    let mut blocks = blocks_with_break(BreakMethod::Allowance(10));
+    assert!(adapt_to_version(&mut blocks, (4, 0)).is_empty());
+    assert_eq!(blocks["Work"]["break"], "10");
+    assert_eq!(blocks["Work"]["schedule"][0]["break"], "10");
+  }
+}
@@ -1,10 +1,146 @@
 use chrono::{NaiveDate, NaiveTime};
-use clap::{ColorChoice, Parser, Subcommand};
-use crate::{convert::*, ctsettings::get_ct_settings};
+use clap::builder::{
+  NonEmptyStringValueParser, PossibleValue, PossibleValuesParser, TypedValueParser,
+};
+use clap::error::ErrorKind;
+use clap::{
+  Arg, ArgAction, ArgMatches, Args, ColorChoice, FromArgMatches, Parser, Subcommand, ValueEnum,
+};
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use crate::{convert::*, ctsettings::{get_ct_settings, GlobalSetting}};
 
-fn get_all_ct_blocks() -> Vec<String> {
-  let ct_settings = get_ct_settings();
-  ct_settings.map_or(Vec::new(), |settings| settings.block_list_info.blocks.into_keys().collect())
+/// Cold Turkey's block names, sorted. Settings are read the first time a block argument
+/// needs them and kept for the rest of the run, so commands without one never ask the
+/// messaging host. `None` when Cold Turkey cannot be read
+fn ct_blocks() -> Option<&'static [String]> {
+  static BLOCKS: OnceLock<Option<Vec<String>>> = OnceLock::new();
+  BLOCKS
+    .get_or_init(|| {
+      get_ct_settings().map(|settings| {
+        let mut blocks: Vec<String> = settings.block_list_info.blocks.into_keys().collect();
+        blocks.sort();
+        blocks
+      })
+    })
+    .as_deref()
+}
+
+/// Takes the name of one of Cold Turkey's blocks. When Cold Turkey cannot be read, any
+/// name is let through, so the command can say what is wrong with Cold Turkey itself
+#[derive(Clone, Copy)]
+struct BlockNameParser;
+
+fn block_name() -> BlockNameParser {
+  BlockNameParser
+}
+
+impl TypedValueParser for BlockNameParser {
+  type Value = String;
+
+  fn parse_ref(
+    &self,
+    cmd: &clap::Command,
+    arg: Option<&clap::Arg>,
+    value: &OsStr,
+  ) -> Result<String, clap::Error> {
+    match ct_blocks() {
+      Some(blocks) => {
+        PossibleValuesParser::new(blocks.iter().map(String::as_str)).parse_ref(cmd, arg, value)
+      }
+      None => NonEmptyStringValueParser::new().parse_ref(cmd, arg, value),
+    }
+  }
+
+  fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+    let names = ct_blocks()?
+      .iter()
+      .map(|block| PossibleValue::new(block.as_str()));
+    Some(Box::new(names))
+  }
+}
+
+/// Takes block names, or groups of blocks like "@social" that ctk resolves to every block
+/// with that tag or in that profile. Unknown names are let through, so commands on several
+/// blocks can report all the missing ones together; the known blocks are only offered in help
+#[derive(Clone, Copy)]
+struct BlockOrGroupParser;
+
+fn block_or_group() -> BlockOrGroupParser {
+  BlockOrGroupParser
+}
+
+impl TypedValueParser for BlockOrGroupParser {
+  type Value = String;
+
+  fn parse_ref(
+    &self,
+    cmd: &clap::Command,
+    arg: Option<&clap::Arg>,
+    value: &OsStr,
+  ) -> Result<String, clap::Error> {
+    NonEmptyStringValueParser::new().parse_ref(cmd, arg, value)
+  }
+
+  fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+    BlockNameParser.possible_values()
+  }
+}
+
+/// When a lock or pause ends: a time, with an optional date before or after it, so both
+/// `until 17:00 friday` and `until tomorrow 9am` read naturally
+#[derive(Clone, Copy, Debug)]
+pub struct EndTime {
+  pub time: NaiveTime,
+  /// Today if not given
+  pub date: Option<NaiveDate>,
+}
+
+impl FromArgMatches for EndTime {
+  fn from_arg_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+    let parts: Vec<When> = matches
+      .get_many::<When>("when")
+      .map(|parts| parts.copied().collect())
+      .unwrap_or_default();
+    match parts[..] {
+      [When::Time(time)] => Ok(Self { time, date: None }),
+      [When::Time(time), When::Date(date)] | [When::Date(date), When::Time(time)] => Ok(Self {
+        time,
+        date: Some(date),
+      }),
+      _ => Err(clap::Error::raw(
+        ErrorKind::ValueValidation,
+        "expected a time, with an optional date before or after it, like `tomorrow 9am`\n",
+      )),
+    }
+  }
+
+  fn update_from_arg_matches(&mut self, matches: &ArgMatches) -> Result<(), clap::Error> {
+    *self = Self::from_arg_matches(matches)?;
+    Ok(())
+  }
+}
+
+impl Args for EndTime {
+  fn augment_args(cmd: clap::Command) -> clap::Command {
+    cmd.arg(
+      Arg::new("when")
+        .value_name("WHEN")
+        .help(
+          "The time it ends, like 17:00 or 9am, and optionally the date, like tomorrow, friday, \
+           +3d or 2024-12-25. Defaults to today if no date is given",
+        )
+        .required(true)
+        .num_args(1..=2)
+        .value_parser(str_to_when),
+    )
+  }
+
+  fn augment_args_for_update(cmd: clap::Command) -> clap::Command {
+    Self::augment_args(cmd)
+  }
 }
 
 #[derive(Parser)]
@@ -22,12 +158,28 @@ fn get_all_ct_blocks() -> Vec<String> {
 pub struct ColdTurkey {
   #[command(subcommand)]
   pub command: Option<Command>,
+  /// Relaunch ctk as an administrator if the command needs it
+  #[arg(long, global = true)]
+  pub elevate: bool,
+  /// Log the raw messages exchanged with Cold Turkey's messaging host to this file
+  #[arg(long, global = true, value_name = "FILE")]
+  pub trace_host: Option<PathBuf>,
+  /// Print machine-readable JSON instead of text, for `list` and `status`
+  #[arg(long, global = true)]
+  pub json: bool,
+  /// Override a setting for this run only, like `--set default_block=Work`. Wins over the
+  /// config files and CTK_* environment variables
+  #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+  pub set: Vec<String>,
 }
 
 #[derive(Subcommand)]
 pub enum StartSubcommands {
   /// Set a time period to block
   For {
+    /// How long to block, like 90m, 1h30m, 2d4h or 1.5h
+    #[arg(value_parser = str_to_duration, conflicts_with_all = ["minutes", "hours", "days"])]
+    duration: Option<Duration>,
     /// How long to block in minutes
     #[arg(long)]
     minutes: Option<u32>,
@@ -38,55 +190,675 @@ pub enum StartSubcommands {
   },
   /// Set when the block is finished
   Until {
+    #[command(flatten)]
+    end: EndTime,
+    #[arg(short, long)]
+    /// Don't ask for confirmation of very long locks
+    yes: bool,
+  },
+}
+
+#[derive(Subcommand)]
+pub enum PauseSubcommands {
+  /// Set how long to pause for
+  For {
+    /// How long to pause in minutes
+    #[arg(long)]
+    minutes: Option<u32>,
+    #[arg(long)]
+    hours: Option<u32>,
+  },
+  /// Set when the block starts blocking again
+  Until {
+    #[command(flatten)]
+    end: EndTime,
+  },
+}
+
+#[derive(Subcommand)]
+pub enum SettingsSubcommands {
+  /// Change a global Cold Turkey option
+  Set {
+    /// The option to change
+    #[arg(value_enum)]
+    key: GlobalSetting,
+    /// Either true or false
+    #[arg(action = ArgAction::Set)]
+    value: bool,
+  },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigSubcommands {
+  /// Show the effective configuration
+  Show {
+    /// Show which config file each value came from
+    #[arg(long)]
+    origin: bool,
+  },
+  /// Print the effective value of one setting
+  Get {
+    /// The setting, e.g. blocker_path or profiles.Work
+    key: String,
+  },
+  /// Change a setting in your own config file
+  Set {
+    /// The setting, e.g. blocker_path or profiles.Work
+    key: String,
+    /// The new value. Lists of blocks are separated by commas
+    value: String,
+  },
+  /// List every setting ctk understands
+  List,
+  /// Open your own config file in your editor
+  Edit,
+}
+
+#[derive(Subcommand)]
+pub enum TemplateSubcommands {
+  /// Fill in a template's {{variables}} for this machine and open it in Cold Turkey
+  Import {
+    /// The .ctbbl template
+    file: PathBuf,
+  },
+  /// Fill in a template's {{variables}} for this machine
+  Expand {
+    /// The .ctbbl template
+    file: PathBuf,
+    /// Write the result to this file instead of printing it
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Write blocks in the format of this Cold Turkey release, like 4.2
+    #[arg(long, value_name = "VERSION", value_parser = str_to_version)]
+    target_version: Option<(u32, u32)>,
+  },
+  /// Turn this machine's paths in a .ctbbl file into {{variables}}
+  Make {
+    /// The .ctbbl file
+    file: PathBuf,
+    /// Write the template to this file instead of printing it
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Write blocks in the format of this Cold Turkey release, like 4.2
+    #[arg(long, value_name = "VERSION", value_parser = str_to_version)]
+    target_version: Option<(u32, u32)>,
+  },
+  /// Show the value of every variable on this machine
+  Vars,
+}
+
+#[derive(Subcommand)]
+pub enum ScheduleSubcommands {
+  /// Show whether blocks will be blocking at a time, and which schedule entry applies then
+  Simulate {
     #[arg(value_parser = str_to_time)]
-    /// The time of the end of a block
-    endtime: NaiveTime,
+    /// The time to check
+    time: NaiveTime,
     #[arg(value_parser = str_to_date)]
-    /// The date of the end of a block. Defaults to today if not given
-    enddate: Option<NaiveDate>,
+    /// The date to check. Defaults to today if not given
+    date: Option<NaiveDate>,
+    /// Only check this block
+    #[arg(long, value_parser = block_name())]
+    block: Option<String>,
+  },
+  /// Draw a running block's schedule as a grid of days by hours
+  View {
+    /// The name of the Cold Turkey block
+    #[arg(value_parser = block_name())]
+    block_name: String,
+  },
+  /// Turn a timetable in a CSV file into a block's schedule
+  ImportCsv {
+    /// The CSV file, with one day,start,end,break line per class, like Mon,09:00,10:30,none
+    file: PathBuf,
+    /// The block to schedule, which is created if it does not exist
+    #[arg(long)]
+    block: String,
+    /// Save the block to this .ctbbl file instead of importing it into Cold Turkey
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    #[arg(short, long)]
+    /// Don't ask for confirmation
+    yes: bool,
   },
 }
 
 #[derive(Subcommand)]
-pub enum Command {
-  /// Start a block
+pub enum TmpSubcommands {
+  /// Make a throwaway block with the given websites and start it locked
   Start {
+    /// The websites to block, like reddit.com twitter.com
+    #[arg(long, num_args = 1.., required = true)]
+    web: Vec<String>,
+    /// How long to block for, like 45m, 2h or 1h30m
+    #[arg(long = "for", value_name = "DURATION", value_parser = str_to_duration)]
+    duration: Duration,
+    /// The name of the block. Defaults to one with the current time in it
+    #[arg(long)]
+    name: Option<String>,
+  },
+  /// Empty the temporary blocks that have expired, so they can be deleted in Cold Turkey
+  Clean {
+    #[arg(short, long)]
+    /// Don't ask for confirmation
+    yes: bool,
+  },
+}
+
+#[derive(Subcommand)]
+pub enum SyncSubcommands {
+  /// Save this machine's running blocks and config to sync_location
+  Push {
+    /// Overwrite changes made there by other machines since your last sync
+    #[arg(long)]
+    force: bool,
+  },
+  /// Bring the blocks and config saved in sync_location to this machine
+  Pull {
+    /// Overwrite changes made on this machine since your last sync
+    #[arg(long)]
+    force: bool,
+  },
+}
+
+#[derive(Subcommand)]
+pub enum BlocklistSubcommands {
+  /// Make a block follow a blocklist online, in plain text or hosts file form, and add its
+  /// websites to the block
+  Subscribe {
     /// The name of the Cold Turkey block
-    #[arg(value_parser = get_all_ct_blocks())]
+    #[arg(value_parser = block_name())]
     block_name: String,
+    /// Where the blocklist is, like https://example.com/gambling.txt
+    url: String,
+    #[arg(short, long)]
+    /// Don't ask for confirmation
+    yes: bool,
+  },
+  /// Stop a block following a blocklist. The websites it added stay in the block
+  Unsubscribe {
+    /// The name of the block
+    block_name: String,
+    /// The blocklist's url, as it was subscribed to
+    url: String,
+  },
+  /// Show the blocklists each block follows
+  List,
+  /// Fetch the blocklists again and add what they added to their blocks, and remove what
+  /// they dropped
+  Update {
+    /// Only update this block, instead of every block that follows a blocklist
+    #[arg(value_parser = block_name())]
+    block_name: Option<String>,
+    #[arg(short, long)]
+    /// Don't ask for confirmation
+    yes: bool,
+  },
+}
+
+#[derive(Subcommand)]
+pub enum ProtocolSubcommands {
+  /// Open ctk:// links with ctk, so bookmarklets and browser buttons can run it (Windows only)
+  Register,
+  /// Stop opening ctk:// links with ctk
+  Unregister,
+  /// Do what a link like ctk://add?url=example.com or ctk://start?block=Social asks
+  Open {
+    /// The ctk:// link
+    link: String,
+  },
+}
+
+#[derive(Subcommand)]
+pub enum StatsSubcommands {
+  /// Count the pomodoro focus cycles completed each day, as seen by `ctk watch`
+  Pomodoro {
+    /// Count over this many days (default 7)
+    #[arg(long)]
+    days: Option<u32>,
+  },
+}
+
+#[derive(Subcommand)]
+pub enum HistorySubcommands {
+  /// Print every entry of the journal, oldest first, including the rotated ones still kept.
+  /// With --json, as one JSON array
+  Export {
+    /// Write the entries to this file instead of printing them
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+  },
+}
+
+/// A shell `ctk completions` writes a script for
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum CompletionShell {
+  Bash,
+  Zsh,
+  Fish,
+  Powershell,
+  Elvish,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+  /// Start a block
+  // Otherwise `for` and `until` would be taken as more block names
+  #[command(subcommand_precedence_over_arg = true)]
+  Start {
+    /// The names of the Cold Turkey blocks, or @<tag> or @<profile> for groups of blocks.
+    /// Without any, ctk lists the blocks to choose from
+    #[arg(
+      value_name = "BLOCK_NAME",
+      value_parser = block_or_group(),
+      conflicts_with = "all"
+    )]
+    block_names: Vec<String>,
+    #[arg(long)]
+    /// Start every block
+    all: bool,
     #[arg(short, long)]
     /// Password to lock the block
     password: bool,
+    #[arg(long)]
+    /// Save Cold Turkey's settings before starting, so there is a record of them to go back to
+    snapshot: bool,
+    #[arg(long, conflicts_with = "password")]
+    /// Count down the lock of `for` or `until` until Cold Turkey ends it
+    watch: bool,
     #[command(subcommand)]
     subcommand: Option<StartSubcommands>,
   },
+  /// Show what can still be changed about a block once it is locked
+  RollbackPlan {
+    /// The name of the Cold Turkey block
+    #[arg(value_parser = block_name())]
+    block_name: String,
+  },
   /// Stop a block
   Stop {
+    /// The names of the Cold Turkey blocks, or @<tag> or @<profile> for groups of blocks.
+    /// Without any, ctk lists the blocks to choose from
+    #[arg(
+      value_name = "BLOCK_NAME",
+      value_parser = block_or_group(),
+      conflicts_with = "all"
+    )]
+    block_names: Vec<String>,
+    #[arg(long)]
+    /// Stop every block except Frozen Turkey
+    all: bool,
+    /// Stop locked blocks once their locks end instead, which `ctk watch` does while it runs
+    #[arg(long)]
+    when_unlocked: bool,
+  },
+  /// Lock the whole computer with Frozen Turkey for a while
+  Frozen {
+    #[command(subcommand)]
+    subcommand: StartSubcommands,
+  },
+  /// Pause a running block for a while
+  Pause {
     /// The name of the Cold Turkey block
-    #[arg(value_parser = get_all_ct_blocks())]
+    #[arg(value_parser = block_name())]
     block_name: String,
+    #[command(subcommand)]
+    subcommand: PauseSubcommands,
   },
   /// Add websites (urls) to a block
   Add {
     /// The name of the Cold Turkey block
-    #[arg(value_parser = get_all_ct_blocks())]
+    #[arg(value_parser = block_name())]
     block_name: String,
-    /// The url to add in the block
-    url: String,
+    /// The urls to add in the block
+    #[arg(value_name = "URL", required_unless_present_any = ["list", "stdin"])]
+    urls: Vec<String>,
     #[arg(short, long)]
     /// Whether it is black or white-listed
     except: bool,
+    #[arg(short, long, conflicts_with_all = ["urls", "stdin"])]
+    /// List the block's current websites (or exceptions, with --except) instead of adding any
+    list: bool,
+    #[arg(long)]
+    /// Also add the urls read from standard input, one per line. Empty lines and lines
+    /// starting with # are skipped
+    stdin: bool,
   },
-  /// Turn on if off, turn off if on
-  Toggle {
+  /// Add a url, or the one on the clipboard, to the default block. Made for a global hotkey
+  #[command(visible_alias = "qa")]
+  Quickadd {
+    /// The url to add. Without it, the clipboard's contents are added
+    url: Option<String>,
+    /// Choose from the websites you visit most that no block covers yet, read from the
+    /// history of Chrome, Edge and Firefox on this computer
+    #[arg(long, conflicts_with = "url")]
+    from_history: bool,
+  },
+  /// Remove websites (urls) from a block by re-importing it without them
+  Remove {
     /// The name of the Cold Turkey block
-    #[arg(value_parser = get_all_ct_blocks())]
+    #[arg(value_parser = block_name())]
     block_name: String,
+    /// The urls to remove from the block
+    #[arg(value_name = "URL", required = true)]
+    urls: Vec<String>,
+    #[arg(short, long)]
+    /// Remove the urls from the exceptions instead of the blocklist
+    except: bool,
+    #[arg(short, long)]
+    /// Don't ask for confirmation
+    yes: bool,
+  },
+  /// Copy a block to a new name and empty the old one. Cold Turkey cannot delete blocks
+  /// from the command line, so the empty block stays until you delete it there
+  CopyBlock {
+    /// The current name of the Cold Turkey block
+    #[arg(value_parser = block_name())]
+    old_name: String,
+    /// The new name of the block
+    new_name: String,
+    #[arg(short, long)]
+    /// Don't ask for confirmation
+    yes: bool,
+  },
+  /// Stop a block and empty it of its websites and apps. Cold Turkey cannot delete blocks
+  /// from the command line, so the empty block stays until you delete it there
+  EmptyBlock {
+    /// The name of the Cold Turkey block
+    #[arg(value_parser = block_name())]
+    block_name: String,
+    #[arg(short, long)]
+    /// Don't ask for confirmation
+    yes: bool,
+  },
+  /// Turn on if off, turn off if on
+  Toggle {
+    /// The names of the Cold Turkey blocks, or @<tag> or @<profile> for groups of blocks.
+    /// Without any, ctk lists the blocks to choose from
+    #[arg(
+      value_name = "BLOCK_NAME",
+      value_parser = block_or_group(),
+      conflicts_with = "all"
+    )]
+    block_names: Vec<String>,
+    #[arg(long)]
+    /// Toggle every block except Frozen Turkey
+    all: bool,
+  },
+  /// Block websites for a while with a throwaway block, cleaned up once it expires
+  Tmp {
+    #[command(subcommand)]
+    subcommand: TmpSubcommands,
+  },
+  /// Clean up what ctk leaves behind: expired temporary blocks, old snapshots and leftover
+  /// import files
+  PurgeExpired {
+    /// Keep snapshots taken within this long, like 30d or 12h
+    #[arg(long, value_name = "DURATION", default_value = "30d", value_parser = str_to_duration)]
+    keep: Duration,
+    #[arg(short, long)]
+    /// Don't ask for confirmation
+    yes: bool,
   },
   /// Interactively suggest what blocks you want Cold Turkey to have
-  Suggest,
+  Suggest {
+    /// Choose a break method for every schedule entry instead of using the block's
+    #[arg(long)]
+    ask_breaks: bool,
+    /// Write blocks in the format of this Cold Turkey release, like 4.2
+    #[arg(long, value_name = "VERSION", value_parser = str_to_version)]
+    target_version: Option<(u32, u32)>,
+    /// Name this session so `--resume` can reopen it, saving its blocks to <NAME>.ctbbl
+    #[arg(long, value_name = "NAME", conflicts_with = "resume")]
+    name: Option<String>,
+    /// Reopen a named session to add more blocks to it
+    #[arg(long, value_name = "NAME")]
+    resume: Option<String>,
+    /// Save the .ctbbl file in this folder instead of the current one
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+    /// Offer the websites you visit most that no block covers yet, read from the history
+    /// of Chrome, Edge and Firefox on this computer
+    #[arg(long)]
+    from_history: bool,
+  },
+  /// Set up a few recommended blocks with a schedule and lock, step by step
+  Wizard,
+  /// Plan the week for all running blocks at once, on one calendar of their schedules
+  Plan,
+  /// Check which parts of Cold Turkey ctk can find, and what works without them
+  Doctor,
+  /// Practice the app shell and schedule times of `ctk suggest` in a sandbox
+  Learn,
   /// List all the blocks in alphabetical order by default
-  List,
+  List {
+    /// Nest blocks that share a profile or a name prefix, like "Work/Email" and "Work/Social"
+    #[arg(short, long)]
+    tree: bool,
+    /// Show each block's note and tags too
+    #[arg(short, long, conflicts_with = "tree")]
+    verbose: bool,
+  },
+  /// Work with the schedules of blocks
+  Schedule {
+    #[command(subcommand)]
+    subcommand: ScheduleSubcommands,
+  },
+  /// Show whether each block is running, how it is locked and what is left of its allowance
+  Status {
+    /// Only show this block
+    #[arg(value_parser = block_name())]
+    block_name: Option<String>,
+    /// Keep showing the status, drawn again as it is read, until stopped with Ctrl+C
+    #[arg(long)]
+    watch: bool,
+    /// How often to read the status with --watch, like 2s or 500ms
+    #[arg(
+      long,
+      value_name = "DURATION",
+      value_parser = str_to_duration,
+      default_value = "2s",
+      requires = "watch"
+    )]
+    interval: Duration,
+  },
+  /// Exit with 0 if a block is blocking and locked, 1 if it is not, or 2 if ctk cannot
+  /// tell, for scripts and monitoring. Prints nothing unless -v is given
+  Check {
+    /// The name of the Cold Turkey block
+    block_name: String,
+    /// Say what the block is doing
+    #[arg(short, long)]
+    verbose: bool,
+  },
+  /// Show how long typing a block's random text lock takes, to see if it is too easy or too
+  /// hard to unlock
+  UnlockHint {
+    /// The name of the block, which does not have to be in Cold Turkey yet if ctk saved it
+    block_name: String,
+  },
+  /// Show, set or clear a note on a block, kept by ctk rather than Cold Turkey
+  Note {
+    /// The name of the Cold Turkey block
+    #[arg(value_parser = block_name())]
+    block_name: String,
+    /// The note, like "created for exam season, safe to delete after June"
+    note: Option<String>,
+    #[arg(long, conflicts_with = "note")]
+    /// Remove the note
+    clear: bool,
+  },
+  /// List the tags on blocks, with the blocks that have each one
+  Tags,
+  /// Tag a block, like "social" or "work", kept by ctk rather than Cold Turkey
+  Tag {
+    /// The name of the Cold Turkey block
+    #[arg(value_parser = block_name())]
+    block_name: String,
+    /// The tags to add
+    #[arg(required = true)]
+    tags: Vec<String>,
+    #[arg(short, long)]
+    /// Remove the tags instead
+    remove: bool,
+  },
+  /// Summarize the time blocks started through ctk were running
+  Report {
+    /// Report on this week so far, from Monday. Without it, the last 7 days are reported on
+    #[arg(long)]
+    week: bool,
+    /// Report on this many days instead
+    #[arg(long, conflicts_with = "week")]
+    days: Option<u32>,
+    /// Write the report as an HTML page, for emailing or archiving
+    #[arg(long)]
+    html: bool,
+    /// Save the HTML report to this file instead of printing it
+    #[arg(short, long, requires = "html")]
+    output: Option<PathBuf>,
+    /// Lay out the HTML report with this file, using {{variables}} like {{hours}} and {{blocks}}
+    #[arg(long, value_name = "FILE", requires = "html")]
+    template: Option<PathBuf>,
+  },
+  /// Show statistics gathered while `ctk watch` runs
+  Stats {
+    #[command(subcommand)]
+    subcommand: StatsSubcommands,
+  },
+  /// Read the journal of what ctk did to blocks
+  History {
+    #[command(subcommand)]
+    subcommand: HistorySubcommands,
+  },
+  /// Show how many days in a row a block has run for long enough
+  Streak {
+    /// The block to track, instead of streak_block from the config
+    #[arg(value_parser = block_name())]
+    block_name: Option<String>,
+    /// How long the block must run each day, like 2h (default streak_goal from the config, or 1h)
+    #[arg(long, value_parser = str_to_duration)]
+    goal: Option<std::time::Duration>,
+    /// Run streak_hook from the config if the streak is at risk today
+    #[arg(long)]
+    notify: bool,
+  },
+  /// Keep running and apply the rules in rules.toml, like daily allowances
+  Watch {
+    /// Use this rules file instead of rules.toml in the ctk config directory
+    #[arg(long)]
+    rules: Option<PathBuf>,
+    /// List each rule with when it runs and the Cold Turkey command it runs, without watching
+    #[arg(long, conflicts_with = "diff")]
+    dry_run: bool,
+    /// Compare the rules file with the rules the running `ctk watch` loaded, without watching
+    #[arg(long)]
+    diff: bool,
+  },
+  /// Keep running a pomodoro timer, starting the block for each work interval and stopping it for each break
+  Pomodoro {
+    /// The name of the Cold Turkey block
+    #[arg(value_parser = block_name())]
+    block_name: String,
+    /// Minutes in each work interval, while the block runs
+    #[arg(long, default_value = "25")]
+    work: u32,
+    /// Minutes in each break, while the block is stopped
+    #[arg(long = "break", default_value = "5")]
+    break_minutes: u32,
+    /// How many work intervals to run, with a break between each
+    #[arg(long, default_value = "4")]
+    cycles: u32,
+  },
+  /// Show Cold Turkey's global options, or change one of them
+  Settings {
+    #[command(subcommand)]
+    subcommand: Option<SettingsSubcommands>,
+  },
+  /// View or edit ctk's own configuration
+  Config {
+    #[command(subcommand)]
+    subcommand: ConfigSubcommands,
+  },
+  /// Explain an error code like CTK001 in detail, or list every code
+  Explain {
+    /// The code printed with the error
+    code: Option<String>,
+  },
+  /// Check a .ctbbl file and open it in Cold Turkey to import its blocks
+  Import {
+    /// The .ctbbl file
+    file: PathBuf,
+    /// Keep running and import the file again every time it changes
+    #[arg(long)]
+    watch: bool,
+  },
+  /// Add websites, exceptions and apps to a block in a .ctbbl file, skipping ones already there
+  MergeInto {
+    /// The .ctbbl file, which is created if it does not exist
+    file: PathBuf,
+    /// The block to add to, which is created if it does not exist
+    #[arg(long)]
+    block: String,
+    /// Websites to block
+    #[arg(long, num_args = 1.., value_name = "URL")]
+    web: Vec<String>,
+    /// Websites to allow
+    #[arg(long, num_args = 1.., value_name = "URL")]
+    except: Vec<String>,
+    /// Apps to block, by path to the executable
+    #[arg(long, num_args = 1.., value_name = "PATH")]
+    app: Vec<String>,
+    /// Folders whose apps to block
+    #[arg(long, num_args = 1.., value_name = "PATH")]
+    folder: Vec<String>,
+    /// Windows to block, by title
+    #[arg(long, num_args = 1.., value_name = "TITLE")]
+    title: Vec<String>,
+    /// Write blocks in the format of this Cold Turkey release, like 4.2
+    #[arg(long, value_name = "VERSION", value_parser = str_to_version)]
+    target_version: Option<(u32, u32)>,
+  },
+  /// Check a .ctbbl file for blocks that may not work the way you meant
+  Lint {
+    /// The .ctbbl file
+    file: PathBuf,
+  },
+  /// Make .ctbbl files that work on any machine, using variables like {{home}}
+  Template {
+    #[command(subcommand)]
+    subcommand: TemplateSubcommands,
+  },
+  /// Keep the same blocks and config on several machines
+  Sync {
+    #[command(subcommand)]
+    subcommand: SyncSubcommands,
+  },
+  /// Keep blocks in step with blocklists hosted online
+  Blocklist {
+    #[command(subcommand)]
+    subcommand: BlocklistSubcommands,
+  },
+  /// Let a bookmarklet or browser button add websites or start blocks through ctk:// links
+  Protocol {
+    #[command(subcommand)]
+    subcommand: ProtocolSubcommands,
+  },
+  /// Set up shell completions and the man page for ctk, after installing it
+  CompleteSetup {
+    /// Show what would be written without writing anything
+    #[arg(long)]
+    dry_run: bool,
+    /// Add the folder ctk is in to your PATH (Windows only)
+    #[arg(long)]
+    add_to_path: bool,
+  },
+  /// Print a completion script for your shell, which completes block names as they are when you press Tab
+  Completions {
+    shell: CompletionShell,
+    /// Print a script that completes without running ctk, with block names as they are now
+    #[arg(long = "static")]
+    static_script: bool,
+  },
   /// Installs Cold Turkey
   Install {
     /// Force installing Cold Turkey, regardless if Cold Turkey Blocker exists
@@ -94,4 +866,3 @@ pub enum Command {
     force: bool,
   },
 }
-
@@ -1,7 +1,22 @@
-use chrono::{NaiveDate, NaiveTime, ParseResult};
+use chrono::{Datelike, Local, NaiveDate, NaiveTime, ParseResult, Weekday};
+use std::time::Duration;
+
+/// Part of when a lock or pause ends: the time, or the date it is on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum When {
+  Time(NaiveTime),
+  Date(NaiveDate),
+}
 
 pub fn str_to_time(s: &str) -> ParseResult<NaiveTime> {
   const ALLOWED_PARSE: [&str; 6] = ["%H:%M", "%k:%M", "%I:%M%P", "%I:%M%p", "%l:%M%P", "%l:%M%p"];
+  // An hour on its own, like "9am", is read as "9:00am"
+  let on_the_hour = ["am", "pm", "AM", "PM"].iter().find_map(|suffix| {
+    let hour = s.strip_suffix(suffix)?;
+    (!hour.is_empty() && hour.chars().all(|c| c.is_ascii_digit()))
+      .then(|| format!("{hour}:00{suffix}"))
+  });
+  let s = on_the_hour.as_deref().unwrap_or(s);
   for parser in &ALLOWED_PARSE {
     match NaiveTime::parse_from_str(s, parser) {
       Ok(time) => return Ok(time),
@@ -11,7 +26,12 @@ pub fn str_to_time(s: &str) -> ParseResult<NaiveTime> {
   NaiveTime::parse_from_str(s, ALLOWED_PARSE[0])
 }
 
+/// Parses a date like "2024-12-25" or "25/12/2024", or one counted from today: "today",
+/// "tomorrow", a weekday like "friday" (the next one, today included) or "+3d"
 pub fn str_to_date(s: &str) -> ParseResult<NaiveDate> {
+  if let Some(date) = relative_date(s, Local::now().date_naive()) {
+    return Ok(date);
+  }
   const ALLOWED_PARSE: [&str; 6] = [
     "%d %B %Y", "%e %B %Y", "%B %d %Y", "%B %e %Y", "%F", "%d/%m/%Y",
   ];
@@ -23,3 +43,196 @@ pub fn str_to_date(s: &str) -> ParseResult<NaiveDate> {
   }
   NaiveDate::parse_from_str(s, ALLOWED_PARSE[0])
 }
+
+/// The date `s` means counted from `today`, if it is one of the relative forms
+fn relative_date(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+  let s = s.trim().to_lowercase();
+  let days_ahead: u32 = match s.as_str() {
+    "today" => 0,
+    "tomorrow" => 1,
+    _ => match s.strip_prefix('+').and_then(|days| days.strip_suffix('d')) {
+      Some(days) => days.parse().ok()?,
+      None => {
+        let weekday: Weekday = s.parse().ok()?;
+        (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7
+      }
+    },
+  };
+  today.checked_add_signed(chrono::Duration::days(days_ahead.into()))
+}
+
+/// Parses either a time `str_to_time` understands or a date `str_to_date` does
+pub fn str_to_when(s: &str) -> Result<When, String> {
+  str_to_time(s)
+    .map(When::Time)
+    .or_else(|_| str_to_date(s).map(When::Date))
+    .map_err(|_| format!("{s} is neither a time like 17:00 or 9am nor a date like tomorrow"))
+}
+
+/// Parses durations made of numbers and units, like "500ms", "90m", "1h30m", "2d4h" or
+/// "1.5h". Parts may be separated by spaces and units spelled out, like "1 hour 30 mins"
+pub fn str_to_duration(s: &str) -> Result<Duration, String> {
+  let mut rest = s.trim();
+  if rest.is_empty() {
+    return Err("a duration cannot be empty".to_string());
+  }
+
+  let mut total = Duration::ZERO;
+  while !rest.is_empty() {
+    let number_end = rest
+      .find(|c: char| !c.is_ascii_digit() && c != '.')
+      .unwrap_or(rest.len());
+    let number = &rest[..number_end];
+    if number.is_empty() || number == "." {
+      return Err(format!("expected a number in {s}"));
+    }
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if fraction.contains('.') {
+      return Err(format!("{number} is not a number in {s}"));
+    }
+    rest = rest[number_end..].trim_start();
+
+    let unit_end = rest
+      .find(|c: char| c.is_ascii_digit() || c == '.' || c.is_whitespace())
+      .unwrap_or(rest.len());
+    let nanos_per_unit: u128 = match &rest[..unit_end] {
+      "ms" | "msec" | "msecs" | "millisecond" | "milliseconds" => 1_000_000,
+      "s" | "sec" | "secs" | "second" | "seconds" => 1_000_000_000,
+      "m" | "min" | "mins" | "minute" | "minutes" => 60 * 1_000_000_000,
+      "h" | "hr" | "hrs" | "hour" | "hours" => 60 * 60 * 1_000_000_000,
+      "d" | "day" | "days" => 24 * 60 * 60 * 1_000_000_000,
+      "" => return Err(format!("missing a unit (ms, s, m, h or d) in {s}")),
+      unit => return Err(format!("unknown unit {unit} in {s}")),
+    };
+    total = part_nanos(whole, fraction, nanos_per_unit)
+      .and_then(|nanos| {
+        let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+        total.checked_add(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+      })
+      .ok_or_else(|| format!("{s} is too long"))?;
+    rest = rest[unit_end..].trim_start();
+  }
+
+  Ok(total)
+}
+
+/// How many nanoseconds "`whole`.`fraction`" units make, rounding off anything shorter.
+/// Nothing if it would not fit
+fn part_nanos(whole: &str, fraction: &str, nanos_per_unit: u128) -> Option<u128> {
+  let whole: u128 = if whole.is_empty() {
+    0
+  } else {
+    whole.parse().ok()?
+  };
+  let mut nanos = whole.checked_mul(nanos_per_unit)?;
+  let mut place = nanos_per_unit;
+  for digit in fraction.chars().filter_map(|c| c.to_digit(10)) {
+    place /= 10;
+    nanos = nanos.checked_add(u128::from(digit) * place)?;
+  }
+  Some(nanos)
+}
+
+/// Parses a Cold Turkey release like "4.2" or "4.2.1" into its major and minor version
+pub fn str_to_version(s: &str) -> Result<(u32, u32), String> {
+  let mut parts = s.trim().split('.');
+  let mut next_number = || -> Result<Option<u32>, String> {
+    parts
+      .next()
+      .map(|part| part.parse().map_err(|_| format!("{s} is not a version like 4.2")))
+      .transpose()
+  };
+  let major = next_number()?.ok_or_else(|| "a version cannot be empty".to_string())?;
+  let minor = next_number()?.unwrap_or(0);
+  Ok((major, minor))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+  }
+
+  fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+  }
+
+  fn minutes(minutes: u64) -> Result<Duration, String> {
+    Ok(Duration::from_secs(minutes * 60))
+  }
+
+  #[test]
+  fn single_units() {
+    assert_eq!(str_to_duration("90m"), minutes(90));
+    assert_eq!(str_to_duration("2h"), minutes(120));
+    assert_eq!(str_to_duration("1d"), minutes(24 * 60));
+    assert_eq!(str_to_duration("45s"), Ok(Duration::from_secs(45)));
+    assert_eq!(str_to_duration("500ms"), Ok(Duration::from_millis(500)));
+  }
+
+  #[test]
+  fn units_add_up() {
+    assert_eq!(str_to_duration("1h30m"), minutes(90));
+    assert_eq!(str_to_duration("2d4h"), minutes((2 * 24 + 4) * 60));
+    assert_eq!(str_to_duration("1m30s"), Ok(Duration::from_secs(90)));
+  }
+
+  #[test]
+  fn decimals_are_parts_of_a_unit() {
+    assert_eq!(str_to_duration("1.5h"), minutes(90));
+    assert_eq!(str_to_duration(".5h"), minutes(30));
+    assert_eq!(str_to_duration("0.25d"), minutes(6 * 60));
+    assert_eq!(str_to_duration("1.5m"), Ok(Duration::from_secs(90)));
+  }
+
+  #[test]
+  fn spaces_and_long_units() {
+    assert_eq!(str_to_duration(" 1h 30m "), minutes(90));
+    assert_eq!(str_to_duration("1 hour 30 mins"), minutes(90));
+    assert_eq!(str_to_duration("2 days"), minutes(2 * 24 * 60));
+  }
+
+  #[test]
+  fn malformed_durations_are_refused() {
+    assert!(str_to_duration("").is_err());
+    assert!(str_to_duration("90").is_err());
+    assert!(str_to_duration("h").is_err());
+    assert!(str_to_duration("1.5.2h").is_err());
+    assert!(str_to_duration(".h").is_err());
+    assert!(str_to_duration("3 weeks").is_err());
+    assert!(str_to_duration("99999999999999999999d").is_err());
+  }
+
+  #[test]
+  fn hours_on_their_own() {
+    assert_eq!(str_to_time("9am"), Ok(time(9, 0)));
+    assert_eq!(str_to_time("12PM"), Ok(time(12, 0)));
+    assert_eq!(str_to_time("9:30pm"), Ok(time(21, 30)));
+    assert!(str_to_time("13pm").is_err());
+  }
+
+  #[test]
+  fn dates_counted_from_today() {
+    // A Wednesday
+    let today = date(2024, 6, 5);
+    assert_eq!(relative_date("today", today), Some(today));
+    assert_eq!(relative_date("Tomorrow", today), Some(date(2024, 6, 6)));
+    assert_eq!(relative_date("+3d", today), Some(date(2024, 6, 8)));
+    assert_eq!(relative_date("+30d", today), Some(date(2024, 7, 5)));
+    assert_eq!(relative_date("friday", today), Some(date(2024, 6, 7)));
+    assert_eq!(relative_date("mon", today), Some(date(2024, 6, 10)));
+    assert_eq!(relative_date("wednesday", today), Some(today));
+    assert_eq!(relative_date("2024-06-07", today), None);
+    assert_eq!(relative_date("+d", today), None);
+  }
+
+  #[test]
+  fn times_and_dates_are_told_apart() {
+    assert_eq!(str_to_when("9am"), Ok(When::Time(time(9, 0))));
+    assert_eq!(str_to_when("2024-06-07"), Ok(When::Date(date(2024, 6, 7))));
+    assert!(matches!(str_to_when("friday"), Ok(When::Date(_))));
+    assert!(str_to_when("soon").is_err());
+  }
+}
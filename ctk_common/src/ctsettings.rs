@@ -1,7 +1,13 @@
+use clap::ValueEnum;
 use serde::de::{Error, Unexpected};
 use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::timeout;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -54,6 +60,15 @@ pub struct BlockInfo {
   pub block_list: Vec<String>,
   pub exception_list: Vec<String>,
   pub title_list: Vec<String>,
+  /// How the block is locked, like "password" or "window". Older releases leave it out
+  #[serde(default)]
+  pub lock: Option<String>,
+  /// Whether the block is "continuous" or "scheduled". Older releases leave it out
+  #[serde(rename = "type", default)]
+  pub schedule_type: Option<String>,
+  /// Settings this version of ctk does not know about, kept so they can be written back out
+  #[serde(flatten)]
+  pub unknown_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -64,6 +79,50 @@ pub enum UserStatus {
   Pro,
 }
 
+/// Global Cold Turkey options that ctk can read and change
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum GlobalSetting {
+  StatsEnabled,
+  StatsEnabledIncognito,
+  StatsStrict,
+  BlockEmbedded,
+  BlockCharity,
+  BlockInactive,
+  ForceAllowFile,
+  IgnoreIncognito,
+}
+
+impl GlobalSetting {
+  /// The key Cold Turkey uses for this option in its settings
+  pub const fn key(self) -> &'static str {
+    match self {
+      Self::StatsEnabled => "statsEnabled",
+      Self::StatsEnabledIncognito => "statsEnabledIncognito",
+      Self::StatsStrict => "statsStrict",
+      Self::BlockEmbedded => "blockEmbedded",
+      Self::BlockCharity => "blockCharity",
+      Self::BlockInactive => "blockInactive",
+      Self::ForceAllowFile => "forceAllowFile",
+      Self::IgnoreIncognito => "ignoreIncognito",
+    }
+  }
+}
+
+impl ColdTurkeySettings {
+  pub const fn global_setting(&self, setting: GlobalSetting) -> bool {
+    match setting {
+      GlobalSetting::StatsEnabled => self.stats_enabled,
+      GlobalSetting::StatsEnabledIncognito => self.stats_enabled_incognito,
+      GlobalSetting::StatsStrict => self.stats_strict,
+      GlobalSetting::BlockEmbedded => self.block_embedded,
+      GlobalSetting::BlockCharity => self.block_charity,
+      GlobalSetting::BlockInactive => self.block_inactive,
+      GlobalSetting::ForceAllowFile => self.force_allow_file,
+      GlobalSetting::IgnoreIncognito => self.ignore_incognito,
+    }
+  }
+}
+
 impl BlockInfo {
   pub fn is_dormant(&self) -> bool {
     self.allowance == None
@@ -76,17 +135,63 @@ impl BlockInfo {
       && self.exception_list.is_empty()
       && self.title_list.is_empty()
   }
+
+  /// Whether any of the block's blocklist covers everything under the site
+  pub fn blocks_site(&self, site: &str) -> bool {
+    let site = normalize_site(site);
+    self
+      .block_list
+      .iter()
+      .any(|entry| site_covers(&normalize_site(entry), &site))
+  }
+
+  /// Whether the block is currently held by a password or random text lock
+  pub fn is_locked(&self) -> bool {
+    !self.password.is_empty() || self.random_text_length.is_some()
+  }
+
+  /// Blocklist entries that the given exception would allow entirely
+  pub fn entries_negated_by(&self, exception: &str) -> Vec<&str> {
+    let exception = normalize_site(exception);
+    self
+      .block_list
+      .iter()
+      .filter(|entry| site_covers(&exception, &normalize_site(entry)))
+      .map(String::as_str)
+      .collect()
+  }
+}
+
+// Lowercases and strips the scheme, "www." and trailing slashes so equivalent sites compare equal
+fn normalize_site(site: &str) -> String {
+  let site = site.trim().to_lowercase();
+  let site = site
+    .split_once("://")
+    .map_or(site.as_str(), |(_, rest)| rest);
+  let site = site.strip_prefix("www.").unwrap_or(site);
+  site.trim_end_matches('/').to_string()
+}
+
+// Whether everything under `site` is also matched by `pattern`, both normalized
+fn site_covers(pattern: &str, site: &str) -> bool {
+  if let Some(domain) = pattern.strip_prefix("*.") {
+    let host = site.split('/').next().unwrap_or(site);
+    host == domain || host.ends_with(&format!(".{domain}"))
+  } else {
+    site == pattern || site.starts_with(&format!("{pattern}/"))
+  }
 }
 
 fn deserialize_string_to_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
   D: Deserializer<'de>,
 {
-  let s: &str = Deserialize::deserialize(deserializer)?;
-  match s {
+  // Owned, since a struct with flattened fields cannot lend out its strings
+  let s: String = Deserialize::deserialize(deserializer)?;
+  match s.as_str() {
     "true" => Ok(true),
     "false" => Ok(false),
-    _ => Err(Error::unknown_variant(s, &["true", "false"])),
+    _ => Err(Error::unknown_variant(&s, &["true", "false"])),
   }
 }
 
@@ -94,11 +199,11 @@ fn deserialize_string_to_u32<'de, D>(deserializer: D) -> Result<u32, D::Error>
 where
   D: Deserializer<'de>,
 {
-  let s: &str = Deserialize::deserialize(deserializer)?;
+  let s: String = Deserialize::deserialize(deserializer)?;
   match s.parse::<u32>() {
     Ok(num) => Ok(num),
     Err(_) => Err(Error::invalid_type(
-      Unexpected::Str(s),
+      Unexpected::Str(&s),
       &"not a u32 integer",
     )),
   }
@@ -108,24 +213,59 @@ fn deserialize_string_to_option_u32<'de, D>(deserializer: D) -> Result<Option<u3
 where
   D: Deserializer<'de>,
 {
-  let s: &str = Deserialize::deserialize(deserializer)?;
+  let s: String = Deserialize::deserialize(deserializer)?;
   if s.is_empty() {
     return Ok(None);
   }
   match s.parse::<u32>() {
     Ok(num) => Ok(Some(num)),
     Err(_) => Err(Error::invalid_type(
-      Unexpected::Str(s),
+      Unexpected::Str(&s),
       &"not a u32 integer",
     )),
   }
 }
 
+/// Where Cold Turkey installs the helper that reports its blocks and settings
+pub const MSG_HOST: &str = r"C:\Program Files\Cold Turkey\CTMsgHostEdge.exe";
+
+/// How long the helper gets to answer before it is stopped, unless set otherwise
+pub const DEFAULT_MSG_HOST_TIMEOUT: Duration = Duration::from_secs(10);
+
+static MSG_HOST_PATH: OnceLock<PathBuf> = OnceLock::new();
+static MSG_HOST_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+/// Reads settings from the helper at `path` instead of the default one from now on. Only the
+/// first call counts, so set it before anything reads the settings
+pub fn set_msg_host_path(path: PathBuf) {
+  let _ = MSG_HOST_PATH.set(path);
+}
+
+pub fn msg_host_path() -> &'static Path {
+  MSG_HOST_PATH
+    .get()
+    .map_or(Path::new(MSG_HOST), PathBuf::as_path)
+}
+
+/// Gives the helper `timeout` to answer instead of the default from now on. Like
+/// `set_msg_host_path`, only the first call counts
+pub fn set_msg_host_timeout(timeout: Duration) {
+  let _ = MSG_HOST_TIMEOUT.set(timeout);
+}
+
+pub fn msg_host_timeout() -> Duration {
+  MSG_HOST_TIMEOUT
+    .get()
+    .copied()
+    .unwrap_or(DEFAULT_MSG_HOST_TIMEOUT)
+}
+
 pub fn get_ct_settings() -> Option<ColdTurkeySettings> {
-  match Command::new(r"C:\Program Files\Cold Turkey\CTMsgHostEdge.exe").output() {
+  match timeout::output(&mut Command::new(msg_host_path()), msg_host_timeout()) {
     Ok(block_stdout) => {
       let output_vector = block_stdout.stdout;
-      match std::str::from_utf8(&output_vector[4..]) {
+      // The first 4 bytes give the message's length. A host that says less is not Cold Turkey's
+      match std::str::from_utf8(output_vector.get(4..)?) {
         Ok(ct_string) => serde_json::from_str(ct_string).ok(),
         Err(_) => None,
       }
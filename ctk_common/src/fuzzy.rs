@@ -0,0 +1,172 @@
+use rayon::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use sublime_fuzzy::{FuzzySearch, Match, Scoring};
+
+/// A path together with how well it matches the keyword searched for
+pub struct MatchString {
+  pub match_object: Match,
+  pub string: String,
+}
+
+impl Ord for MatchString {
+  fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    self.match_object.cmp(&other.match_object)
+  }
+}
+
+impl Eq for MatchString {}
+
+impl PartialOrd for MatchString {
+  fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl PartialEq for MatchString {
+  fn eq(&self, other: &Self) -> bool {
+    self.match_object.eq(&other.match_object)
+  }
+}
+
+impl fmt::Display for MatchString {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.string)
+  }
+}
+
+/// Matches one keyword against many paths, doing the work that only depends on the keyword once
+pub struct Matcher {
+  query: String,
+  // Lowercased the way sublime_fuzzy does it, whitespace left out since it is ignored too
+  lower_query: Vec<char>,
+  scoring: Scoring,
+}
+
+impl Matcher {
+  pub fn new(query: &str) -> Self {
+    let lower_query = query
+      .chars()
+      .zip(query.to_lowercase().chars())
+      .filter(|(original, _)| !original.is_whitespace())
+      .map(|(_, lower)| lower)
+      .collect();
+    Matcher {
+      query: query.to_string(),
+      lower_query,
+      scoring: Scoring::new(50, 0, 20, 0),
+    }
+  }
+
+  pub fn best_match(&self, target: &str) -> Option<Match> {
+    // Scoring builds a table for every target, which is wasted on the many paths that do
+    // not even contain the keyword's letters in order
+    if !self.could_match(target) {
+      return None;
+    }
+    FuzzySearch::new(&self.query, target)
+      .score_with(&self.scoring)
+      .case_insensitive()
+      .best_match()
+  }
+
+  /// Only turns the path into an owned string when it matches
+  pub fn match_string<S: AsRef<str> + Into<String>>(&self, target: S) -> Option<MatchString> {
+    let match_object = self.best_match(target.as_ref())?;
+    Some(MatchString {
+      match_object,
+      string: target.into(),
+    })
+  }
+
+  /// Whether every letter of the keyword is in the target in order, ignoring case
+  fn could_match(&self, target: &str) -> bool {
+    if self.lower_query.is_empty() {
+      return false;
+    }
+    if target.is_ascii() && self.lower_query.iter().all(char::is_ascii) {
+      let mut bytes = target.bytes();
+      return self
+        .lower_query
+        .iter()
+        .all(|&c| bytes.any(|b| b.to_ascii_lowercase() == c as u8));
+    }
+    let lower_target = target.to_lowercase();
+    let mut chars = lower_target.chars();
+    self.lower_query.iter().all(|&c| chars.any(|t| t == c))
+  }
+}
+
+/// How well `target` matches `query`, if at all
+pub fn best_match(query: &str, target: &str) -> Option<Match> {
+  Matcher::new(query).best_match(target)
+}
+
+/// The best matches seen so far, keeping no more than a set number of them
+pub struct TopMatches {
+  // Reversed so the worst match kept is the one on top, ready to be replaced
+  heap: BinaryHeap<Reverse<MatchString>>,
+  limit: usize,
+  found: usize,
+}
+
+impl TopMatches {
+  pub fn new(limit: usize) -> Self {
+    TopMatches {
+      heap: BinaryHeap::with_capacity(limit.min(1024) + 1),
+      limit,
+      found: 0,
+    }
+  }
+
+  pub fn push(mut self, item: MatchString) -> Self {
+    self.found += 1;
+    self.keep(item);
+    self
+  }
+
+  pub fn merge(mut self, other: Self) -> Self {
+    self.found += other.found;
+    for Reverse(item) in other.heap {
+      self.keep(item);
+    }
+    self
+  }
+
+  fn keep(&mut self, item: MatchString) {
+    if self.heap.len() < self.limit {
+      self.heap.push(Reverse(item));
+    } else if let Some(mut worst) = self.heap.peek_mut() {
+      if item > worst.0 {
+        *worst = Reverse(item);
+      }
+    }
+  }
+
+  /// How many matches there were, including the ones not kept
+  pub fn found(&self) -> usize {
+    self.found
+  }
+
+  /// The matches kept, best first
+  pub fn into_sorted_vec(self) -> Vec<MatchString> {
+    // Sorting the reversed matches ascending puts the best ones first
+    self
+      .heap
+      .into_sorted_vec()
+      .into_iter()
+      .map(|Reverse(item)| item)
+      .collect()
+  }
+}
+
+/// Gathers the best `limit` matches from a parallel search without sorting all of them
+pub fn top_matches<I>(matches: I, limit: usize) -> TopMatches
+where
+  I: ParallelIterator<Item = MatchString>,
+{
+  matches
+    .fold(|| TopMatches::new(limit), TopMatches::push)
+    .reduce(|| TopMatches::new(limit), TopMatches::merge)
+}
@@ -1,4 +1,6 @@
+pub mod blocksettings;
 pub mod ctsettings;
 pub mod cli_parser;
 pub mod convert;
-
+pub mod fuzzy;
+pub mod timeout;
@@ -0,0 +1,60 @@
+use std::io::{self, Read};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often a running process is checked on
+const POLL: Duration = Duration::from_millis(20);
+
+/// Waits for the child to exit like `Child::wait`, killing it if it takes longer than
+/// `timeout`. The error for that has the kind `TimedOut`
+pub fn wait(child: &mut Child, timeout: Duration) -> io::Result<ExitStatus> {
+  let started = Instant::now();
+  loop {
+    if let Some(status) = child.try_wait()? {
+      return Ok(status);
+    }
+    if started.elapsed() >= timeout {
+      // It may have exited just now, which is as good as killed
+      let _ = child.kill();
+      let _ = child.wait();
+      return Err(io::Error::new(
+        io::ErrorKind::TimedOut,
+        format!("did not finish within {timeout:?}, so it was stopped"),
+      ));
+    }
+    thread::sleep(POLL);
+  }
+}
+
+/// Runs the command to the end and collects what it prints like `Command::output`, killing
+/// it if it takes longer than `timeout`
+pub fn output(command: &mut Command, timeout: Duration) -> io::Result<Output> {
+  let mut child = command
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()?;
+  // Read on their own threads, since a process filling a pipe nobody reads never exits
+  let stdout = child.stdout.take().map(read_to_end);
+  let stderr = child.stderr.take().map(read_to_end);
+  let status = wait(&mut child, timeout)?;
+  let collect = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+    reader
+      .and_then(|reader| reader.join().ok())
+      .unwrap_or_default()
+  };
+  Ok(Output {
+    status,
+    stdout: collect(stdout),
+    stderr: collect(stderr),
+  })
+}
+
+fn read_to_end(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+  thread::spawn(move || {
+    let mut bytes = Vec::new();
+    let _ = pipe.read_to_end(&mut bytes);
+    bytes
+  })
+}
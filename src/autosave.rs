@@ -0,0 +1,80 @@
+use crate::config;
+use chrono::{DateTime, Local};
+use ctk_common::blocksettings::BlockSettings;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+const AUTOSAVE_FILE_NAME: &str = "suggest_autosave.json";
+
+/// The blocks of a `ctk suggest` that has not finished, so closing the terminal part way
+/// through does not lose them
+#[derive(Debug, Serialize, Deserialize)]
+struct Autosave {
+  saved_at: DateTime<Local>,
+  blocks: HashMap<String, BlockSettings>,
+  /// The label of each schedule entry by block, in order, since blocks are written without them
+  #[serde(default)]
+  labels: HashMap<String, Vec<Option<String>>>,
+}
+
+fn autosave_path() -> Option<PathBuf> {
+  config::data_dir().map(|dir| dir.join(AUTOSAVE_FILE_NAME))
+}
+
+/// Writes the blocks so far over the last autosave
+pub fn save(blocks: &HashMap<String, BlockSettings>) -> Result<(), Box<dyn std::error::Error>> {
+  let path = autosave_path().ok_or("cannot find your data directory")?;
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir)?;
+  }
+  let labels = blocks
+    .iter()
+    .map(|(block_name, block_settings)| {
+      let labels = block_settings
+        .schedule
+        .iter()
+        .map(|entry| entry.label.clone())
+        .collect();
+      (block_name.clone(), labels)
+    })
+    .collect();
+  let autosave = Autosave {
+    saved_at: Local::now(),
+    blocks: blocks.clone(),
+    labels,
+  };
+  fs::write(path, serde_json::to_string(&autosave)?)?;
+  Ok(())
+}
+
+/// The blocks of a `ctk suggest` that did not finish and when they were saved, if there
+/// are any
+pub fn load() -> Option<(DateTime<Local>, HashMap<String, BlockSettings>)> {
+  let contents = fs::read_to_string(autosave_path()?).ok()?;
+  let Autosave {
+    saved_at,
+    mut blocks,
+    labels,
+  } = serde_json::from_str(&contents).ok()?;
+  if blocks.is_empty() {
+    return None;
+  }
+  for (block_name, block_settings) in &mut blocks {
+    let Some(labels) = labels.get(block_name) else {
+      continue;
+    };
+    for (entry, label) in block_settings.schedule.iter_mut().zip(labels) {
+      entry.label = label.clone();
+    }
+  }
+  Some((saved_at, blocks))
+}
+
+/// Forgets the autosave, once the blocks are saved or not wanted
+pub fn clear() {
+  if let Some(path) = autosave_path() {
+    let _ = fs::remove_file(path);
+  }
+}
@@ -0,0 +1,252 @@
+use crate::config::Config;
+use crate::error::ErrorCode;
+use ctk_common::timeout;
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::process::{Child, Command};
+use std::thread;
+use std::time::Duration;
+
+/// How a block is locked when it is started
+#[derive(Debug, Clone, Copy)]
+pub enum StartLock<'a> {
+  None,
+  Minutes(i64),
+  Password(&'a str),
+}
+
+/// Something ctk asks Cold Turkey Blocker to do, turned into its command line in one place
+#[derive(Debug, Clone, Copy)]
+pub enum BlockerCommand<'a> {
+  /// Opens Cold Turkey's window
+  Open,
+  Start {
+    block: &'a str,
+    lock: StartLock<'a>,
+  },
+  Stop {
+    block: &'a str,
+  },
+  Toggle {
+    block: &'a str,
+  },
+  Pause {
+    block: &'a str,
+    minutes: i64,
+  },
+  Add {
+    block: &'a str,
+    url: &'a str,
+    /// To the block's exceptions rather than its blocklist
+    except: bool,
+  },
+  Setting {
+    key: &'a str,
+    value: bool,
+  },
+  /// Opens a .ctbbl file, which Cold Turkey asks the user to confirm importing
+  Import {
+    path: &'a Path,
+  },
+}
+
+impl<'a> BlockerCommand<'a> {
+  fn args(&self) -> Vec<Cow<'a, str>> {
+    let block_args = |option: &'static str, block: &'a str| -> Vec<Cow<'a, str>> {
+      vec![option.into(), block.into()]
+    };
+    match *self {
+      Self::Open => Vec::new(),
+      Self::Start { block, lock } => {
+        let mut args = block_args("-start", block);
+        match lock {
+          StartLock::None => {}
+          StartLock::Minutes(minutes) => args.extend(["-lock".into(), minutes.to_string().into()]),
+          StartLock::Password(password) => args.extend(["-password".into(), password.into()]),
+        }
+        args
+      }
+      Self::Stop { block } => block_args("-stop", block),
+      Self::Toggle { block } => block_args("-toggle", block),
+      Self::Pause { block, minutes } => {
+        let mut args = block_args("-pause", block);
+        args.push(minutes.to_string().into());
+        args
+      }
+      Self::Add { block, url, except } => {
+        let mut args = block_args("-add", block);
+        args.push(if except { "-exception" } else { "-web" }.into());
+        args.push(url.into());
+        args
+      }
+      Self::Setting { key, value } => {
+        vec!["-setting".into(), key.into(), value.to_string().into()]
+      }
+      Self::Import { path } => vec![path.to_string_lossy()],
+    }
+  }
+
+  /// The first Cold Turkey release with the command line option, and what it does, for
+  /// options older releases do not have
+  fn since(&self) -> Option<((u32, u32), &'static str)> {
+    match self {
+      Self::Start {
+        lock: StartLock::Password(_),
+        ..
+      } => Some(((4, 1), "-password, for starting blocks with a password")),
+      Self::Toggle { .. } => Some(((4, 1), "-toggle, for toggling blocks")),
+      Self::Pause { .. } => Some(((4, 3), "-pause, for pausing blocks")),
+      Self::Setting { .. } => Some(((4, 4), "-setting, for changing Cold Turkey's settings")),
+      _ => None,
+    }
+  }
+
+  /// Whether ctk waits for Cold Turkey Blocker to exit. Opening it, or a file to import,
+  /// leaves its window up for as long as the user likes
+  fn waits(&self) -> bool {
+    !matches!(self, Self::Open | Self::Import { .. })
+  }
+}
+
+/// Why a command could not be run by the blocker
+#[derive(Debug)]
+pub enum BlockerError {
+  /// blocker_version is older than the first release with the option
+  Unsupported {
+    option: &'static str,
+    since: (u32, u32),
+    version: (u32, u32),
+  },
+  Spawn(SpawnError),
+  /// It did not exit within blocker_timeout, so it was stopped
+  TimedOut(Duration),
+  Wait(io::Error),
+}
+
+impl BlockerError {
+  pub fn code(&self) -> ErrorCode {
+    match self {
+      Self::Unsupported { .. } => ErrorCode::Unsupported,
+      Self::Spawn(err) => err.code(),
+      Self::TimedOut(_) => ErrorCode::TimedOut,
+      Self::Wait(_) => ErrorCode::BlockerFailed,
+    }
+  }
+}
+
+impl fmt::Display for BlockerError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Unsupported {
+        option,
+        since: (since_major, since_minor),
+        version: (major, minor),
+      } => write!(
+        f,
+        "your Cold Turkey {major}.{minor} does not support {option}, which needs {since_major}.{since_minor} or later"
+      ),
+      Self::Spawn(err) => write!(f, "{err}"),
+      Self::TimedOut(timeout) => write!(
+        f,
+        "Cold Turkey Blocker did not finish within {timeout:?}, so it was stopped. Give it longer with `ctk config set blocker_timeout <duration>`"
+      ),
+      Self::Wait(err) => write!(f, "cannot wait for Cold Turkey Blocker to finish: {err}"),
+    }
+  }
+}
+
+/// Runs the command, unless blocker_version says the installed Cold Turkey is too old for
+/// it, since older releases ignore options they do not know. It gets blocker_timeout to
+/// finish, so a hung Cold Turkey Blocker cannot hold up scripts running ctk
+pub fn run(config: &Config, command: BlockerCommand) -> Result<(), BlockerError> {
+  if let (Some((since, option)), Some(version)) = (command.since(), config.blocker_version()) {
+    if version < since {
+      return Err(BlockerError::Unsupported {
+        option,
+        since,
+        version,
+      });
+    }
+  }
+  let args = command.args();
+  let args: Vec<&str> = args.iter().map(Cow::as_ref).collect();
+  let mut child = spawn(config, &args).map_err(BlockerError::Spawn)?;
+  let Some(timeout) = config.blocker_timeout().filter(|_| command.waits()) else {
+    return Ok(());
+  };
+  match timeout::wait(&mut child, timeout) {
+    Ok(_) => Ok(()),
+    Err(err) if err.kind() == io::ErrorKind::TimedOut => Err(BlockerError::TimedOut(timeout)),
+    Err(err) => Err(BlockerError::Wait(err)),
+  }
+}
+
+/// The command line `run` starts Cold Turkey Blocker with for the command, quoted to be
+/// shown rather than run
+pub fn command_line(config: &Config, command: &BlockerCommand) -> String {
+  let quote = |arg: &str| {
+    if arg.is_empty() || arg.contains(char::is_whitespace) {
+      format!("\"{arg}\"")
+    } else {
+      arg.to_string()
+    }
+  };
+  let mut line = quote(&config.blocker_path().to_string_lossy());
+  for arg in command.args() {
+    line.push(' ');
+    line.push_str(&quote(&arg));
+  }
+  line
+}
+
+/// Why the Cold Turkey Blocker executable could not be started, even after retrying
+#[derive(Debug)]
+pub struct SpawnError {
+  pub attempts: u32,
+  pub source: io::Error,
+}
+
+impl SpawnError {
+  pub fn code(&self) -> ErrorCode {
+    if self.source.kind() == io::ErrorKind::NotFound {
+      ErrorCode::BlockerNotFound
+    } else {
+      ErrorCode::BlockerFailed
+    }
+  }
+}
+
+impl fmt::Display for SpawnError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.attempts == 1 {
+      write!(f, "{}", self.source)
+    } else {
+      write!(f, "{} (tried {} times)", self.source, self.attempts)
+    }
+  }
+}
+
+/// Spawns the blocker with the given arguments, retrying with a doubling delay
+/// since the first spawn can fail while an antivirus scan or elevation prompt is in the way
+fn spawn(config: &Config, args: &[&str]) -> Result<Child, SpawnError> {
+  let max_attempts = config.spawn_attempts();
+  let mut delay = config.spawn_retry_delay();
+  let mut attempts = 1;
+
+  loop {
+    match Command::new(config.blocker_path()).args(args).spawn() {
+      Ok(child) => return Ok(child),
+      // No amount of waiting makes a missing executable appear
+      Err(source) if source.kind() == io::ErrorKind::NotFound || attempts >= max_attempts => {
+        return Err(SpawnError { attempts, source });
+      }
+      Err(_) => {
+        thread::sleep(delay);
+        delay *= 2;
+        attempts += 1;
+      }
+    }
+  }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+use std::net::IpAddr;
+
+/// Fetches the blocklist at `url` and reads the websites in it
+pub fn fetch(url: &str) -> Result<Vec<String>, String> {
+  let text = reqwest::blocking::get(url)
+    .and_then(|response| response.error_for_status())
+    .and_then(|response| response.text())
+    .map_err(|err| format!("cannot fetch {url}: {err}"))?;
+  let sites = parse(&text);
+  if sites.is_empty() {
+    return Err(format!("{url} lists no websites"));
+  }
+  Ok(sites)
+}
+
+/// The websites in a blocklist with one on each line, either on its own or after the
+/// address it is sent to, as in a hosts file. Comments are skipped, and each website is
+/// kept once, where it is first listed
+pub fn parse(text: &str) -> Vec<String> {
+  let mut seen = HashSet::new();
+  text
+    .lines()
+    .filter_map(|line| {
+      let line = line.split('#').next().unwrap_or_default().trim();
+      // Adblock lists start comments and headers with these
+      if line.starts_with('!') || line.starts_with('[') {
+        return None;
+      }
+      let mut words = line.split_whitespace();
+      let first = words.next()?;
+      let site = match first.parse::<IpAddr>() {
+        Ok(_) => words.next()?,
+        Err(_) => first,
+      };
+      Some(site.trim_end_matches('.').to_lowercase())
+    })
+    // Hosts files also name the machine itself, like localhost, which is no website
+    .filter(|site| site.contains('.') && site.parse::<IpAddr>().is_err())
+    .filter(|site| site != "localhost.localdomain")
+    .filter(|site| seen.insert(site.clone()))
+    .collect()
+}
+
+/// What a blocklist added and dropped since it was last applied
+pub struct Changes {
+  pub added: Vec<String>,
+  pub removed: Vec<String>,
+}
+
+pub fn changes(previous: &[String], latest: &[String]) -> Changes {
+  let previous_set: HashSet<&String> = previous.iter().collect();
+  let latest_set: HashSet<&String> = latest.iter().collect();
+  Changes {
+    added: latest
+      .iter()
+      .filter(|site| !previous_set.contains(site))
+      .cloned()
+      .collect(),
+    removed: previous
+      .iter()
+      .filter(|site| !latest_set.contains(site))
+      .cloned()
+      .collect(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testutil::strings;
+
+  #[test]
+  fn plain_lists_have_a_website_on_each_line() {
+    let list = "# Social media\n\
+                facebook.com\n\
+                \n\
+                  Twitter.com.  \n\
+                reddit.com # the front page too\n";
+    assert_eq!(parse(list), ["facebook.com", "twitter.com", "reddit.com"]);
+  }
+
+  #[test]
+  fn hosts_files_give_the_website_after_the_address() {
+    let hosts = "127.0.0.1 localhost\n\
+                 127.0.0.1 localhost.localdomain\n\
+                 ::1 localhost\n\
+                 255.255.255.255 broadcasthost\n\
+                 0.0.0.0 0.0.0.0\n\
+                 0.0.0.0 ads.example.com\n\
+                 127.0.0.1\ttracker.example.net  # tracks you\n";
+    assert_eq!(parse(hosts), ["ads.example.com", "tracker.example.net"]);
+  }
+
+  #[test]
+  fn adblock_comments_and_headers_are_skipped() {
+    let list = "[Adblock Plus 2.0]\n! Title: Example\nexample.com";
+    assert_eq!(parse(list), ["example.com"]);
+  }
+
+  #[test]
+  fn websites_listed_twice_are_kept_once() {
+    let list = "example.com\n0.0.0.0 Example.com\nexample.org\nexample.com";
+    assert_eq!(parse(list), ["example.com", "example.org"]);
+  }
+
+  #[test]
+  fn lists_without_websites_are_empty() {
+    assert!(parse("").is_empty());
+    assert!(parse("# nothing yet\n127.0.0.1 localhost\n").is_empty());
+  }
+
+  #[test]
+  fn new_websites_are_added_and_dropped_ones_removed() {
+    let previous = strings(&["a.com", "b.com", "c.com"]);
+    let latest = strings(&["c.com", "d.com", "a.com", "e.com"]);
+    let changes = changes(&previous, &latest);
+    assert_eq!(changes.added, ["d.com", "e.com"]);
+    assert_eq!(changes.removed, ["b.com"]);
+  }
+
+  #[test]
+  fn the_same_list_changes_nothing() {
+    let list = strings(&["a.com", "b.com"]);
+    let reordered = strings(&["b.com", "a.com"]);
+    let changes = changes(&list, &reordered);
+    assert!(changes.added.is_empty() && changes.removed.is_empty());
+  }
+
+  #[test]
+  fn a_first_list_adds_everything() {
+    let latest = strings(&["a.com", "b.com"]);
+    let changes = changes(&[], &latest);
+    assert_eq!(changes.added, latest);
+    assert!(changes.removed.is_empty());
+  }
+}
@@ -0,0 +1,52 @@
+use colour::e_yellow_ln;
+use std::collections::BTreeMap;
+
+const GROUP_SEPARATOR: char = '/';
+
+/// Blocks nested by their profile and name prefixes, so "Work/Email" and "Work/Social" both sit under "Work"
+#[derive(Default)]
+pub struct BlockTree<'a> {
+  children: BTreeMap<&'a str, BlockTree<'a>>,
+  // Some if a block has exactly this name, holding whether it is inactive
+  inactive: Option<bool>,
+}
+
+impl<'a> BlockTree<'a> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn insert(&mut self, profile: Option<&'a str>, block_name: &'a str, inactive: bool) {
+    let mut node = self;
+    if let Some(profile) = profile {
+      node = node.children.entry(profile).or_default();
+    }
+    for part in block_name.split(GROUP_SEPARATOR) {
+      node = node.children.entry(part).or_default();
+    }
+    node.inactive = Some(inactive);
+  }
+
+  pub fn print(&self) {
+    self.print_with_depth(0);
+  }
+
+  fn print_with_depth(&self, depth: usize) {
+    let indent = "  ".repeat(depth);
+    for (name, child) in &self.children {
+      let label = if child.children.is_empty() {
+        name.to_string()
+      } else {
+        format!("{}{}", name, GROUP_SEPARATOR)
+      };
+
+      match child.inactive {
+        Some(false) => {
+          e_yellow_ln!("{}* {} *", indent, label);
+        }
+        _ => println!("{}{}", indent, label),
+      }
+      child.print_with_depth(depth + 1);
+    }
+  }
+}
@@ -0,0 +1,67 @@
+use crate::blocker::{self, BlockerCommand, BlockerError};
+use crate::config::Config;
+use ctk_common::blocksettings::{AppString, BlockSettings, LockMethod, SchedType};
+use ctk_common::ctsettings::BlockInfo;
+use rand::Rng;
+use serde::Serialize;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Rebuilds the .ctbbl settings of a block from what Cold Turkey reports about it.
+///
+/// Cold Turkey only reports websites, exceptions, window titles, whether the block is
+/// scheduled and some lock details, so everything else (apps) is left at its default. Settings ctk does not
+/// recognize are carried over as they are, so newer Cold Turkey settings are not lost.
+pub fn export_block(info: &BlockInfo) -> BlockSettings {
+  let mut block_settings = BlockSettings::new();
+  block_settings.unknown_fields = info
+    .unknown_fields
+    .iter()
+    .map(|(key, value)| (key.clone(), value.clone()))
+    .collect();
+  block_settings.web = info.block_list.clone();
+  block_settings.exceptions = info.exception_list.clone();
+  block_settings.apps = info
+    .title_list
+    .iter()
+    .cloned()
+    .map(AppString::Title)
+    .collect();
+
+  // Without these, re-importing a scheduled or window-locked block would make it a
+  // continuous, unlocked one
+  if info.schedule_type.as_deref() == Some("scheduled") {
+    block_settings.sched_type = SchedType::Scheduled;
+  }
+  if !info.password.is_empty() {
+    block_settings.lock = LockMethod::Password;
+    block_settings.password = info.password.clone();
+  } else if let Some(length) = info.random_text_length {
+    block_settings.lock = LockMethod::RandomText;
+    block_settings.random_text_length = u16::try_from(length).unwrap_or(u16::MAX);
+  } else {
+    block_settings.lock = match info.lock.as_deref() {
+      Some("window") => LockMethod::Window,
+      Some("restart") => LockMethod::Restart,
+      Some("schedule") => LockMethod::Schedule,
+      _ => LockMethod::None,
+    };
+  }
+
+  block_settings
+}
+
+/// Writes the blocks, keyed by name, to a new .ctbbl file in the temporary directory
+pub fn write_temp_ctbbl<T: Serialize>(blocks: &T) -> io::Result<PathBuf> {
+  let num: u64 = rand::thread_rng().gen();
+  let path = std::env::temp_dir().join(format!("ctk_{}.ctbbl", num));
+  let file = File::create(&path)?;
+  serde_json::to_writer_pretty(file, blocks)?;
+  Ok(path)
+}
+
+/// Hands a .ctbbl file to Cold Turkey, which asks the user to confirm the import
+pub fn import_ctbbl(config: &Config, path: &Path) -> Result<(), BlockerError> {
+  blocker::run(config, BlockerCommand::Import { path })
+}
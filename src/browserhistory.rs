@@ -0,0 +1,136 @@
+use ctk_common::ctsettings::ColdTurkeySettings;
+use rand::Rng;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// How many of the most visited websites are offered
+pub const CANDIDATES: usize = 20;
+
+#[derive(Debug, Clone, Copy)]
+enum Browser {
+  Chrome,
+  Edge,
+  Firefox,
+}
+
+impl Browser {
+  const ALL: [Browser; 3] = [Self::Chrome, Self::Edge, Self::Firefox];
+
+  /// The history databases of each of the browser's profiles
+  fn history_files(self) -> Vec<PathBuf> {
+    let (base, pattern) = match self {
+      Self::Chrome if cfg!(windows) => {
+        (dirs::data_local_dir(), "Google/Chrome/User Data/*/History")
+      }
+      Self::Chrome => (dirs::data_local_dir(), "Google/Chrome/*/History"),
+      Self::Edge if cfg!(windows) => (dirs::data_local_dir(), "Microsoft/Edge/User Data/*/History"),
+      Self::Edge => (dirs::data_local_dir(), "Microsoft Edge/*/History"),
+      Self::Firefox if cfg!(windows) => {
+        (dirs::data_dir(), "Mozilla/Firefox/Profiles/*/places.sqlite")
+      }
+      Self::Firefox => (dirs::data_dir(), "Firefox/Profiles/*/places.sqlite"),
+    };
+    let Some(base) = base else {
+      return Vec::new();
+    };
+    let pattern = format!(
+      "{}/{pattern}",
+      glob::Pattern::escape(&base.to_string_lossy())
+    );
+    glob::glob(&pattern)
+      .map(|paths| paths.flatten().collect())
+      .unwrap_or_default()
+  }
+
+  fn visits_query(self) -> &'static str {
+    match self {
+      Self::Chrome | Self::Edge => "SELECT url, visit_count FROM urls WHERE visit_count > 0",
+      Self::Firefox => "SELECT url, visit_count FROM moz_places WHERE visit_count > 0",
+    }
+  }
+}
+
+/// Reads how many times each url was visited from a copy of the history, since browsers
+/// lock it while they are open. Nothing read is kept or sent anywhere
+fn read_visits(browser: Browser, history: &Path) -> Result<Vec<(String, i64)>, String> {
+  let num: u32 = rand::thread_rng().gen();
+  let copy = env::temp_dir().join(format!("ctk_history_{num}.sqlite"));
+  let copy_wal = env::temp_dir().join(format!("ctk_history_{num}.sqlite-wal"));
+  fs::copy(history, &copy).map_err(|err| err.to_string())?;
+  // Firefox keeps its latest visits in a write-ahead log next to the database
+  let wal = history.with_extension("sqlite-wal");
+  if matches!(browser, Browser::Firefox) && wal.exists() {
+    fs::copy(wal, &copy_wal).map_err(|err| err.to_string())?;
+  }
+
+  let visits = query_visits(&copy, browser.visits_query()).map_err(|err| err.to_string());
+  let _ = fs::remove_file(&copy);
+  let _ = fs::remove_file(&copy_wal);
+  visits
+}
+
+fn query_visits(database: &Path, query: &str) -> rusqlite::Result<Vec<(String, i64)>> {
+  let connection = Connection::open(database)?;
+  let mut statement = connection.prepare(query)?;
+  let visits = statement
+    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+    .collect();
+  visits
+}
+
+/// The website a url is on, without "www.", if it is a website that can be blocked
+fn site_of(url: &str) -> Option<String> {
+  let url = Url::parse(url).ok()?;
+  if !matches!(url.scheme(), "http" | "https") {
+    return None;
+  }
+  // Addresses like 192.168.0.1 have no domain
+  let domain = url.domain().filter(|domain| *domain != "localhost")?;
+  Some(domain.strip_prefix("www.").unwrap_or(domain).to_string())
+}
+
+/// The `count` websites visited most in Chrome, Edge and Firefox that none of the blocks
+/// cover yet, with how many times each was visited, most visited first
+pub fn unblocked_favorites(
+  settings: Option<&ColdTurkeySettings>,
+  count: usize,
+) -> Vec<(String, u64)> {
+  let mut visits: HashMap<String, u64> = HashMap::new();
+  for browser in Browser::ALL {
+    for history in browser.history_files() {
+      match read_visits(browser, &history) {
+        Ok(rows) => {
+          for (url, times) in rows {
+            if let Some(site) = site_of(&url) {
+              *visits.entry(site).or_default() += u64::try_from(times).unwrap_or(0);
+            }
+          }
+        }
+        Err(why) => eprintln!("Cannot read the history in {}: {why}", history.display()),
+      }
+    }
+  }
+
+  let blocked = |site: &str| {
+    settings.is_some_and(|settings| {
+      settings
+        .block_list_info
+        .blocks
+        .values()
+        .any(|info| info.blocks_site(site))
+    })
+  };
+  let mut sites: Vec<(String, u64)> = visits
+    .into_iter()
+    .filter(|(site, _)| !blocked(site))
+    .collect();
+  sites.sort_unstable_by(|(a_site, a_times), (b_site, b_times)| {
+    b_times.cmp(a_times).then_with(|| a_site.cmp(b_site))
+  });
+  sites.truncate(count);
+  sites
+}
@@ -0,0 +1,62 @@
+use crate::config::Config;
+use crate::error::ErrorCode;
+use ctk_common::ctsettings;
+use std::path::PathBuf;
+
+/// What of Cold Turkey a command needs to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+  /// Only works on files, so it runs without Cold Turkey
+  Nothing,
+  /// Reads blocks or settings through Cold Turkey's messaging host
+  Settings,
+  /// Runs Cold Turkey Blocker to start, stop or change blocks
+  Blocker,
+}
+
+/// The parts of Cold Turkey ctk can find on this machine
+pub struct Capabilities {
+  pub blocker_path: PathBuf,
+  pub blocker_found: bool,
+  pub msg_host_path: PathBuf,
+  pub msg_host_found: bool,
+}
+
+impl Capabilities {
+  pub fn detect(config: &Config) -> Self {
+    let blocker_path = config.blocker_path();
+    let msg_host_path = ctsettings::msg_host_path().to_path_buf();
+    Self {
+      blocker_found: blocker_path.is_file(),
+      blocker_path,
+      msg_host_found: msg_host_path.is_file(),
+      msg_host_path,
+    }
+  }
+
+  /// Whether Cold Turkey cannot be found at all, so only the commands working on files are left
+  pub fn is_degraded(&self) -> bool {
+    !self.blocker_found && !self.msg_host_found
+  }
+
+  /// Why a command with this requirement cannot run here, if it cannot
+  pub fn check(&self, requirement: Requirement) -> Result<(), (ErrorCode, String)> {
+    match requirement {
+      Requirement::Blocker if !self.blocker_found => Err((
+        ErrorCode::BlockerNotFound,
+        format!(
+          "Cold Turkey Blocker cannot be found at {}. Is it installed? `ctk doctor` shows what works without it",
+          self.blocker_path.display()
+        ),
+      )),
+      Requirement::Settings if !self.msg_host_found => Err((
+        ErrorCode::SettingsUnreadable,
+        format!(
+          "Cold Turkey's messaging host cannot be found at {}. Is Cold Turkey installed? `ctk doctor` shows what works without it",
+          self.msg_host_path.display()
+        ),
+      )),
+      _ => Ok(()),
+    }
+  }
+}
@@ -0,0 +1,268 @@
+use crate::blocklist;
+use crate::config;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What a website entry names a category with, like "@category:video"
+pub const PREFIX: &str = "@category:";
+
+/// A list of websites of one kind, bundled with ctk
+struct Bundled {
+  name: &'static str,
+  sites: &'static [&'static str],
+}
+
+const BUNDLED: [Bundled; 5] = [
+  Bundled {
+    name: "social",
+    sites: &[
+      "facebook.com",
+      "instagram.com",
+      "twitter.com",
+      "x.com",
+      "threads.net",
+      "bsky.app",
+      "tiktok.com",
+      "reddit.com",
+      "snapchat.com",
+      "pinterest.com",
+      "tumblr.com",
+      "linkedin.com",
+      "discord.com",
+      "mastodon.social",
+    ],
+  },
+  Bundled {
+    name: "video",
+    sites: &[
+      "youtube.com",
+      "twitch.tv",
+      "netflix.com",
+      "disneyplus.com",
+      "hulu.com",
+      "primevideo.com",
+      "max.com",
+      "vimeo.com",
+      "dailymotion.com",
+      "kick.com",
+      "crunchyroll.com",
+    ],
+  },
+  Bundled {
+    name: "news",
+    sites: &[
+      "news.google.com",
+      "cnn.com",
+      "bbc.com",
+      "bbc.co.uk",
+      "nytimes.com",
+      "theguardian.com",
+      "foxnews.com",
+      "washingtonpost.com",
+      "reuters.com",
+      "apnews.com",
+      "bloomberg.com",
+      "news.ycombinator.com",
+      "news.yahoo.com",
+    ],
+  },
+  Bundled {
+    name: "shopping",
+    sites: &[
+      "amazon.com",
+      "ebay.com",
+      "etsy.com",
+      "aliexpress.com",
+      "temu.com",
+      "shein.com",
+      "walmart.com",
+      "target.com",
+      "bestbuy.com",
+      "wish.com",
+    ],
+  },
+  Bundled {
+    name: "adult",
+    sites: &[
+      "pornhub.com",
+      "xvideos.com",
+      "xnxx.com",
+      "xhamster.com",
+      "redtube.com",
+      "youporn.com",
+      "spankbang.com",
+      "onlyfans.com",
+      "chaturbate.com",
+      "stripchat.com",
+    ],
+  },
+];
+
+/// Where your own category lists go, as `<category>.txt` in plain text or hosts file form.
+/// One with the name of a bundled category replaces it
+pub fn categories_dir() -> Option<PathBuf> {
+  config::config_dir().map(|dir| dir.join("categories"))
+}
+
+/// Your own lists in `dir`, by category name. Names are lowercase, like the ones in
+/// `@category:<name>`, whatever case the file has
+fn own_lists(dir: Option<&Path>) -> Vec<(String, PathBuf)> {
+  let entries = dir.and_then(|dir| fs::read_dir(dir).ok());
+  entries
+    .into_iter()
+    .flatten()
+    .flatten()
+    .map(|entry| entry.path())
+    .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+    .filter_map(|path| {
+      let name = path.file_stem()?.to_string_lossy().to_lowercase();
+      Some((name, path))
+    })
+    .collect()
+}
+
+/// Every category, bundled or your own in `dir`, in alphabetical order
+fn names(dir: Option<&Path>) -> Vec<String> {
+  let mut names: BTreeSet<String> = BUNDLED
+    .iter()
+    .map(|category| category.name.to_string())
+    .collect();
+  names.extend(own_lists(dir).into_iter().map(|(name, _)| name));
+  names.into_iter().collect()
+}
+
+/// The websites in the category as it is now, if there is one by that name, taking your
+/// own list in `dir` over a bundled one
+fn sites(dir: Option<&Path>, name: &str) -> Option<Vec<String>> {
+  let own = own_lists(dir)
+    .into_iter()
+    .find(|(own_name, _)| own_name == name)
+    .and_then(|(_, path)| fs::read_to_string(path).ok());
+  match own {
+    Some(text) => Some(blocklist::parse(&text)),
+    None => BUNDLED
+      .iter()
+      .find(|category| category.name == name)
+      .map(|category| category.sites.iter().map(|site| site.to_string()).collect()),
+  }
+}
+
+/// What a category entry stood for when it was expanded
+pub struct Expansion {
+  pub category: String,
+  pub sites: Vec<String>,
+}
+
+/// Replaces every `@category:<name>` entry with the websites the category has now, keeping
+/// the other entries as they are. Each website is kept once, where it first comes
+pub fn expand(entries: &[String]) -> Result<(Vec<String>, Vec<Expansion>), String> {
+  expand_in(categories_dir().as_deref(), entries)
+}
+
+fn expand_in(
+  dir: Option<&Path>,
+  entries: &[String],
+) -> Result<(Vec<String>, Vec<Expansion>), String> {
+  let mut expanded: Vec<String> = Vec::new();
+  let mut expansions = Vec::new();
+  for entry in entries {
+    let Some(name) = entry.strip_prefix(PREFIX) else {
+      if !expanded.contains(entry) {
+        expanded.push(entry.clone());
+      }
+      continue;
+    };
+    let name = name.trim().to_lowercase();
+    let sites = sites(dir, &name).ok_or_else(|| {
+      format!(
+        "there is no category {name}. The categories are {}",
+        names(dir).join(", ")
+      )
+    })?;
+    for site in &sites {
+      if !expanded.contains(site) {
+        expanded.push(site.clone());
+      }
+    }
+    expansions.push(Expansion {
+      category: name,
+      sites,
+    });
+  }
+  Ok((expanded, expansions))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testutil::{strings, TempDir};
+
+  /// A categories directory of its own with these lists in it
+  fn categories_with(name: &str, lists: &[(&str, &str)]) -> TempDir {
+    let dir = TempDir::new(&format!("categories_{name}"));
+    for (list, contents) in lists {
+      fs::write(dir.path().join(list), contents).unwrap();
+    }
+    dir
+  }
+
+  #[test]
+  fn categories_expand_to_the_bundled_list() {
+    let (expanded, expansions) = expand_in(None, &strings(&["@category:video"])).unwrap();
+    assert_eq!(expanded.len(), 11);
+    assert_eq!(expanded[0], "youtube.com");
+    assert_eq!(expansions.len(), 1);
+    assert_eq!(expansions[0].category, "video");
+    assert_eq!(expansions[0].sites, expanded);
+  }
+
+  #[test]
+  fn other_entries_are_kept_once_around_categories() {
+    let (expanded, _) = expand_in(
+      None,
+      &strings(&[
+        "example.com",
+        "twitch.tv",
+        "@category: Video ",
+        "example.com",
+      ]),
+    )
+    .unwrap();
+    assert_eq!(&expanded[..3], ["example.com", "twitch.tv", "youtube.com"]);
+    assert_eq!(expanded.len(), 12);
+  }
+
+  #[test]
+  fn your_own_list_adds_a_category() {
+    let dir = categories_with(
+      "added",
+      &[(
+        "Games.txt",
+        "# Games\nsteampowered.com\n0.0.0.0 roblox.com\n",
+      )],
+    );
+    assert!(names(Some(dir.path())).contains(&"games".to_string()));
+    assert!(names(Some(dir.path())).contains(&"video".to_string()));
+    let (expanded, _) = expand_in(Some(dir.path()), &strings(&["@category:games"])).unwrap();
+    assert_eq!(expanded, ["steampowered.com", "roblox.com"]);
+  }
+
+  #[test]
+  fn your_own_list_replaces_a_bundled_one() {
+    let dir = categories_with("replaced", &[("video.txt", "youtube.com\nnebula.tv\n")]);
+    let (expanded, _) = expand_in(Some(dir.path()), &strings(&["@category:video"])).unwrap();
+    assert_eq!(expanded, ["youtube.com", "nebula.tv"]);
+    let social = sites(Some(dir.path()), "social").unwrap();
+    assert_eq!(social[0], "facebook.com");
+  }
+
+  #[test]
+  fn unknown_categories_are_refused() {
+    let Err(err) = expand_in(None, &strings(&["example.com", "@category:games"])) else {
+      panic!("games is no bundled category");
+    };
+    assert!(err.starts_with("there is no category games."));
+    assert!(err.ends_with("adult, news, shopping, social, video"));
+  }
+}
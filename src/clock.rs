@@ -0,0 +1,287 @@
+use chrono::{DateTime, Local, LocalResult, NaiveDate, NaiveTime, TimeZone};
+
+/// Where the commands get the current time from, so the time logic can be run at any
+/// moment in any time zone
+pub trait Clock {
+  type Tz: TimeZone;
+
+  fn now(&self) -> DateTime<Self::Tz>;
+}
+
+/// The clock of this computer, in its own time zone
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+  type Tz = Local;
+
+  fn now(&self) -> DateTime<Local> {
+    Local::now()
+  }
+}
+
+/// When `endtime` (on `enddate`, or today without one) is in the clock's time zone
+pub fn end_datetime<C: Clock>(
+  clock: &C,
+  endtime: NaiveTime,
+  enddate: Option<NaiveDate>,
+) -> Result<DateTime<C::Tz>, &'static str> {
+  let now = clock.now();
+  let date = enddate.unwrap_or_else(|| now.date_naive());
+  match (
+    now.timezone().from_local_datetime(&date.and_time(endtime)),
+    enddate,
+  ) {
+    (LocalResult::Single(datetime), _) => Ok(datetime),
+    (LocalResult::None, Some(_)) => Err("Can't get the datetime specified."),
+    (LocalResult::Ambiguous(_, _), Some(_)) => {
+      Err("Datetime given is ambiguous. Maybe try to be more clear in your time?")
+    }
+    (_, None) => {
+      Err("The date is assumed to be today, however, the time given seems to make it invalid.")
+    }
+  }
+}
+
+/// Whole minutes from now until `datetime`, rounded up so a lock never ends early
+pub fn minutes_until<C: Clock>(clock: &C, datetime: &DateTime<C::Tz>) -> i64 {
+  let duration = datetime.clone().signed_duration_since(clock.now());
+  // If duration is exactly a multiple of 60, do not round up
+  if duration.num_seconds() % 60 == 0 {
+    duration.num_minutes()
+  } else {
+    duration.num_minutes() + 1
+  }
+}
+
+/// When a lock until `endtime` ends, and how many minutes that is from now. Times in the
+/// past are refused, since Cold Turkey cannot lock for them
+pub fn lock_until<C: Clock>(
+  clock: &C,
+  endtime: NaiveTime,
+  enddate: Option<NaiveDate>,
+) -> Result<(DateTime<C::Tz>, i64), &'static str> {
+  let datetime = end_datetime(clock, endtime, enddate)?;
+  let minutes = minutes_until(clock, &datetime);
+  if minutes <= 0 {
+    return Err("Cannot start block until a time in the past. Please enter a time in the future.");
+  }
+  Ok((datetime, minutes))
+}
+
+/// Spells out a number of minutes, like "2 days, 1 hour and 5 minutes"
+pub fn describe_minutes(minutes: i64) -> String {
+  let parts: Vec<String> = [
+    (minutes / (24 * 60), "day"),
+    (minutes / 60 % 24, "hour"),
+    (minutes % 60, "minute"),
+  ]
+  .into_iter()
+  .filter(|(count, _)| *count > 0)
+  .map(|(count, unit)| format!("{count} {unit}{}", if count == 1 { "" } else { "s" }))
+  .collect();
+  match parts.as_slice() {
+    [] => "0 minutes".to_string(),
+    [only] => only.clone(),
+    [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::{Duration, FixedOffset, NaiveDateTime};
+
+  /// New York in 2024: clocks go forward at 2:00 on March 10 and back at 2:00 on November 3
+  #[derive(Clone, Copy)]
+  struct NewYork2024;
+
+  const HOUR: i32 = 3600;
+
+  fn standard() -> FixedOffset {
+    FixedOffset::west_opt(5 * HOUR).unwrap()
+  }
+
+  fn daylight() -> FixedOffset {
+    FixedOffset::west_opt(4 * HOUR).unwrap()
+  }
+
+  fn utc(date: (i32, u32, u32), time: (u32, u32)) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(date.0, date.1, date.2)
+      .unwrap()
+      .and_hms_opt(time.0, time.1, 0)
+      .unwrap()
+  }
+
+  impl TimeZone for NewYork2024 {
+    type Offset = FixedOffset;
+
+    fn from_offset(_: &FixedOffset) -> Self {
+      NewYork2024
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+      self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+      let fits = |offset: FixedOffset| {
+        let utc = *local - Duration::seconds(offset.local_minus_utc().into());
+        self.offset_from_utc_datetime(&utc) == offset
+      };
+      match (fits(daylight()), fits(standard())) {
+        (true, true) => LocalResult::Ambiguous(daylight(), standard()),
+        (true, false) => LocalResult::Single(daylight()),
+        (false, true) => LocalResult::Single(standard()),
+        (false, false) => LocalResult::None,
+      }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+      self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+    }
+
+    fn offset_from_utc_datetime(&self, at: &NaiveDateTime) -> FixedOffset {
+      if *at >= utc((2024, 3, 10), (7, 0)) && *at < utc((2024, 11, 3), (6, 0)) {
+        daylight()
+      } else {
+        standard()
+      }
+    }
+  }
+
+  struct MockClock(DateTime<NewYork2024>);
+
+  impl MockClock {
+    /// A clock stopped at this local time in New York
+    fn at(date: (i32, u32, u32), time: (u32, u32, u32)) -> Self {
+      let local = NaiveDate::from_ymd_opt(date.0, date.1, date.2)
+        .unwrap()
+        .and_hms_opt(time.0, time.1, time.2)
+        .unwrap();
+      Self(NewYork2024.from_local_datetime(&local).earliest().unwrap())
+    }
+  }
+
+  impl Clock for MockClock {
+    type Tz = NewYork2024;
+
+    fn now(&self) -> DateTime<NewYork2024> {
+      self.0
+    }
+  }
+
+  fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+  }
+
+  fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, day)
+  }
+
+  #[test]
+  fn exact_minutes_are_not_rounded() {
+    let clock = MockClock::at((2024, 6, 1), (9, 0, 0));
+    assert_eq!(lock_until(&clock, time(10, 0), None).unwrap().1, 60);
+  }
+
+  #[test]
+  fn part_minutes_are_rounded_up() {
+    let clock = MockClock::at((2024, 6, 1), (9, 0, 30));
+    assert_eq!(lock_until(&clock, time(10, 0), None).unwrap().1, 60);
+    let clock = MockClock::at((2024, 6, 1), (9, 59, 59));
+    assert_eq!(lock_until(&clock, time(10, 0), None).unwrap().1, 1);
+  }
+
+  #[test]
+  fn past_times_are_refused() {
+    let clock = MockClock::at((2024, 6, 1), (9, 0, 0));
+    assert!(lock_until(&clock, time(8, 0), None).is_err());
+    assert!(lock_until(&clock, time(9, 0), None).is_err());
+    assert!(lock_until(&clock, time(10, 0), date(2024, 5, 31)).is_err());
+  }
+
+  #[test]
+  fn a_time_without_a_date_is_today() {
+    let clock = MockClock::at((2024, 6, 1), (9, 0, 0));
+    let (end, _) = lock_until(&clock, time(17, 30), None).unwrap();
+    assert_eq!(end.date_naive(), date(2024, 6, 1).unwrap());
+  }
+
+  #[test]
+  fn midnight_without_a_date_is_the_midnight_already_past() {
+    let clock = MockClock::at((2024, 6, 1), (23, 50, 0));
+    assert!(lock_until(&clock, time(0, 0), None).is_err());
+  }
+
+  #[test]
+  fn midnight_tomorrow_rolls_over_the_date() {
+    let clock = MockClock::at((2024, 6, 1), (23, 50, 0));
+    assert_eq!(
+      lock_until(&clock, time(0, 0), date(2024, 6, 2)).unwrap().1,
+      10
+    );
+    assert_eq!(
+      lock_until(&clock, time(0, 30), date(2024, 6, 2)).unwrap().1,
+      40
+    );
+  }
+
+  #[test]
+  fn a_lock_over_new_year_rolls_over_the_year() {
+    let clock = MockClock::at((2024, 12, 31), (23, 0, 0));
+    assert_eq!(
+      lock_until(&clock, time(1, 0), date(2025, 1, 1)).unwrap().1,
+      120
+    );
+  }
+
+  #[test]
+  fn times_skipped_by_spring_forward_are_refused() {
+    let clock = MockClock::at((2024, 3, 9), (12, 0, 0));
+    assert_eq!(
+      end_datetime(&clock, time(2, 30), date(2024, 3, 10)),
+      Err("Can't get the datetime specified.")
+    );
+    let clock = MockClock::at((2024, 3, 10), (1, 0, 0));
+    assert!(end_datetime(&clock, time(2, 30), None).is_err());
+  }
+
+  #[test]
+  fn locks_over_spring_forward_last_the_real_time() {
+    // 1:00 to 3:00 on the clock is only an hour, since 2:00 to 3:00 is skipped
+    let clock = MockClock::at((2024, 3, 10), (1, 0, 0));
+    assert_eq!(lock_until(&clock, time(3, 0), None).unwrap().1, 60);
+  }
+
+  #[test]
+  fn times_repeated_by_fall_back_are_ambiguous() {
+    let clock = MockClock::at((2024, 11, 2), (12, 0, 0));
+    assert_eq!(
+      end_datetime(&clock, time(1, 30), date(2024, 11, 3)),
+      Err("Datetime given is ambiguous. Maybe try to be more clear in your time?")
+    );
+    let clock = MockClock::at((2024, 11, 3), (0, 30, 0));
+    assert!(end_datetime(&clock, time(1, 30), None).is_err());
+  }
+
+  #[test]
+  fn locks_over_fall_back_last_the_real_time() {
+    // 0:00 to 3:00 on the clock is four hours, since 1:00 to 2:00 happens twice
+    let clock = MockClock::at((2024, 11, 3), (0, 0, 0));
+    assert_eq!(lock_until(&clock, time(3, 0), None).unwrap().1, 240);
+  }
+
+  #[test]
+  fn minutes_are_spelled_out() {
+    assert_eq!(describe_minutes(5), "5 minutes");
+    assert_eq!(describe_minutes(60), "1 hour");
+    assert_eq!(
+      describe_minutes(24 * 60 + 65),
+      "1 day, 1 hour and 5 minutes"
+    );
+    assert_eq!(
+      describe_minutes(21 * 24 * 60 + 20),
+      "21 days and 20 minutes"
+    );
+  }
+}
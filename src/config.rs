@@ -0,0 +1,1012 @@
+use crate::journal;
+use crate::platform;
+use crate::protocol;
+use ctk_common::{convert, ctsettings};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+use toml::{Table, Value};
+
+const CONFIG_DIR_NAME: &str = "ctk";
+const CONFIG_FILE_NAME: &str = "config.toml";
+// Environment variables overriding a setting are this followed by the key in capitals, with
+// a table's entries named after two underscores, like CTK_PROFILES__Work
+const ENV_PREFIX: &str = "CTK_";
+// Points ctk to another user config file, like one mounted into a container
+const CONFIG_PATH_VAR: &str = "CTK_CONFIG";
+const SET_FLAG: &str = "--set";
+
+pub const CT_EXEC: &str = r"C:\Program Files\Cold Turkey\Cold Turkey Blocker.exe";
+const MSG_HOST_FILE_NAME: &str = "CTMsgHostEdge.exe";
+
+const DEFAULT_SPAWN_ATTEMPTS: u32 = 3;
+const DEFAULT_SPAWN_RETRY_DELAY: Duration = Duration::from_millis(500);
+const DEFAULT_STREAK_GOAL: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_IDLE_AFTER: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_CONFIRM_LOCK_OVER: Duration = Duration::from_secs(24 * 60 * 60);
+const DEFAULT_VERIFY_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_VERIFY_INTERVAL: Duration = Duration::from_millis(250);
+const DEFAULT_BLOCKER_TIMEOUT: Duration = Duration::from_secs(30);
+// The installer waits for someone to click through it
+const DEFAULT_INSTALLER_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+// Nobody scrolls through more matches than this
+const DEFAULT_SEARCH_RESULTS: usize = 1000;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ValueKind {
+  /// A path to a file that must exist
+  Path,
+  /// A whole number above zero
+  Count,
+  /// A duration like 500ms, 30s or 1h30m
+  Duration,
+  /// A comma-separated list of block names
+  BlockList,
+  /// Any text, like a block name or a command
+  Text,
+  /// true or false
+  Flag,
+  /// A Cold Turkey release, like 4.2
+  Version,
+  /// A comma-separated list of what ctk:// links may do, out of add, extend, start and status
+  LinkActions,
+}
+
+pub struct ConfigKey {
+  pub name: &'static str,
+  pub kind: ValueKind,
+  pub description: &'static str,
+}
+
+pub const KEYS: [ConfigKey; 29] = [
+  ConfigKey {
+    name: "blocker_path",
+    kind: ValueKind::Path,
+    description: "Path to the Cold Turkey Blocker executable",
+  },
+  ConfigKey {
+    name: "msg_host_path",
+    kind: ValueKind::Path,
+    description: "Path to Cold Turkey's messaging host (CTMsgHostEdge.exe on Windows), which ctk reads blocks and settings from",
+  },
+  ConfigKey {
+    name: "blocker_version",
+    kind: ValueKind::Version,
+    description: "The Cold Turkey release installed, so ctk can tell when a command is too new for it (default the latest)",
+  },
+  ConfigKey {
+    name: "spawn_attempts",
+    kind: ValueKind::Count,
+    description: "How many times to try starting Cold Turkey Blocker before giving up (default 3)",
+  },
+  ConfigKey {
+    name: "spawn_retry_delay",
+    kind: ValueKind::Duration,
+    description: "How long to wait before the second try, doubling after each try (default 500ms)",
+  },
+  ConfigKey {
+    name: "verify_timeout",
+    kind: ValueKind::Duration,
+    description: "How long to wait for Cold Turkey to apply a command before reporting it failed (default 5s)",
+  },
+  ConfigKey {
+    name: "verify_interval",
+    kind: ValueKind::Duration,
+    description: "How often to read Cold Turkey's settings while waiting for a command to apply (default 250ms)",
+  },
+  ConfigKey {
+    name: "blocker_timeout",
+    kind: ValueKind::Duration,
+    description: "How long Cold Turkey Blocker gets to take a command before it is stopped, or 0s to not wait for it (default 30s)",
+  },
+  ConfigKey {
+    name: "msg_host_timeout",
+    kind: ValueKind::Duration,
+    description: "How long CTMsgHostEdge.exe gets to report the blocks and settings before it is stopped (default 10s)",
+  },
+  ConfigKey {
+    name: "installer_timeout",
+    kind: ValueKind::Duration,
+    description: "How long the Cold Turkey installer run by `ctk install` gets before it is stopped (default 30m)",
+  },
+  ConfigKey {
+    name: "profiles.<name>",
+    kind: ValueKind::BlockList,
+    description: "Blocks grouped under <name> in `ctk list --tree`",
+  },
+  ConfigKey {
+    name: "streak_block",
+    kind: ValueKind::Text,
+    description: "The block `ctk streak` tracks when no block is given",
+  },
+  ConfigKey {
+    name: "default_block",
+    kind: ValueKind::Text,
+    description: "The block `ctk quickadd` adds websites to",
+  },
+  ConfigKey {
+    name: "streak_goal",
+    kind: ValueKind::Duration,
+    description: "How long the streak block must run each day to keep the streak (default 1h)",
+  },
+  ConfigKey {
+    name: "streak_hook",
+    kind: ValueKind::Text,
+    description: "Command `ctk streak --notify` runs when the streak is at risk",
+  },
+  ConfigKey {
+    name: "sync_location",
+    kind: ValueKind::Text,
+    description: "Folder, git repository or WebDAV URL used by `ctk sync`",
+  },
+  ConfigKey {
+    name: "variables.<name>",
+    kind: ValueKind::Text,
+    description: "Value of {{<name>}} in block templates on this machine",
+  },
+  ConfigKey {
+    name: "bookmarks.<name>",
+    kind: ValueKind::Path,
+    description: "Folder `cd @<name>` goes to in the app shell of `ctk suggest`",
+  },
+  ConfigKey {
+    name: "also_start.<name>",
+    kind: ValueKind::BlockList,
+    description: "Blocks `ctk start` starts too, with the same lock, whenever it starts <name>",
+  },
+  ConfigKey {
+    name: "keep_on_while.<name>",
+    kind: ValueKind::BlockList,
+    description: "Blocks that, while any of them is running, stop ctk from stopping <name>",
+  },
+  ConfigKey {
+    name: "idle_after",
+    kind: ValueKind::Duration,
+    description: "How long without input before `ctk watch` counts you as away (default 5m)",
+  },
+  ConfigKey {
+    name: "confirm_lock_over",
+    kind: ValueKind::Duration,
+    description: "How long a `ctk start until` lock can be before ctk asks to go ahead (default 24h)",
+  },
+  ConfigKey {
+    name: "search_results",
+    kind: ValueKind::Count,
+    description: "How many of the best matches `search` in `ctk suggest` shows (default 1000)",
+  },
+  ConfigKey {
+    name: "journal_rotate_kb",
+    kind: ValueKind::Count,
+    description: "How big the journal grows, in KiB, before it is moved aside for a new one (default 1024)",
+  },
+  ConfigKey {
+    name: "journal_keep",
+    kind: ValueKind::Count,
+    description: "How many journals moved aside are kept, oldest dropped first (default 4)",
+  },
+  ConfigKey {
+    name: "link_actions",
+    kind: ValueKind::LinkActions,
+    description: "What ctk:// links may do, out of add, extend, start and status, like \"add,start,status\" (default start and status)",
+  },
+  ConfigKey {
+    name: "link_blocks",
+    kind: ValueKind::BlockList,
+    description: "The only blocks ctk:// links may add to, extend or start (default any block)",
+  },
+  ConfigKey {
+    name: "watch_toasts",
+    kind: ValueKind::Flag,
+    description: "Shows a Windows notification, with buttons to lock the block for longer or show the status, when `ctk watch` starts or stops a block",
+  },
+  ConfigKey {
+    name: "read_only",
+    kind: ValueKind::Flag,
+    description: "Turns off commands that change blocks or Cold Turkey's settings. Users cannot undo it if it is set in the system config",
+  },
+];
+
+// What ctk:// links may do unless link_actions says otherwise. Any web page can open a
+// link, so adding websites and extending locks have to be turned on
+const DEFAULT_LINK_ACTIONS: [&str; 2] = ["start", "status"];
+
+// Flags a user config cannot turn off once the system config sets them, so an admin can
+// lock ctk down on a shared machine. Turning one on is always allowed, being stricter
+const SYSTEM_ENFORCED_KEYS: [&str; 1] = ["read_only"];
+
+/// Finds the known key a (possibly dotted) key belongs to, so "profiles.Work" finds "profiles.<name>"
+pub fn find_key(key: &str) -> Option<&'static ConfigKey> {
+  KEYS.iter().find(|known| match known.name.split_once('.') {
+    Some((table, _)) => key
+      .split_once('.')
+      .is_some_and(|(key_table, name)| key_table == table && !name.is_empty()),
+    None => known.name == key,
+  })
+}
+
+fn parse_value(kind: ValueKind, raw: &str) -> Result<Value, String> {
+  match kind {
+    ValueKind::Path => {
+      if Path::new(raw).exists() {
+        Ok(Value::String(raw.to_string()))
+      } else {
+        Err(format!("{raw} does not exist"))
+      }
+    }
+    ValueKind::Count => match raw.parse::<u32>() {
+      Ok(count) if count > 0 => Ok(Value::Integer(count.into())),
+      _ => Err(format!("{raw} is not a whole number above zero")),
+    },
+    ValueKind::Duration => convert::str_to_duration(raw).map(|_| Value::String(raw.to_string())),
+    ValueKind::BlockList => Ok(Value::Array(
+      raw
+        .split(',')
+        .map(str::trim)
+        .filter(|block| !block.is_empty())
+        .map(|block| Value::String(block.to_string()))
+        .collect(),
+    )),
+    ValueKind::Text => Ok(Value::String(raw.to_string())),
+    ValueKind::Flag => match raw {
+      "true" => Ok(Value::Boolean(true)),
+      "false" => Ok(Value::Boolean(false)),
+      _ => Err(format!("{raw} is not true or false")),
+    },
+    ValueKind::Version => convert::str_to_version(raw).map(|_| Value::String(raw.to_string())),
+    ValueKind::LinkActions => raw
+      .split(',')
+      .map(str::trim)
+      .filter(|action| !action.is_empty())
+      .map(|action| match protocol::ACTIONS.contains(&action) {
+        true => Ok(Value::String(action.to_string())),
+        false => Err(format!("{action} is not add, extend, start or status")),
+      })
+      .collect::<Result<Vec<Value>, String>>()
+      .map(Value::Array),
+  }
+}
+
+fn check_value(kind: ValueKind, value: &Value) -> Result<(), String> {
+  match (kind, value) {
+    (ValueKind::Path, Value::String(path)) => parse_value(kind, path).map(|_| ()),
+    (ValueKind::Count, Value::Integer(count)) => parse_value(kind, &count.to_string()).map(|_| ()),
+    (ValueKind::Duration, Value::String(duration)) => parse_value(kind, duration).map(|_| ()),
+    (ValueKind::BlockList, Value::Array(blocks)) if blocks.iter().all(Value::is_str) => Ok(()),
+    (ValueKind::Text, Value::String(_)) => Ok(()),
+    (ValueKind::Flag, Value::Boolean(_)) => Ok(()),
+    (ValueKind::Version, Value::String(version)) => parse_value(kind, version).map(|_| ()),
+    (ValueKind::LinkActions, Value::Array(actions)) => {
+      actions.iter().try_for_each(|action| match action.as_str() {
+        Some(action) => parse_value(kind, action).map(|_| ()),
+        None => Err("expected a list of add, extend, start and status".to_string()),
+      })
+    }
+    (ValueKind::Path, _) => Err("expected a path in quotes".to_string()),
+    (ValueKind::Count, _) => Err("expected a whole number".to_string()),
+    (ValueKind::Duration, _) => Err("expected a duration in quotes, like \"500ms\"".to_string()),
+    (ValueKind::BlockList, _) => Err("expected a list of block names".to_string()),
+    (ValueKind::Text, _) => Err("expected text in quotes".to_string()),
+    (ValueKind::Flag, _) => Err("expected true or false".to_string()),
+    (ValueKind::Version, _) => Err("expected a version in quotes, like \"4.2\"".to_string()),
+    (ValueKind::LinkActions, _) => {
+      Err("expected a list of add, extend, start and status".to_string())
+    }
+  }
+}
+
+/// Where an effective config value came from
+#[derive(Debug, Clone)]
+pub enum Origin {
+  System(PathBuf),
+  User(PathBuf),
+  /// The name of the environment variable
+  Env(String),
+  Flag,
+}
+
+impl fmt::Display for Origin {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::System(path) => write!(f, "system config ({})", path.display()),
+      Self::User(path) => write!(f, "user config ({})", path.display()),
+      Self::Env(var) => write!(f, "environment variable {var}"),
+      Self::Flag => write!(f, "{SET_FLAG} flag"),
+    }
+  }
+}
+
+/// The machine-wide config merged with the user's own config, then CTK_* environment
+/// variables and --set flags, each winning over the ones before it
+pub struct Config {
+  // Keys are dotted, so a value in the [profiles] table is stored as "profiles.<name>"
+  values: BTreeMap<String, (Value, Origin)>,
+}
+
+impl Config {
+  pub fn load() -> Self {
+    let mut tables = Vec::new();
+    if let Some(path) = system_config_path() {
+      if let Some(table) = read_config_table(&path) {
+        tables.push((table, Origin::System(path)));
+      }
+    }
+    if let Some(path) = user_config_path() {
+      if let Some(table) = read_config_table(&path) {
+        tables.push((table, Origin::User(path)));
+      }
+    }
+    let env_vars = std::env::vars_os()
+      .filter_map(|(var, value)| Some((var.into_string().ok()?, value.into_string().ok()?)));
+    Self::layered(tables, env_vars, set_flags(std::env::args_os().skip(1)))
+  }
+
+  /// Layers the config files, then the environment variables, then the --set flags, each
+  /// winning over the ones before it
+  pub(crate) fn layered(
+    tables: Vec<(Table, Origin)>,
+    env_vars: impl Iterator<Item = (String, String)>,
+    settings: Vec<String>,
+  ) -> Self {
+    let mut config = Self {
+      values: BTreeMap::new(),
+    };
+    for (table, origin) in tables {
+      config.merge(table, &origin);
+    }
+
+    for (var, value) in env_vars {
+      if let Some(key) = env_var_key(&var) {
+        config.set_raw(&key, &value, Origin::Env(var));
+      }
+    }
+
+    for setting in settings {
+      match setting.split_once('=') {
+        Some((key, value)) => config.set_raw(key.trim(), value.trim(), Origin::Flag),
+        None => eprintln!("WARNING: Ignoring {SET_FLAG} {setting} because it is not KEY=VALUE"),
+      }
+    }
+
+    config
+  }
+
+  /// Sets a value written as on the command line, like `ctk config set`, warning instead
+  /// when the key is unknown or the value is invalid
+  fn set_raw(&mut self, key: &str, raw: &str, origin: Origin) {
+    let Some(known) = find_key(key) else {
+      eprintln!("WARNING: Ignoring {key} from {origin} because it is not a known setting");
+      return;
+    };
+    match parse_value(known.kind, raw) {
+      Ok(value) => self.insert(key.to_string(), value, origin),
+      Err(why) => eprintln!("WARNING: Ignoring {key} from {origin}: {why}"),
+    }
+  }
+
+  fn insert(&mut self, key: String, value: Value, origin: Origin) {
+    if SYSTEM_ENFORCED_KEYS.contains(&key.as_str()) {
+      if let Some((enforced, system @ Origin::System(_))) = self.values.get(&key) {
+        // Keeping the system config's value where it is means nothing after it can turn it
+        // off either
+        if value == *enforced {
+          return;
+        }
+        if value != Value::Boolean(true) {
+          eprintln!(
+            "WARNING: Ignoring {key} = {value} from {origin}, since the {system} sets it to {enforced} and only an administrator can turn it off"
+          );
+          return;
+        }
+      }
+    }
+    self.values.insert(key, (value, origin));
+  }
+
+  fn merge(&mut self, table: Table, origin: &Origin) {
+    for (key, value) in table {
+      match value {
+        // Tables are merged one level deep, so a user can add a profile
+        // without hiding every profile from the system config
+        Value::Table(inner) => {
+          for (inner_key, inner_value) in inner {
+            self.insert(format!("{key}.{inner_key}"), inner_value, origin.clone());
+          }
+        }
+        _ => self.insert(key, value, origin.clone()),
+      }
+    }
+  }
+
+  pub fn get(&self, key: &str) -> Option<&Value> {
+    self.values.get(key).map(|(value, _)| value)
+  }
+
+  pub fn blocker_path(&self) -> PathBuf {
+    self
+      .get("blocker_path")
+      .and_then(Value::as_str)
+      .map_or_else(platform::default_blocker_path, PathBuf::from)
+  }
+
+  /// The configured helper, or the one next to a configured blocker_path, since a portable
+  /// install or one on another drive keeps them together, or else the installed one
+  pub fn msg_host_path(&self) -> PathBuf {
+    if let Some(path) = self.get("msg_host_path").and_then(Value::as_str) {
+      return PathBuf::from(path);
+    }
+    self
+      .get("blocker_path")
+      .and_then(Value::as_str)
+      .and_then(|blocker| Path::new(blocker).parent())
+      .map(|dir| dir.join(MSG_HOST_FILE_NAME))
+      .filter(|host| host.is_file())
+      .unwrap_or_else(platform::default_msg_host_path)
+  }
+
+  /// The Cold Turkey release set in the config, if one is
+  pub fn blocker_version(&self) -> Option<(u32, u32)> {
+    self
+      .get("blocker_version")
+      .and_then(Value::as_str)
+      .and_then(|version| convert::str_to_version(version).ok())
+  }
+
+  pub fn spawn_attempts(&self) -> u32 {
+    self
+      .get("spawn_attempts")
+      .and_then(Value::as_integer)
+      .and_then(|count| u32::try_from(count).ok())
+      .filter(|count| *count > 0)
+      .unwrap_or(DEFAULT_SPAWN_ATTEMPTS)
+  }
+
+  pub fn spawn_retry_delay(&self) -> Duration {
+    self
+      .get("spawn_retry_delay")
+      .and_then(Value::as_str)
+      .and_then(|delay| convert::str_to_duration(delay).ok())
+      .unwrap_or(DEFAULT_SPAWN_RETRY_DELAY)
+  }
+
+  pub fn verify_timeout(&self) -> Duration {
+    self
+      .get("verify_timeout")
+      .and_then(Value::as_str)
+      .and_then(|timeout| convert::str_to_duration(timeout).ok())
+      .unwrap_or(DEFAULT_VERIFY_TIMEOUT)
+  }
+
+  pub fn verify_interval(&self) -> Duration {
+    self
+      .get("verify_interval")
+      .and_then(Value::as_str)
+      .and_then(|interval| convert::str_to_duration(interval).ok())
+      .filter(|interval| !interval.is_zero())
+      .unwrap_or(DEFAULT_VERIFY_INTERVAL)
+  }
+
+  /// None to send commands without waiting for Cold Turkey Blocker to finish
+  pub fn blocker_timeout(&self) -> Option<Duration> {
+    let timeout = self
+      .get("blocker_timeout")
+      .and_then(Value::as_str)
+      .and_then(|timeout| convert::str_to_duration(timeout).ok())
+      .unwrap_or(DEFAULT_BLOCKER_TIMEOUT);
+    Some(timeout).filter(|timeout| !timeout.is_zero())
+  }
+
+  pub fn msg_host_timeout(&self) -> Duration {
+    self
+      .get("msg_host_timeout")
+      .and_then(Value::as_str)
+      .and_then(|timeout| convert::str_to_duration(timeout).ok())
+      .filter(|timeout| !timeout.is_zero())
+      .unwrap_or(ctsettings::DEFAULT_MSG_HOST_TIMEOUT)
+  }
+
+  pub fn installer_timeout(&self) -> Duration {
+    self
+      .get("installer_timeout")
+      .and_then(Value::as_str)
+      .and_then(|timeout| convert::str_to_duration(timeout).ok())
+      .filter(|timeout| !timeout.is_zero())
+      .unwrap_or(DEFAULT_INSTALLER_TIMEOUT)
+  }
+
+  pub fn streak_block(&self) -> Option<&str> {
+    self.get("streak_block").and_then(Value::as_str)
+  }
+
+  pub fn default_block(&self) -> Option<&str> {
+    self.get("default_block").and_then(Value::as_str)
+  }
+
+  pub fn streak_goal(&self) -> Duration {
+    self
+      .get("streak_goal")
+      .and_then(Value::as_str)
+      .and_then(|goal| convert::str_to_duration(goal).ok())
+      .unwrap_or(DEFAULT_STREAK_GOAL)
+  }
+
+  pub fn streak_hook(&self) -> Option<&str> {
+    self.get("streak_hook").and_then(Value::as_str)
+  }
+
+  pub fn sync_location(&self) -> Option<&str> {
+    self.get("sync_location").and_then(Value::as_str)
+  }
+
+  /// The template variables set in the [variables] table, by name
+  pub fn variables(&self) -> impl Iterator<Item = (&str, &str)> {
+    self
+      .values
+      .iter()
+      .filter_map(|(key, (value, _))| Some((key.strip_prefix("variables.")?, value.as_str()?)))
+  }
+
+  /// The app shell's bookmarked folders, by name
+  pub fn bookmarks(&self) -> impl Iterator<Item = (&str, &str)> {
+    self
+      .values
+      .iter()
+      .filter_map(|(key, (value, _))| Some((key.strip_prefix("bookmarks.")?, value.as_str()?)))
+  }
+
+  pub fn idle_after(&self) -> Duration {
+    self
+      .get("idle_after")
+      .and_then(Value::as_str)
+      .and_then(|idle_after| convert::str_to_duration(idle_after).ok())
+      .unwrap_or(DEFAULT_IDLE_AFTER)
+  }
+
+  pub fn confirm_lock_over(&self) -> Duration {
+    self
+      .get("confirm_lock_over")
+      .and_then(Value::as_str)
+      .and_then(|threshold| convert::str_to_duration(threshold).ok())
+      .unwrap_or(DEFAULT_CONFIRM_LOCK_OVER)
+  }
+
+  pub fn search_results(&self) -> usize {
+    self
+      .get("search_results")
+      .and_then(Value::as_integer)
+      .and_then(|count| usize::try_from(count).ok())
+      .filter(|count| *count > 0)
+      .unwrap_or(DEFAULT_SEARCH_RESULTS)
+  }
+
+  pub fn journal_rotation(&self) -> journal::Rotation {
+    let count = |key: &str| {
+      self
+        .get(key)
+        .and_then(Value::as_integer)
+        .and_then(|count| u32::try_from(count).ok())
+        .filter(|count| *count > 0)
+    };
+    let default = journal::Rotation::default();
+    journal::Rotation {
+      size: count("journal_rotate_kb").map_or(default.size, |kb| u64::from(kb) * 1024),
+      keep: count("journal_keep").unwrap_or(default.keep),
+    }
+  }
+
+  pub fn read_only(&self) -> bool {
+    self
+      .get("read_only")
+      .and_then(Value::as_bool)
+      .unwrap_or(false)
+  }
+
+  /// Checks every value against the known keys, returning one message per problem
+  pub fn validate(&self) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (key, (value, origin)) in &self.values {
+      match find_key(key) {
+        Some(known) => {
+          if let Err(why) = check_value(known.kind, value) {
+            problems.push(format!("{key} in {origin}: {why}"));
+          }
+        }
+        None => problems.push(format!("{key} in {origin}: unknown setting")),
+      }
+    }
+    problems
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&String, &Value, &Origin)> {
+    self
+      .values
+      .iter()
+      .map(|(key, (value, origin))| (key, value, origin))
+  }
+
+  /// The first profile (in alphabetical order) listing the given block, if any
+  pub fn profile_of(&self, block_name: &str) -> Option<&str> {
+    self.values.iter().find_map(|(key, (value, _))| {
+      let profile = key.strip_prefix("profiles.")?;
+      let blocks = value.as_array()?;
+      blocks
+        .iter()
+        .any(|block| block.as_str() == Some(block_name))
+        .then_some(profile)
+    })
+  }
+
+  /// The blocks listed in the given profile, or none if there is no such profile
+  pub fn profile_blocks(&self, profile: &str) -> Vec<&str> {
+    self.block_list(&format!("profiles.{profile}"))
+  }
+
+  /// The blocks to start along with the given one
+  pub fn also_start(&self, block_name: &str) -> Vec<&str> {
+    self.block_list(&format!("also_start.{block_name}"))
+  }
+
+  /// The blocks that keep the given one from being stopped while they run
+  pub fn keep_on_while(&self, block_name: &str) -> Vec<&str> {
+    self.block_list(&format!("keep_on_while.{block_name}"))
+  }
+
+  /// Whether a ctk:// link may do the action, like "start", to the block, going by
+  /// link_actions and link_blocks
+  pub fn link_allows(&self, action: &str, block_name: &str) -> bool {
+    let blocks = self.block_list("link_blocks");
+    self.link_allows_action(action) && (blocks.is_empty() || blocks.contains(&block_name))
+  }
+
+  /// Whether link_actions lets ctk:// links do `action`, for the ones on no block
+  pub fn link_allows_action(&self, action: &str) -> bool {
+    match self.get("link_actions").and_then(Value::as_array) {
+      Some(actions) => actions
+        .iter()
+        .any(|allowed| allowed.as_str() == Some(action)),
+      None => DEFAULT_LINK_ACTIONS.contains(&action),
+    }
+  }
+
+  pub fn watch_toasts(&self) -> bool {
+    self
+      .get("watch_toasts")
+      .and_then(Value::as_bool)
+      .unwrap_or(false)
+  }
+
+  fn block_list(&self, key: &str) -> Vec<&str> {
+    self
+      .get(key)
+      .and_then(Value::as_array)
+      .map_or_else(Vec::new, |blocks| {
+        blocks.iter().filter_map(Value::as_str).collect()
+      })
+  }
+}
+
+/// Validates and stores a value in the user config file, keeping everything else in it
+pub fn set_user_value(key: &str, raw: &str) -> Result<PathBuf, String> {
+  let known = find_key(key).ok_or_else(|| format!("{key} is not a known setting"))?;
+  let value = parse_value(known.kind, raw)?;
+  let path = user_config_path().ok_or("cannot find your config directory")?;
+
+  let mut table = if path.exists() {
+    let contents = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+    contents
+      .parse::<Table>()
+      .map_err(|err| format!("{} is not valid TOML: {}", path.display(), err))?
+  } else {
+    Table::new()
+  };
+
+  match key.split_once('.') {
+    Some((table_name, name)) => {
+      let inner = table
+        .entry(table_name)
+        .or_insert_with(|| Value::Table(Table::new()));
+      match inner.as_table_mut() {
+        Some(inner) => {
+          inner.insert(name.to_string(), value);
+        }
+        None => return Err(format!("{table_name} in {} is not a table", path.display())),
+      }
+    }
+    None => {
+      table.insert(key.to_string(), value);
+    }
+  }
+
+  write_user_table(&path, &table)?;
+  Ok(path)
+}
+
+/// Opens the user config file in $EDITOR (or the platform's default editor)
+pub fn edit_user_config() -> Result<PathBuf, String> {
+  let path = user_config_path().ok_or("cannot find your config directory")?;
+  if !path.exists() {
+    write_user_table(&path, &Table::new())?;
+  }
+
+  let editor = std::env::var("VISUAL")
+    .or_else(|_| std::env::var("EDITOR"))
+    .unwrap_or_else(|_| {
+      if cfg!(windows) {
+        "notepad".to_string()
+      } else {
+        "vi".to_string()
+      }
+    });
+
+  match process::Command::new(&editor).arg(&path).status() {
+    Ok(status) if status.success() => Ok(path),
+    Ok(status) => Err(format!("{editor} exited with {status}")),
+    Err(err) => Err(format!("cannot open {editor}: {err}")),
+  }
+}
+
+fn write_user_table(path: &Path, table: &Table) -> Result<(), String> {
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+  }
+  let contents = toml::to_string_pretty(table).map_err(|err| err.to_string())?;
+  fs::write(path, contents).map_err(|err| err.to_string())
+}
+
+fn read_config_table(path: &PathBuf) -> Option<Table> {
+  let contents = fs::read_to_string(path).ok()?;
+  match contents.parse::<Table>() {
+    Ok(table) => Some(table),
+    Err(err) => {
+      eprintln!(
+        "WARNING: Ignoring config file {} because it is not valid TOML: {}",
+        path.display(),
+        err
+      );
+      None
+    }
+  }
+}
+
+/// The directory holding the user's config file and other ctk files like rules.toml
+pub fn config_dir() -> Option<PathBuf> {
+  dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME))
+}
+
+/// The directory holding files ctk writes for itself, like the journal
+pub fn data_dir() -> Option<PathBuf> {
+  dirs::data_dir().map(|dir| dir.join(CONFIG_DIR_NAME))
+}
+
+pub fn user_config_path() -> Option<PathBuf> {
+  match std::env::var_os(CONFIG_PATH_VAR) {
+    Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+    _ => config_dir().map(|dir| dir.join(CONFIG_FILE_NAME)),
+  }
+}
+
+/// The key an environment variable like CTK_STREAK_GOAL or CTK_PROFILES__Work sets, if any
+fn env_var_key(var: &str) -> Option<String> {
+  let rest = var.strip_prefix(ENV_PREFIX)?;
+  let key = match rest.split_once("__") {
+    Some((table, name)) if !name.is_empty() => format!("{}.{name}", table.to_lowercase()),
+    Some(_) => return None,
+    None => rest.to_lowercase(),
+  };
+  // Other CTK_* variables, like CTK_STREAK_DAYS given to streak_hook, are not settings
+  find_key(&key).map(|_| key)
+}
+
+/// The values of every --set flag. They are read before clap parses the arguments, since
+/// block names are checked against Cold Turkey's settings while parsing, and the flags can
+/// say where to read those from
+fn set_flags(args: impl Iterator<Item = OsString>) -> Vec<String> {
+  let mut settings = Vec::new();
+  let mut args = args.filter_map(|arg| arg.into_string().ok());
+  while let Some(arg) = args.next() {
+    if arg == "--" {
+      break;
+    } else if arg == SET_FLAG {
+      settings.extend(args.next());
+    } else if let Some(setting) = arg.strip_prefix("--set=") {
+      settings.push(setting.to_string());
+    }
+  }
+  settings
+}
+
+pub fn system_config_path() -> Option<PathBuf> {
+  if cfg!(windows) {
+    std::env::var_os("ProgramData").map(|dir| {
+      PathBuf::from(dir)
+        .join(CONFIG_DIR_NAME)
+        .join(CONFIG_FILE_NAME)
+    })
+  } else {
+    Some(
+      PathBuf::from("/etc")
+        .join(CONFIG_DIR_NAME)
+        .join(CONFIG_FILE_NAME),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testutil::strings;
+
+  fn table(toml: &str) -> Table {
+    toml.parse().unwrap()
+  }
+
+  fn system(toml: &str) -> (Table, Origin) {
+    (table(toml), Origin::System(PathBuf::from("system.toml")))
+  }
+
+  fn user(toml: &str) -> (Table, Origin) {
+    (table(toml), Origin::User(PathBuf::from("config.toml")))
+  }
+
+  fn env(vars: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+    vars
+      .iter()
+      .map(|(var, value)| (var.to_string(), value.to_string()))
+      .collect::<Vec<_>>()
+      .into_iter()
+  }
+
+  fn args(args: &[&str]) -> Vec<String> {
+    set_flags(args.iter().map(OsString::from))
+  }
+
+  #[test]
+  fn later_layers_win() {
+    let tables = vec![
+      system("spawn_attempts = 2\nstreak_goal = \"1h\"\nsearch_results = 10"),
+      user("spawn_attempts = 4\nstreak_goal = \"2h\""),
+    ];
+    let config = Config::layered(
+      tables,
+      env(&[("CTK_SPAWN_ATTEMPTS", "6")]),
+      strings(&["spawn_attempts=8"]),
+    );
+    assert_eq!(config.spawn_attempts(), 8);
+    assert_eq!(config.streak_goal(), Duration::from_secs(2 * 60 * 60));
+    assert_eq!(config.search_results(), 10);
+
+    let config = Config::layered(
+      vec![user("spawn_attempts = 4")],
+      env(&[("CTK_SPAWN_ATTEMPTS", "6")]),
+      Vec::new(),
+    );
+    assert_eq!(config.spawn_attempts(), 6);
+    assert!(matches!(
+      config.values.get("spawn_attempts"),
+      Some((_, Origin::Env(var))) if var == "CTK_SPAWN_ATTEMPTS"
+    ));
+  }
+
+  #[test]
+  fn tables_are_merged_one_level_deep() {
+    let config = Config::layered(
+      vec![
+        system("[profiles]\nWork = [\"Email\"]"),
+        user("[profiles]\nStudy = [\"Games\"]"),
+      ],
+      env(&[("CTK_PROFILES__Evening", "News, Video")]),
+      Vec::new(),
+    );
+    assert_eq!(config.profile_blocks("Work"), ["Email"]);
+    assert_eq!(config.profile_blocks("Study"), ["Games"]);
+    assert_eq!(config.profile_blocks("Evening"), ["News", "Video"]);
+  }
+
+  #[test]
+  fn environment_variables_name_keys() {
+    assert_eq!(
+      env_var_key("CTK_STREAK_GOAL"),
+      Some("streak_goal".to_string())
+    );
+    assert_eq!(
+      env_var_key("CTK_PROFILES__Work"),
+      Some("profiles.Work".to_string())
+    );
+    // The name after __ keeps its case, since block and profile names have one
+    assert_eq!(
+      env_var_key("CTK_ALSO_START__Deep Work"),
+      Some("also_start.Deep Work".to_string())
+    );
+    assert_eq!(env_var_key("CTK_PROFILES__"), None);
+    assert_eq!(env_var_key("CTK_STREAK_DAYS"), None);
+    assert_eq!(env_var_key("STREAK_GOAL"), None);
+  }
+
+  #[test]
+  fn set_flags_are_read_in_both_forms() {
+    assert_eq!(
+      args(&["start", "--set", "a=1", "Work", "--set=b=2"]),
+      ["a=1", "b=2"]
+    );
+    assert_eq!(args(&["--set", "a=1", "--", "--set", "b=2"]), ["a=1"]);
+    assert!(args(&["start", "--set"]).is_empty());
+  }
+
+  #[test]
+  fn bad_set_flags_change_nothing() {
+    let config = Config::layered(
+      vec![user("spawn_attempts = 4")],
+      env(&[]),
+      strings(&[
+        "spawn_attempts",
+        "spawn_attempts=0",
+        "spawn_attempts=many",
+        "no_such_setting=1",
+        "streak_goal=soon",
+      ]),
+    );
+    assert_eq!(config.spawn_attempts(), 4);
+    assert!(config.get("no_such_setting").is_none());
+    assert!(config.get("streak_goal").is_none());
+  }
+
+  #[test]
+  fn links_only_start_blocks_and_show_the_status_by_default() {
+    let config = Config::layered(vec![], env(&[]), strings(&[]));
+    assert!(config.link_allows("start", "Work"));
+    assert!(config.link_allows_action("status"));
+    assert!(!config.link_allows("add", "Work"));
+    assert!(!config.link_allows("extend", "Work"));
+
+    let config = Config::layered(
+      vec![user(
+        "link_actions = [\"add\", \"extend\"]\nlink_blocks = [\"Work\"]",
+      )],
+      env(&[]),
+      strings(&[]),
+    );
+    assert!(config.link_allows("add", "Work"));
+    assert!(config.link_allows("extend", "Work"));
+    assert!(!config.link_allows("extend", "Social"));
+    assert!(!config.link_allows("start", "Work"));
+  }
+
+  #[test]
+  fn users_cannot_turn_off_a_system_read_only() {
+    let config = Config::layered(
+      vec![system("read_only = true"), user("read_only = false")],
+      env(&[("CTK_READ_ONLY", "false")]),
+      strings(&["read_only=false"]),
+    );
+    assert!(config.read_only());
+
+    // Without the system config saying so, it is the user's to set
+    let config = Config::layered(
+      vec![user("read_only = true")],
+      env(&[]),
+      strings(&["read_only=false"]),
+    );
+    assert!(!config.read_only());
+  }
+
+  #[test]
+  fn users_can_be_as_strict_as_the_system_read_only() {
+    let config = Config::layered(
+      vec![system("read_only = true"), user("read_only = true")],
+      env(&[]),
+      strings(&["read_only=false"]),
+    );
+    assert!(config.read_only());
+
+    // Turning it on over a system config that leaves it off is stricter, so it is allowed
+    let config = Config::layered(
+      vec![system("read_only = false"), user("read_only = true")],
+      env(&[]),
+      strings(&[]),
+    );
+    assert!(config.read_only());
+  }
+}
@@ -0,0 +1,68 @@
+use crate::settingsfeed::SettingsFeed;
+use chrono::{DateTime, Local};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::thread;
+use std::time::Duration;
+
+// How often the countdown is drawn, and how often Cold Turkey is asked whether the blocks
+// are still running
+const TICK: Duration = Duration::from_secs(1);
+const REFRESH: Duration = Duration::from_secs(5);
+
+/// Counts down from `start` to `end`, until Cold Turkey stops running every one of
+/// `blocks`. The end is only ctk's own reckoning, so Cold Turkey's settings have the last
+/// word: the countdown finishes early if the blocks stop early, and waits past zero while
+/// they are still running
+pub fn count_down(blocks: &[String], start: DateTime<Local>, end: DateTime<Local>) {
+  let total = seconds(end - start).max(1);
+  let bar = ProgressBar::new(total);
+  bar.set_style(ProgressStyle::default_bar().template("{bar:40} {msg}"));
+  let names = blocks.join(", ");
+  let feed = SettingsFeed::start(REFRESH);
+
+  eprintln!("Press Ctrl+C to stop counting down. The lock keeps going either way");
+  loop {
+    let left = seconds(end - Local::now());
+    if let Some(Some(settings)) = feed.latest() {
+      let running = blocks.iter().any(|block| {
+        settings
+          .block_list_info
+          .blocks
+          .get(block)
+          .is_some_and(|info| !info.is_dormant())
+      });
+      if !running {
+        bar.finish_with_message(match left {
+          0..=59 => format!("{names}: the lock is over"),
+          _ => format!(
+            "{names}: Cold Turkey ended the lock {} early",
+            describe_seconds(left)
+          ),
+        });
+        return;
+      }
+    }
+
+    bar.set_position(total.saturating_sub(left));
+    bar.set_message(match left {
+      0 => format!("{names}: time is up, waiting for Cold Turkey to end the lock"),
+      _ => format!("{names}: {} left", describe_seconds(left)),
+    });
+    thread::sleep(TICK);
+  }
+}
+
+/// Whole seconds in the duration, or 0 if it is negative
+fn seconds(duration: chrono::Duration) -> u64 {
+  u64::try_from(duration.num_seconds()).unwrap_or(0)
+}
+
+/// Like "1:05:09", for 1 hour, 5 minutes and 9 seconds
+fn describe_seconds(seconds: u64) -> String {
+  format!(
+    "{}:{:02}:{:02}",
+    seconds / 3600,
+    seconds / 60 % 60,
+    seconds % 60
+  )
+}
@@ -0,0 +1,335 @@
+use std::fmt;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// Exit codes, so scripts can tell what went wrong. 2 is clap's, for mistakes in the arguments
+pub const EXIT_FAILURE: u8 = 1;
+/// A command was sent to Cold Turkey, but could not be checked afterwards
+pub const EXIT_UNVERIFIED: u8 = 3;
+pub const EXIT_BLOCK_NOT_FOUND: u8 = 4;
+pub const EXIT_BLOCKER_NOT_FOUND: u8 = 5;
+pub const EXIT_BLOCKER_FAILED: u8 = 6;
+/// Cold Turkey was checked after a command, and had not done what it was asked
+pub const EXIT_NOT_APPLIED: u8 = 7;
+/// A process ctk ran did not finish within its timeout, and was stopped
+pub const EXIT_TIMED_OUT: u8 = 8;
+/// `ctk check` found the block not blocking, or blocking without a lock
+pub const EXIT_CHECK_INACTIVE: u8 = 1;
+/// `ctk check` cannot tell what the block is doing
+pub const EXIT_CHECK_UNKNOWN: u8 = 2;
+
+static EXIT_CODE: AtomicU8 = AtomicU8::new(0);
+
+/// Marks the command as failed. The first failure decides the exit code
+pub fn fail(exit_code: u8) {
+  let _ = EXIT_CODE.compare_exchange(0, exit_code, Ordering::Relaxed, Ordering::Relaxed);
+}
+
+/// 0 unless something failed, in which case the code for the first failure
+pub fn exit_code() -> ExitCode {
+  ExitCode::from(EXIT_CODE.load(Ordering::Relaxed))
+}
+
+/// A kind of error ctk reports, with a code that `ctk explain` gives the details of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+  BlockerNotFound,
+  BlockerFailed,
+  SettingsUnreadable,
+  BlockNotFound,
+  BlockLocked,
+  FrozenTurkey,
+  ProRequired,
+  InvalidTime,
+  BlockNotRunning,
+  InvalidConfig,
+  MissingVariable,
+  ReadOnly,
+  OperationRunning,
+  BlockedByRule,
+  Unsupported,
+  TimedOut,
+}
+
+impl ErrorCode {
+  pub const ALL: [ErrorCode; 16] = [
+    Self::BlockerNotFound,
+    Self::BlockerFailed,
+    Self::SettingsUnreadable,
+    Self::BlockNotFound,
+    Self::BlockLocked,
+    Self::FrozenTurkey,
+    Self::ProRequired,
+    Self::InvalidTime,
+    Self::BlockNotRunning,
+    Self::InvalidConfig,
+    Self::MissingVariable,
+    Self::ReadOnly,
+    Self::OperationRunning,
+    Self::BlockedByRule,
+    Self::Unsupported,
+    Self::TimedOut,
+  ];
+
+  pub fn number(self) -> usize {
+    // Why unwrap? Every code is in ALL
+    Self::ALL.iter().position(|code| *code == self).unwrap() + 1
+  }
+
+  pub fn exit_code(self) -> u8 {
+    match self {
+      Self::BlockerNotFound => EXIT_BLOCKER_NOT_FOUND,
+      Self::BlockerFailed => EXIT_BLOCKER_FAILED,
+      Self::BlockNotFound => EXIT_BLOCK_NOT_FOUND,
+      Self::TimedOut => EXIT_TIMED_OUT,
+      _ => EXIT_FAILURE,
+    }
+  }
+
+  pub fn summary(self) -> &'static str {
+    match self {
+      Self::BlockerNotFound => "Cold Turkey Blocker cannot be found",
+      Self::BlockerFailed => "Cold Turkey Blocker cannot be started",
+      Self::SettingsUnreadable => "ctk cannot read Cold Turkey's settings",
+      Self::BlockNotFound => "The block does not exist in Cold Turkey",
+      Self::BlockLocked => "The block is locked",
+      Self::FrozenTurkey => "Frozen Turkey cannot be used this way",
+      Self::ProRequired => "The feature needs Cold Turkey Pro",
+      Self::InvalidTime => "The time or date cannot be used",
+      Self::BlockNotRunning => "Cold Turkey does not report the block because it is not running",
+      Self::InvalidConfig => "A setting in ctk's config is invalid",
+      Self::MissingVariable => "A template variable has no value on this machine",
+      Self::ReadOnly => "ctk is read-only on this machine",
+      Self::OperationRunning => "Another ctk operation is running",
+      Self::BlockedByRule => "A rule between blocks in ctk's config does not allow this",
+      Self::Unsupported => "The installed Cold Turkey is too old for the command",
+      Self::TimedOut => "A program ctk ran did not finish in time",
+    }
+  }
+
+  /// What went wrong, its likely causes and how to fix it
+  pub fn explanation(self) -> &'static str {
+    match self {
+      Self::BlockerNotFound => {
+        "ctk runs Cold Turkey Blocker to start, stop and change blocks, and the executable is not where ctk looks for it.
+
+Causes:
+  - Cold Turkey Blocker is not installed.
+  - It is installed somewhere other than C:\\Program Files\\Cold Turkey on Windows, or the Applications folder on macOS.
+  - blocker_path in ctk's config points to the wrong file.
+
+Fixes:
+  - Install it with `ctk install`, or download it from getcoldturkey.com.
+  - Point ctk to it with `ctk config set blocker_path <path to Cold Turkey Blocker.exe>`.
+  - Check the effective path with `ctk config get blocker_path`.
+  - Commands that only work on files, like `ctk suggest` and `ctk lint`, work without it. `ctk doctor` lists them."
+      }
+      Self::BlockerFailed => {
+        "Cold Turkey Blocker exists, but the operating system refused to start it, even after retrying.
+
+Causes:
+  - An antivirus scan or elevation prompt was in the way.
+  - ctk does not have the rights to run it.
+
+Fixes:
+  - Try again, or run the command again with --elevate.
+  - Give it more tries with `ctk config set spawn_attempts 5` and `ctk config set spawn_retry_delay 1s`."
+      }
+      Self::SettingsUnreadable => {
+        "ctk reads blocks and settings from Cold Turkey's browser extension helper, CTMsgHostEdge.exe, which did not answer.
+
+Causes:
+  - Cold Turkey Blocker is not running.
+  - Cold Turkey is not installed, or is too old to include the helper.
+
+Fixes:
+  - Open Cold Turkey by running `ctk` with no arguments, then try again.
+  - Update Cold Turkey to the latest version."
+      }
+      Self::BlockNotFound => {
+        "Cold Turkey has no block with the given name.
+
+Causes:
+  - The name is misspelt. Block names are case sensitive.
+  - The block was renamed or deleted.
+
+Fixes:
+  - See every block with `ctk list`.
+  - Create the block in Cold Turkey, or with `ctk suggest`."
+      }
+      Self::BlockLocked => {
+        "The block has a password or random text lock, so ctk will not change it until it is unlocked.
+
+Fixes:
+  - Wait until the lock ends, then try again.
+  - Unlock it in Cold Turkey, then try again."
+      }
+      Self::FrozenTurkey => {
+        "Frozen Turkey locks the whole computer, so Cold Turkey only lets it run for a set time and does not let its settings be changed from the command line.
+
+Fixes:
+  - Start it for some time with `ctk frozen for --minutes <n>`.
+  - Start it until a time with `ctk frozen until <time>`."
+      }
+      Self::ProRequired => {
+        "Some locks and features, like password locks, are only available in Cold Turkey Pro.
+
+Fixes:
+  - Use a lock free users have, like `ctk start <block> for` or `ctk start <block> until`.
+  - Upgrade to Cold Turkey Pro at getcoldturkey.com."
+      }
+      Self::InvalidTime => {
+        "The time or date given cannot be turned into a moment in the future.
+
+Causes:
+  - The time is in the past. A time without a date is assumed to be today.
+  - The time is skipped or repeated by a daylight saving change.
+  - The date or time is not in a format ctk understands.
+
+Fixes:
+  - Add a date after the time, or pick a later time.
+  - Use 24 hour times like 17:30 or times like 5:30pm."
+      }
+      Self::BlockNotRunning => {
+        "Cold Turkey only reports the websites and settings of running blocks, so ctk cannot read or rebuild a block that is not running.
+
+Fixes:
+  - Start the block without a lock with `ctk start <block>`, then try again.
+  - Make the change in Cold Turkey itself."
+      }
+      Self::InvalidConfig => {
+        "A value in ctk's config files does not have the type ctk expects, or a setting does not exist.
+
+Fixes:
+  - See every setting and the problems ctk found with `ctk config list`.
+  - See which file each value comes from with `ctk config show --origin`.
+  - Fix the value with `ctk config set` or `ctk config edit`."
+      }
+      Self::MissingVariable => {
+        "A block template uses a {{variable}} that has no value on this machine.
+
+Causes:
+  - The variable was made up on another machine with `ctk config set variables.<name>`.
+  - A built-in variable like {{browser_profile}} cannot be found here.
+
+Fixes:
+  - See this machine's variables with `ctk template vars`.
+  - Set the missing one with `ctk config set variables.<name> <value>`."
+      }
+      Self::ReadOnly => {
+        "read_only is set in ctk's config, so commands that start, stop or change blocks, or change Cold Turkey's settings, are turned off. Commands that only look, like `ctk list`, `ctk report` and `ctk stats`, still work.
+
+Causes:
+  - An administrator set read_only in the system config, which users cannot undo.
+  - read_only was set in your own config.
+
+Fixes:
+  - See where it is set with `ctk config show --origin`.
+  - Ask the administrator of this machine, or turn it off with `ctk config set read_only false` if it is in your own config."
+      }
+      Self::OperationRunning => {
+        "ctk changes Cold Turkey one operation at a time, so that commands, `ctk watch` and `ctk import --watch` running together do not read and import blocks over each other. Another operation held on for longer than ctk waits.
+
+Causes:
+  - Another ctk command is waiting for input, like a confirmation or password.
+  - Cold Turkey Blocker is slow to start, holding up the other operation.
+
+Fixes:
+  - Finish or stop the other ctk command, then try again.
+  - Look for a ctk still running in another window or in the background, like `ctk watch` stuck on Cold Turkey, and stop it. A ctk that crashed or was killed lets go of the lock by itself."
+      }
+      Self::BlockedByRule => {
+        "keep_on_while.<block> in ctk's config lists blocks that keep <block> on while any of them is running, and one of them is running now.
+
+Causes:
+  - A block the rule names is running, like an exam block keeping a news block on.
+  - ctk cannot read Cold Turkey's settings, so it cannot check that the rule allows it.
+
+Fixes:
+  - Wait until the blocks the rule names have stopped, then try again.
+  - See the rule with `ctk config get keep_on_while.<block>`, and change it with `ctk config edit`."
+      }
+      Self::Unsupported => {
+        "blocker_version in ctk's config says the installed Cold Turkey is older than the first release that has the command line option ctk needs, so ctk does not run it. Older releases ignore options they do not know, so the command would do nothing.
+
+Causes:
+  - Cold Turkey has not been updated in a while.
+  - blocker_version is out of date after an update.
+
+Fixes:
+  - Update Cold Turkey to the latest version, then run `ctk config set blocker_version <version>`.
+  - Check the version in Cold Turkey's About page, and see what is set with `ctk config get blocker_version`."
+      }
+      Self::TimedOut => {
+        "ctk runs Cold Turkey Blocker to send commands, CTMsgHostEdge.exe to read blocks and settings, and the installer in `ctk install`. One of them did not finish within the time ctk gives it, so ctk stopped it rather than wait forever.
+
+Causes:
+  - Cold Turkey is hung, or busy starting up.
+  - The computer is under heavy load.
+
+Fixes:
+  - Try again, or restart Cold Turkey.
+  - Give it longer with `ctk config set blocker_timeout <duration>`, `msg_host_timeout` or `installer_timeout`, like 1m. The defaults are 30s, 10s and 30m.
+  - `ctk config set blocker_timeout 0s` sends commands without waiting for Cold Turkey Blocker to finish."
+      }
+    }
+  }
+}
+
+impl fmt::Display for ErrorCode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "CTK{:03}", self.number())
+  }
+}
+
+impl FromStr for ErrorCode {
+  type Err = String;
+
+  /// Accepts CTK004, ctk004 or 4
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let digits = s
+      .get(..3)
+      .filter(|prefix| prefix.eq_ignore_ascii_case("ctk"))
+      .map_or(s, |_| &s[3..]);
+    digits
+      .parse::<usize>()
+      .ok()
+      .and_then(|number| number.checked_sub(1))
+      .and_then(|index| Self::ALL.get(index))
+      .copied()
+      .ok_or_else(|| format!("{s} is not a ctk error code"))
+  }
+}
+
+/// Prints an error with its code, and how to find out more about it
+macro_rules! ctk_error {
+  ($code:expr, $($arg:tt)*) => {{
+    let code: $crate::error::ErrorCode = $code;
+    $crate::error::fail(code.exit_code());
+    eprintln!("ERROR[{code}]: {}", format_args!($($arg)*));
+    eprintln!("For more information, try `ctk explain {code}`");
+  }};
+}
+
+/// Prints an error that has no code of its own
+macro_rules! error {
+  ($($arg:tt)*) => {{
+    $crate::error::fail($crate::error::EXIT_FAILURE);
+    eprintln!("ERROR: {}", format_args!($($arg)*));
+  }};
+}
+
+/// Prints that something was not done. Starting with an exit code, like
+/// `failure!(EXIT_NOT_APPLIED; ...)`, uses it instead of the general one
+macro_rules! failure {
+  ($exit_code:expr; $($arg:tt)*) => {{
+    $crate::error::fail($exit_code);
+    eprintln!("FAILURE: {}", format_args!($($arg)*));
+  }};
+  ($($arg:tt)*) => {
+    $crate::error::failure!($crate::error::EXIT_FAILURE; $($arg)*)
+  };
+}
+
+pub(crate) use {ctk_error, error, failure};
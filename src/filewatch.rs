@@ -0,0 +1,40 @@
+use notify::{Event, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+// Editors often save a file in several steps, so changes are gathered for a moment
+// before reacting to them
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+/// Calls `on_change` every time the file changes, until watching it fails
+pub fn watch_file(path: &Path, mut on_change: impl FnMut()) -> notify::Result<()> {
+  let file_name = path
+    .file_name()
+    .ok_or_else(|| notify::Error::generic("not a file"))?
+    .to_os_string();
+  // Watching the folder rather than the file keeps working when an editor saves by
+  // replacing the file
+  let dir = match path.parent() {
+    Some(dir) if !dir.as_os_str().is_empty() => dir,
+    _ => Path::new("."),
+  };
+
+  let (sender, receiver) = mpsc::channel::<notify::Result<Event>>();
+  let mut watcher = notify::recommended_watcher(sender)?;
+  watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+  for event in receiver.iter() {
+    let event = event?;
+    let is_change = !event.kind.is_access()
+      && event
+        .paths
+        .iter()
+        .any(|changed| changed.file_name() == Some(&file_name));
+    if is_change {
+      while receiver.recv_timeout(SETTLE_TIME).is_ok() {}
+      on_change();
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,145 @@
+use chrono::Local;
+use std::ffi::OsString;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::sync::OnceLock;
+
+// Enough of each payload to recognize it without filling the trace with whole blocklists
+const PAYLOAD_PREVIEW_LEN: usize = 512;
+
+const TRACE_FLAG: &str = "--trace-host";
+
+static TRACE_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// The file given with --trace-host, if any. It is read before clap parses the arguments,
+/// since block names are checked against Cold Turkey's settings while parsing, and that
+/// is often the only time ctk talks to the messaging host
+pub fn trace_flag(mut args: impl Iterator<Item = OsString>) -> Option<PathBuf> {
+  let mut path = None;
+  while let Some(arg) = args.next() {
+    if arg == "--" {
+      break;
+    } else if arg == TRACE_FLAG {
+      path = args.next().map(PathBuf::from);
+    } else if let Some(file) = arg
+      .to_str()
+      .and_then(|arg| arg.strip_prefix(TRACE_FLAG)?.strip_prefix('='))
+    {
+      path = Some(PathBuf::from(file));
+    }
+  }
+  path
+}
+
+/// Starts logging the messages exchanged with Cold Turkey's messaging host to the file
+pub fn enable(path: PathBuf) {
+  let _ = TRACE_FILE.set(path);
+}
+
+/// Logs what the messaging host answered, frame by frame, if tracing is on
+pub fn record_output(host: &Path, output: &Output) {
+  record(|file| {
+    writeln!(
+      file,
+      "{} ctk -> host: ran {} with nothing on stdin",
+      Local::now().to_rfc3339(),
+      host.display()
+    )?;
+    writeln!(
+      file,
+      "{} host -> ctk: {} ({} bytes on stdout, {} on stderr)",
+      Local::now().to_rfc3339(),
+      output.status,
+      output.stdout.len(),
+      output.stderr.len()
+    )?;
+    write_frames(file, &output.stdout)
+  });
+}
+
+/// Logs that the messaging host could not be run, if tracing is on
+pub fn record_error(host: &Path, err: &io::Error) {
+  record(|file| {
+    writeln!(
+      file,
+      "{} ctk -> host: cannot run {}: {err}",
+      Local::now().to_rfc3339(),
+      host.display()
+    )
+  });
+}
+
+fn record(write: impl FnOnce(&mut std::fs::File) -> io::Result<()>) {
+  let Some(path) = TRACE_FILE.get() else {
+    return;
+  };
+  if let Err(err) = open(path).and_then(|mut file| write(&mut file)) {
+    eprintln!(
+      "WARNING: ctk cannot write the host trace to {}: {err}",
+      path.display()
+    );
+  }
+}
+
+fn open(path: &Path) -> io::Result<std::fs::File> {
+  OpenOptions::new().create(true).append(true).open(path)
+}
+
+// Native messaging frames are a 4 byte little-endian length followed by that many bytes of JSON
+fn write_frames(file: &mut std::fs::File, mut rest: &[u8]) -> io::Result<()> {
+  while !rest.is_empty() {
+    if rest.len() < 4 {
+      writeln!(file, "  {} trailing bytes: {rest:?}", rest.len())?;
+      break;
+    }
+    let (length, payload) = rest.split_at(4);
+    // Why unwrap? length is exactly 4 bytes
+    let declared = u32::from_le_bytes(length.try_into().unwrap()) as usize;
+    let (frame, next) = payload.split_at(declared.min(payload.len()));
+    writeln!(
+      file,
+      "  frame of {declared} bytes ({} received): {}",
+      frame.len(),
+      preview(frame)
+    )?;
+    rest = next;
+  }
+  Ok(())
+}
+
+fn preview(payload: &[u8]) -> String {
+  let text = String::from_utf8_lossy(payload);
+  match text.char_indices().nth(PAYLOAD_PREVIEW_LEN) {
+    Some((end, _)) => format!("{}... ({} more bytes)", &text[..end], text.len() - end),
+    None => text.into_owned(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn trace_file(args: &[&str]) -> Option<PathBuf> {
+    trace_flag(args.iter().map(OsString::from))
+  }
+
+  #[test]
+  fn the_trace_file_is_read_in_both_forms() {
+    assert_eq!(
+      trace_file(&["start", "--trace-host", "host.log", "Work"]),
+      Some(PathBuf::from("host.log"))
+    );
+    assert_eq!(
+      trace_file(&["--trace-host=host.log", "list"]),
+      Some(PathBuf::from("host.log"))
+    );
+    assert_eq!(
+      trace_file(&["list", "--", "--trace-host", "host.log"]),
+      None
+    );
+    assert_eq!(trace_file(&["list", "--trace-host"]), None);
+    assert_eq!(trace_file(&["list", "--trace-hostname"]), None);
+  }
+}
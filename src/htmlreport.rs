@@ -0,0 +1,150 @@
+use crate::journal::{Entry, Event};
+use crate::report::{self, Period, Session};
+use crate::template::Variables;
+use chrono::{DateTime, Duration, Local};
+use std::collections::{BTreeMap, HashMap};
+
+// Enough to see what was blocked most without the list taking over the page
+const TOP_URL_COUNT: usize = 10;
+
+/// The layout used without --template. Custom templates can use the same {{variables}}
+const DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>ctk report, {{from}} to {{to}}</title>
+<style>
+  body { font-family: sans-serif; max-width: 40em; margin: 2em auto; color: #222; }
+  h1 { font-size: 1.4em; }
+  .totals { display: flex; gap: 2em; }
+  .totals div { font-size: 2em; font-weight: bold; }
+  .totals span { display: block; font-size: 0.45em; font-weight: normal; color: #666; }
+  table { border-collapse: collapse; width: 100%; }
+  th, td { text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #ddd; }
+  td.number, th.number { text-align: right; }
+</style>
+</head>
+<body>
+<h1>Focus report from {{from}} to {{to}}</h1>
+<div class="totals">
+  <div>{{sessions}}<span>sessions started</span></div>
+  <div>{{hours}}<span>hours blocked</span></div>
+</div>
+<h2>Blocks</h2>
+{{blocks}}
+<h2>Most added websites</h2>
+{{top_urls}}
+<p><small>Made by ctk on {{generated}}</small></p>
+</body>
+</html>
+"#;
+
+/// Fills in `template` (or the default layout) with a summary of the period.
+/// Besides {{from}}, {{to}}, {{days}}, {{sessions}}, {{hours}} and {{generated}}, there are
+/// {{blocks}} and {{top_urls}}, which are ready-made HTML
+pub fn render(entries: &[Entry], period: Period, template: Option<&str>) -> Result<String, String> {
+  let now = Local::now();
+  let from = period.start(now);
+  let sessions = report::sessions(entries, now);
+  let totals = report::total_per_block(&sessions, from, now);
+  let started = sessions_started(&sessions, from);
+  let total_minutes: i64 = totals.values().map(Duration::num_minutes).sum();
+
+  let values = BTreeMap::from([
+    ("from".to_string(), from.format("%B %d %Y").to_string()),
+    ("to".to_string(), now.format("%B %d %Y").to_string()),
+    ("days".to_string(), period.days(now).to_string()),
+    (
+      "sessions".to_string(),
+      started.values().sum::<usize>().to_string(),
+    ),
+    (
+      "hours".to_string(),
+      format!("{:.1}", total_minutes as f64 / 60.0),
+    ),
+    (
+      "generated".to_string(),
+      now.format("%B %d %Y %H:%M").to_string(),
+    ),
+    (
+      "blocks".to_string(),
+      blocks_table(&sessions, &totals, &started, now),
+    ),
+    ("top_urls".to_string(), top_urls_list(entries, from)),
+  ]);
+  Variables::new(values).expand(template.unwrap_or(DEFAULT_TEMPLATE))
+}
+
+/// How many sessions of each block started since `from`
+fn sessions_started(sessions: &[Session], from: DateTime<Local>) -> BTreeMap<String, usize> {
+  let mut started: BTreeMap<String, usize> = BTreeMap::new();
+  for session in sessions.iter().filter(|session| session.start >= from) {
+    *started.entry(session.block.clone()).or_default() += 1;
+  }
+  started
+}
+
+fn blocks_table(
+  sessions: &[Session],
+  totals: &BTreeMap<String, Duration>,
+  started: &BTreeMap<String, usize>,
+  now: DateTime<Local>,
+) -> String {
+  if totals.is_empty() {
+    return "<p>No blocks were started through ctk in this time.</p>".to_string();
+  }
+  let mut table = String::from(
+    "<table>\n<tr><th>Block</th><th class=\"number\">Sessions</th>\
+     <th class=\"number\">Hours</th><th class=\"number\">Streak</th></tr>\n",
+  );
+  for (block, total) in totals {
+    let days = report::daily_totals(sessions, block).into_keys().collect();
+    let streak = report::current_streak(&days, now.date_naive());
+    table.push_str(&format!(
+      "<tr><td>{}</td><td class=\"number\">{}</td><td class=\"number\">{:.1}</td>\
+       <td class=\"number\">{streak}d</td></tr>\n",
+      escape(block),
+      started.get(block).copied().unwrap_or(0),
+      total.num_minutes() as f64 / 60.0,
+    ));
+  }
+  table.push_str("</table>");
+  table
+}
+
+/// The websites added to blocks (not as exceptions) most often since `from`
+fn top_urls_list(entries: &[Entry], from: DateTime<Local>) -> String {
+  let mut counts: HashMap<&str, usize> = HashMap::new();
+  for entry in entries.iter().filter(|entry| entry.time >= from) {
+    if let Event::Add {
+      url, except: false, ..
+    } = &entry.event
+    {
+      *counts.entry(url).or_default() += 1;
+    }
+  }
+  if counts.is_empty() {
+    return "<p>No websites were added to blocks through ctk in this time.</p>".to_string();
+  }
+
+  let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+  counts.sort_unstable_by(|(a_url, a_count), (b_url, b_count)| {
+    b_count.cmp(a_count).then(a_url.cmp(b_url))
+  });
+  let mut list = String::from("<ol>\n");
+  for (url, count) in counts.into_iter().take(TOP_URL_COUNT) {
+    let times = if count == 1 { "time" } else { "times" };
+    list.push_str(&format!("<li>{} ({count} {times})</li>\n", escape(url)));
+  }
+  list.push_str("</ol>");
+  list
+}
+
+/// Block names and urls are the user's own, but still should not be able to break the page
+fn escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
@@ -0,0 +1,334 @@
+use crate::config;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const JOURNAL_FILE_NAME: &str = "journal.jsonl";
+// Taken by everything that reads or writes the journal, so `ctk watch` and commands
+// running at the same time never see a journal in the middle of being rotated
+const JOURNAL_LOCK_NAME: &str = "journal.lock";
+
+const DEFAULT_ROTATE_SIZE: u64 = 1024 * 1024;
+const DEFAULT_KEEP: u32 = 4;
+
+/// When the journal is moved aside for a new one, and how many old ones are kept
+#[derive(Debug, Clone, Copy)]
+pub struct Rotation {
+  /// In bytes
+  pub size: u64,
+  pub keep: u32,
+}
+
+impl Default for Rotation {
+  fn default() -> Self {
+    Self {
+      size: DEFAULT_ROTATE_SIZE,
+      keep: DEFAULT_KEEP,
+    }
+  }
+}
+
+static ROTATION: OnceLock<Rotation> = OnceLock::new();
+
+/// Rotates the journal as the config says from now on. Only the first call counts
+pub fn set_rotation(rotation: Rotation) {
+  let _ = ROTATION.set(rotation);
+}
+
+fn rotation() -> Rotation {
+  ROTATION.get().copied().unwrap_or_default()
+}
+
+/// Something ctk did to a block, as recorded in the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum Event {
+  Start {
+    block: String,
+    /// How long the block was locked for, if it was
+    lock_minutes: Option<i64>,
+  },
+  Stop {
+    block: String,
+  },
+  /// A website was added to a block, or to its exceptions
+  Add {
+    block: String,
+    url: String,
+    except: bool,
+  },
+  /// A command was sent to Cold Turkey, but whether it worked could not be checked
+  Unverified {
+    command: String,
+    block: String,
+  },
+  /// A pomodoro block moved to another phase, as seen by `ctk watch`
+  Pomodoro {
+    block: String,
+    /// Cold Turkey's name for the new phase
+    phase: String,
+  },
+  /// A block was saved with a random text lock, by `ctk suggest` or `ctk wizard`
+  #[serde(rename = "randomtext")]
+  RandomText {
+    block: String,
+    /// How many characters have to be typed to unlock it
+    length: u16,
+  },
+  /// Cold Turkey's settings were saved before starting blocks with `ctk start --snapshot`
+  Snapshot {
+    blocks: Vec<String>,
+    file: PathBuf,
+  },
+}
+
+impl fmt::Display for Event {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Start {
+        block,
+        lock_minutes: Some(minutes),
+      } => write!(f, "started {block}, locked for {minutes} minutes"),
+      Self::Start { block, .. } => write!(f, "started {block}"),
+      Self::Stop { block } => write!(f, "stopped {block}"),
+      Self::Add {
+        block,
+        url,
+        except: true,
+      } => write!(f, "added {url} to the exceptions of {block}"),
+      Self::Add { block, url, .. } => write!(f, "added {url} to {block}"),
+      Self::Unverified { command, block } => {
+        write!(f, "sent {command} for {block}, but could not check it")
+      }
+      Self::Pomodoro { block, phase } => write!(f, "{block} moved to pomodoro phase {phase}"),
+      Self::RandomText { block, length } => {
+        write!(f, "saved {block} with {length} characters of random text")
+      }
+      Self::Snapshot { blocks, file } => write!(
+        f,
+        "saved a snapshot to {} before starting {}",
+        file.display(),
+        blocks.join(", ")
+      ),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+  pub time: DateTime<Local>,
+  #[serde(flatten)]
+  pub event: Event,
+}
+
+/// The journal's files, in one directory
+struct Journal {
+  dir: PathBuf,
+  rotation: Rotation,
+}
+
+impl Journal {
+  /// The journal in ctk's data directory, rotated as the config says
+  fn open() -> Option<Self> {
+    config::data_dir().map(|dir| Self {
+      dir,
+      rotation: rotation(),
+    })
+  }
+
+  fn path(&self) -> PathBuf {
+    self.dir.join(JOURNAL_FILE_NAME)
+  }
+
+  /// Where the journal goes once it has been rotated `n` times, like journal.1.jsonl
+  fn rotated_path(&self, n: u32) -> PathBuf {
+    self.dir.join(format!("journal.{n}.jsonl"))
+  }
+
+  /// Opens the journal's lock file, creating the directory if needed. The lock is let go
+  /// when the file is closed
+  fn lock_file(&self) -> Result<File, Box<dyn std::error::Error>> {
+    fs::create_dir_all(&self.dir)?;
+    let file = OpenOptions::new()
+      .create(true)
+      .truncate(false)
+      .write(true)
+      .open(self.dir.join(JOURNAL_LOCK_NAME))?;
+    Ok(file)
+  }
+
+  fn record(&self, entry: &Entry) -> Result<(), Box<dyn std::error::Error>> {
+    let lock = self.lock_file()?;
+    lock.lock()?;
+
+    let path = self.path();
+    if fs::metadata(&path).is_ok_and(|metadata| metadata.len() >= self.rotation.size) {
+      self.rotate()?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    // One entry per line, so a torn write only ever loses the last entry
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+  }
+
+  /// Moves journal.jsonl to journal.1.jsonl, journal.1.jsonl to journal.2.jsonl and so
+  /// on, dropping the oldest past `keep`. Only called with the journal locked
+  fn rotate(&self) -> Result<(), Box<dyn std::error::Error>> {
+    let keep = self.rotation.keep;
+    if keep == 0 {
+      fs::remove_file(self.path())?;
+      return Ok(());
+    }
+    let _ = fs::remove_file(self.rotated_path(keep));
+    for n in (1..keep).rev() {
+      let from = self.rotated_path(n);
+      if from.exists() {
+        fs::rename(from, self.rotated_path(n + 1))?;
+      }
+    }
+    fs::rename(self.path(), self.rotated_path(1))?;
+    Ok(())
+  }
+
+  fn read_entries(&self) -> Vec<Entry> {
+    // Reading without the lock is better than not reading at all
+    let lock = self.lock_file().ok();
+    if let Some(lock) = &lock {
+      let _ = lock.lock_shared();
+    }
+
+    let rotated = (1..=self.rotation.keep).rev().map(|n| self.rotated_path(n));
+    rotated
+      .chain([self.path()])
+      .filter_map(|path| File::open(path).ok())
+      .flat_map(|file| {
+        BufReader::new(file)
+          .lines()
+          .map_while(Result::ok)
+          .filter_map(|line| serde_json::from_str(&line).ok())
+          .collect::<Vec<Entry>>()
+      })
+      .collect()
+  }
+}
+
+/// Appends the event to the journal. The journal is only a record, so failing to write
+/// it is a warning rather than a reason to fail the command
+pub fn record(event: Event) {
+  let entry = Entry {
+    time: Local::now(),
+    event,
+  };
+  let recorded = Journal::open()
+    .ok_or_else(|| "cannot find your data directory".into())
+    .and_then(|journal| journal.record(&entry));
+  if let Err(err) = recorded {
+    eprintln!("WARNING: ctk cannot write to its journal: {err}");
+  }
+}
+
+/// Reads every entry in the journal, including the rotated ones still kept, oldest first,
+/// skipping lines that cannot be read
+pub fn read_entries() -> Vec<Entry> {
+  Journal::open().map_or_else(Vec::new, |journal| journal.read_entries())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testutil::TempDir;
+
+  fn journal(dir: &TempDir, size: u64, keep: u32) -> Journal {
+    Journal {
+      dir: dir.path().to_path_buf(),
+      rotation: Rotation { size, keep },
+    }
+  }
+
+  fn record_stops(journal: &Journal, blocks: &[&str]) {
+    for block in blocks {
+      let entry = Entry {
+        time: Local::now(),
+        event: Event::Stop {
+          block: block.to_string(),
+        },
+      };
+      journal.record(&entry).unwrap();
+    }
+  }
+
+  fn blocks(entries: &[Entry]) -> Vec<&str> {
+    entries
+      .iter()
+      .map(|entry| match &entry.event {
+        Event::Stop { block } => block.as_str(),
+        event => panic!("only stops were recorded, not {event}"),
+      })
+      .collect()
+  }
+
+  fn lines(path: PathBuf) -> usize {
+    fs::read_to_string(path).map_or(0, |contents| contents.lines().count())
+  }
+
+  #[test]
+  fn the_journal_is_rotated_once_it_reaches_its_size() {
+    let dir = TempDir::new("journal_rotated");
+    let journal = journal(&dir, 1, 4);
+    record_stops(&journal, &["a", "b", "c"]);
+    assert_eq!(lines(journal.path()), 1);
+    assert_eq!(lines(journal.rotated_path(1)), 1);
+    assert_eq!(lines(journal.rotated_path(2)), 1);
+    assert!(!journal.rotated_path(3).exists());
+  }
+
+  #[test]
+  fn the_journal_grows_until_it_reaches_its_size() {
+    let dir = TempDir::new("journal_growing");
+    let journal = journal(&dir, 1024, 4);
+    record_stops(&journal, &["a", "b", "c"]);
+    assert_eq!(lines(journal.path()), 3);
+    assert!(!journal.rotated_path(1).exists());
+  }
+
+  #[test]
+  fn entries_are_read_back_across_rotated_journals() {
+    let dir = TempDir::new("journal_read_back");
+    let journal = journal(&dir, 1, 4);
+    record_stops(&journal, &["a", "b", "c", "d"]);
+    assert_eq!(blocks(&journal.read_entries()), ["a", "b", "c", "d"]);
+  }
+
+  #[test]
+  fn only_so_many_rotated_journals_are_kept() {
+    let dir = TempDir::new("journal_kept");
+    let journal = journal(&dir, 1, 2);
+    record_stops(&journal, &["a", "b", "c", "d", "e"]);
+    assert!(!journal.rotated_path(3).exists());
+    assert_eq!(blocks(&journal.read_entries()), ["c", "d", "e"]);
+
+    let journal = Journal {
+      rotation: Rotation { size: 1, keep: 0 },
+      ..journal
+    };
+    record_stops(&journal, &["f"]);
+    assert_eq!(blocks(&journal.read_entries()), ["f"]);
+  }
+
+  #[test]
+  fn lines_that_cannot_be_read_are_skipped() {
+    let dir = TempDir::new("journal_torn");
+    let journal = journal(&dir, 1, 4);
+    record_stops(&journal, &["a", "b"]);
+    let mut rotated = OpenOptions::new()
+      .append(true)
+      .open(journal.rotated_path(1))
+      .unwrap();
+    write!(rotated, "{{\"time\":\"2024-").unwrap();
+    assert_eq!(blocks(&journal.read_entries()), ["a", "b"]);
+  }
+}
@@ -0,0 +1,379 @@
+use crate::loop_dialoguer::LoopDialogue;
+use crate::suggestdialog;
+use chrono::NaiveTime;
+use ctk_common::fuzzy::{self, Matcher};
+use dialoguer::Input;
+use rayon::prelude::*;
+
+const ROOT: &str = "C:/Program Files";
+
+// Forward slashes, like in .ctbbl files, so the practice paths split the same way everywhere
+const PRACTICE_FILES: [&str; 8] = [
+  "C:/Program Files/Steam/steam.exe",
+  "C:/Program Files/Steam/steamerrorreporter.exe",
+  "C:/Program Files/Steam/GameOverlayUI.exe",
+  "C:/Program Files/Steam/steamapps/common/Portal 2/portal2.exe",
+  "C:/Program Files/Discord/Update.exe",
+  "C:/Program Files/Discord/app-1.0.9/Discord.exe",
+  "C:/Program Files/Mozilla Firefox/firefox.exe",
+  "C:/Program Files/Google/Chrome/Application/chrome.exe",
+];
+
+// A few are enough to show how the matches are ranked
+const SEARCH_RESULTS: usize = 5;
+
+/// A made-up folder tree standing in for the disk, so nothing real is read or blocked
+struct Sandbox {
+  current_dir: String,
+  previous_dir: Option<String>,
+  selected: Vec<String>,
+  start_time: Option<NaiveTime>,
+}
+
+/// One step of the tutorial. `check` looks at what the user typed, after it has been run in
+/// the sandbox, and says what to fix if it is not what the step asks for
+struct Step {
+  explanation: &'static str,
+  task: &'static str,
+  /// Whether what is typed is an app shell command, run in the sandbox before it is checked
+  shell: bool,
+  check: fn(&mut Sandbox, &[String]) -> Result<(), String>,
+}
+
+const STEPS: [Step; 10] = [
+  Step {
+    explanation: "When `ctk suggest` asks to add executables or folders, it opens a small shell. It starts in the folder ctk was run from. Here, that is a practice Program Files folder.",
+    task: "List what is in this folder with `ls`.",
+    shell: true,
+    check: |_, args| expect_command(args, "ls"),
+  },
+  Step {
+    explanation: "`cd <folder>` moves into a folder, `cd ..` goes up, and `cd -` goes back to where you were. Quote names with spaces, like `cd \"Mozilla Firefox\"`.",
+    task: "Move into the Steam folder.",
+    shell: true,
+    check: |sandbox, args| {
+      expect_command(args, "cd")?;
+      expect_dir(sandbox, &format!("{ROOT}/Steam"))
+    },
+  },
+  Step {
+    explanation: "`add <pattern>...` adds everything in this folder that matches, without asking. `*` stands for any text, and case does not matter.",
+    task: "Add every .exe in the Steam folder at once.",
+    shell: true,
+    check: |sandbox, args| {
+      expect_command(args, "add")?;
+      if is_selected(sandbox, "steam.exe") && is_selected(sandbox, "GameOverlayUI.exe") {
+        Ok(())
+      } else {
+        Err("Use a pattern that matches every .exe, like *.exe".to_string())
+      }
+    },
+  },
+  Step {
+    explanation: "`selected` shows everything added so far, numbered.",
+    task: "See what you have added.",
+    shell: true,
+    check: |_, args| expect_command(args, "selected"),
+  },
+  Step {
+    explanation: "`deselect` takes things out again, by their number in `selected` or by pattern.",
+    task: "The error reporter does not need blocking. Deselect it, and only it.",
+    shell: true,
+    check: |sandbox, args| {
+      expect_command(args, "deselect")?;
+      if is_selected(sandbox, "steamerrorreporter.exe") {
+        Err("steamerrorreporter.exe is still selected".to_string())
+      } else if !is_selected(sandbox, "steam.exe") {
+        Err("steam.exe was deselected too. Add it back with `add steam.exe`".to_string())
+      } else {
+        Ok(())
+      }
+    },
+  },
+  Step {
+    explanation: "`cd -` goes back to the folder you were in before.",
+    task: "Go back to Program Files.",
+    shell: true,
+    check: |sandbox, args| {
+      expect_command(args, "cd")?;
+      expect_dir(sandbox, ROOT)
+    },
+  },
+  Step {
+    explanation: "`search <keyword>` looks through this folder and everything below it for names close to the keyword, best matches first. In `ctk suggest` you then pick the ones to add; here the best match is added for you.",
+    task: "Find Discord without knowing which folder it is in.",
+    shell: true,
+    check: |sandbox, args| {
+      expect_command(args, "search")?;
+      if sandbox.selected.iter().any(|path| path.contains("Discord")) {
+        Ok(())
+      } else {
+        Err("Search for a keyword close to discord".to_string())
+      }
+    },
+  },
+  Step {
+    explanation: "`done`, `quit` or `q` leaves the shell with everything selected added to the block.",
+    task: "Leave the shell.",
+    shell: true,
+    check: |_, args| match args.first().map(String::as_str) {
+      Some("done" | "quit" | "q") => Ok(()),
+      _ => Err("Type done".to_string()),
+    },
+  },
+  Step {
+    explanation: "Schedules are made of times like 9:00, 17:30 or 5:30pm. Cold Turkey only takes times in steps of 5 minutes.",
+    task: "Enter a start time for a school-hours schedule.",
+    shell: false,
+    check: |sandbox, args| {
+      let time = suggestdialog::parse_schedule_time(&args.join(" ")).map_err(str::to_string)?;
+      sandbox.start_time = Some(time);
+      Ok(())
+    },
+  },
+  Step {
+    explanation: "The end time must be after the start time, or be midnight (0:00) for a schedule that runs to the end of the day.",
+    task: "Enter an end time for the same schedule.",
+    shell: false,
+    check: |sandbox, args| {
+      let time = suggestdialog::parse_schedule_time(&args.join(" ")).map_err(str::to_string)?;
+      match sandbox.start_time {
+        Some(start) if time != NaiveTime::MIN && time <= start => Err(format!(
+          "End time must either be after the start time ({}), or end time is midnight",
+          start.format("%H:%M")
+        )),
+        _ => Ok(()),
+      }
+    },
+  },
+];
+
+/// Walks through the app shell and schedule times of `ctk suggest`, checking each try
+pub fn learn() {
+  println!("Welcome to the ctk tutorial. Everything here happens in a practice sandbox, so nothing on your computer or in Cold Turkey changes.");
+  println!("Type `skip` to skip a step, or `exit` to leave the tutorial.");
+
+  let mut sandbox = Sandbox {
+    current_dir: ROOT.to_string(),
+    previous_dir: None,
+    selected: Vec::new(),
+    start_time: None,
+  };
+
+  for (number, step) in STEPS.iter().enumerate() {
+    println!();
+    println!(
+      "Step {} of {}: {}",
+      number + 1,
+      STEPS.len(),
+      step.explanation
+    );
+    println!("{}", step.task);
+    loop {
+      let prompt = if step.shell {
+        format!("{} >", sandbox.current_dir)
+      } else {
+        "Time".to_string()
+      };
+      let input: String = Input::new()
+        .with_prompt(prompt)
+        .allow_empty(true)
+        .loop_interact();
+      let Some(args) = shlex::split(&input).filter(|args| !args.is_empty()) else {
+        continue;
+      };
+      match args[0].as_str() {
+        "exit" => return,
+        "skip" => break,
+        _ => {}
+      }
+
+      if step.shell {
+        sandbox.run(&args);
+      }
+      match (step.check)(&mut sandbox, &args) {
+        Ok(()) => {
+          println!("Well done!");
+          break;
+        }
+        Err(why) => println!("Not quite: {why}. Try again."),
+      }
+    }
+  }
+
+  println!();
+  println!("That is everything. Run `ctk suggest` to make blocks for real, or `ctk wizard` to start from some recommended ones.");
+}
+
+fn expect_command(args: &[String], command: &str) -> Result<(), String> {
+  if args[0] == command {
+    Ok(())
+  } else {
+    Err(format!("This step is about `{command}`"))
+  }
+}
+
+fn expect_dir(sandbox: &Sandbox, dir: &str) -> Result<(), String> {
+  if sandbox.current_dir == dir {
+    Ok(())
+  } else {
+    Err(format!(
+      "You are in {} rather than {dir}",
+      sandbox.current_dir
+    ))
+  }
+}
+
+fn is_selected(sandbox: &Sandbox, file_name: &str) -> bool {
+  sandbox
+    .selected
+    .iter()
+    .any(|path| path.ends_with(&format!("/{file_name}")))
+}
+
+impl Sandbox {
+  /// Runs a shell command the way `ctk suggest` would, on the practice folders
+  fn run(&mut self, args: &[String]) {
+    match args[0].as_str() {
+      "cd" => {
+        let target = match args.get(1).map(String::as_str) {
+          None => Some(self.current_dir.clone()),
+          Some("-") => self.previous_dir.clone(),
+          Some("..") => self
+            .current_dir
+            .rsplit_once('/')
+            .map(|(parent, _)| parent.to_string()),
+          Some(folder) => Some(format!("{}/{folder}", self.current_dir)),
+        };
+        match target.filter(|target| is_dir(target)) {
+          Some(target) => {
+            self.previous_dir = Some(std::mem::replace(&mut self.current_dir, target));
+          }
+          None => println!("There is no such folder here."),
+        }
+      }
+      "ls" => {
+        let Some(patterns) = suggestdialog::parse_globs(&args[1..]) else {
+          return;
+        };
+        for path in self.children() {
+          if patterns.is_empty() || suggestdialog::matches_any_glob(&path, &patterns) {
+            println!("{path}");
+          }
+        }
+      }
+      "add" => {
+        let Some(patterns) = suggestdialog::parse_globs(&args[1..]) else {
+          return;
+        };
+        let matching: Vec<String> = self
+          .children()
+          .into_iter()
+          .filter(|path| suggestdialog::matches_any_glob(path, &patterns))
+          .collect();
+        if matching.is_empty() {
+          println!("Nothing here matches.");
+        }
+        for path in matching {
+          self.select(path);
+        }
+      }
+      "selected" => {
+        if self.selected.is_empty() {
+          println!("Nothing is selected yet.");
+        }
+        for (i, path) in self.selected.iter().enumerate() {
+          println!("{:>3}  {path}", i + 1);
+        }
+      }
+      "deselect" => {
+        let Some(patterns) = suggestdialog::parse_globs(&args[1..]) else {
+          return;
+        };
+        let mut number = 0;
+        self.selected.retain(|path| {
+          number += 1;
+          let is_picked = args[1..].contains(&number.to_string())
+            || suggestdialog::matches_any_glob(path, &patterns);
+          if is_picked {
+            println!("Deselected {path}");
+          }
+          !is_picked
+        });
+      }
+      "search" => {
+        let Some(keyword) = args.get(1) else {
+          println!("Give a keyword to search for, like `search steam`.");
+          return;
+        };
+        let matcher = Matcher::new(keyword);
+        let prefix = format!("{}/", self.current_dir);
+        let paths: Vec<String> = all_paths()
+          .into_iter()
+          .filter(|path| path.starts_with(&prefix))
+          .collect();
+        let matches = paths
+          .into_par_iter()
+          .filter_map(|path| matcher.match_string(path));
+        let best = fuzzy::top_matches(matches, SEARCH_RESULTS).into_sorted_vec();
+        if best.is_empty() {
+          println!("Nothing below here matches.");
+        }
+        for found in &best {
+          println!("{found}");
+        }
+        if let Some(found) = best.into_iter().next() {
+          self.select(found.string);
+        }
+      }
+      "done" | "quit" | "q" => {}
+      other => println!("The app shell has no {other} command."),
+    }
+  }
+
+  fn select(&mut self, path: String) {
+    if !self.selected.contains(&path) {
+      println!("Added {path}");
+      self.selected.push(path);
+    }
+  }
+
+  /// The files and folders directly inside the current folder
+  fn children(&self) -> Vec<String> {
+    let prefix = format!("{}/", self.current_dir);
+    let mut children: Vec<String> = PRACTICE_FILES
+      .iter()
+      .filter_map(|file| file.strip_prefix(&prefix))
+      .map(|rest| rest.split('/').next().unwrap_or(rest))
+      .map(|name| format!("{prefix}{name}"))
+      .collect();
+    children.sort_unstable();
+    children.dedup();
+    children
+  }
+}
+
+/// Every practice file and the folders they are in
+fn all_paths() -> Vec<String> {
+  let mut paths: Vec<String> = PRACTICE_FILES
+    .iter()
+    .flat_map(|file| {
+      file
+        .match_indices('/')
+        .map(|(end, _)| file[..end].to_string())
+        .chain(std::iter::once(file.to_string()))
+    })
+    .filter(|path| path.len() > ROOT.len())
+    .collect();
+  paths.sort_unstable();
+  paths.dedup();
+  paths
+}
+
+fn is_dir(path: &str) -> bool {
+  path == ROOT
+    || path.starts_with(ROOT)
+      && PRACTICE_FILES
+        .iter()
+        .any(|file| file.starts_with(&format!("{path}/")))
+}
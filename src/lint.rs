@@ -0,0 +1,187 @@
+use ctk_common::blocksettings;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+  /// The block will not work the way it was written
+  Error,
+  /// The block works, but probably not the way it was meant to
+  Warning,
+  /// Harmless, but worth cleaning up
+  Info,
+}
+
+impl fmt::Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Error => write!(f, "error"),
+      Self::Warning => write!(f, "warning"),
+      Self::Info => write!(f, "info"),
+    }
+  }
+}
+
+pub struct Finding {
+  pub block: String,
+  pub severity: Severity,
+  pub rule: &'static str,
+  pub message: String,
+  pub explanation: &'static str,
+}
+
+// Characters that cannot appear in a website address, so a pattern with them never matches
+const INVALID_PATTERN_CHARS: [char; 10] = [' ', '\t', '<', '>', '"', '{', '}', '|', '^', '`'];
+
+/// Runs every lint rule on every block of a .ctbbl file
+pub fn lint(blocks: &Map<String, Value>) -> Vec<Finding> {
+  let mut findings = Vec::new();
+  for (name, block) in blocks {
+    match block.as_object() {
+      Some(block) => lint_block(name, block, &mut findings),
+      None => findings.push(Finding {
+        block: name.clone(),
+        severity: Severity::Error,
+        rule: "not-a-block",
+        message: "is not a block's settings".to_string(),
+        explanation: "Every block in a .ctbbl file must be an object of settings.",
+      }),
+    }
+  }
+  findings.sort_by_key(|finding| finding.severity);
+  findings
+}
+
+fn lint_block(name: &str, block: &Map<String, Value>, findings: &mut Vec<Finding>) {
+  let mut find = |severity, rule, message: String, explanation| {
+    findings.push(Finding {
+      block: name.to_string(),
+      severity,
+      rule,
+      message,
+      explanation,
+    })
+  };
+
+  for problem in blocksettings::schema_problems(block) {
+    find(
+      Severity::Error,
+      "invalid-setting",
+      problem,
+      "Cold Turkey cannot read a setting with the wrong kind of value, so it does not import the block.",
+    );
+  }
+
+  let lock = block.get("lock").and_then(Value::as_str).unwrap_or("none");
+  if lock == "none" {
+    find(
+      Severity::Warning,
+      "no-lock",
+      "has no lock".to_string(),
+      "Anyone can stop a block without a lock as soon as it gets hard. Use a password, random text or time window lock.",
+    );
+  }
+  if lock == "password"
+    && block
+      .get("password")
+      .and_then(Value::as_str)
+      .unwrap_or("")
+      .is_empty()
+  {
+    find(
+      Severity::Error,
+      "empty-password",
+      "is locked with an empty password".to_string(),
+      "An empty password unlocks the block straight away. Set a password or use another lock.",
+    );
+  }
+
+  let is_scheduled = block.get("type").and_then(Value::as_str) == Some("scheduled");
+  let schedule_len = block
+    .get("schedule")
+    .and_then(Value::as_array)
+    .map_or(0, Vec::len);
+  if is_scheduled && schedule_len == 0 {
+    find(
+      Severity::Error,
+      "empty-schedule",
+      "is scheduled, but has nothing in its schedule".to_string(),
+      "A scheduled block only runs during its schedule, so with no schedule it never runs. Add times to the schedule or make it continuous.",
+    );
+  }
+
+  let web = string_list(block, "web");
+  let exceptions = string_list(block, "exceptions");
+
+  for (list, entries) in [("web", &web), ("exceptions", &exceptions)] {
+    let mut seen = HashSet::new();
+    for entry in entries {
+      if let Some(why) = unmatchable_reason(entry) {
+        find(
+          Severity::Warning,
+          "unmatchable-pattern",
+          format!("has {entry} in {list}, which {why}"),
+          "A pattern that cannot match a website does nothing. Check it for typos.",
+        );
+      }
+      if !seen.insert(normalize(entry)) {
+        find(
+          Severity::Info,
+          "duplicate-entry",
+          format!("has {entry} more than once in {list}"),
+          "Listing the same website twice does nothing more than listing it once.",
+        );
+      }
+    }
+  }
+
+  let exception_set: HashSet<String> = exceptions.iter().map(|entry| normalize(entry)).collect();
+  for entry in &web {
+    if exception_set.contains(&normalize(entry)) {
+      find(
+        Severity::Warning,
+        "blocked-and-excepted",
+        format!("has {entry} in both web and exceptions"),
+        "Exceptions win over the blocklist, so this website is never blocked. Remove it from one of the lists.",
+      );
+    }
+  }
+}
+
+fn string_list<'a>(block: &'a Map<String, Value>, key: &str) -> Vec<&'a str> {
+  block
+    .get(key)
+    .and_then(Value::as_array)
+    .map(|entries| entries.iter().filter_map(Value::as_str).collect())
+    .unwrap_or_default()
+}
+
+fn normalize(entry: &str) -> String {
+  entry.trim().trim_end_matches('/').to_lowercase()
+}
+
+/// Why a blocklist or exception pattern can never match, if it cannot
+fn unmatchable_reason(entry: &str) -> Option<&'static str> {
+  let address = entry.split_once("://").map_or(entry, |(_, rest)| rest);
+  let host = address.split('/').next().unwrap_or("");
+
+  if entry.trim().is_empty() {
+    Some("is empty")
+  } else if entry.contains(INVALID_PATTERN_CHARS) {
+    Some("has characters no website address can have")
+  } else if host.is_empty() && !entry.contains("://") {
+    Some("has no website")
+  } else if !host.is_empty()
+    && host != "*"
+    && host
+      .strip_prefix("*.")
+      .unwrap_or(host)
+      .split('.')
+      .any(str::is_empty)
+  {
+    Some("has an empty part between dots")
+  } else {
+    None
+  }
+}
@@ -1,418 +1,3893 @@
-use chrono::{Date, DateTime, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
-use clap::Parser;
-use ctk_common::cli_parser::{ColdTurkey, Command, StartSubcommands};
-use ctk_common::ctsettings::{ColdTurkeySettings, UserStatus};
+use blocker::{BlockerCommand, StartLock};
+use blocktree::BlockTree;
+use capability::{Capabilities, Requirement};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, ValueEnum};
+use clap_complete::env::Shells;
+use clap_complete::{CompleteEnv, Shell};
+use clock::{Clock, SystemClock};
 use colour::e_yellow_ln;
-use dialoguer::Password;
+use config::Config;
+use ctk_common::blocksettings::{self, AppString, BlockSettings, SchedType};
+use ctk_common::cli_parser::{
+  BlocklistSubcommands, ColdTurkey, Command, CompletionShell, ConfigSubcommands, EndTime,
+  HistorySubcommands, PauseSubcommands, ProtocolSubcommands, ScheduleSubcommands,
+  SettingsSubcommands, StartSubcommands, StatsSubcommands, SyncSubcommands, TemplateSubcommands,
+  TmpSubcommands,
+};
+use ctk_common::ctsettings::{self, BlockInfo, ColdTurkeySettings, GlobalSetting, UserStatus};
+use ctk_common::timeout;
+use dialoguer::{Confirm, Password};
+use error::{ctk_error, error, failure, ErrorCode, EXIT_NOT_APPLIED, EXIT_UNVERIFIED};
+use journal::Event;
+use loop_dialoguer::LoopDialogue;
+use oplock::OperationLock;
+use serde::Serialize;
+use sidecar::Sidecar;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::Write;
-use std::path::Path;
-use std::{fs::File, process};
+use std::fs::File;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, ExitCode};
+use summary::{success, Status, Table};
+use template::Variables;
 use zeroize::Zeroizing;
 
-mod blocksettings;
+mod autosave;
+mod blocker;
+mod blocklist;
+mod blocktree;
+mod bridge;
+mod browserhistory;
+mod capability;
+mod categories;
+mod clock;
+mod config;
+mod countdown;
+mod error;
+mod filewatch;
 mod historydeque;
+mod hosttrace;
+mod htmlreport;
+mod journal;
+mod learn;
+mod lint;
 mod loop_dialoguer;
-mod matchstring;
+mod oplock;
+mod picker;
+mod plan;
+mod platform;
+mod pomodoro;
+mod protocol;
+mod purge;
+mod randomtext;
+mod report;
+mod schedule;
+mod session;
+mod settingsfeed;
+mod setup;
+mod sidecar;
+mod snapshot;
+mod status;
+mod streak;
 mod suggestdialog;
-
-const CT_EXEC: &str = r"C:\Program Files\Cold Turkey\Cold Turkey Blocker.exe";
+mod summary;
+mod sync;
+mod template;
+#[cfg(test)]
+mod testutil;
+mod toast;
+mod undostack;
+mod verify;
+mod watch;
+mod wizard;
+mod workspace;
 
 const FROZEN_TURKEY: &str = "Frozen Turkey";
 
-fn main() {
+// Cold Turkey has no way to report or delete everything in a block from the command line,
+// so block edits are limited to what it does report
+const EXPORT_NOTE: &str =
+  "Only the websites, exceptions, window titles and password or random text lock of a block are kept.";
+
+/// How long `ctk tmp start` waits for the import of its block to be confirmed in Cold Turkey
+const TMP_IMPORT_WAIT: std::time::Duration = std::time::Duration::from_secs(2 * 60);
+
+fn main() -> ExitCode {
+  // Block names on the command line are checked against Cold Turkey's settings while the
+  // arguments are parsed, so the config has to say where to read them from before that,
+  // and the host trace has to be on to catch it
+  if let Some(path) = hosttrace::trace_flag(std::env::args_os().skip(1)) {
+    hosttrace::enable(path);
+  }
+  let config = Config::load();
+  ctsettings::set_msg_host_path(config.msg_host_path());
+  ctsettings::set_msg_host_timeout(config.msg_host_timeout());
+  journal::set_rotation(config.journal_rotation());
+  // Shells ask for completions by running ctk with COMPLETE set, which answers them here and
+  // exits, reading the block names fresh each time
+  CompleteEnv::with_factory(ColdTurkey::command).complete();
   let args = ColdTurkey::parse();
+
+  if let Some(cmd) = &args.command {
+    if config.read_only() && changes_cold_turkey(cmd) {
+      ctk_error!(
+        ErrorCode::ReadOnly,
+        "ctk is read-only on this machine, so this command is turned off"
+      );
+      return error::exit_code();
+    }
+  }
+  // Checked up front, so commands working on files never fail for want of Cold Turkey
+  let requirement = args
+    .command
+    .as_ref()
+    .map_or(Requirement::Blocker, requirement);
+  if let Err((code, why)) = Capabilities::detect(&config).check(requirement) {
+    ctk_error!(code, "{why}");
+    return error::exit_code();
+  }
+  if let Some(cmd) = &args.command {
+    if needs_elevation(cmd) && !platform::is_elevated() {
+      if args.elevate {
+        match platform::relaunch_elevated() {
+          Ok(status) => process::exit(status.code().unwrap_or(1)),
+          Err(err) => {
+            error!("Cannot relaunch ctk as an administrator: {err}");
+            return error::exit_code();
+          }
+        }
+      }
+      eprintln!("WARNING: This command usually needs administrator rights. If it fails, run it again with --elevate.");
+    }
+  }
+  let lock = match &args.command {
+    Some(cmd) if takes_operation_lock(cmd) => match OperationLock::acquire() {
+      Ok(lock) => Some(lock),
+      Err(err) => {
+        ctk_error!(
+          ErrorCode::OperationRunning,
+          "Cannot run this command: {err}"
+        );
+        return error::exit_code();
+      }
+    },
+    _ => None,
+  };
   match &args.command {
     Some(cmd) => match &cmd {
       Command::Start {
-        block_name,
+        block_names,
+        all,
         password,
+        snapshot,
+        watch,
         subcommand,
-      } => match password {
-        true => start_block_with_password(block_name),
-        false => match subcommand {
-          Some(method) => match method {
-            StartSubcommands::For {
-              minutes,
-              hours,
-              days,
-            } => {
-              let total_minutes =
-                days.unwrap_or(0) * 24 * 60 + hours.unwrap_or(0) * 60 + minutes.unwrap_or(0);
-              start_block_for_some_minutes(block_name, total_minutes);
-            }
-            StartSubcommands::Until { endtime, enddate } => {
-              start_block_until_time(block_name, *endtime, *enddate);
-            }
-          },
-          None => start_block_unlocked(block_name),
-        },
+      } => {
+        let Some(block_names) = names_or_picked(block_names, *all, "start") else {
+          return error::exit_code();
+        };
+        let Some((blocks, missing)) = blocks_given(&config, &block_names, *all, "start") else {
+          return error::exit_code();
+        };
+        let blocks = with_also_started(&config, blocks);
+        if let (false, Some(StartSubcommands::Until { end, yes: false })) = (password, subcommand) {
+          if !confirm_long_lock(&config, &SystemClock, end) {
+            return error::exit_code();
+          }
+        }
+        if *snapshot && !take_snapshot(&blocks, &lock_description(*password, subcommand)) {
+          return error::exit_code();
+        }
+        // A group of blocks is locked with one password, asked for once
+        let mut lock_password = None;
+        let started_at = Local::now();
+        let statuses: Vec<Status> = summary::with_table(blocks.len() + missing.len() > 1, || {
+          blocks
+            .iter()
+            .map(|block_name| match (password, subcommand) {
+              (true, _) => start_block_with_password(&config, block_name, &mut lock_password),
+              (
+                false,
+                Some(StartSubcommands::For {
+                  duration,
+                  minutes,
+                  hours,
+                  days,
+                }),
+              ) => start_block_for_some_minutes(
+                &config,
+                block_name,
+                total_minutes(*duration, *minutes, *hours, *days),
+              ),
+              (false, Some(StartSubcommands::Until { end, .. })) => {
+                start_block_until_time(&config, &SystemClock, block_name, end)
+              }
+              (false, None) => start_block_unlocked(&config, block_name),
+            })
+            .collect()
+        });
+        summarize("started", &blocks, &statuses, &missing);
+        if *watch {
+          // Counting down can take hours, and other commands should not wait for it
+          drop(lock);
+          count_down_lock(&blocks, &statuses, subcommand, started_at);
+          return error::exit_code();
+        }
+      }
+      Command::RollbackPlan { block_name } => match get_ct_settings() {
+        Some(settings) => snapshot::print_rollback_plan(&settings, block_name),
+        None => ctk_error!(
+          ErrorCode::SettingsUnreadable,
+          "ctk cannot read block {block_name} right now, so it cannot plan for it"
+        ),
       },
-      Command::Stop { block_name } => stop_block(block_name),
+      Command::Stop {
+        block_names,
+        all,
+        when_unlocked,
+      } => {
+        let Some(block_names) = names_or_picked(block_names, *all, "stop") else {
+          return error::exit_code();
+        };
+        let Some((blocks, missing)) = blocks_given(&config, &block_names, *all, "stop") else {
+          return error::exit_code();
+        };
+        let statuses: Vec<Status> = summary::with_table(blocks.len() + missing.len() > 1, || {
+          blocks
+            .iter()
+            .map(|block_name| {
+              if *when_unlocked {
+                stop_block_when_unlocked(&config, block_name)
+              } else {
+                stop_block(&config, block_name)
+              }
+            })
+            .collect()
+        });
+        let done = if *when_unlocked {
+          "stopped or queued"
+        } else {
+          "stopped"
+        };
+        summarize(done, &blocks, &statuses, &missing);
+      }
+      Command::Frozen { subcommand } => start_frozen_turkey(&config, &SystemClock, subcommand),
+      Command::Pause {
+        block_name,
+        subcommand,
+      } => pause_block(&config, &SystemClock, block_name, subcommand),
       Command::Add {
         block_name,
-        url,
+        urls,
+        except,
+        list,
+        stdin,
+      } => {
+        if *list {
+          list_block_websites(block_name, *except);
+        } else if *stdin {
+          let Some(urls_read) = urls_from_stdin() else {
+            return error::exit_code();
+          };
+          let urls = [urls.as_slice(), &urls_read].concat();
+          if urls.is_empty() {
+            error!("No urls to add were given on standard input");
+          } else {
+            add_websites_with_categories(&config, block_name, &urls, *except);
+          }
+        } else {
+          add_websites_with_categories(&config, block_name, urls, *except);
+        }
+      }
+      Command::Quickadd { url, from_history } => quick_add(&config, url.as_deref(), *from_history),
+      Command::Remove {
+        block_name,
+        urls,
+        except,
+        yes,
+      } => remove_websites_from_block(&config, block_name, urls, *except, *yes),
+      Command::CopyBlock {
+        old_name,
+        new_name,
+        yes,
+      } => copy_block(&config, old_name, new_name, *yes),
+      Command::EmptyBlock { block_name, yes } => empty_block(&config, block_name, *yes),
+      Command::Toggle { block_names, all } => {
+        let Some(block_names) = names_or_picked(block_names, *all, "toggle") else {
+          return error::exit_code();
+        };
+        let Some((blocks, missing)) = blocks_given(&config, &block_names, *all, "toggle") else {
+          return error::exit_code();
+        };
+        let statuses: Vec<Status> = summary::with_table(blocks.len() + missing.len() > 1, || {
+          blocks
+            .iter()
+            .map(|block_name| toggle_block(&config, block_name))
+            .collect()
+        });
+        summarize("toggled", &blocks, &statuses, &missing);
+      }
+      Command::Tmp { subcommand } => match subcommand {
+        TmpSubcommands::Start {
+          web,
+          duration,
+          name,
+        } => start_temporary_block(&config, web, *duration, name.as_deref()),
+        TmpSubcommands::Clean { yes } => clean_temporary_blocks(&config, *yes),
+      },
+      Command::PurgeExpired { keep, yes } => purge_expired(&config, *keep, *yes),
+      Command::Suggest {
+        ask_breaks,
+        target_version,
+        name,
+        resume,
+        output_dir,
+        from_history,
+      } => {
+        suggestdialog::suggest(
+          *ask_breaks,
+          *target_version,
+          name.as_deref(),
+          resume.as_deref(),
+          output_dir.as_deref(),
+          *from_history,
+        );
+      }
+      Command::Wizard => run_wizard(&config),
+      Command::Plan => run_plan(&config),
+      Command::Learn => learn::learn(),
+      Command::Doctor => run_doctor(&config),
+      Command::List { tree, verbose } => match args.json {
+        true => list_blocks_json(&config),
+        false => list_all_blocks(&config, *tree, *verbose),
+      },
+      Command::Schedule { subcommand } => match subcommand {
+        ScheduleSubcommands::Simulate { time, date, block } => {
+          simulate_schedule(&SystemClock, *time, *date, block.as_deref())
+        }
+        ScheduleSubcommands::View { block_name } => view_schedule(block_name),
+        ScheduleSubcommands::ImportCsv {
+          file,
+          block,
+          output,
+          yes,
+        } => import_schedule_csv(&config, file, block, output.as_deref(), *yes),
+      },
+      Command::Status {
+        block_name,
+        watch: true,
+        interval,
+      } => status::watch_status(block_name.as_deref(), *interval, args.json),
+      Command::Status { block_name, .. } => match get_ct_settings() {
+        Some(settings) if args.json => status::print_status_json(&settings, block_name.as_deref()),
+        Some(settings) => status::print_status(&settings, block_name.as_deref()),
+        None => ctk_error!(
+          ErrorCode::SettingsUnreadable,
+          "ctk cannot read the state of your blocks right now"
+        ),
+      },
+      Command::Check {
+        block_name,
+        verbose,
+      } => {
+        let locked_until = schedule::lock_end(&journal::read_entries(), block_name, Local::now());
+        let settings = get_ct_settings();
+        error::fail(status::check_block(
+          settings.as_ref(),
+          locked_until,
+          block_name,
+          *verbose,
+        ));
+      }
+      Command::UnlockHint { block_name } => unlock_hint(block_name),
+      Command::Note {
+        block_name,
+        note,
+        clear,
+      } => note_block(block_name, note.as_deref(), *clear),
+      Command::Tag {
+        block_name,
+        tags,
+        remove,
+      } => tag_block(block_name, tags, *remove),
+      Command::Tags => list_tags(&config),
+      Command::Report {
+        week,
+        days,
+        html,
+        output,
+        template,
+      } => {
+        let entries = journal::read_entries();
+        let period = match week {
+          true => report::Period::ThisWeek,
+          false => report::Period::Days(days.unwrap_or(7)),
+        };
+        if *html {
+          write_html_report(&entries, period, output.as_deref(), template.as_deref());
+        } else {
+          report::print_report(&entries, period);
+        }
+      }
+      Command::Stats { subcommand } => match subcommand {
+        StatsSubcommands::Pomodoro { days } => {
+          report::print_pomodoro_stats(&journal::read_entries(), days.unwrap_or(7))
+        }
+      },
+      Command::History { subcommand } => match subcommand {
+        HistorySubcommands::Export { output } => export_history(args.json, output.as_deref()),
+      },
+      Command::Streak {
+        block_name,
+        goal,
+        notify,
+      } => show_streak(&config, block_name.as_deref(), *goal, *notify),
+      Command::Watch {
+        rules,
+        dry_run,
+        diff,
+      } => {
+        if *dry_run {
+          preview_watch(&config, rules.as_deref());
+        } else if *diff {
+          diff_watch(&config, rules.as_deref());
+        } else {
+          start_watching(&config, rules.as_deref());
+        }
+      }
+      Command::Pomodoro {
+        block_name,
+        work,
+        break_minutes,
+        cycles,
+      } => run_pomodoros(
+        &config,
+        block_name,
+        &pomodoro::Plan {
+          work: *work,
+          break_minutes: *break_minutes,
+          cycles: *cycles,
+        },
+      ),
+      Command::Settings { subcommand } => match subcommand {
+        Some(SettingsSubcommands::Set { key, value }) => set_global_setting(&config, *key, *value),
+        None => list_global_settings(),
+      },
+      Command::Config { subcommand } => match subcommand {
+        ConfigSubcommands::Show { origin } => show_config(&config, *origin),
+        ConfigSubcommands::Get { key } => get_config_value(&config, key),
+        ConfigSubcommands::Set { key, value } => set_config_value(key, value),
+        ConfigSubcommands::List => list_config_keys(&config),
+        ConfigSubcommands::Edit => edit_config(),
+      },
+      Command::Explain { code } => explain_error(code.as_deref()),
+      Command::Import { file, watch } => import_ctbbl_file(&config, file, *watch),
+      Command::MergeInto {
+        file,
+        block,
+        web,
         except,
-      } => add_websites_to_block(block_name, url, *except),
-      Command::Toggle { block_name } => toggle_block(block_name),
-      Command::Suggest => {
-        suggestdialog::suggest();
+        app,
+        folder,
+        title,
+        target_version,
+      } => {
+        let apps = app
+          .iter()
+          .cloned()
+          .map(AppString::File)
+          .chain(folder.iter().cloned().map(AppString::Folder))
+          .chain(title.iter().cloned().map(AppString::Title))
+          .collect::<Vec<_>>();
+        merge_into_ctbbl_file(file, block, web, except, &apps, *target_version)
       }
-      Command::List => list_all_blocks(),
-      Command::Install { force } => install_cold_turkey(*force),
+      Command::Lint { file } => lint_ctbbl_file(file),
+      Command::Template { subcommand } => match subcommand {
+        TemplateSubcommands::Import { file } => import_template(&config, file),
+        TemplateSubcommands::Expand {
+          file,
+          output,
+          target_version,
+        } => expand_template(&config, file, output.as_deref(), *target_version),
+        TemplateSubcommands::Make {
+          file,
+          output,
+          target_version,
+        } => make_template(&config, file, output.as_deref(), *target_version),
+        TemplateSubcommands::Vars => list_template_variables(&config),
+      },
+      Command::Sync { subcommand } => match subcommand {
+        SyncSubcommands::Push { force } => push_sync(&config, *force),
+        SyncSubcommands::Pull { force } => pull_sync(&config, *force),
+      },
+      Command::Blocklist { subcommand } => match subcommand {
+        BlocklistSubcommands::Subscribe {
+          block_name,
+          url,
+          yes,
+        } => subscribe_blocklist(&config, block_name, url, *yes),
+        BlocklistSubcommands::Unsubscribe { block_name, url } => {
+          unsubscribe_blocklist(block_name, url)
+        }
+        BlocklistSubcommands::List => list_blocklists(),
+        BlocklistSubcommands::Update { block_name, yes } => {
+          update_blocklists(&config, block_name.as_deref(), *yes)
+        }
+      },
+      Command::Protocol { subcommand } => match subcommand {
+        ProtocolSubcommands::Register => match protocol::register() {
+          Ok(()) => success!("Opens ctk:// links with ctk"),
+          Err(err) => error!("Cannot register ctk:// links: {err}"),
+        },
+        ProtocolSubcommands::Unregister => match protocol::unregister() {
+          Ok(()) => success!("Stops opening ctk:// links with ctk"),
+          Err(err) => error!("Cannot unregister ctk:// links: {err}"),
+        },
+        ProtocolSubcommands::Open { link } => open_protocol_link(&config, link),
+      },
+      Command::CompleteSetup {
+        dry_run,
+        add_to_path,
+      } => complete_setup(*dry_run, *add_to_path),
+      Command::Completions {
+        shell,
+        static_script,
+      } => print_completions(*shell, *static_script),
+      Command::Install { force } => install_cold_turkey(&config, *force),
+    },
+    None => open_cold_turkey(&config),
+  }
+
+  drop(lock);
+  error::exit_code()
+}
+
+/// Whether the command starts, stops or changes blocks, or changes Cold Turkey's settings
+fn changes_cold_turkey(cmd: &Command) -> bool {
+  match cmd {
+    Command::Start { .. }
+    | Command::Stop { .. }
+    | Command::Frozen { .. }
+    | Command::Pause { .. }
+    | Command::Add { .. }
+    | Command::Quickadd { .. }
+    | Command::Remove { .. }
+    | Command::CopyBlock { .. }
+    | Command::EmptyBlock { .. }
+    | Command::Toggle { .. }
+    | Command::Tmp { .. }
+    | Command::PurgeExpired { .. }
+    | Command::Watch {
+      dry_run: false,
+      diff: false,
+      ..
+    }
+    | Command::Pomodoro { .. }
+    | Command::Import { .. }
+    | Command::Wizard
+    | Command::Plan
+    | Command::Install { .. } => true,
+    Command::Settings { subcommand } => subcommand.is_some(),
+    Command::Template { subcommand } => matches!(subcommand, TemplateSubcommands::Import { .. }),
+    Command::Sync { subcommand } => matches!(subcommand, SyncSubcommands::Pull { .. }),
+    Command::Blocklist { subcommand } => matches!(
+      subcommand,
+      BlocklistSubcommands::Subscribe { .. } | BlocklistSubcommands::Update { .. }
+    ),
+    Command::Protocol { subcommand } => matches!(subcommand, ProtocolSubcommands::Open { .. }),
+    Command::Schedule { subcommand } => matches!(
+      subcommand,
+      ScheduleSubcommands::ImportCsv { output: None, .. }
+    ),
+    _ => false,
+  }
+}
+
+/// What of Cold Turkey the command needs, so the rest keep working when it is not installed
+fn requirement(cmd: &Command) -> Requirement {
+  match cmd {
+    Command::Start { .. }
+    | Command::Stop { .. }
+    | Command::Frozen { .. }
+    | Command::Pause { .. }
+    | Command::Add { list: false, .. }
+    | Command::Quickadd { .. }
+    | Command::Remove { .. }
+    | Command::CopyBlock { .. }
+    | Command::EmptyBlock { .. }
+    | Command::Toggle { .. }
+    | Command::Tmp { .. }
+    | Command::Watch {
+      dry_run: false,
+      diff: false,
+      ..
+    }
+    | Command::Pomodoro { .. }
+    | Command::Plan
+    | Command::Import { watch: true, .. }
+    | Command::Template {
+      subcommand: TemplateSubcommands::Import { .. },
+    }
+    | Command::Protocol {
+      subcommand: ProtocolSubcommands::Open { .. },
+    }
+    | Command::Blocklist {
+      subcommand: BlocklistSubcommands::Subscribe { .. } | BlocklistSubcommands::Update { .. },
+    }
+    | Command::Schedule {
+      subcommand: ScheduleSubcommands::ImportCsv { output: None, .. },
+    } => Requirement::Blocker,
+    Command::Add { list: true, .. }
+    | Command::RollbackPlan { .. }
+    | Command::List { .. }
+    | Command::Schedule {
+      subcommand: ScheduleSubcommands::Simulate { .. } | ScheduleSubcommands::View { .. },
+    }
+    | Command::Status { .. } => Requirement::Settings,
+    Command::Settings { subcommand } => match subcommand {
+      Some(_) => Requirement::Blocker,
+      None => Requirement::Settings,
+    },
+    Command::Sync { subcommand } => match subcommand {
+      SyncSubcommands::Push { .. } => Requirement::Settings,
+      SyncSubcommands::Pull { .. } => Requirement::Blocker,
     },
-    None => open_cold_turkey(),
+    // The wizard saves its blocks either way, and `ctk import` checks the file either way,
+    // both only going on to Cold Turkey if it is there
+    _ => Requirement::Nothing,
   }
 }
 
+/// Long-running commands take the operation lock for each change instead, so they do not
+/// hold up everything else
+fn takes_operation_lock(cmd: &Command) -> bool {
+  changes_cold_turkey(cmd)
+    && !matches!(
+      cmd,
+      Command::Watch { .. } | Command::Pomodoro { .. } | Command::Plan | Command::Import { .. }
+    )
+}
+
+fn needs_elevation(cmd: &Command) -> bool {
+  // The installer writes to Program Files
+  matches!(cmd, Command::Install { .. })
+}
+
 fn check_if_block_exists(block_name: &str) -> Option<bool> {
   if block_name == FROZEN_TURKEY {
     return Some(true);
   }
 
-  let ct_settings = get_ct_settings();
-  if let Some(settings) = &ct_settings {
-    if settings.block_list_info.blocks.contains_key(block_name) {
-      Some(true)
-    } else {
-      eprintln!(
-        "ERROR: Block {} cannot be found in your Cold Turkey application",
-        block_name
-      );
-      Some(false)
+  let ct_settings = get_ct_settings();
+  if let Some(settings) = &ct_settings {
+    if settings.block_list_info.blocks.contains_key(block_name) {
+      Some(true)
+    } else {
+      ctk_error!(
+        ErrorCode::BlockNotFound,
+        "Block {} cannot be found in your Cold Turkey application",
+        block_name
+      );
+      Some(false)
+    }
+  } else {
+    eprintln!(
+      "WARNING: ctk cannot check if block {} is in your Cold Turkey application right now",
+      block_name
+    );
+    None
+  }
+}
+
+fn total_minutes(
+  duration: Option<std::time::Duration>,
+  minutes: Option<u32>,
+  hours: Option<u32>,
+  days: Option<u32>,
+) -> u32 {
+  match duration {
+    // Part minutes are rounded up, as Cold Turkey only locks for whole minutes
+    Some(duration) => u32::try_from(duration.as_secs().div_ceil(60)).unwrap_or(u32::MAX),
+    None => days.unwrap_or(0) * 24 * 60 + hours.unwrap_or(0) * 60 + minutes.unwrap_or(0),
+  }
+}
+
+/// The block names a command like `ctk start` was given, or, with none given in a terminal,
+/// the ones chosen from a list of every block
+fn names_or_picked(block_names: &[String], all: bool, verb: &str) -> Option<Vec<String>> {
+  if all || !block_names.is_empty() {
+    return Some(block_names.to_vec());
+  }
+  if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+    let mut cli = ColdTurkey::command();
+    // Gives the subcommand its full name, like `ctk stop`, in the usage
+    cli.build();
+    let error = match cli.find_subcommand_mut(verb) {
+      Some(command) => command.error(
+        ErrorKind::MissingRequiredArgument,
+        "a block name or --all is needed when ctk cannot ask which blocks to use",
+      ),
+      None => cli.error(ErrorKind::MissingRequiredArgument, "a block name is needed"),
+    };
+    error.exit();
+  }
+
+  let Some(settings) = get_ct_settings() else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot read your blocks right now, so it cannot list them. Give the block names instead"
+    );
+    return None;
+  };
+  let picked = picker::pick_blocks(&settings, verb, FROZEN_TURKEY);
+  if picked.is_empty() {
+    eprintln!("No blocks chosen, so there is nothing to {verb}.");
+    return None;
+  }
+  Some(picked)
+}
+
+/// The blocks a command like `ctk start` was given, with groups resolved, or every block
+/// but Frozen Turkey with --all, followed by the ones that cannot be found. Those are
+/// reported together and left out, so the command still runs on the rest
+fn blocks_given(
+  config: &Config,
+  block_names: &[String],
+  all: bool,
+  verb: &str,
+) -> Option<(Vec<String>, Vec<String>)> {
+  let settings = get_ct_settings();
+  if all {
+    let Some(settings) = settings else {
+      ctk_error!(
+        ErrorCode::SettingsUnreadable,
+        "ctk cannot read your blocks right now, so it cannot {verb} them all"
+      );
+      return None;
+    };
+    let mut blocks: Vec<String> = settings
+      .block_list_info
+      .blocks
+      .into_keys()
+      .filter(|block| block != FROZEN_TURKEY)
+      .collect();
+    blocks.sort_unstable();
+    return Some((blocks, Vec::new()));
+  }
+
+  let mut blocks: Vec<String> = Vec::new();
+  for block in block_names
+    .iter()
+    .flat_map(|name| blocks_named(config, name))
+  {
+    if !blocks.contains(&block) {
+      blocks.push(block);
+    }
+  }
+  // Without the settings, each block warns that it cannot be checked when the command runs
+  let mut missing = Vec::new();
+  if let Some(settings) = settings {
+    let found;
+    (found, missing) = blocks.into_iter().partition(|block| {
+      block == FROZEN_TURKEY || settings.block_list_info.blocks.contains_key(block)
+    });
+    if !missing.is_empty() {
+      ctk_error!(
+        ErrorCode::BlockNotFound,
+        "{} cannot be found in your Cold Turkey application, so ctk does not {verb} {}",
+        missing.join(", "),
+        if missing.len() == 1 { "it" } else { "them" }
+      );
+    }
+    blocks = found;
+  }
+  (!blocks.is_empty()).then_some((blocks, missing))
+}
+
+/// Adds the blocks that also_start rules in the config start along with the given ones,
+/// and the ones those start in turn
+fn with_also_started(config: &Config, mut blocks: Vec<String>) -> Vec<String> {
+  let mut index = 0;
+  while index < blocks.len() {
+    let block_name = blocks[index].clone();
+    for also in config.also_start(&block_name) {
+      if !blocks.iter().any(|block| block == also) {
+        eprintln!(
+          "Also starts {also} with {block_name}, as also_start.{block_name} in your config says"
+        );
+        blocks.push(also.to_string());
+      }
+    }
+    index += 1;
+  }
+  blocks
+}
+
+/// Whether a keep_on_while rule in the config stops the block from being stopped right now
+fn kept_on_by_rule(config: &Config, block_name: &str) -> bool {
+  let keepers = config.keep_on_while(block_name);
+  if keepers.is_empty() {
+    return false;
+  }
+  let Some(settings) = get_ct_settings() else {
+    ctk_error!(
+      ErrorCode::BlockedByRule,
+      "ctk cannot check whether {} is running, so it does not stop {block_name}",
+      keepers.join(" or ")
+    );
+    return true;
+  };
+  let running = keepers.into_iter().find(|keeper| {
+    settings
+      .block_list_info
+      .blocks
+      .get(*keeper)
+      .is_some_and(|info| !info.is_dormant())
+  });
+  match running {
+    Some(keeper) => {
+      ctk_error!(
+        ErrorCode::BlockedByRule,
+        "Block {block_name} stays on while {keeper} is running, as keep_on_while.{block_name} in your config says"
+      );
+      true
+    }
+    None => false,
+  }
+}
+
+/// How `ctk start` is about to lock, in words for the snapshot
+fn lock_description(password: bool, subcommand: &Option<StartSubcommands>) -> String {
+  match subcommand {
+    _ if password => "with a password".to_string(),
+    Some(StartSubcommands::For {
+      duration,
+      minutes,
+      hours,
+      days,
+    }) => format!(
+      "for {} minutes",
+      total_minutes(*duration, *minutes, *hours, *days)
+    ),
+    Some(StartSubcommands::Until { end, .. }) => match end.date {
+      Some(date) => format!("until {} on {date}", end.time),
+      None => format!("until {}", end.time),
+    },
+    None => "unlocked".to_string(),
+  }
+}
+
+/// Counts down the lock `ctk start --watch` gave the blocks that started
+fn count_down_lock(
+  blocks: &[String],
+  statuses: &[Status],
+  subcommand: &Option<StartSubcommands>,
+  started_at: DateTime<Local>,
+) {
+  let started: Vec<String> = blocks
+    .iter()
+    .zip(statuses)
+    .filter(|(_, status)| **status != Status::Failed)
+    .map(|(block, _)| block.clone())
+    .collect();
+  if started.is_empty() {
+    return;
+  }
+  let end = match subcommand {
+    Some(StartSubcommands::For {
+      duration,
+      minutes,
+      hours,
+      days,
+    }) => {
+      Some(started_at + Duration::minutes(total_minutes(*duration, *minutes, *hours, *days).into()))
+    }
+    Some(StartSubcommands::Until { end, .. }) => {
+      clock::end_datetime(&SystemClock, end.time, end.date).ok()
+    }
+    None => None,
+  };
+  match end {
+    Some(end) if io::stderr().is_terminal() => countdown::count_down(&started, started_at, end),
+    Some(_) => eprintln!("WARNING: --watch only counts down in a terminal"),
+    None => {
+      eprintln!("WARNING: --watch needs a lock to count down, like `for 1h` or `until 17:00`")
+    }
+  }
+}
+
+/// Shows how long a lock until `end` would be and asks to go ahead when it is longer than
+/// confirm_lock_over, since a wrong date can lock someone out for weeks
+fn confirm_long_lock(config: &Config, clock: &impl Clock<Tz = Local>, end: &EndTime) -> bool {
+  // Times that cannot be locked until are reported when the block starts
+  let Ok((datetime, minutes)) = clock::lock_until(clock, end.time, end.date) else {
+    return true;
+  };
+  let threshold = config.confirm_lock_over().as_secs() / 60;
+  if u64::try_from(minutes).is_ok_and(|minutes| minutes <= threshold) {
+    return true;
+  }
+  eprintln!(
+    "WARNING: This locks until {}, which is {} from now",
+    datetime.format("%H:%M %A %B %d %Y"),
+    clock::describe_minutes(minutes)
+  );
+  if confirm_continue() {
+    true
+  } else {
+    eprintln!("Does not start anything, so nothing is locked");
+    false
+  }
+}
+
+/// Saves Cold Turkey's settings before `ctk start --snapshot` starts anything. Starting
+/// without the snapshot asked for would defeat the point, so failing stops the command
+fn take_snapshot(blocks: &[String], lock: &str) -> bool {
+  let Some(settings) = get_ct_settings() else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot read Cold Turkey's settings right now, so it cannot take a snapshot. Nothing was started."
+    );
+    return false;
+  };
+  match snapshot::take(&settings, blocks, lock) {
+    Ok(file) => {
+      success!(
+        "Saves a snapshot of Cold Turkey's settings to {}",
+        file.display()
+      );
+      for block in blocks {
+        eprintln!(
+          "See what you can still change about {block} with `ctk rollback-plan \"{block}\"`"
+        );
+      }
+      journal::record(Event::Snapshot {
+        blocks: blocks.to_vec(),
+        file,
+      });
+      true
+    }
+    Err(err) => {
+      error!("ctk cannot save the snapshot, so nothing was started: {err}");
+      false
+    }
+  }
+}
+
+fn start_block_with_password(
+  config: &Config,
+  block_name: &str,
+  password: &mut Option<Zeroizing<String>>,
+) -> Status {
+  if block_name == FROZEN_TURKEY {
+    ctk_error!(ErrorCode::FrozenTurkey, "You can only start Frozen Turkey when time is provided. Consider `ctk frozen for` or `ctk frozen until`.");
+    return Status::Failed;
+  }
+
+  let ct_settings = get_ct_settings();
+  if let Some(settings) = &ct_settings {
+    if settings.is_pro == UserStatus::Free {
+      ctk_error!(
+        ErrorCode::ProRequired,
+        "Cannot start a block with a password as a free user. Consider upgrading to pro."
+      );
+      return Status::Failed;
+    }
+
+    if !settings.block_list_info.blocks.contains_key(block_name) {
+      ctk_error!(
+        ErrorCode::BlockNotFound,
+        "Block {} cannot be found in your Cold Turkey application",
+        block_name
+      );
+      return Status::Failed;
+    }
+  } else {
+    eprintln!(
+      "WARNING: ctk cannot check if block {} is in your Cold Turkey application right now",
+      block_name
+    );
+  }
+
+  let p = password.get_or_insert_with(|| {
+    Zeroizing::new(loop {
+      match Password::new().with_prompt("Enter a password").interact() {
+        Ok(pass) => break pass,
+        Err(_) => continue,
+      }
+    })
+  });
+
+  let start = BlockerCommand::Start {
+    block: block_name,
+    lock: StartLock::Password(p.as_str()),
+  };
+  match blocker::run(config, start) {
+    Ok(_) => {
+      let status = start_applied(config, "ctk start --password", block_name);
+      if status != Status::Done {
+        return status;
+      }
+      success!("Starts blocking {} with a password", block_name);
+      journal::record(Event::Start {
+        block: block_name.to_string(),
+        lock_minutes: None,
+      });
+      Status::Done
+    }
+    Err(err) => {
+      ctk_error!(err.code(), "Cannot run `ctk start --password`: {err}");
+      Status::Failed
+    }
+  }
+}
+
+/// Starts Frozen Turkey, which Cold Turkey only has in Pro, after showing when it unlocks
+fn start_frozen_turkey(
+  config: &Config,
+  clock: &impl Clock<Tz = Local>,
+  subcommand: &StartSubcommands,
+) {
+  match get_ct_settings() {
+    Some(settings) if settings.is_pro == UserStatus::Free => {
+      ctk_error!(
+        ErrorCode::ProRequired,
+        "Cannot start Frozen Turkey as a free user. Consider upgrading to pro."
+      );
+      return;
+    }
+    Some(_) => {}
+    None => eprintln!("WARNING: ctk cannot check if your Cold Turkey has Frozen Turkey right now"),
+  }
+
+  let (unlocks, minutes) = match subcommand {
+    StartSubcommands::For {
+      duration,
+      minutes,
+      hours,
+      days,
+    } => {
+      let minutes = total_minutes(*duration, *minutes, *hours, *days);
+      if minutes == 0 {
+        ctk_error!(
+          ErrorCode::InvalidTime,
+          "Frozen Turkey needs a time to run for, like `ctk frozen for 30m`"
+        );
+        return;
+      }
+      (
+        clock.now() + Duration::minutes(minutes.into()),
+        i64::from(minutes),
+      )
+    }
+    StartSubcommands::Until { end, yes } => {
+      if !yes && !confirm_long_lock(config, clock, end) {
+        return;
+      }
+      match clock::lock_until(clock, end.time, end.date) {
+        Ok(lock) => lock,
+        Err(why) => {
+          ctk_error!(ErrorCode::InvalidTime, "{why}");
+          return;
+        }
+      }
+    }
+  };
+  eprintln!(
+    "Frozen Turkey locks this computer until {}, {} from now",
+    unlocks.format("%H:%M %A %B %d %Y"),
+    clock::describe_minutes(minutes)
+  );
+
+  match subcommand {
+    StartSubcommands::For {
+      duration,
+      minutes,
+      hours,
+      days,
+    } => start_block_for_some_minutes(
+      config,
+      FROZEN_TURKEY,
+      total_minutes(*duration, *minutes, *hours, *days),
+    ),
+    StartSubcommands::Until { end, .. } => {
+      start_block_until_time(config, clock, FROZEN_TURKEY, end)
+    }
+  };
+}
+
+fn start_block_for_some_minutes(config: &Config, block_name: &str, minutes: u32) -> Status {
+  if Some(false) == check_if_block_exists(block_name) {
+    return Status::Failed;
+  }
+
+  let start = BlockerCommand::Start {
+    block: block_name,
+    lock: StartLock::Minutes(minutes.into()),
+  };
+  match blocker::run(config, start) {
+    Ok(_) => {
+      let status = start_applied(config, "ctk start for", block_name);
+      if status != Status::Done {
+        return status;
+      }
+      success!(
+        "Starts blocking {} locked for {} minutes",
+        block_name,
+        minutes
+      );
+      journal::record(Event::Start {
+        block: block_name.to_string(),
+        lock_minutes: Some(minutes.into()),
+      });
+      Status::Done
+    }
+    Err(err) => {
+      ctk_error!(err.code(), "Cannot run `ctk start for`: {err}");
+      Status::Failed
+    }
+  }
+}
+
+fn start_block_until_time(
+  config: &Config,
+  clock: &impl Clock<Tz = Local>,
+  block_name: &str,
+  end: &EndTime,
+) -> Status {
+  if Some(false) == check_if_block_exists(block_name) {
+    return Status::Failed;
+  }
+
+  let (datetime, duration_minutes) = match clock::lock_until(clock, end.time, end.date) {
+    Ok(lock) => lock,
+    Err(why) => {
+      ctk_error!(ErrorCode::InvalidTime, "{why}");
+      return Status::Failed;
+    }
+  };
+
+  let start = BlockerCommand::Start {
+    block: block_name,
+    lock: StartLock::Minutes(duration_minutes),
+  };
+  match blocker::run(config, start) {
+    Ok(_) => {
+      let status = start_applied(config, "ctk start until", block_name);
+      if status != Status::Done {
+        return status;
+      }
+      success!(
+        "Starts blocking {} locked until {}",
+        block_name,
+        datetime.format("%H:%M %B %d %Y")
+      );
+      journal::record(Event::Start {
+        block: block_name.to_string(),
+        lock_minutes: Some(duration_minutes),
+      });
+      Status::Done
+    }
+    Err(err) => {
+      ctk_error!(err.code(), "Cannot run `ctk start until`: {err}");
+      Status::Failed
+    }
+  }
+}
+
+fn pause_block(
+  config: &Config,
+  clock: &impl Clock<Tz = Local>,
+  block_name: &str,
+  pause: &PauseSubcommands,
+) {
+  if block_name == FROZEN_TURKEY {
+    ctk_error!(
+      ErrorCode::FrozenTurkey,
+      "You cannot pause Frozen Turkey, since it is a timed, locked block."
+    );
+    return;
+  }
+
+  match get_ct_settings() {
+    Some(settings) => match settings.block_list_info.blocks.get(block_name) {
+      Some(block_info) if block_info.is_dormant() => {
+        ctk_error!(
+          ErrorCode::BlockNotRunning,
+          "Block {block_name} is not running, so there is nothing to pause"
+        );
+        return;
+      }
+      Some(_) => {}
+      None => {
+        ctk_error!(
+          ErrorCode::BlockNotFound,
+          "Block {} cannot be found in your Cold Turkey application",
+          block_name
+        );
+        return;
+      }
+    },
+    None => eprintln!(
+      "WARNING: ctk cannot check if block {} is in your Cold Turkey application right now",
+      block_name
+    ),
+  }
+
+  // Pausing lets everything through for a while, so the same rules as stopping apply
+  if kept_on_by_rule(config, block_name) {
+    return;
+  }
+
+  let (minutes, until) = match pause {
+    PauseSubcommands::For { minutes, hours } => {
+      (i64::from(total_minutes(None, *minutes, *hours, None)), None)
+    }
+    PauseSubcommands::Until { end } => {
+      let datetime = match clock::end_datetime(clock, end.time, end.date) {
+        Ok(datetime) => datetime,
+        Err(why) => {
+          ctk_error!(ErrorCode::InvalidTime, "{why}");
+          return;
+        }
+      };
+      (clock::minutes_until(clock, &datetime), Some(datetime))
+    }
+  };
+  if minutes <= 0 {
+    ctk_error!(
+      ErrorCode::InvalidTime,
+      "Cannot pause a block for no time or until a time in the past."
+    );
+    return;
+  }
+
+  match blocker::run(
+    config,
+    BlockerCommand::Pause {
+      block: block_name,
+      minutes,
+    },
+  ) {
+    Ok(_) => match until {
+      Some(datetime) => success!(
+        "Pauses {block_name} until {}",
+        datetime.format("%H:%M %B %d %Y")
+      ),
+      None => success!("Pauses {block_name} for {minutes} minutes"),
+    },
+    Err(err) => ctk_error!(err.code(), "Cannot run `ctk pause`: {err}"),
+  }
+}
+
+fn start_block_unlocked(config: &Config, block_name: &str) -> Status {
+  if block_name == FROZEN_TURKEY {
+    ctk_error!(ErrorCode::FrozenTurkey, "You can only start Frozen Turkey when time is provided. Consider `ctk frozen for` or `ctk frozen until`.");
+    return Status::Failed;
+  }
+
+  if Some(false) == check_if_block_exists(block_name) {
+    return Status::Failed;
+  }
+
+  let start = BlockerCommand::Start {
+    block: block_name,
+    lock: StartLock::None,
+  };
+  match blocker::run(config, start) {
+    Ok(_) => {
+      let status = start_applied(config, "ctk start", block_name);
+      if status != Status::Done {
+        return status;
+      }
+      success!("Starts blocking {}", block_name);
+      journal::record(Event::Start {
+        block: block_name.to_string(),
+        lock_minutes: None,
+      });
+      Status::Done
+    }
+    Err(err) => {
+      ctk_error!(err.code(), "Cannot run `ctk start`: {err}");
+      Status::Failed
+    }
+  }
+}
+
+fn stop_block(config: &Config, block_name: &str) -> Status {
+  if FROZEN_TURKEY == block_name {
+    failure!("Cannot stop Frozen Turkey because it is a timed, locked block. If it is already off, no need to worry.");
+    return Status::Failed;
+  }
+
+  if kept_on_by_rule(config, block_name) {
+    return Status::Failed;
+  }
+
+  if Some(false) == check_if_block_exists(block_name) {
+    return Status::Failed;
+  }
+
+  match blocker::run(config, BlockerCommand::Stop { block: block_name }) {
+    Ok(_) => match block_after(config, "ctk stop", block_name, BlockInfo::is_dormant) {
+      Some(block_info) if block_info.is_dormant() => {
+        success!("Stops blocking {}", block_name);
+        journal::record(Event::Stop {
+          block: block_name.to_string(),
+        });
+        Status::Done
+      }
+      Some(_) => {
+        failure!(EXIT_NOT_APPLIED; "Failed to stop blocking {block_name}");
+        Status::Failed
+      }
+      None => Status::Unverified,
+    },
+    Err(err) => {
+      ctk_error!(err.code(), "Cannot run `ctk stop`: {err}");
+      Status::Failed
+    }
+  }
+}
+
+/// Stops the block now if no lock holds it on, and otherwise leaves the stop for `ctk watch`
+/// to run once the lock ends
+fn stop_block_when_unlocked(config: &Config, block_name: &str) -> Status {
+  if FROZEN_TURKEY == block_name {
+    failure!("Frozen Turkey stops by itself when its lock ends, so there is nothing to queue.");
+    return Status::Failed;
+  }
+  let Some(settings) = get_ct_settings() else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot tell whether block {block_name} is locked right now"
+    );
+    return Status::Failed;
+  };
+  let locked_until = schedule::lock_end(&journal::read_entries(), block_name, Local::now());
+  let locked = settings
+    .block_list_info
+    .blocks
+    .get(block_name)
+    .is_some_and(|info| !info.is_dormant() && status::holds_lock(info, locked_until));
+  if !locked {
+    return stop_block(config, block_name);
+  }
+
+  let mut sidecar = Sidecar::load();
+  sidecar.queue_stop(block_name);
+  match sidecar.save() {
+    Ok(()) => {
+      success!(
+        "Block {block_name} is locked, so `ctk watch` stops it once the lock ends. Keep it running until then"
+      );
+      Status::Done
+    }
+    Err(err) => {
+      error!("ctk cannot remember to stop block {block_name}: {err}");
+      Status::Failed
+    }
+  }
+}
+
+/// Adds the websites like `add_websites_to_block`, expanding each `@category:<name>` to the
+/// websites the category has now and remembering in the sidecar where they came from
+fn add_websites_with_categories(config: &Config, block_name: &str, urls: &[String], except: bool) {
+  let (urls, expansions) = match categories::expand(urls) {
+    Ok(expanded) => expanded,
+    Err(err) => {
+      error!("{err}");
+      return;
+    }
+  };
+  add_websites_to_block(config, block_name, &urls, except);
+  if expansions.is_empty() || except {
+    return;
+  }
+
+  let mut sidecar = Sidecar::load();
+  for expansion in &expansions {
+    sidecar.record_category(block_name, &expansion.category, &expansion.sites);
+  }
+  if let Err(err) = sidecar.save() {
+    eprintln!("WARNING: ctk cannot remember which categories the websites came from: {err}");
+  }
+}
+
+fn add_websites_to_block(config: &Config, block_name: &str, urls: &[String], except: bool) {
+  if block_name == FROZEN_TURKEY {
+    ctk_error!(
+      ErrorCode::FrozenTurkey,
+      "You cannot add websites to the Frozen Turkey block."
+    );
+    return;
+  }
+
+  let ct_settings = get_ct_settings();
+  if let Some(settings) = &ct_settings {
+    match settings.block_list_info.blocks.get(block_name) {
+      Some(block_info) if except => {
+        // Review the exceptions before sending them, since an exception covering a
+        // whole blocklist entry quietly makes that entry useless
+        for url in urls {
+          for entry in block_info.entries_negated_by(url) {
+            eprintln!(
+              "WARNING: Exception {url} allows everything {entry} blocks in block {block_name}"
+            );
+          }
+        }
+      }
+      Some(_) => {}
+      None => {
+        ctk_error!(
+          ErrorCode::BlockNotFound,
+          "Block {} cannot be found in your Cold Turkey application",
+          block_name
+        );
+        return;
+      }
+    }
+  } else {
+    eprintln!(
+      "WARNING: ctk cannot check if block {} is in your Cold Turkey application right now",
+      block_name
+    );
+  }
+
+  let added_label = if except {
+    "added as exception"
+  } else {
+    "added"
+  };
+  let mut table = Table::new();
+  summary::with_table(urls.len() > 1, || {
+    let mut sent_urls = Vec::new();
+    for url in urls {
+      let add = BlockerCommand::Add {
+        block: block_name,
+        url,
+        except,
+      };
+      match blocker::run(config, add) {
+        Ok(_) => sent_urls.push(url),
+        Err(err) => {
+          ctk_error!(err.code(), "Cannot run `ctk add` for url {url}: {err}");
+          table.add(url, Status::Failed, "FAILED", "");
+        }
+      }
+    }
+
+    if sent_urls.is_empty() {
+      return;
+    }
+
+    let all_added = |block_info: &BlockInfo| {
+      let list = match except {
+        true => &block_info.exception_list,
+        false => &block_info.block_list,
+      };
+      block_info.is_dormant() || sent_urls.iter().all(|url| list.contains(*url))
+    };
+    let Some(block_info) = block_after(config, "ctk add", block_name, all_added) else {
+      for url in sent_urls {
+        table.add(url, Status::Unverified, "sent", "");
+      }
+      return;
+    };
+
+    let is_dormant = block_info.is_dormant();
+
+    for url in sent_urls {
+      let added = if except && (is_dormant || block_info.exception_list.contains(url)) {
+        success!("Adds url {url} to block {block_name} as an exception");
+        true
+      } else if !except && (is_dormant || block_info.block_list.contains(url)) {
+        success!("Adds url {url} to block {block_name}");
+        true
+      } else {
+        failure!(EXIT_NOT_APPLIED; "Cannot add url {url} to block {block_name}");
+        false
+      };
+      if added {
+        table.add(url, Status::Done, added_label, "");
+        journal::record(Event::Add {
+          block: block_name.to_string(),
+          url: url.to_string(),
+          except,
+        });
+      } else {
+        table.add(url, Status::Failed, "FAILED", "");
+      }
+    }
+  });
+
+  if urls.len() > 1 {
+    eprintln!();
+    eprintln!("{}", table.render());
+  }
+}
+
+/// Reads urls from standard input, one per line, skipping empty lines and # comments
+fn urls_from_stdin() -> Option<Vec<String>> {
+  let mut urls = Vec::new();
+  for line in io::stdin().lines() {
+    match line {
+      Ok(line) => {
+        let url = line.trim();
+        if !url.is_empty() && !url.starts_with('#') {
+          urls.push(url.to_string());
+        }
+      }
+      Err(err) => {
+        error!("Cannot read urls from standard input: {err}");
+        return None;
+      }
+    }
+  }
+  Some(urls)
+}
+
+fn quick_add(config: &Config, url: Option<&str>, from_history: bool) {
+  let Some(block_name) = config.default_block() else {
+    error!("No default block. Choose one with `ctk config set default_block <block>`");
+    return;
+  };
+
+  if from_history {
+    let visited =
+      browserhistory::unblocked_favorites(get_ct_settings().as_ref(), browserhistory::CANDIDATES);
+    if visited.is_empty() {
+      error!("There are no websites in the browser history that are not blocked already");
+      return;
+    }
+    let urls = suggestdialog::sites_from_history(&visited);
+    if !urls.is_empty() {
+      add_websites_to_block(config, block_name, &urls, false);
+    }
+    return;
+  }
+
+  let url = match url {
+    Some(url) => url.trim().to_string(),
+    None => match platform::clipboard_text() {
+      Some(text) => text.trim().to_string(),
+      None => {
+        error!("Cannot read the clipboard. Give the url to add instead");
+        return;
+      }
+    },
+  };
+  // A hotkey fires whatever happens to be copied, so a paragraph is not added as a website
+  if url.is_empty() || url.contains(char::is_whitespace) {
+    error!("\"{url}\" is not a url, so it is not added to block {block_name}");
+    return;
+  }
+
+  add_websites_to_block(config, block_name, &[url], false);
+}
+
+fn subscribe_blocklist(config: &Config, block_name: &str, url: &str, yes: bool) {
+  if let Err(err) = reqwest::Url::parse(url) {
+    error!("{url} is not a link: {err}");
+    return;
+  }
+  let mut sidecar = Sidecar::load();
+  if !sidecar.subscribe(block_name, url) {
+    eprintln!("Block {block_name} already follows {url}, so it is only updated");
+  } else if let Err(err) = sidecar.save() {
+    error!("ctk cannot remember the blocklist: {err}");
+    return;
+  }
+  update_blocklists(config, Some(block_name), yes);
+}
+
+fn unsubscribe_blocklist(block_name: &str, url: &str) {
+  let mut sidecar = Sidecar::load();
+  if !sidecar.unsubscribe(block_name, url) {
+    error!("Block {block_name} does not follow {url}. See the blocklists it follows with `ctk blocklist list`");
+    return;
+  }
+  match sidecar.save() {
+    Ok(()) => success!("Block {block_name} no longer follows {url}. The websites it added stay in the block, and `ctk remove` takes them out"),
+    Err(err) => error!("ctk cannot forget the blocklist: {err}"),
+  }
+}
+
+fn list_blocklists() {
+  let sidecar = Sidecar::load();
+  let blocks = sidecar.subscribed_blocks();
+  if blocks.is_empty() {
+    println!("No block follows a blocklist. Make one with `ctk blocklist subscribe <block> <url>`");
+    return;
+  }
+  for block_name in &blocks {
+    println!("{block_name}:");
+    for subscription in sidecar.subscriptions(block_name) {
+      match subscription.updated {
+        Some(updated) => println!(
+          "  {} ({} websites, updated {})",
+          subscription.url,
+          subscription.sites.len(),
+          updated.format("%H:%M %B %d %Y")
+        ),
+        None => println!("  {} (not applied yet)", subscription.url),
+      }
+    }
+  }
+}
+
+/// Brings the block, or every block following a blocklist, in step with its blocklists
+fn update_blocklists(config: &Config, block_name: Option<&str>, yes: bool) {
+  let mut sidecar = Sidecar::load();
+  let blocks = match block_name {
+    Some(block_name) => vec![block_name.to_string()],
+    None => sidecar.subscribed_blocks(),
+  };
+  if blocks.is_empty() {
+    eprintln!(
+      "No block follows a blocklist. Make one with `ctk blocklist subscribe <block> <url>`"
+    );
+    return;
+  }
+  let Some(settings) = get_ct_settings() else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot read your blocks right now, so it cannot update them"
+    );
+    return;
+  };
+
+  for block_name in &blocks {
+    update_block_blocklists(config, &settings, &mut sidecar, block_name, yes);
+  }
+  if let Err(err) = sidecar.save() {
+    eprintln!("WARNING: ctk cannot remember what the blocklists had: {err}");
+  }
+}
+
+/// How many websites of a change are listed before the rest are only counted
+const LISTED_CHANGES: usize = 10;
+
+fn update_block_blocklists(
+  config: &Config,
+  settings: &ColdTurkeySettings,
+  sidecar: &mut Sidecar,
+  block_name: &str,
+  yes: bool,
+) {
+  let subscriptions = sidecar.subscriptions(block_name).to_vec();
+  if subscriptions.is_empty() {
+    error!(
+      "Block {block_name} follows no blocklist. Make it follow one with `ctk blocklist subscribe`"
+    );
+    return;
+  }
+  let Some(block_info) = exportable_block(settings, block_name) else {
+    return;
+  };
+
+  // A list that cannot be fetched keeps what it had, so its websites stay in the block
+  let mut fetched = Vec::new();
+  let mut wanted: HashSet<String> = HashSet::new();
+  for subscription in subscriptions {
+    match blocklist::fetch(&subscription.url) {
+      Ok(sites) => {
+        wanted.extend(sites.iter().cloned());
+        fetched.push((subscription, sites));
+      }
+      Err(err) => {
+        error!("Cannot update block {block_name}: {err}");
+        wanted.extend(subscription.sites);
+      }
+    }
+  }
+
+  let mut web = block_info.block_list.clone();
+  let mut added = Vec::new();
+  let mut removed = Vec::new();
+  for (subscription, sites) in &fetched {
+    let changes = blocklist::changes(&subscription.sites, sites);
+    for site in changes.added {
+      if !web.contains(&site) {
+        web.push(site.clone());
+        added.push(site);
+      }
+    }
+    // Another list may still have it
+    for site in changes.removed {
+      if !wanted.contains(&site) && web.contains(&site) {
+        web.retain(|entry| *entry != site);
+        removed.push(site);
+      }
+    }
+  }
+
+  let remember = |sidecar: &mut Sidecar| {
+    for (subscription, sites) in &fetched {
+      sidecar.set_subscription_sites(block_name, &subscription.url, sites.clone());
+    }
+  };
+  if added.is_empty() && removed.is_empty() {
+    remember(sidecar);
+    if !fetched.is_empty() {
+      success!("Block {block_name} is up to date with its blocklists");
+    }
+    return;
+  }
+
+  eprintln!("Block {block_name} will be re-imported with:");
+  for (sign, sites) in [("+", &added), ("-", &removed)] {
+    for site in sites.iter().take(LISTED_CHANGES) {
+      eprintln!("  {sign} {site}");
+    }
+    if sites.len() > LISTED_CHANGES {
+      eprintln!("  {sign} and {} more", sites.len() - LISTED_CHANGES);
+    }
+  }
+  eprintln!("{EXPORT_NOTE}");
+  if !yes && !confirm_continue() {
+    return;
+  }
+
+  let mut block_settings = bridge::export_block(block_info);
+  block_settings.web = web;
+  let mut blocks = HashMap::new();
+  blocks.insert(block_name.to_string(), block_settings);
+  if import_blocks(config, &blocks, "ctk blocklist update") {
+    remember(sidecar);
+    success!(
+      "Sends block {block_name} with {} websites added and {} removed to Cold Turkey. Confirm the import in Cold Turkey to finish.",
+      added.len(),
+      removed.len()
+    );
+  }
+}
+
+/// Only adding websites, starting blocks without a lock, locking them for longer and showing
+/// the status are offered, since any web page can open a ctk:// link. The config can narrow
+/// that down further
+fn open_protocol_link(config: &Config, link: &str) {
+  let action = match protocol::parse(link) {
+    Ok(action) => action,
+    Err(err) => {
+      error!("Cannot open the link: {err}");
+      return;
+    }
+  };
+  let allowed = |block_name: &str| {
+    let allows = config.link_allows(action.name(), block_name);
+    if !allows {
+      failure!(
+        "ctk:// links may not {} block {block_name}. Change link_actions and link_blocks in the config to allow it",
+        action.name()
+      );
+    }
+    allows
+  };
+
+  match &action {
+    protocol::Action::Add { url, block } => {
+      match block.as_deref().or_else(|| config.default_block()) {
+        Some(block_name) if allowed(block_name) => {
+          add_websites_to_block(config, block_name, std::slice::from_ref(url), false)
+        }
+        Some(_) => {}
+        None => error!(
+          "The link names no block and there is no default block. Choose one with `ctk config set default_block <block>`"
+        ),
+      }
+    }
+    protocol::Action::Start { block } => {
+      for block_name in &with_also_started(config, blocks_named(config, block)) {
+        if allowed(block_name) {
+          start_block_unlocked(config, block_name);
+        }
+      }
+    }
+    protocol::Action::Extend { block } => {
+      for block_name in &with_also_started(config, blocks_named(config, block)) {
+        if allowed(block_name) {
+          extend_lock(config, block_name);
+        }
+      }
+    }
+    protocol::Action::Status if !config.link_allows_action("status") => failure!(
+      "ctk:// links may not show the status. Change link_actions in the config to allow it"
+    ),
+    protocol::Action::Status => match get_ct_settings() {
+      Some(settings) => {
+        status::print_status(&settings, None);
+        // The link was most likely opened from a notification, with no terminal to read
+        if let Err(err) = toast::show("Cold Turkey", &status::summary_lines(&settings), &[]) {
+          eprintln!("WARNING: Cannot show the status in a notification: {err}");
+        }
+      }
+      None => ctk_error!(
+        ErrorCode::SettingsUnreadable,
+        "ctk cannot read the state of your blocks right now"
+      ),
+    },
+  }
+}
+
+/// Locks the block for EXTEND_MINUTES longer than what is left of the timed lock ctk last
+/// started it with, or for EXTEND_MINUTES from now without one
+fn extend_lock(config: &Config, block_name: &str) {
+  let left = schedule::lock_end(&journal::read_entries(), block_name, Local::now())
+    .map_or(0, |end| clock::minutes_until(&SystemClock, &end));
+  let total = u32::try_from(left)
+    .unwrap_or(0)
+    .saturating_add(toast::EXTEND_MINUTES);
+  start_block_for_some_minutes(config, block_name, total);
+}
+
+fn export_history(json: bool, output: Option<&Path>) {
+  let entries = journal::read_entries();
+  let text = if json {
+    // Why unwrap? Entries always serialize
+    format!("{}\n", serde_json::to_string_pretty(&entries).unwrap())
+  } else {
+    entries
+      .iter()
+      .map(|entry| {
+        format!(
+          "{}  {}\n",
+          entry.time.format("%Y-%m-%d %H:%M:%S"),
+          entry.event
+        )
+      })
+      .collect()
+  };
+  match output {
+    Some(path) => match fs::write(path, text) {
+      Ok(()) => success!(
+        "Exports {} journal entries to {}",
+        entries.len(),
+        path.display()
+      ),
+      Err(err) => error!("Cannot write {}: {err}", path.display()),
+    },
+    None => print!("{text}"),
+  }
+}
+
+fn list_block_websites(block_name: &str, except: bool) {
+  if let Some(settings) = get_ct_settings() {
+    match settings.block_list_info.blocks.get(block_name) {
+      Some(block_info) => {
+        let websites = if except {
+          &block_info.exception_list
+        } else {
+          &block_info.block_list
+        };
+        for website in websites {
+          println!("{website}");
+        }
+        // On stderr, so scripts reading the list are not thrown off
+        if !except {
+          for source in Sidecar::load().categories(block_name) {
+            eprintln!(
+              "{} website(s) came from {}{} on {}",
+              source.sites.len(),
+              categories::PREFIX,
+              source.category,
+              source.expanded.format("%B %d %Y")
+            );
+          }
+        }
+      }
+      None => ctk_error!(
+        ErrorCode::BlockNotFound,
+        "Block {} cannot be found in your Cold Turkey application",
+        block_name
+      ),
+    }
+  } else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot read the websites of block {block_name} right now"
+    );
+  }
+}
+
+fn remove_websites_from_block(
+  config: &Config,
+  block_name: &str,
+  urls: &[String],
+  except: bool,
+  yes: bool,
+) {
+  if block_name == FROZEN_TURKEY {
+    ctk_error!(
+      ErrorCode::FrozenTurkey,
+      "You cannot remove websites from the Frozen Turkey block."
+    );
+    return;
+  }
+
+  let settings = match get_ct_settings() {
+    Some(settings) => settings,
+    None => {
+      ctk_error!(
+        ErrorCode::SettingsUnreadable,
+        "ctk cannot read block {block_name} right now, so it cannot remove websites from it"
+      );
+      return;
+    }
+  };
+
+  let block_info = match exportable_block(&settings, block_name) {
+    Some(block_info) => block_info,
+    None => return,
+  };
+
+  let current_list = if except {
+    &block_info.exception_list
+  } else {
+    &block_info.block_list
+  };
+  let (found_urls, missing_urls): (Vec<&String>, Vec<&String>) =
+    urls.iter().partition(|url| current_list.contains(url));
+
+  for url in &missing_urls {
+    eprintln!("WARNING: url {url} is not in block {block_name}");
+  }
+
+  if found_urls.is_empty() {
+    return;
+  }
+
+  let mut block_settings = bridge::export_block(block_info);
+  let entries = if except {
+    &mut block_settings.exceptions
+  } else {
+    &mut block_settings.web
+  };
+  entries.retain(|entry| !found_urls.contains(&entry));
+
+  eprintln!("Block {block_name} will be re-imported without:");
+  for url in &found_urls {
+    eprintln!("  - {url}");
+  }
+  eprintln!("{EXPORT_NOTE}");
+
+  if !yes && !confirm_continue() {
+    return;
+  }
+
+  let mut blocks = HashMap::new();
+  blocks.insert(block_name.to_string(), block_settings);
+  if import_blocks(config, &blocks, "ctk remove") {
+    success!("Sends block {block_name} without the removed urls to Cold Turkey. Confirm the import in Cold Turkey to finish.");
+  }
+}
+
+fn copy_block(config: &Config, old_name: &str, new_name: &str, yes: bool) {
+  if old_name == FROZEN_TURKEY || new_name == FROZEN_TURKEY {
+    ctk_error!(
+      ErrorCode::FrozenTurkey,
+      "You cannot copy the Frozen Turkey block."
+    );
+    return;
+  }
+
+  let settings = match get_ct_settings() {
+    Some(settings) => settings,
+    None => {
+      ctk_error!(
+        ErrorCode::SettingsUnreadable,
+        "ctk cannot read block {old_name} right now, so it cannot copy it"
+      );
+      return;
+    }
+  };
+
+  if settings.block_list_info.blocks.contains_key(new_name) {
+    error!("Block {new_name} already exists in your Cold Turkey application");
+    return;
+  }
+
+  let block_info = match exportable_block(&settings, old_name) {
+    Some(block_info) => block_info,
+    None => return,
+  };
+
+  if block_info.is_locked() {
+    ctk_error!(
+      ErrorCode::BlockLocked,
+      "Block {old_name} is locked. Wait until it is unlocked before copying it."
+    );
+    return;
+  }
+
+  eprintln!("Block {old_name} will be copied to {new_name} and then emptied.");
+  eprintln!("{EXPORT_NOTE}");
+
+  if !yes && !confirm_continue() {
+    return;
+  }
+
+  let mut blocks = HashMap::new();
+  blocks.insert(new_name.to_string(), bridge::export_block(block_info));
+  blocks.insert(old_name.to_string(), BlockSettings::new());
+  if import_blocks(config, &blocks, "ctk copy-block") {
+    success!("Sends block {new_name} and the emptied block {old_name} to Cold Turkey. Confirm the import in Cold Turkey to finish. The empty block {old_name} stays until you delete it there.");
+  }
+}
+
+fn empty_block(config: &Config, block_name: &str, yes: bool) {
+  if block_name == FROZEN_TURKEY {
+    ctk_error!(
+      ErrorCode::FrozenTurkey,
+      "You cannot empty the Frozen Turkey block."
+    );
+    return;
+  }
+
+  let settings = match get_ct_settings() {
+    Some(settings) => settings,
+    None => {
+      ctk_error!(
+        ErrorCode::SettingsUnreadable,
+        "ctk cannot read block {block_name} right now, so it cannot empty it"
+      );
+      return;
+    }
+  };
+
+  let block_info = match settings.block_list_info.blocks.get(block_name) {
+    Some(block_info) => block_info,
+    None => {
+      ctk_error!(
+        ErrorCode::BlockNotFound,
+        "Block {} cannot be found in your Cold Turkey application",
+        block_name
+      );
+      return;
+    }
+  };
+
+  if block_info.is_locked() {
+    ctk_error!(
+      ErrorCode::BlockLocked,
+      "Block {block_name} is locked. Wait until it is unlocked before emptying it."
+    );
+    return;
+  }
+
+  if !block_info.is_dormant() && kept_on_by_rule(config, block_name) {
+    return;
+  }
+
+  eprintln!("Block {block_name} will be stopped and emptied of all its websites and apps.");
+  if !yes && !confirm_continue() {
+    return;
+  }
+
+  if !block_info.is_dormant() && stop_block(config, block_name) == Status::Failed {
+    return;
+  }
+
+  let mut blocks = HashMap::new();
+  blocks.insert(block_name.to_string(), BlockSettings::new());
+  if import_blocks(config, &blocks, "ctk empty-block") {
+    success!("Sends the emptied block {block_name} to Cold Turkey. Confirm the import in Cold Turkey to finish. The empty block stays until you delete it there.");
+  }
+}
+
+fn start_temporary_block(
+  config: &Config,
+  web: &[String],
+  duration: std::time::Duration,
+  name: Option<&str>,
+) {
+  // Cold Turkey locks by the minute, so a part of a minute is rounded up
+  let minutes = u32::try_from(duration.as_secs().div_ceil(60))
+    .unwrap_or(u32::MAX)
+    .max(1);
+  let block_name = match name {
+    Some(name) => name.to_string(),
+    None => format!("ctk tmp {}", Local::now().format("%Y-%m-%d %H:%M")),
+  };
+
+  let Some(settings) = get_ct_settings() else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot read your blocks right now, so it cannot make block {block_name}"
+    );
+    return;
+  };
+  if block_name == FROZEN_TURKEY || settings.block_list_info.blocks.contains_key(&block_name) {
+    error!("Block {block_name} already exists in your Cold Turkey application. Give the temporary block another name with --name.");
+    return;
+  }
+
+  let mut block_settings = BlockSettings::new();
+  block_settings.web = web.to_vec();
+  let mut blocks = HashMap::new();
+  blocks.insert(block_name.clone(), block_settings);
+  if !import_blocks(config, &blocks, "ctk tmp start") {
+    return;
+  }
+
+  // Marked before the import is confirmed, so the block is still cleaned up if it is
+  // confirmed after ctk stops waiting
+  let mut sidecar = Sidecar::load();
+  sidecar.set_expiry(
+    &block_name,
+    Some(Local::now() + Duration::minutes(minutes.into())),
+  );
+  sidecar.set_note(
+    &block_name,
+    Some("Made by `ctk tmp start`, and emptied by `ctk tmp clean` once it expires".to_string()),
+  );
+  if let Err(err) = sidecar.save() {
+    eprintln!("WARNING: ctk cannot remember that block {block_name} is temporary: {err}");
+  }
+
+  eprintln!("Confirm the import of block {block_name} in Cold Turkey, and ctk starts it.");
+  let imported = verify::settings_within(config, TMP_IMPORT_WAIT, |settings| {
+    settings.block_list_info.blocks.contains_key(&block_name)
+  })
+  .is_some_and(|settings| settings.block_list_info.blocks.contains_key(&block_name));
+  if !imported {
+    failure!(
+      EXIT_NOT_APPLIED;
+      "Block {block_name} was not imported in time. Once it is, start it with `ctk start \"{block_name}\" for --minutes {minutes}`."
+    );
+    return;
+  }
+
+  start_block_for_some_minutes(config, &block_name, minutes);
+}
+
+/// Empties the expired temporary blocks, since Cold Turkey cannot delete blocks from the
+/// command line, and forgets the ones already deleted
+fn clean_temporary_blocks(config: &Config, yes: bool) {
+  let mut sidecar = Sidecar::load();
+  let now = Local::now();
+  let (expired, waiting): (Vec<_>, Vec<_>) = sidecar
+    .temporary_blocks()
+    .into_iter()
+    .partition(|(_, expires)| *expires <= now);
+  if expired.is_empty() && waiting.is_empty() {
+    eprintln!("There are no temporary blocks. Make one with `ctk tmp start`.");
+    return;
+  }
+  for (block_name, expires) in &waiting {
+    eprintln!(
+      "Block {block_name} expires at {}",
+      expires.format("%H:%M %B %d %Y")
+    );
+  }
+  let expired: Vec<String> = expired
+    .into_iter()
+    .map(|(block_name, _)| block_name.to_string())
+    .collect();
+  if expired.is_empty() {
+    return;
+  }
+
+  let Some(settings) = get_ct_settings() else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot read your blocks right now, so it cannot clean up the temporary ones"
+    );
+    return;
+  };
+
+  let mut to_empty = Vec::new();
+  for block_name in expired {
+    match settings.block_list_info.blocks.get(&block_name) {
+      // Deleted already, or never imported
+      None => sidecar.forget(&block_name),
+      Some(info) if info.is_locked() => {
+        eprintln!("Block {block_name} is still locked, so it is left for later");
+      }
+      Some(info) => to_empty.push((block_name, info)),
+    }
+  }
+
+  if !to_empty.is_empty() {
+    eprintln!("These expired temporary blocks will be stopped and emptied:");
+    for (block_name, _) in &to_empty {
+      eprintln!("  {block_name}");
+    }
+    if yes || confirm_continue() {
+      empty_temporary_blocks(config, &to_empty, &mut sidecar);
+    }
+  }
+
+  if let Err(err) = sidecar.save() {
+    eprintln!("WARNING: ctk cannot save which blocks are temporary: {err}");
+  }
+}
+
+/// Cleans up the expired temporary blocks, then deletes old snapshots and leftover files.
+/// Files are deleted even when Cold Turkey is not there
+fn purge_expired(config: &Config, keep: std::time::Duration, yes: bool) {
+  if !Sidecar::load().temporary_blocks().is_empty() {
+    clean_temporary_blocks(config, yes);
+  }
+
+  let stale = purge::stale_files(keep);
+  if stale.is_empty() {
+    eprintln!("There are no old snapshots or leftover files to delete.");
+    return;
+  }
+  eprintln!("These files are no longer needed and will be deleted:");
+  for file in &stale {
+    eprintln!("  {} ({})", file.path.display(), file.kind);
+  }
+  if yes || confirm_continue() {
+    let deleted = purge::delete(&stale);
+    success!("Deletes {deleted} of {} files", stale.len());
+  }
+}
+
+fn empty_temporary_blocks(
+  config: &Config,
+  to_empty: &[(String, &BlockInfo)],
+  sidecar: &mut Sidecar,
+) {
+  let mut blocks = HashMap::new();
+  for (block_name, info) in to_empty {
+    if !info.is_dormant() {
+      if let Err(err) = blocker::run(config, BlockerCommand::Stop { block: block_name }) {
+        ctk_error!(err.code(), "Cannot stop block {block_name}: {err}");
+        continue;
+      }
+    }
+    blocks.insert(block_name.clone(), BlockSettings::new());
+  }
+  if blocks.is_empty() || !import_blocks(config, &blocks, "ctk tmp clean") {
+    return;
+  }
+  for block_name in blocks.keys() {
+    sidecar.forget(block_name);
+  }
+  success!("Sends the emptied blocks to Cold Turkey. Confirm the import in Cold Turkey, then delete the blocks there to remove them from the list.");
+}
+
+fn exportable_block<'a>(
+  settings: &'a ColdTurkeySettings,
+  block_name: &str,
+) -> Option<&'a BlockInfo> {
+  match settings.block_list_info.blocks.get(block_name) {
+    Some(block_info) if block_info.is_dormant() => {
+      ctk_error!(ErrorCode::BlockNotRunning, "Cold Turkey only reports the settings of running blocks. Start {block_name} without a lock and try again.");
+      None
+    }
+    Some(block_info) => Some(block_info),
+    None => {
+      ctk_error!(
+        ErrorCode::BlockNotFound,
+        "Block {} cannot be found in your Cold Turkey application",
+        block_name
+      );
+      None
+    }
+  }
+}
+
+fn confirm_continue() -> bool {
+  Confirm::new()
+    .with_prompt("Do you want to continue?")
+    .loop_interact()
+}
+
+fn import_blocks<T: Serialize>(config: &Config, blocks: &T, command: &str) -> bool {
+  match bridge::write_temp_ctbbl(blocks) {
+    Ok(path) => match bridge::import_ctbbl(config, &path) {
+      Ok(()) => true,
+      Err(err) => {
+        ctk_error!(err.code(), "Cannot run `{command}`: {err}");
+        false
+      }
+    },
+    Err(err) => {
+      error!("Cannot write the edited blocks to a temporary file: {err}");
+      false
+    }
+  }
+}
+
+fn run_wizard(config: &Config) {
+  let Some(blocks) = wizard::starter_blocks() else {
+    return;
+  };
+  let Some(path) = wizard::save(&blocks) else {
+    return;
+  };
+  if !Capabilities::detect(config).blocker_found {
+    eprintln!(
+      "WARNING: Cold Turkey Blocker is not installed, so the blocks are only saved. Install it with `ctk install`, then run `ctk import {}`",
+      path.display()
+    );
+    return;
+  }
+  match bridge::import_ctbbl(config, &path) {
+    Ok(()) => {
+      success!("Sends the starter blocks to Cold Turkey. Confirm the import there to finish.")
+    }
+    Err(err) => ctk_error!(
+      err.code(),
+      "Cannot import {} into Cold Turkey: {err}",
+      path.display()
+    ),
+  }
+}
+
+/// Plans the schedules of the running blocks together, then imports the ones that changed
+fn run_plan(config: &Config) {
+  let Some(settings) = get_ct_settings() else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot read the schedules of your blocks right now"
+    );
+    return;
+  };
+  let Some(blocks) = plan::plan_week(&settings, FROZEN_TURKEY) else {
+    return;
+  };
+  if blocks.is_empty() {
+    eprintln!("No schedules were changed, so nothing is sent to Cold Turkey.");
+    return;
+  }
+
+  let mut block_names: Vec<&str> = blocks.keys().map(String::as_str).collect();
+  block_names.sort_unstable();
+  eprintln!(
+    "These blocks will get their new schedules: {}",
+    block_names.join(", ")
+  );
+  eprintln!("{EXPORT_NOTE}");
+  if !confirm_continue() {
+    return;
+  }
+  if import_blocks(config, &blocks, "ctk plan") {
+    suggestdialog::save_schedule_labels(&blocks);
+    success!("Sends the blocks with their new schedules to Cold Turkey. Confirm the import in Cold Turkey to finish.");
+  }
+}
+
+fn toggle_block(config: &Config, block_name: &str) -> Status {
+  if block_name == FROZEN_TURKEY {
+    ctk_error!(ErrorCode::FrozenTurkey, "You can only start Frozen Turkey when time is provided. Consider `ctk frozen for` or `ctk frozen until`.");
+    return Status::Failed;
+  }
+
+  let ct_settings = get_ct_settings();
+  let is_off_old = if let Some(settings) = &ct_settings {
+    if settings.block_list_info.blocks.contains_key(block_name) {
+      settings.block_list_info.blocks[block_name].is_dormant()
+    } else {
+      ctk_error!(
+        ErrorCode::BlockNotFound,
+        "Block {} cannot be found in your Cold Turkey application",
+        block_name
+      );
+      return Status::Failed;
+    }
+  } else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot read block {block_name} right now, so it cannot tell whether to start or stop it"
+    );
+    return Status::Failed;
+  };
+
+  if !is_off_old && kept_on_by_rule(config, block_name) {
+    return Status::Failed;
+  }
+
+  match blocker::run(config, BlockerCommand::Toggle { block: block_name }) {
+    Ok(_) => {
+      let toggled = |block_info: &BlockInfo| block_info.is_dormant() != is_off_old;
+      let Some(block_info) = block_after(config, "ctk toggle", block_name, toggled) else {
+        return Status::Unverified;
+      };
+      let is_off_new = block_info.is_dormant();
+
+      match (is_off_old, is_off_new) {
+        (true, false) => {
+          success!("Starts block {}", block_name);
+          journal::record(Event::Start {
+            block: block_name.to_string(),
+            lock_minutes: None,
+          });
+          Status::Done
+        }
+        (false, true) => {
+          success!("Stops block {block_name}");
+          journal::record(Event::Stop {
+            block: block_name.to_string(),
+          });
+          Status::Done
+        }
+        (false, false) => {
+          failure!(EXIT_NOT_APPLIED; "Failed to stop blocking {block_name}");
+          Status::Failed
+        }
+        (true, true) => {
+          failure!(EXIT_NOT_APPLIED; "Failed to start blocking {block_name}"); // rare, probs impossible
+          Status::Failed
+        }
+      }
+    }
+    Err(err) => {
+      ctk_error!(err.code(), "Cannot run `ctk toggle`: {err}");
+      Status::Failed
+    }
+  }
+}
+
+/// Prints a table of how a command on several blocks went, after any errors it printed
+fn summarize(done: &str, blocks: &[String], statuses: &[Status], missing: &[String]) {
+  if blocks.len() + missing.len() < 2 {
+    return;
+  }
+  let mut table = Table::new();
+  for (block, status) in blocks.iter().zip(statuses) {
+    let label = match status {
+      Status::Done => done,
+      Status::Unverified => "sent",
+      Status::Failed => "FAILED",
+    };
+    table.add(block, *status, label, "");
+  }
+  for block in missing {
+    table.add(block, Status::Failed, "FAILED", "cannot be found");
+  }
+  eprintln!();
+  eprintln!("{}", table.render());
+}
+
+/// Reads a block again after a command was sent to Cold Turkey for it, waiting a while for
+/// `applied` to hold, so the command can be checked. If the block cannot be read, warns that
+/// the command was sent but not verified and journals it, so it can be reconciled later
+fn block_after(
+  config: &Config,
+  command: &str,
+  block_name: &str,
+  applied: impl Fn(&BlockInfo) -> bool,
+) -> Option<BlockInfo> {
+  let settings = verify::settings_until(config, |settings| {
+    settings
+      .block_list_info
+      .blocks
+      .get(block_name)
+      .is_some_and(&applied)
+  });
+  let why = match settings {
+    Some(mut settings) => match settings.block_list_info.blocks.remove(block_name) {
+      Some(block_info) => return Some(block_info),
+      None => "Cold Turkey no longer reports the block",
+    },
+    None => "ctk cannot read Cold Turkey's settings",
+  };
+
+  eprintln!("WARNING: Sends `{command}` for block {block_name}, but cannot verify it: {why}");
+  error::fail(EXIT_UNVERIFIED);
+  journal::record(Event::Unverified {
+    command: command.to_string(),
+    block: block_name.to_string(),
+  });
+  None
+}
+
+/// Waits for a block Cold Turkey was asked to start to be running
+fn start_applied(config: &Config, command: &str, block_name: &str) -> Status {
+  let running = |block_info: &BlockInfo| !block_info.is_dormant();
+  match block_after(config, command, block_name, running) {
+    Some(block_info) if running(&block_info) => Status::Done,
+    Some(_) => {
+      failure!(EXIT_NOT_APPLIED; "Failed to start blocking {block_name}");
+      Status::Failed
+    }
+    None => Status::Unverified,
+  }
+}
+
+fn run_doctor(config: &Config) {
+  let capabilities = Capabilities::detect(config);
+  let found = |found: bool| match found {
+    true => (Status::Done, "found"),
+    false => (Status::Failed, "NOT FOUND"),
+  };
+  let mut table = Table::new();
+  let (status, label) = found(capabilities.blocker_found);
+  table.add(
+    "Cold Turkey Blocker",
+    status,
+    label,
+    capabilities.blocker_path.display().to_string(),
+  );
+  let (status, label) = found(capabilities.msg_host_found);
+  table.add(
+    "Messaging host",
+    status,
+    label,
+    capabilities.msg_host_path.display().to_string(),
+  );
+  if capabilities.msg_host_found {
+    match get_ct_settings() {
+      Some(settings) => table.add(
+        "Reading blocks",
+        Status::Done,
+        "works",
+        format!(
+          "{} blocks, {} running",
+          settings.block_list_info.blocks.len(),
+          settings
+            .block_list_info
+            .blocks
+            .values()
+            .filter(|info| !info.is_dormant())
+            .count()
+        ),
+      ),
+      None => table.add(
+        "Reading blocks",
+        Status::Failed,
+        "FAILS",
+        "Is Cold Turkey Blocker running?",
+      ),
+    }
+  }
+  // Neither has to exist yet, so a missing one is not a failure
+  let made = |path: &Path| {
+    if path.exists() {
+      "found"
+    } else {
+      "not made yet"
+    }
+  };
+  if let Some(path) = config::user_config_path() {
+    table.add(
+      "Config",
+      Status::Done,
+      made(&path),
+      path.display().to_string(),
+    );
+  }
+  if let Some(dir) = config::data_dir() {
+    table.add(
+      "Data folder",
+      Status::Done,
+      made(&dir),
+      dir.display().to_string(),
+    );
+  }
+  println!("{}", table.render());
+
+  println!();
+  if capabilities.is_degraded() {
+    println!("ctk is in degraded mode: Cold Turkey cannot be found, so commands that start, stop, list or change blocks are turned off.");
+    println!("These still work, since they only use files: suggest, learn, wizard (saving only), lint, merge-into, template expand/make/vars, note, tag, tags, report, stats, streak, config, explain and complete-setup.");
+    println!("Install Cold Turkey with `ctk install`, or point ctk to it with `ctk config set blocker_path <path>`.");
+  } else if !capabilities.blocker_found || !capabilities.msg_host_found {
+    println!("Part of Cold Turkey is missing, so some commands are turned off. Set blocker_path or msg_host_path in the config if it is installed elsewhere.");
+  } else {
+    println!("Everything ctk needs is here.");
+  }
+}
+
+fn open_cold_turkey(config: &Config) {
+  match blocker::run(config, BlockerCommand::Open) {
+    Ok(_) => success!("Launches Cold Turkey!"),
+    Err(err) => ctk_error!(
+      err.code(),
+      "Cannot launch Cold Turkey from {}: {err}",
+      config.blocker_path().display()
+    ),
+  }
+}
+
+fn list_all_blocks(config: &Config, tree: bool, verbose: bool) {
+  let ct_settings = get_ct_settings();
+  if let Some(settings) = ct_settings {
+    if tree {
+      let mut block_tree = BlockTree::new();
+      for (key, info) in &settings.block_list_info.blocks {
+        block_tree.insert(config.profile_of(key), key, info.is_dormant());
+      }
+      block_tree.print();
+      return;
+    }
+
+    let keys = settings.block_list_info.blocks.keys();
+    let mut sorted_keys = Vec::new();
+    for key in keys {
+      let block_inactive = settings.block_list_info.blocks[key].is_dormant();
+      sorted_keys.push((key, block_inactive));
+    }
+
+    sorted_keys.sort_unstable();
+    let sidecar = Sidecar::load();
+    for (key, inactive) in sorted_keys {
+      if inactive {
+        println!("{}", key);
+      } else {
+        e_yellow_ln!("* {} *", key);
+      }
+      for schedule_label in sidecar.schedule_labels(key) {
+        println!("    {}  {}", schedule_label.times(), schedule_label.label);
+      }
+      if verbose {
+        if let Some(note) = sidecar.note(key) {
+          println!("    Note: {note}");
+        }
+        let tags: Vec<&str> = sidecar.tags(key).collect();
+        if !tags.is_empty() {
+          println!("    Tags: {}", tags.join(", "));
+        }
+      }
+    }
+  } else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot determine all the blocks right now"
+    );
+  }
+}
+
+/// A block as `ctk list --json` prints it
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListedBlock<'a> {
+  name: &'a str,
+  active: bool,
+  profile: Option<&'a str>,
+  note: Option<&'a str>,
+  tags: Vec<&'a str>,
+  schedule_labels: Vec<ListedLabel<'a>>,
+}
+
+#[derive(Serialize)]
+struct ListedLabel<'a> {
+  times: String,
+  label: &'a str,
+}
+
+fn list_blocks_json(config: &Config) {
+  let Some(settings) = get_ct_settings() else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot determine all the blocks right now"
+    );
+    return;
+  };
+  let sidecar = Sidecar::load();
+  let mut blocks: Vec<ListedBlock> = settings
+    .block_list_info
+    .blocks
+    .iter()
+    .map(|(name, info)| ListedBlock {
+      name,
+      active: !info.is_dormant(),
+      profile: config.profile_of(name),
+      note: sidecar.note(name),
+      tags: sidecar.tags(name).collect(),
+      schedule_labels: sidecar
+        .schedule_labels(name)
+        .iter()
+        .map(|label| ListedLabel {
+          times: label.times(),
+          label: &label.label,
+        })
+        .collect(),
+    })
+    .collect();
+  blocks.sort_unstable_by_key(|block| block.name);
+  match serde_json::to_string_pretty(&blocks) {
+    Ok(json) => println!("{json}"),
+    Err(err) => error!("Cannot write the blocks as JSON: {err}"),
+  }
+}
+
+/// The blocks a name on the command line stands for: the block itself, or for "@name" every
+/// block tagged name or listed in the profile name
+fn blocks_named(config: &Config, block_name: &str) -> Vec<String> {
+  let Some(group) = block_name.strip_prefix('@') else {
+    return vec![block_name.to_string()];
+  };
+  let sidecar = Sidecar::load();
+  let mut blocks: Vec<String> = sidecar
+    .all_tags()
+    .get(group)
+    .into_iter()
+    .flatten()
+    .chain(config.profile_blocks(group).iter())
+    .map(|block| block.to_string())
+    .collect();
+  blocks.sort_unstable();
+  blocks.dedup();
+
+  if blocks.is_empty() {
+    error!(
+      "No blocks are tagged {group} or in a profile called {group}. See the tags with `ctk tags`."
+    );
+  }
+  blocks
+}
+
+fn list_tags(config: &Config) {
+  let sidecar = Sidecar::load();
+  let tags = sidecar.all_tags();
+  if tags.is_empty() {
+    eprintln!("No blocks are tagged yet. Tag one with `ctk tag <block> <tag>`.");
+  }
+  for (tag, blocks) in tags {
+    println!("@{tag}: {}", blocks.join(", "));
+  }
+
+  let profiles: Vec<&str> = config
+    .iter()
+    .filter_map(|(key, _, _)| key.strip_prefix("profiles."))
+    .collect();
+  if !profiles.is_empty() {
+    eprintln!("Profiles work as groups too: @{}", profiles.join(", @"));
+  }
+}
+
+fn simulate_schedule(
+  clock: &impl Clock<Tz = Local>,
+  time: NaiveTime,
+  date: Option<NaiveDate>,
+  block_name: Option<&str>,
+) {
+  let when = match clock::end_datetime(clock, time, date) {
+    Ok(when) => when,
+    Err(why) => {
+      ctk_error!(ErrorCode::InvalidTime, "{why}");
+      return;
+    }
+  };
+  match get_ct_settings() {
+    Some(settings) => schedule::print_simulation(
+      &settings,
+      &Sidecar::load(),
+      &journal::read_entries(),
+      block_name,
+      clock.now(),
+      when,
+    ),
+    None => ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot read the state of your blocks right now"
+    ),
+  }
+}
+
+fn view_schedule(block_name: &str) {
+  let Some(settings) = get_ct_settings() else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot read the state of your blocks right now"
+    );
+    return;
+  };
+  let Some(info) = exportable_block(&settings, block_name) else {
+    return;
+  };
+  if info.schedule_type.as_deref() != Some("scheduled") {
+    println!("Block {block_name} has no schedule, so it blocks the whole time it is running");
+    return;
+  }
+  println!("Block {block_name}:");
+  let ranges = schedule::entry_ranges(&schedule::schedule_entries(info));
+  for line in schedule::week_grid(&ranges) {
+    println!("{line}");
+  }
+}
+
+fn import_schedule_csv(
+  config: &Config,
+  file: &Path,
+  block_name: &str,
+  output: Option<&Path>,
+  yes: bool,
+) {
+  if block_name == FROZEN_TURKEY {
+    ctk_error!(
+      ErrorCode::FrozenTurkey,
+      "You cannot give the Frozen Turkey block a schedule."
+    );
+    return;
+  }
+
+  let contents = match fs::read_to_string(file) {
+    Ok(contents) => contents,
+    Err(err) => {
+      error!("Cannot read {}: {err}", file.display());
+      return;
+    }
+  };
+  let schedule = match schedule::parse_csv(&contents) {
+    Ok(schedule) => schedule,
+    Err(problems) => {
+      failure!(
+        "Does not import {} because of these errors:",
+        file.display()
+      );
+      for problem in problems {
+        eprintln!("  {problem}");
+      }
+      return;
+    }
+  };
+  for overlap in schedule::describe_overlaps(&schedule) {
+    eprintln!("WARNING: {overlap}");
+  }
+  let entries = schedule.len();
+  let schedule = schedule::merge_overlapping(schedule);
+  if schedule.len() < entries {
+    eprintln!(
+      "Joins the entries with the same break that overlap or follow on from each other, leaving {} of {entries}",
+      schedule.len()
+    );
+  }
+
+  // Saving to a file starts from an empty block. Importing keeps the websites of a block
+  // that already exists, since the import replaces all of it
+  let (mut block_settings, exported) = match output {
+    Some(_) => (BlockSettings::new(), false),
+    None => {
+      let Some(settings) = get_ct_settings() else {
+        ctk_error!(
+          ErrorCode::SettingsUnreadable,
+          "ctk cannot read block {block_name} right now, so it cannot give it a schedule"
+        );
+        return;
+      };
+      if settings.block_list_info.blocks.contains_key(block_name) {
+        match exportable_block(&settings, block_name) {
+          Some(block_info) => (bridge::export_block(block_info), true),
+          None => return,
+        }
+      } else {
+        (BlockSettings::new(), false)
+      }
+    }
+  };
+  block_settings.sched_type = SchedType::Scheduled;
+  block_settings.schedule = schedule;
+  // The schedule Cold Turkey reported would otherwise be written after the new one, and win
+  block_settings.unknown_fields.remove("schedule");
+
+  eprintln!("Block {block_name} will be scheduled for:");
+  for entry in &block_settings.schedule {
+    eprintln!(
+      "  - {}",
+      sidecar::describe_times(&entry.start_time.to_string(), &entry.end_time.to_string())
+    );
+  }
+
+  let mut blocks = HashMap::new();
+  blocks.insert(block_name.to_string(), block_settings);
+  if let Some(output) = output {
+    let json = match serde_json::to_value(&blocks) {
+      Ok(json) => json,
+      Err(err) => {
+        error!("Cannot write the block: {err}");
+        return;
+      }
+    };
+    match write_ctbbl_json(json, Some(output), None) {
+      Ok(()) => success!("Saves block {block_name} to {}", output.display()),
+      Err(why) => error!("Cannot save block {block_name}: {why}"),
+    }
+    return;
+  }
+
+  if exported {
+    eprintln!("{EXPORT_NOTE}");
+  }
+  if !yes && !confirm_continue() {
+    return;
+  }
+  if import_blocks(config, &blocks, "ctk schedule import-csv") {
+    success!("Sends block {block_name} with its new schedule to Cold Turkey. Confirm the import in Cold Turkey to finish.");
+  }
+}
+
+/// Goes by the lock Cold Turkey has for the block when it is running, and otherwise by the
+/// lock ctk last saved it with
+fn unlock_hint(block_name: &str) {
+  let running_length = get_ct_settings().and_then(|settings| {
+    let info = settings.block_list_info.blocks.get(block_name)?;
+    let length = info.random_text_length.filter(|_| !info.is_dormant())?;
+    u16::try_from(length).ok()
+  });
+  let length =
+    running_length.or_else(|| randomtext::recorded_length(&journal::read_entries(), block_name));
+
+  let Some(length) = length else {
+    ctk_error!(
+      ErrorCode::BlockNotFound,
+      "ctk does not know of a random text lock on block {block_name}. It knows of them on running blocks, and on blocks saved by `ctk suggest` or `ctk wizard`."
+    );
+    return;
+  };
+
+  println!("Unlocking block {block_name} means typing {length} random characters, which takes");
+  for estimate in randomtext::typing_estimates(length) {
+    println!("{estimate}");
+  }
+}
+
+fn note_block(block_name: &str, note: Option<&str>, clear: bool) {
+  // An empty note is taken as clearing it, rather than keeping a blank one
+  let note = note.map(str::trim);
+  let clear = clear || note == Some("");
+  let note = note.filter(|_| !clear);
+  let mut sidecar = Sidecar::load();
+  if note.is_none() && !clear {
+    match sidecar.note(block_name) {
+      Some(note) => println!("{note}"),
+      None => eprintln!(
+        "Block {block_name} has no note. Add one with `ctk note \"{block_name}\" <note>`."
+      ),
+    }
+    return;
+  }
+
+  sidecar.set_note(block_name, note.map(str::to_string));
+  match sidecar.save() {
+    Ok(()) if clear => success!("Removes the note on block {block_name}"),
+    Ok(()) => success!("Saves the note on block {block_name}"),
+    Err(err) => error!("ctk cannot save the note: {err}"),
+  }
+}
+
+fn tag_block(block_name: &str, tags: &[String], remove: bool) {
+  // "@social" is how tags are written elsewhere, so it is taken to mean "social" here
+  let tags: Vec<String> = tags
+    .iter()
+    .map(|tag| tag.trim().trim_start_matches('@').to_string())
+    .filter(|tag| !tag.is_empty())
+    .collect();
+  if tags.is_empty() {
+    error!("No tags given");
+    return;
+  }
+  if let Some(tag) = tags.iter().find(|tag| tag.contains(char::is_whitespace)) {
+    error!("Tag \"{tag}\" has spaces in it. Use a dash or underscore instead.");
+    return;
+  }
+
+  let mut sidecar = Sidecar::load();
+  let changed = if remove {
+    sidecar.remove_tags(block_name, &tags)
+  } else {
+    sidecar.add_tags(block_name, &tags)
+  };
+  if changed.is_empty() {
+    if remove {
+      eprintln!("Block {block_name} has none of those tags.");
+    } else {
+      eprintln!("Block {block_name} already has those tags.");
+    }
+    return;
+  }
+
+  match sidecar.save() {
+    Ok(()) if remove => success!("Removes {} from block {block_name}", changed.join(", ")),
+    Ok(()) => success!("Tags block {block_name} with {}", changed.join(", ")),
+    Err(err) => error!("ctk cannot save the tags: {err}"),
+  }
+}
+
+fn show_streak(
+  config: &Config,
+  block_name: Option<&str>,
+  goal: Option<std::time::Duration>,
+  notify: bool,
+) {
+  let block_name = match block_name.or_else(|| config.streak_block()) {
+    Some(block_name) => block_name,
+    None => {
+      error!("No block given. Name one, or choose one with `ctk config set streak_block <block>`");
+      return;
+    }
+  };
+
+  let goal = goal.unwrap_or_else(|| config.streak_goal());
+  let goal = match chrono::Duration::from_std(goal) {
+    Ok(goal) => goal,
+    Err(_) => {
+      error!("The streak goal is too long");
+      return;
+    }
+  };
+
+  let streak =
+    streak::Streak::from_journal(&journal::read_entries(), block_name, goal, Local::now());
+  streak::print_streak(block_name, &streak);
+
+  if notify && streak.is_at_risk() {
+    match config.streak_hook() {
+      Some(hook) => match streak::run_hook(hook, block_name, &streak) {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("WARNING: streak_hook exited with {status}"),
+        Err(err) => error!("Cannot run streak_hook: {err}"),
+      },
+      None => {
+        let lines = [format!(
+          "{} left to keep the {}-day streak",
+          streak::format_duration(streak.goal - streak.today),
+          streak.current
+        )];
+        let buttons = [toast::Button::status()];
+        if toast::show(&format!("{block_name} streak at risk"), &lines, &buttons).is_err() {
+          eprintln!(
+            "WARNING: The streak is at risk, but there is no streak_hook to notify you. Set one with `ctk config set streak_hook <command>`"
+          );
+        }
+      }
+    }
+  }
+}
+
+/// The rules file given with --rules, or rules.toml in the config directory
+fn find_rules_path(rules_path: Option<&Path>) -> Option<PathBuf> {
+  match rules_path {
+    Some(path) => Some(path.to_path_buf()),
+    None => {
+      let path = watch::default_rules_path();
+      if path.is_none() {
+        error!("Cannot find your config directory. Use --rules to choose a rules file.");
+      }
+      path
+    }
+  }
+}
+
+fn start_watching(config: &Config, rules_path: Option<&Path>) {
+  let Some(rules_path) = find_rules_path(rules_path) else {
+    return;
+  };
+  match watch::load_rules(&rules_path) {
+    Ok(rules) => watch::watch(config, &rules, &rules_path),
+    Err(why) => error!("Cannot read the rules: {why}"),
+  }
+}
+
+fn preview_watch(config: &Config, rules_path: Option<&Path>) {
+  let Some(rules_path) = find_rules_path(rules_path) else {
+    return;
+  };
+  let rules = match watch::load_rules(&rules_path) {
+    Ok(rules) => rules,
+    Err(why) => {
+      error!("Cannot read the rules: {why}");
+      return;
+    }
+  };
+
+  let planned = watch::plan(config, &rules);
+  if planned.is_empty() {
+    println!("{} has no rules", rules_path.display());
+    return;
+  }
+  for rule in planned {
+    println!("{} rule for {}", rule.kind, rule.block);
+    println!("  when: {}, {}", rule.trigger, rule.days);
+    for command in &rule.commands {
+      println!("  runs: {command}");
+    }
+  }
+}
+
+fn diff_watch(config: &Config, rules_path: Option<&Path>) {
+  let running = match watch::running_rules() {
+    Ok(Some(running)) => running,
+    Ok(None) => {
+      failure!("No ctk watch is running");
+      return;
+    }
+    Err(why) => {
+      error!("Cannot read the rules of the running ctk watch: {why}");
+      return;
+    }
+  };
+  // The file the running watch loaded, unless another one is asked for
+  let rules_path = rules_path.map_or_else(|| running.path.clone(), Path::to_path_buf);
+  let rules = match watch::load_rules(&rules_path) {
+    Ok(rules) => rules,
+    Err(why) => {
+      error!("Cannot read the rules: {why}");
+      return;
+    }
+  };
+
+  let lines = watch::diff(&running.rules, &watch::plan(config, &rules));
+  if lines.is_empty() {
+    success!(
+      "The ctk watch running since {} has the rules in {}",
+      running.loaded.format("%Y-%m-%d %H:%M"),
+      rules_path.display()
+    );
+    return;
+  }
+  for line in lines {
+    println!("{line}");
+  }
+  failure!(
+    "The ctk watch running since {} loaded other rules than {} has now. Restart it to load them",
+    running.loaded.format("%Y-%m-%d %H:%M"),
+    rules_path.display()
+  );
+}
+
+fn run_pomodoros(config: &Config, block_name: &str, plan: &pomodoro::Plan) {
+  if plan.work == 0 || plan.break_minutes == 0 || plan.cycles == 0 {
+    error!("--work, --break and --cycles must be more than 0");
+    return;
+  }
+  if block_name == FROZEN_TURKEY {
+    ctk_error!(
+      ErrorCode::FrozenTurkey,
+      "Frozen Turkey cannot be stopped for breaks, so it cannot run pomodoros."
+    );
+    return;
+  }
+  if Some(false) == check_if_block_exists(block_name) {
+    return;
+  }
+  pomodoro::run(config, block_name, plan);
+}
+
+fn list_global_settings() {
+  if let Some(settings) = get_ct_settings() {
+    for setting in GlobalSetting::value_variants() {
+      // Why unwrap? None of the GlobalSetting variants are skipped
+      let name = setting.to_possible_value().unwrap();
+      println!(
+        "{} = {}",
+        name.get_name(),
+        settings.global_setting(*setting)
+      );
+    }
+  } else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot read Cold Turkey's settings right now"
+    );
+  }
+}
+
+fn set_global_setting(config: &Config, setting: GlobalSetting, value: bool) {
+  let key = setting.key();
+  if let Some(settings) = get_ct_settings() {
+    if settings.global_setting(setting) == value {
+      success!("{key} is already set to {value}");
+      return;
+    }
+  }
+
+  let applied = |settings: &ColdTurkeySettings| settings.global_setting(setting) == value;
+  match blocker::run(config, BlockerCommand::Setting { key, value }) {
+    Ok(_) => match verify::settings_until(config, applied) {
+      Some(settings) if applied(&settings) => {
+        success!("Sets {key} to {value}");
+      }
+      Some(_) => failure!(
+        EXIT_NOT_APPLIED;
+        "Cold Turkey did not change {key}. Your version of Cold Turkey may not allow changing it from the command line."
+      ),
+      None => eprintln!(
+        "WARNING: Sent {key} = {value} to Cold Turkey, but ctk cannot check if it was applied"
+      ),
+    },
+    Err(err) => ctk_error!(err.code(), "Cannot run `ctk settings set`: {err}"),
+  }
+}
+
+fn show_config(config: &Config, origin: bool) {
+  let mut is_empty = true;
+  for (key, value, value_origin) in config.iter() {
+    is_empty = false;
+    if origin {
+      println!("{key} = {value}    # from {value_origin}");
+    } else {
+      println!("{key} = {value}");
+    }
+  }
+
+  if is_empty {
+    eprintln!("No configuration set. ctk reads these files, if they exist:");
+    if let Some(path) = config::system_config_path() {
+      eprintln!("  {}", path.display());
+    }
+    if let Some(path) = config::user_config_path() {
+      eprintln!("  {}", path.display());
     }
-  } else {
     eprintln!(
-      "WARNING: ctk cannot check if block {} is in your Cold Turkey application right now",
-      block_name
+      "Settings can also be given as CTK_* environment variables or with --set <key>=<value>"
     );
-    None
   }
 }
 
-fn start_block_with_password(block_name: &str) {
-  if block_name == FROZEN_TURKEY {
-    eprintln!("ERROR: You can only start Frozen Turkey when time is provided. Consider `ctk start for` or `ctk start until`.");
-    return;
+fn get_config_value(config: &Config, key: &str) {
+  match config.get(key) {
+    Some(value) => println!("{value}"),
+    None if key == "blocker_path" => println!("{}", config.blocker_path().display()),
+    None if key == "msg_host_path" => println!("{}", config.msg_host_path().display()),
+    None => error!("{key} is not set"),
   }
+}
 
-  let ct_settings = get_ct_settings();
-  if let Some(settings) = &ct_settings {
-    if settings.is_pro == UserStatus::Free {
-      eprintln!(
-        "ERROR: Cannot start a block with a password as a free user. Consider upgrading to pro."
-      );
-      return;
+fn set_config_value(key: &str, value: &str) {
+  match config::set_user_value(key, value) {
+    Ok(path) => success!("Sets {key} in {}", path.display()),
+    Err(why) => ctk_error!(ErrorCode::InvalidConfig, "Cannot set {key}: {why}"),
+  }
+}
+
+fn list_config_keys(config: &Config) {
+  for key in &config::KEYS {
+    println!("{} - {}", key.name, key.description);
+  }
+
+  let problems = config.validate();
+  if !problems.is_empty() {
+    eprintln!();
+    for problem in problems {
+      eprintln!("WARNING: {problem}");
     }
+  }
+}
 
-    if !settings.block_list_info.blocks.contains_key(block_name) {
-      eprintln!(
-        "ERROR: Block {} cannot be found in your Cold Turkey application",
-        block_name
-      );
-      return;
+fn edit_config() {
+  match config::edit_user_config() {
+    Ok(path) => {
+      // Reload so mistakes made in the editor are reported straight away
+      let problems = Config::load().validate();
+      if problems.is_empty() {
+        success!("Saved {}", path.display());
+      } else {
+        for problem in problems {
+          eprintln!("WARNING: {problem}");
+        }
+      }
     }
-  } else {
-    eprintln!(
-      "WARNING: ctk cannot check if block {} is in your Cold Turkey application right now",
-      block_name
-    );
+    Err(why) => error!("Cannot edit the config file: {why}"),
   }
+}
+
+fn read_ctbbl_json(path: &Path) -> Result<serde_json::Value, String> {
+  let contents = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+  serde_json::from_str(&contents)
+    .map_err(|err| format!("{} is not a valid .ctbbl file: {}", path.display(), err))
+}
 
-  let p = Zeroizing::new(loop {
-    match Password::new().with_prompt("Enter a password").interact() {
-      Ok(pass) => break pass,
-      Err(_) => continue,
+fn write_ctbbl_json(
+  mut json: serde_json::Value,
+  output: Option<&Path>,
+  target_version: Option<(u32, u32)>,
+) -> Result<(), String> {
+  if let Some(version) = target_version {
+    for warning in blocksettings::adapt_to_version(&mut json, version) {
+      eprintln!("WARNING: {warning}");
     }
-  });
+  }
+  let contents = serde_json::to_string_pretty(&json).map_err(|err| err.to_string())?;
+  match output {
+    Some(path) => fs::write(path, contents).map_err(|err| format!("{}: {}", path.display(), err)),
+    None => {
+      println!("{contents}");
+      Ok(())
+    }
+  }
+}
 
-  if process::Command::new(CT_EXEC)
-    .args(["-start", block_name, "-password", &p])
-    .spawn()
-    .is_ok()
-  {
-    eprintln!("SUCCESS: Starts blocking {} with a password", block_name);
-  } else {
-    eprintln!("ERROR: Cannot run `ctk start --password`. Did you make sure Cold Turkey is installed and in the right folder? Try typing ctk");
+fn import_ctbbl_file(config: &Config, file: &Path, watch: bool) {
+  let mut last_imported = None;
+  import_checked_ctbbl(config, file, &mut last_imported);
+  if !watch {
+    return;
+  }
+
+  eprintln!(
+    "Watching {} for changes. Press Ctrl+C to stop.",
+    file.display()
+  );
+  if let Err(err) = filewatch::watch_file(file, || {
+    import_checked_ctbbl(config, file, &mut last_imported)
+  }) {
+    error!("Cannot watch {}: {err}", file.display());
   }
 }
 
-fn start_block_for_some_minutes(block_name: &str, minutes: u32) {
-  if Some(false) == check_if_block_exists(block_name) {
+/// Imports the file if it changed since `last_imported` and has no lint errors
+fn import_checked_ctbbl(config: &Config, file: &Path, last_imported: &mut Option<String>) {
+  let contents = match fs::read_to_string(file) {
+    Ok(contents) => contents,
+    Err(err) => {
+      error!("Cannot read {}: {err}", file.display());
+      return;
+    }
+  };
+  if last_imported.as_ref() == Some(&contents) {
     return;
   }
 
-  if process::Command::new(CT_EXEC)
-    .args(["-start", block_name, "-lock", &minutes.to_string()])
-    .spawn()
-    .is_ok()
-  {
-    eprintln!(
-      "SUCCESS: Starts blocking {} locked for {} minutes",
-      block_name, minutes
+  let json: serde_json::Value = match serde_json::from_str(&contents) {
+    Ok(json) => json,
+    Err(err) => {
+      error!("{} is not a valid .ctbbl file: {err}", file.display());
+      return;
+    }
+  };
+  let Some(blocks) = json.as_object() else {
+    error!("{} does not hold blocks by name", file.display());
+    return;
+  };
+
+  let errors: Vec<lint::Finding> = lint::lint(blocks)
+    .into_iter()
+    .filter(|finding| finding.severity == lint::Severity::Error)
+    .collect();
+  if !errors.is_empty() {
+    failure!(
+      "Does not import {} because of these errors:",
+      file.display()
     );
-  } else {
-    eprintln!("ERROR: Cannot run `ctk start for`. Did you make sure Cold Turkey is installed and in the right folder? Try typing ctk");
+    for finding in errors {
+      eprintln!(
+        "  {}[{}]: {} {}",
+        finding.severity, finding.rule, finding.block, finding.message
+      );
+    }
+    return;
   }
-}
 
-fn start_block_until_time(block_name: &str, endtime: NaiveTime, enddate: Option<NaiveDate>) {
-  if Some(false) == check_if_block_exists(block_name) {
+  let capabilities = Capabilities::detect(config);
+  if !capabilities.blocker_found {
+    print_import_steps(file, &capabilities);
+    *last_imported = Some(contents);
     return;
   }
 
-  let datetime: DateTime<Local> = match enddate {
-    Some(date) => {
-      let naive_datetime: NaiveDateTime = date.and_time(endtime);
-      let datetime_result: LocalResult<DateTime<Local>> =
-        Local.from_local_datetime(&naive_datetime);
-      match datetime_result {
-        LocalResult::None => {
-          eprintln!("ERROR: Can't get the datetime specified.");
-          return;
-        }
-        LocalResult::Single(datetime) => datetime,
-        LocalResult::Ambiguous(_, _) => {
-          eprintln!("ERROR: Datetime given is ambiguous. Maybe try to be more clear in your time?");
-          return;
-        }
-      }
+  let _lock = match OperationLock::acquire() {
+    Ok(lock) => lock,
+    Err(err) => {
+      ctk_error!(
+        ErrorCode::OperationRunning,
+        "Cannot import {}: {err}",
+        file.display()
+      );
+      return;
     }
-    None => {
-      let today: Date<Local> = Local::today();
-      let today_time_option: Option<DateTime<Local>> = today.and_time(endtime);
-      match today_time_option {
-        Some(datetime) => datetime,
-        None => {
-          eprintln!("ERROR: The date is assumed to be today, however, the time given seems to make it invalid.");
-          return;
-        }
+  };
+  if import_blocks(config, &json, "ctk import") {
+    if blocks.len() > 1 {
+      let mut table = Table::new();
+      for block_name in blocks.keys() {
+        table.add(block_name, Status::Done, "sent", "");
       }
+      eprintln!("{}", table.render());
     }
-  };
+    success!(
+      "Opens {} block(s) in Cold Turkey for you to confirm the import",
+      blocks.len()
+    );
+    *last_imported = Some(contents);
+  }
+}
 
-  let duration = datetime.signed_duration_since(Local::now());
-  // If duration is exactly a multiple of 60, do not round up
-  let duration_minutes = if duration.num_seconds() % 60 == 0 {
-    duration.num_minutes()
+/// How to get a checked .ctbbl file into Cold Turkey once it is installed
+fn print_import_steps(file: &Path, capabilities: &Capabilities) {
+  eprintln!(
+    "WARNING: {} has no errors, but is not imported, since Cold Turkey Blocker cannot be found at {}. To import it:",
+    file.display(),
+    capabilities.blocker_path.display()
+  );
+  eprintln!("  1. Install Cold Turkey Blocker with `ctk install`, or from https://getcoldturkey.com/download/");
+  eprintln!(
+    "  2. If it is installed somewhere else, run `ctk config set blocker_path <path to Cold Turkey Blocker.exe>`"
+  );
+  eprintln!(
+    "  3. Run `ctk import {}` again, or open the file in Cold Turkey Blocker by double-clicking it",
+    file.display()
+  );
+}
+
+fn merge_into_ctbbl_file(
+  file: &Path,
+  block_name: &str,
+  web: &[String],
+  except: &[String],
+  apps: &[AppString],
+  target_version: Option<(u32, u32)>,
+) {
+  let mut json = if file.exists() {
+    match read_ctbbl_json(file) {
+      Ok(json) => json,
+      Err(why) => {
+        error!("Cannot run `ctk merge-into`: {why}");
+        return;
+      }
+    }
   } else {
-    duration.num_minutes() + 1
+    serde_json::Value::Object(serde_json::Map::new())
   };
 
-  if duration_minutes <= 0 {
-    eprintln!(
-      "ERROR: Cannot start block until a time in the past. Please enter a time in the future."
+  let Some(blocks) = json.as_object_mut() else {
+    error!(
+      "Cannot run `ctk merge-into`: {} does not hold blocks by name",
+      file.display()
     );
     return;
+  };
+  let block = blocks
+    .entry(block_name)
+    // Why unwrap? BlockSettings always serializes
+    .or_insert_with(|| serde_json::to_value(BlockSettings::new()).unwrap());
+
+  // Why unwrap? AppString always serializes
+  let apps: Vec<String> = apps
+    .iter()
+    .filter_map(|app| {
+      serde_json::to_value(app)
+        .unwrap()
+        .as_str()
+        .map(str::to_string)
+    })
+    .collect();
+  let mut added = 0;
+  let mut skipped = 0;
+  for (list, entries) in [("web", web), ("exceptions", except), ("apps", &apps)] {
+    let Some(list) = block
+      .get_mut(list)
+      .and_then(serde_json::Value::as_array_mut)
+    else {
+      error!("Cannot run `ctk merge-into`: block {block_name} has no {list} list");
+      return;
+    };
+    for entry in entries {
+      if list.iter().any(|existing| existing.as_str() == Some(entry)) {
+        skipped += 1;
+      } else {
+        list.push(serde_json::Value::String(entry.clone()));
+        added += 1;
+      }
+    }
   }
 
-  if process::Command::new(CT_EXEC)
-    .args(["-start", block_name, "-lock", &duration_minutes.to_string()])
-    .spawn()
-    .is_ok()
-  {
-    eprintln!(
-      "SUCCESS: Starts blocking {} locked until {}",
-      block_name,
-      datetime.format("%H:%M %B %d %Y")
-    );
-  } else {
-    eprintln!("ERROR: Cannot run `ctk start until`. Did you make sure Cold Turkey is installed and in the right folder? Try typing ctk");
+  match write_ctbbl_json(json, Some(file), target_version) {
+    Ok(()) if skipped == 0 => {
+      success!("Adds {added} entries to {block_name} in {}", file.display())
+    }
+    Ok(()) => success!(
+      "Adds {added} entries to {block_name} in {}, skipping {skipped} already there",
+      file.display()
+    ),
+    Err(why) => error!("Cannot run `ctk merge-into`: {why}"),
   }
 }
 
-fn start_block_unlocked(block_name: &str) {
-  if block_name == FROZEN_TURKEY {
-    eprintln!("ERROR: You can only start Frozen Turkey when time is provided. Consider `ctk start for` or `ctk start until`.");
-    return;
+fn explain_error(code: Option<&str>) {
+  match code.map(str::parse::<ErrorCode>) {
+    Some(Ok(code)) => {
+      println!("{code}: {}", code.summary());
+      println!();
+      println!("{}", code.explanation());
+    }
+    Some(Err(why)) => error!("{why}. Run `ctk explain` to see every code."),
+    None => {
+      for code in ErrorCode::ALL {
+        println!("{code}  {}", code.summary());
+      }
+    }
   }
+}
 
-  if Some(false) == check_if_block_exists(block_name) {
+fn lint_ctbbl_file(file: &Path) {
+  let json = match read_ctbbl_json(file) {
+    Ok(json) => json,
+    Err(why) => {
+      error!("Cannot lint: {why}");
+      return;
+    }
+  };
+  let Some(blocks) = json.as_object() else {
+    error!(
+      "Cannot lint: {} does not hold blocks by name",
+      file.display()
+    );
     return;
+  };
+
+  let findings = lint::lint(blocks);
+  for finding in &findings {
+    println!(
+      "{}[{}]: {} {}",
+      finding.severity, finding.rule, finding.block, finding.message
+    );
+    println!("  {}", finding.explanation);
   }
 
-  if process::Command::new(CT_EXEC)
-    .args(["-start", block_name])
-    .spawn()
-    .is_ok()
-  {
-    eprintln!("SUCCESS: Starts blocking {}", block_name);
+  let count = |severity| {
+    findings
+      .iter()
+      .filter(|finding| finding.severity == severity)
+      .count()
+  };
+  if findings.is_empty() {
+    success!("Finds nothing to fix in {} block(s)", blocks.len());
   } else {
-    eprintln!("ERROR: Cannot run `ctk start`. Did you make sure Cold Turkey is installed and in the right folder? Try typing ctk");
+    eprintln!(
+      "{} error(s), {} warning(s), {} info",
+      count(lint::Severity::Error),
+      count(lint::Severity::Warning),
+      count(lint::Severity::Info)
+    );
   }
 }
 
-fn stop_block(block_name: &str) {
-  if FROZEN_TURKEY == block_name {
-    eprintln!("FAILURE: Cannot stop Frozen Turkey because it is a timed, locked block. If it is already off, no need to worry.");
-    return;
+fn expanded_template(config: &Config, file: &Path, command: &str) -> Option<serde_json::Value> {
+  let mut json = match read_ctbbl_json(file) {
+    Ok(json) => json,
+    Err(why) => {
+      error!("Cannot run `{command}`: {why}");
+      return None;
+    }
+  };
+  match Variables::for_this_machine(config).expand_json(&mut json) {
+    Ok(()) => Some(json),
+    Err(why) => {
+      ctk_error!(ErrorCode::MissingVariable, "Cannot run `{command}`: {why}");
+      None
+    }
   }
+}
 
-  if Some(false) == check_if_block_exists(block_name) {
-    return;
+fn import_template(config: &Config, file: &Path) {
+  if let Some(json) = expanded_template(config, file, "ctk template import") {
+    if import_blocks(config, &json, "ctk template import") {
+      success!("Opens the blocks in Cold Turkey for you to confirm the import");
+    }
   }
+}
 
-  if process::Command::new(CT_EXEC)
-    .args(["-stop", block_name])
-    .spawn()
-    .is_ok()
-  {
-    // Why unwrap? It's safe to assume that if the first get_ct_settings works, why not the second?
-    let new_ct_settings = get_ct_settings().unwrap();
-    if new_ct_settings.block_list_info.blocks[block_name].is_dormant() {
-      eprintln!("SUCCESS: Stops blocking {}", block_name);
-    } else {
-      eprintln!("FAILURE: Failed to stop blocking {block_name}");
+fn expand_template(
+  config: &Config,
+  file: &Path,
+  output: Option<&Path>,
+  target_version: Option<(u32, u32)>,
+) {
+  if let Some(json) = expanded_template(config, file, "ctk template expand") {
+    match write_ctbbl_json(json, output, target_version) {
+      Ok(()) => {
+        if let Some(output) = output {
+          success!("Saves the expanded blocks to {}", output.display());
+        }
+      }
+      Err(why) => error!("Cannot save the expanded blocks: {why}"),
     }
-  } else {
-    eprintln!("ERROR: Cannot run `ctk stop`. Did you make sure Cold Turkey is installed and in the right folder? Try typing ctk");
   }
 }
 
-fn add_websites_to_block(block_name: &str, url: &str, except: bool) {
-  if block_name == FROZEN_TURKEY {
-    eprintln!("ERROR: You cannot add websites to the Frozen Turkey block.");
-    return;
+fn make_template(
+  config: &Config,
+  file: &Path,
+  output: Option<&Path>,
+  target_version: Option<(u32, u32)>,
+) {
+  let result = read_ctbbl_json(file).and_then(|mut json| {
+    Variables::for_this_machine(config).templatize_json(&mut json);
+    write_ctbbl_json(json, output, target_version)
+  });
+  match result {
+    Ok(()) => {
+      if let Some(output) = output {
+        success!("Saves the template to {}", output.display());
+      }
+    }
+    Err(why) => error!("Cannot make a template: {why}"),
   }
+}
 
-  if Some(false) == check_if_block_exists(block_name) {
-    return;
+fn write_html_report(
+  entries: &[journal::Entry],
+  period: report::Period,
+  output: Option<&Path>,
+  template: Option<&Path>,
+) {
+  let template = match template.map(fs::read_to_string).transpose() {
+    Ok(template) => template,
+    Err(err) => {
+      error!("Cannot read the report template: {err}");
+      return;
+    }
+  };
+  let html = match htmlreport::render(entries, period, template.as_deref()) {
+    Ok(html) => html,
+    Err(why) => {
+      error!("Cannot fill in the report template: {why}");
+      return;
+    }
+  };
+  match output {
+    Some(output) => match fs::write(output, html) {
+      Ok(()) => success!("Saves the report to {}", output.display()),
+      Err(err) => error!("Cannot save the report: {err}"),
+    },
+    None => print!("{html}"),
   }
+}
 
-  let except_cmd: &str = if except { "-exception" } else { "-web" };
-  if process::Command::new(CT_EXEC)
-    .args(["-add", block_name, except_cmd, url])
-    .spawn()
-    .is_ok()
-  {
-    let new_ct_settings = get_ct_settings().unwrap();
-    let block_info = &new_ct_settings.block_list_info.blocks[block_name];
+fn list_template_variables(config: &Config) {
+  for (name, value) in Variables::for_this_machine(config).iter() {
+    println!("{{{{{name}}}}} = {value}");
+  }
+}
 
-    let is_dormant = block_info.is_dormant();
+/// Everything a sync compares: this machine, the sync location and the last sync
+struct SyncState {
+  location: sync::Location,
+  base_location: sync::Location,
+  local: sync::Snapshot,
+  remote: sync::Snapshot,
+  base: sync::Snapshot,
+  settings: ColdTurkeySettings,
+}
 
-    let string_url = url.to_string();
-    if except && (is_dormant || block_info.exception_list.contains(&string_url)) {
-      eprintln!("SUCCESS: Adds url {url} to block {block_name} as an exception");
-    } else if !except && (is_dormant || block_info.block_list.contains(&string_url)) {
-      eprintln!("SUCCESS: Adds url {url} to block {block_name}");
-    } else {
-      eprintln!("FAILURE: Cannot add url {url} to block {block_name}");
+fn read_sync_state(config: &Config) -> Option<SyncState> {
+  let Some(raw_location) = config.sync_location() else {
+    error!("No sync location set. Choose a folder, git repository or WebDAV URL with `ctk config set sync_location <location>`");
+    return None;
+  };
+
+  let Some(settings) = get_ct_settings() else {
+    ctk_error!(
+      ErrorCode::SettingsUnreadable,
+      "ctk cannot read your blocks right now"
+    );
+    return None;
+  };
+
+  let result = sync::Location::parse(raw_location).and_then(|location| {
+    let base_location = sync::base_location()?;
+    Ok(SyncState {
+      local: sync::Snapshot::local(&settings, &Variables::for_this_machine(config))?,
+      remote: sync::Snapshot::read_from(&location)?,
+      base: sync::Snapshot::read_from(&base_location)?,
+      location,
+      base_location,
+      settings,
+    })
+  });
+
+  match result {
+    Ok(state) => Some(state),
+    Err(why) => {
+      error!("Cannot sync: {why}");
+      None
     }
-  } else {
-    eprintln!("ERROR: Cannot run `ctk add`. Did you make sure Cold Turkey is installed and in the right folder? Try typing ctk");
   }
 }
 
-fn toggle_block(block_name: &str) {
-  if block_name == FROZEN_TURKEY {
-    eprintln!("ERROR: You can only start Frozen Turkey when time is provided. Consider `ctk start for` or `ctk start until`.");
-    return;
+fn print_sync_conflicts(conflicts: &[String], fix: &str) {
+  failure!("These changed both here and in the sync location since your last sync:");
+  for conflict in conflicts {
+    eprintln!("  {conflict}");
   }
+  eprintln!("{fix}");
+}
 
-  let ct_settings = get_ct_settings();
-  let is_off_old = if let Some(settings) = &ct_settings {
-    if settings.block_list_info.blocks.contains_key(block_name) {
-      settings.block_list_info.blocks[block_name].is_dormant()
-    } else {
-      eprintln!(
-        "ERROR: Block {} cannot be found in your Cold Turkey application",
-        block_name
+fn push_sync(config: &Config, force: bool) {
+  let Some(state) = read_sync_state(config) else {
+    return;
+  };
+
+  let pushed = match sync::plan_push(&state.local, &state.remote, &state.base, force) {
+    Ok(pushed) => pushed,
+    Err(conflicts) => {
+      print_sync_conflicts(
+        &conflicts,
+        "Run `ctk sync pull` first, or `ctk sync push --force` to overwrite them.",
       );
       return;
     }
-  } else {
-    eprintln!(
-      "WARNING: ctk cannot check if block {} is in your Cold Turkey application right now",
-      block_name
-    );
-    return;
   };
 
-  if process::Command::new(CT_EXEC)
-    .args(["-toggle", block_name])
-    .spawn()
-    .is_ok()
-  {
-    // Why unwrap? It's safe to assume that if the first get_ct_settings works, why not the second?
-    let new_settings = get_ct_settings().unwrap();
-    let is_off_new = new_settings.block_list_info.blocks[block_name].is_dormant();
-
-    match (is_off_old, is_off_new) {
-      (true, false) => eprintln!("SUCCESS: Starts block {}", block_name),
-      (false, true) => eprintln!("SUCCESS: Stops block {block_name}"),
-      (false, false) => eprintln!("FAILURE: Failed to stop blocking {block_name}"),
-      (true, true) => eprintln!("FAILURE: Failed to start blocking {block_name}"), // rare, probs impossible
+  if pushed != state.remote {
+    if let Err(why) = pushed.write_to(&state.location) {
+      error!("Cannot push: {why}");
+      return;
     }
-  } else {
-    eprintln!("ERROR: Cannot run `ctk toggle`. Did you make sure Cold Turkey is installed and in the right folder? Try typing ctk");
   }
-}
-
-fn open_cold_turkey() {
-  if process::Command::new(CT_EXEC).spawn().is_ok() {
-    eprintln!("SUCCESS: Launches Cold Turkey!");
-  } else {
-    eprintln!(
-      r"ERROR: Looks like you don't have Cold Turkey installed on C:\Program Files\Cold Turkey\Cold Turkey Blocker.exe"
-    );
+  if let Err(why) = pushed.write_to(&state.base_location) {
     eprintln!(
-      "If you do have it installed, please put Cold Turkey Blocker.exe in the folder mentioned."
+      "WARNING: Pushed, but cannot remember this sync, so the next one may report conflicts: {why}"
     );
-    eprintln!("If not, you're welcome to download it at getcoldturkey.com.");
   }
+
+  success!(
+    "Pushes {} running block(s) and your config. Blocks that are not running are not pushed.",
+    state.local.blocks.len()
+  );
 }
 
-fn list_all_blocks() {
-  let ct_settings = get_ct_settings();
-  if let Some(settings) = ct_settings {
-    let keys = settings.block_list_info.blocks.keys();
-    let mut sorted_keys = Vec::new();
-    for key in keys {
-      let block_inactive = settings.block_list_info.blocks[key].is_dormant();
-      sorted_keys.push((key, block_inactive));
+fn pull_sync(config: &Config, force: bool) {
+  let Some(state) = read_sync_state(config) else {
+    return;
+  };
+
+  let mut pull = match sync::plan_pull(&state.local, &state.remote, &state.base, force) {
+    Ok(pull) => pull,
+    Err(conflicts) => {
+      print_sync_conflicts(
+        &conflicts,
+        "Run `ctk sync push` to keep this machine's changes, or `ctk sync pull --force` to overwrite them.",
+      );
+      return;
     }
+  };
 
-    sorted_keys.sort_unstable();
-    for (key, inactive) in sorted_keys {
-      if inactive {
-        println!("{}", key);
-      } else {
-        e_yellow_ln!("* {} *", key);
+  // Cold Turkey does not report blocks that are not running, so importing over one could
+  // lose changes ctk cannot see
+  pull.blocks.retain(|name, _| {
+    let is_hidden = !state.local.blocks.contains_key(name)
+      && state.settings.block_list_info.blocks.contains_key(name);
+    if is_hidden {
+      eprintln!("WARNING: Skips {name} because it is not running here. Start it and pull again to update it.");
+    }
+    !is_hidden
+  });
+
+  if let Some(new_config) = &pull.config {
+    let result = config::user_config_path()
+      .ok_or_else(|| "cannot find your config directory".to_string())
+      .and_then(|path| match new_config {
+        Some(contents) => path
+          .parent()
+          .map_or(Ok(()), fs::create_dir_all)
+          .and_then(|_| fs::write(&path, contents))
+          .map_err(|err| err.to_string()),
+        None => fs::remove_file(&path).map_err(|err| err.to_string()),
+      });
+    match result {
+      Ok(()) => success!("Updates your config"),
+      Err(why) => error!("Cannot update your config: {why}"),
+    }
+  }
+
+  if !pull.blocks.is_empty() {
+    let variables = Variables::for_this_machine(config);
+    for block in pull.blocks.values_mut() {
+      if let Err(why) = variables.expand_json(block) {
+        ctk_error!(ErrorCode::MissingVariable, "Cannot pull: {why}");
+        return;
       }
     }
-  } else {
-    eprintln!("ERROR: ctk cannot determine all the blocks right now");
+    if !import_blocks(config, &pull.blocks, "ctk sync pull") {
+      return;
+    }
+    success!(
+      "Opens {} changed block(s) in Cold Turkey for you to confirm the import",
+      pull.blocks.len()
+    );
+  } else if pull.config.is_none() {
+    success!("Already up to date");
+  }
+
+  if let Err(why) = state.remote.write_to(&state.base_location) {
+    eprintln!(
+      "WARNING: Pulled, but cannot remember this sync, so the next one may report conflicts: {why}"
+    );
   }
 }
 
 fn get_ct_settings() -> Option<ColdTurkeySettings> {
-  match process::Command::new(r"C:\Program Files\Cold Turkey\CTMsgHostEdge.exe").output() {
+  let host = ctsettings::msg_host_path();
+  let timeout = ctsettings::msg_host_timeout();
+  match timeout::output(&mut process::Command::new(host), timeout) {
     Ok(block_stdout) => {
+      hosttrace::record_output(host, &block_stdout);
       let output_vector = block_stdout.stdout;
-      match std::str::from_utf8(&output_vector[4..]) {
+      // The first 4 bytes give the message's length. A host that says less is not Cold Turkey's
+      match std::str::from_utf8(output_vector.get(4..)?) {
         Ok(ct_string) => serde_json::from_str(ct_string).ok(),
         Err(_) => None,
       }
     }
-    Err(_) => None,
+    Err(err) => {
+      hosttrace::record_error(host, &err);
+      if err.kind() == io::ErrorKind::TimedOut {
+        ctk_error!(
+          ErrorCode::TimedOut,
+          "{} {err}. Give it longer with `ctk config set msg_host_timeout <duration>`",
+          host.display()
+        );
+      }
+      None
+    }
+  }
+}
+
+fn print_completions(shell: CompletionShell, static_script: bool) {
+  // Why unwrap? None of the CompletionShell variants are skipped
+  let name = shell.to_possible_value().unwrap();
+  let mut stdout = io::stdout();
+  if static_script {
+    // Why unwrap? Every shell ctk offers is one clap_complete has
+    let shell: Shell = name.get_name().parse().unwrap();
+    clap_complete::generate(shell, &mut ColdTurkey::command(), "ctk", &mut stdout);
+    return;
+  }
+
+  let shells = Shells::builtins();
+  // Why unwrap? Every shell ctk offers is one clap_complete has
+  let completer = shells.completer(name.get_name()).unwrap();
+  // The script runs this very ctk, so completions work even when it is not on PATH
+  let ctk = std::env::current_exe().map_or_else(
+    |_| "ctk".to_string(),
+    |path| path.to_string_lossy().into_owned(),
+  );
+  if let Err(err) = completer.write_registration("COMPLETE", "ctk", "ctk", &ctk, &mut stdout) {
+    error!("Cannot print the completion script: {err}");
+  }
+}
+
+fn complete_setup(dry_run: bool, add_to_path: bool) {
+  let changes = setup::plan(&mut ColdTurkey::command(), add_to_path);
+  let mut failed = false;
+  for change in &changes {
+    if dry_run {
+      println!("Would {}", change.describe());
+      continue;
+    }
+    match change.apply() {
+      Ok(()) => success!("{}", change.done()),
+      Err(err) => {
+        error!("Cannot {}: {err}", change.describe());
+        failed = true;
+      }
+    }
+  }
+
+  if !dry_run && !failed {
+    if cfg!(windows) {
+      eprintln!("Open a new terminal for the completions and PATH to take effect.");
+    } else {
+      eprintln!("For zsh, add `fpath+=~/.zfunc` before `compinit` in your .zshrc. Open a new terminal for the completions to take effect.");
+    }
   }
 }
 
-fn install_cold_turkey(force: bool) {
-  if !force && Path::new(CT_EXEC).exists() {
+fn install_cold_turkey(config: &Config, force: bool) {
+  if !force && config.blocker_path().exists() {
     eprintln!("Cold Turkey Blocker already exists");
   } else {
-    match try_install_cold_turkey() {
-      Ok(_) => eprintln!("SUCCESS: Installation successful"),
+    match try_install_cold_turkey(config.installer_timeout()) {
+      Ok(_) => success!("Installation successful"),
+      Err(err) if is_timeout(err.as_ref()) => ctk_error!(
+        ErrorCode::TimedOut,
+        "The Cold Turkey installer {err}. Give it longer with `ctk config set installer_timeout <duration>`"
+      ),
       Err(err) => {
         dbg!(err);
-        eprintln!("ERROR: Something went wrong in downloading the Cold Turkey installer.")
+        error!("Something went wrong in downloading the Cold Turkey installer.")
       }
     }
   }
 }
 
-fn try_install_cold_turkey() -> Result<(), Box<dyn std::error::Error>> {
+fn is_timeout(err: &(dyn std::error::Error + 'static)) -> bool {
+  err
+    .downcast_ref::<io::Error>()
+    .is_some_and(|err| err.kind() == io::ErrorKind::TimedOut)
+}
+
+fn try_install_cold_turkey(timeout: std::time::Duration) -> Result<(), Box<dyn std::error::Error>> {
   // Installs Cold Turkey if it does not exist
   let url = "https://getcoldturkey.com/files/Cold_Turkey_Installer.exe";
   let response = reqwest::blocking::get(url)?;
@@ -424,8 +3899,10 @@ fn try_install_cold_turkey() -> Result<(), Box<dyn std::error::Error>> {
     file.write_all(&bytes)?;
   }
   let mut child = process::Command::new(&tmp_path).spawn()?;
-  child.wait()?;
-  fs::remove_file(&tmp_path)?;
+  let waited = timeout::wait(&mut child, timeout);
+  let removed = fs::remove_file(&tmp_path);
+  waited?;
+  removed?;
 
   Ok(())
 }
@@ -0,0 +1,86 @@
+use crate::config;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Read, Seek, Write};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const LOCK_FILE_NAME: &str = "operation.lock";
+
+// Long enough for another command to finish, short enough that a stuck one is noticed
+const WAIT: Duration = Duration::from_secs(10);
+const POLL: Duration = Duration::from_millis(100);
+
+/// Why the operation lock could not be taken
+#[derive(Debug)]
+pub enum LockError {
+  /// Another ctk process, if it could be told which, held the lock the whole time
+  Busy(Option<u32>),
+  Io(io::Error),
+}
+
+impl std::fmt::Display for LockError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Busy(Some(pid)) => write!(f, "another ctk operation is running (process {pid})"),
+      Self::Busy(None) => write!(f, "another ctk operation is running"),
+      Self::Io(err) => write!(f, "cannot use the lock file: {err}"),
+    }
+  }
+}
+
+/// Held while ctk reads and changes Cold Turkey, so that commands, `ctk watch` and
+/// `ctk import --watch` running at the same time take turns. The OS lets go of the lock
+/// when the file is closed, on drop or when ctk crashes or is killed.
+pub struct OperationLock {
+  _file: File,
+}
+
+impl OperationLock {
+  /// Takes the lock, waiting a few seconds for another ctk operation to finish first
+  pub fn acquire() -> Result<Self, LockError> {
+    let dir = config::data_dir()
+      .ok_or_else(|| LockError::Io(io::Error::new(io::ErrorKind::NotFound, "no data directory")))?;
+    fs::create_dir_all(&dir).map_err(LockError::Io)?;
+    let mut file = OpenOptions::new()
+      .read(true)
+      .write(true)
+      .create(true)
+      .truncate(false)
+      .open(dir.join(LOCK_FILE_NAME))
+      .map_err(LockError::Io)?;
+
+    let started = Instant::now();
+    let mut told_to_wait = false;
+    loop {
+      match file.try_lock() {
+        Ok(()) => {
+          // Only for telling others who holds it, so failing to write it changes nothing
+          let _ = file
+            .set_len(0)
+            .and_then(|()| write!(file, "{}", process::id()));
+          return Ok(Self { _file: file });
+        }
+        Err(TryLockError::WouldBlock) => {}
+        Err(TryLockError::Error(err)) => return Err(LockError::Io(err)),
+      }
+
+      if started.elapsed() >= WAIT {
+        return Err(LockError::Busy(holder(&mut file)));
+      }
+      if !told_to_wait {
+        eprintln!("Waiting for another ctk operation to finish...");
+        told_to_wait = true;
+      }
+      thread::sleep(POLL);
+    }
+  }
+}
+
+/// The process id the holder wrote in the lock file, if it can be read while it is locked
+fn holder(file: &mut File) -> Option<u32> {
+  let mut pid = String::new();
+  file.rewind().ok()?;
+  file.read_to_string(&mut pid).ok()?;
+  pid.trim().parse().ok()
+}
@@ -0,0 +1,75 @@
+use crate::loop_dialoguer::LoopDialogue;
+use ctk_common::ctsettings::ColdTurkeySettings;
+use ctk_common::fuzzy::{Matcher, TopMatches};
+use dialoguer::{Input, MultiSelect};
+
+// Past this many blocks, the list is long enough to be worth narrowing down first
+const FILTER_OVER: usize = 10;
+
+/// Lets the user choose blocks from a list of every block, running ones marked, for a
+/// command like `ctk start` run without a block name. Frozen Turkey is left out, since it
+/// cannot be started or stopped like other blocks
+pub fn pick_blocks(settings: &ColdTurkeySettings, verb: &str, frozen_turkey: &str) -> Vec<String> {
+  let mut blocks: Vec<(&String, bool)> = settings
+    .block_list_info
+    .blocks
+    .iter()
+    .filter(|(name, _)| *name != frozen_turkey)
+    .map(|(name, info)| (name, !info.is_dormant()))
+    .collect();
+  blocks.sort_unstable();
+  if blocks.is_empty() {
+    eprintln!("There are no blocks to {verb}.");
+    return Vec::new();
+  }
+
+  if blocks.len() > FILTER_OVER {
+    let query: String = Input::new()
+      .with_prompt("Type part of a block's name to narrow down the list [empty string for all]")
+      .allow_empty(true)
+      .loop_interact();
+    if !query.trim().is_empty() {
+      blocks = best_matches(blocks, query.trim());
+      if blocks.is_empty() {
+        eprintln!("No block's name is close to {query}.");
+        return Vec::new();
+      }
+    }
+  }
+
+  let items: Vec<String> = blocks
+    .iter()
+    .map(|(name, active)| match active {
+      true => format!("{name} (blocking)"),
+      false => name.to_string(),
+    })
+    .collect();
+  MultiSelect::new()
+    .with_prompt(format!(
+      "Choose the blocks to {verb} (space to select, enter to continue)"
+    ))
+    .items(&items)
+    .loop_interact()
+    .into_iter()
+    .map(|index| blocks[index].0.clone())
+    .collect()
+}
+
+/// The blocks whose names match `query`, best match first
+fn best_matches<'a>(blocks: Vec<(&'a String, bool)>, query: &str) -> Vec<(&'a String, bool)> {
+  let matcher = Matcher::new(query);
+  let top = blocks
+    .iter()
+    .filter_map(|(name, _)| matcher.match_string(name.as_str()))
+    .fold(TopMatches::new(blocks.len()), TopMatches::push);
+  top
+    .into_sorted_vec()
+    .into_iter()
+    .filter_map(|found| {
+      blocks
+        .iter()
+        .find(|(name, _)| **name == found.string)
+        .copied()
+    })
+    .collect()
+}
@@ -0,0 +1,241 @@
+use crate::bridge;
+use crate::loop_dialoguer::LoopDialogue;
+use crate::schedule;
+use crate::sidecar::Sidecar;
+use crate::suggestdialog;
+use ctk_common::blocksettings::{BlockSettings, BreakMethod, SchedType, ScheduleBlock};
+use ctk_common::ctsettings::{BlockInfo, ColdTurkeySettings};
+use dialoguer::{MultiSelect, Select};
+use std::collections::HashMap;
+
+const PLAN_ACTIONS: [&str; 5] = [
+  "Show the week",
+  "List the schedules",
+  "Add times to blocks",
+  "Delete entries",
+  "Done",
+];
+
+/// A running block, with its schedule as planned so far
+struct Planned<'a> {
+  name: &'a str,
+  info: &'a BlockInfo,
+  schedule: Vec<ScheduleBlock>,
+  /// Whether the block blocks only at its scheduled times, rather than whenever it runs
+  scheduled: bool,
+  changed: bool,
+}
+
+/// Plans the schedules of all running blocks at once, on one calendar of the week. Gives
+/// the blocks whose schedule changed as .ctbbl settings, ready to import, or None if there
+/// are no blocks to plan
+pub fn plan_week(
+  settings: &ColdTurkeySettings,
+  frozen: &str,
+) -> Option<HashMap<String, BlockSettings>> {
+  let mut names: Vec<&String> = settings
+    .block_list_info
+    .blocks
+    .keys()
+    .filter(|name| *name != frozen)
+    .collect();
+  names.sort_unstable();
+
+  let sidecar = Sidecar::load();
+  let mut blocks = Vec::new();
+  let mut dormant = Vec::new();
+  for name in names {
+    let info = &settings.block_list_info.blocks[name];
+    if info.is_dormant() {
+      dormant.push(name.as_str());
+      continue;
+    }
+    let Some(mut schedule) = reported_schedule(info) else {
+      eprintln!("WARNING: Leaves out block {name}, since ctk cannot read all of its schedule");
+      continue;
+    };
+    suggestdialog::label_schedule(&sidecar, name, &mut schedule);
+    blocks.push(Planned {
+      name,
+      info,
+      schedule,
+      scheduled: info.schedule_type.as_deref() == Some("scheduled"),
+      changed: false,
+    });
+  }
+  if !dormant.is_empty() {
+    eprintln!(
+      "Leaves out {}, since Cold Turkey only reports the schedules of running blocks",
+      dormant.join(", ")
+    );
+  }
+  if blocks.is_empty() {
+    eprintln!("There are no running blocks to plan. Start them without a lock and try again.");
+    return None;
+  }
+
+  print_week(&blocks);
+  loop {
+    let action = Select::new()
+      .with_prompt("What do you want to do with the week?")
+      .items(&PLAN_ACTIONS)
+      .default(0)
+      .loop_interact();
+
+    match action {
+      0 => print_week(&blocks),
+      1 => list_schedules(&blocks),
+      2 => add_times(&mut blocks),
+      3 => delete_entries(&mut blocks),
+      _ => break,
+    }
+  }
+
+  Some(
+    blocks
+      .into_iter()
+      .filter(|block| block.changed)
+      .filter_map(|block| Some((block.name.to_string(), block_settings(block)?)))
+      .collect(),
+  )
+}
+
+/// The block's schedule as Cold Turkey reports it, which is empty for blocks that run
+/// continuously. None if an entry cannot be read, since importing would lose it
+fn reported_schedule(info: &BlockInfo) -> Option<Vec<ScheduleBlock>> {
+  if info.schedule_type.as_deref() != Some("scheduled") {
+    return Some(Vec::new());
+  }
+  schedule::schedule_entries(info)
+    .into_iter()
+    .enumerate()
+    .map(|(id, entry)| {
+      Some(ScheduleBlock {
+        id,
+        start_time: entry.start.parse().ok()?,
+        end_time: entry.end.parse().ok()?,
+        break_type: entry.break_type.parse().ok()?,
+        label: None,
+      })
+    })
+    .collect()
+}
+
+fn print_week(blocks: &[Planned]) {
+  let week: Vec<(String, Vec<(u32, u32)>)> = blocks
+    .iter()
+    .map(|block| {
+      let mut legend = block.name.to_string();
+      if !block.scheduled {
+        legend.push_str(" (no schedule, blocks whenever it runs)");
+      }
+      if block.changed {
+        legend.push_str(" (changed)");
+      }
+      (legend, schedule::block_ranges(&block.schedule))
+    })
+    .collect();
+  for line in schedule::combined_week_grid(&week) {
+    eprintln!("{line}");
+  }
+}
+
+fn list_schedules(blocks: &[Planned]) {
+  for block in blocks {
+    eprintln!("Block {}:", block.name);
+    suggestdialog::print_schedule(&block.schedule);
+  }
+}
+
+/// Adds the same times to each of the blocks the user picks, so blocks that go together
+/// are planned in one go
+fn add_times(blocks: &mut [Planned]) {
+  let names: Vec<&str> = blocks.iter().map(|block| block.name).collect();
+  let chosen = MultiSelect::new()
+    .with_prompt("Which blocks do the times go to? [press space to select]")
+    .items(&names)
+    .loop_interact();
+  if chosen.is_empty() {
+    eprintln!("No blocks were chosen, so no times are added.");
+    return;
+  }
+
+  let entries = suggestdialog::schedule_entries_from_stdin(&BreakMethod::None, true);
+  for i in chosen {
+    let block = &mut blocks[i];
+    block.schedule.extend(entries.iter().cloned());
+    if !schedule::describe_overlaps(&block.schedule).is_empty() {
+      eprintln!("In block {}:", block.name);
+      suggestdialog::resolve_overlaps(&mut block.schedule);
+    }
+    for (id, entry) in block.schedule.iter_mut().enumerate() {
+      entry.id = id;
+    }
+    block.scheduled = true;
+    block.changed = true;
+  }
+}
+
+/// Deletes the entries the user picks from any of the blocks
+fn delete_entries(blocks: &mut [Planned]) {
+  let entries: Vec<(usize, usize)> = blocks
+    .iter()
+    .enumerate()
+    .flat_map(|(b, block)| (0..block.schedule.len()).map(move |e| (b, e)))
+    .collect();
+  if entries.is_empty() {
+    eprintln!("There are no schedule entries to delete.");
+    return;
+  }
+  let width = blocks
+    .iter()
+    .map(|block| block.name.len())
+    .max()
+    .unwrap_or(0);
+  let items: Vec<String> = entries
+    .iter()
+    .map(|&(b, e)| {
+      format!(
+        "{:<width$}  {}",
+        blocks[b].name,
+        suggestdialog::describe_schedule_entry(&blocks[b].schedule[e])
+      )
+    })
+    .collect();
+  let mut chosen = MultiSelect::new()
+    .with_prompt("Which entries do you want to delete? [press space to select]")
+    .items(&items)
+    .loop_interact();
+  chosen.sort_unstable();
+
+  // From the back, so the entries left to remove are still where they were
+  for &i in chosen.iter().rev() {
+    let (b, e) = entries[i];
+    blocks[b].schedule.remove(e);
+    blocks[b].changed = true;
+  }
+  for block in blocks.iter_mut().filter(|block| block.changed) {
+    for (id, entry) in block.schedule.iter_mut().enumerate() {
+      entry.id = id;
+    }
+  }
+  eprintln!("Deleted {} schedule entries", chosen.len());
+}
+
+/// The block as it is exported, with its new schedule. A block left without times is not
+/// imported, since a scheduled block without them never blocks
+fn block_settings(block: Planned) -> Option<BlockSettings> {
+  if block.schedule.is_empty() {
+    eprintln!(
+      "WARNING: Leaves block {} as it is, since it has no times left and would never block",
+      block.name
+    );
+    return None;
+  }
+  let mut block_settings = bridge::export_block(block.info);
+  block_settings.sched_type = SchedType::Scheduled;
+  block_settings.schedule = block.schedule;
+  // The schedule Cold Turkey reported would otherwise be written after the new one, and win
+  block_settings.unknown_fields.remove("schedule");
+  Some(block_settings)
+}
@@ -0,0 +1,214 @@
+use crate::config::CT_EXEC;
+use ctk_common::ctsettings;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+
+/// Whether ctk is running as an administrator (or root outside of Windows)
+pub fn is_elevated() -> bool {
+  if cfg!(windows) {
+    // `net session` is only allowed for administrators, so its exit status tells us
+    // without having to call into the Windows API
+    Command::new("net")
+      .arg("session")
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .status()
+      .is_ok_and(|status| status.success())
+  } else {
+    Command::new("id")
+      .arg("-u")
+      .output()
+      .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "0")
+  }
+}
+
+/// Runs this same ctk invocation again as an administrator and waits for it to finish
+pub fn relaunch_elevated() -> io::Result<ExitStatus> {
+  let exe = env::current_exe()?;
+  // --elevate is dropped so the elevated ctk doesn't try to relaunch itself
+  let args: Vec<String> = env::args()
+    .skip(1)
+    .filter(|arg| arg != "--elevate")
+    .collect();
+
+  if cfg!(windows) {
+    let mut script = format!(
+      "$p = Start-Process -FilePath {} -Verb RunAs -Wait -PassThru",
+      powershell_quote(&exe.to_string_lossy())
+    );
+    if !args.is_empty() {
+      // Start-Process joins arguments with spaces, so each one is quoted again for the command line
+      let quoted: Vec<String> = args
+        .iter()
+        .map(|arg| powershell_quote(&format!("\"{}\"", arg.replace('"', "\\\""))))
+        .collect();
+      script.push_str(&format!(" -ArgumentList {}", quoted.join(",")));
+    }
+    script.push_str("; exit $p.ExitCode");
+
+    Command::new("powershell")
+      .args(["-NoProfile", "-Command", &script])
+      .status()
+  } else {
+    Command::new("sudo").arg(exe).args(args).status()
+  }
+}
+
+/// Quotes `s` as a PowerShell string that nothing in it is expanded in
+pub fn powershell_quote(s: &str) -> String {
+  format!("'{}'", s.replace('\'', "''"))
+}
+
+/// The name of the process whose window has focus, like "steam" (without ".exe") on Windows
+/// or "Safari" on macOS
+pub fn foreground_app() -> Option<String> {
+  let output = if cfg!(windows) {
+    // PowerShell can reach the two user32 functions needed, sparing ctk a Windows API dependency
+    let script = "Add-Type -Namespace Ctk -Name Focus -MemberDefinition '\
+      [DllImport(\"user32.dll\")] public static extern IntPtr GetForegroundWindow();\
+      [DllImport(\"user32.dll\")] public static extern uint GetWindowThreadProcessId(IntPtr w, out uint p);';\
+      $p = 0; [void][Ctk.Focus]::GetWindowThreadProcessId([Ctk.Focus]::GetForegroundWindow(), [ref]$p);\
+      (Get-Process -Id $p).ProcessName";
+    Command::new("powershell")
+      .args(["-NoProfile", "-Command", script])
+      .output()
+  } else if cfg!(target_os = "macos") {
+    Command::new("osascript")
+      .args([
+        "-e",
+        "tell application \"System Events\" to get name of first application process whose frontmost is true",
+      ])
+      .output()
+  } else {
+    Command::new("sh")
+      .args(["-c", "cat /proc/$(xdotool getactivewindow getwindowpid)/comm"])
+      .output()
+  }
+  .ok()?;
+
+  let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+  (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+/// The text on the clipboard, if there is any and it can be read
+pub fn clipboard_text() -> Option<String> {
+  let output = if cfg!(windows) {
+    Command::new("powershell")
+      .args(["-NoProfile", "-Command", "Get-Clipboard -Raw"])
+      .output()
+  } else if cfg!(target_os = "macos") {
+    Command::new("pbpaste").output()
+  } else {
+    // wl-paste on Wayland, xclip on X11
+    Command::new("wl-paste")
+      .arg("--no-newline")
+      .output()
+      .or_else(|_| {
+        Command::new("xclip")
+          .args(["-selection", "clipboard", "-o"])
+          .output()
+      })
+  }
+  .ok()?;
+
+  let text = String::from_utf8_lossy(&output.stdout).to_string();
+  (output.status.success() && !text.trim().is_empty()).then_some(text)
+}
+
+const BLOCKER_APP: &str = "Cold Turkey Blocker.app";
+const BLOCKER_APP_EXEC: &str = "Contents/MacOS/Cold Turkey Blocker";
+// The messaging host's name has changed with the browsers Cold Turkey supports, so any
+// file starting with this in the app counts
+const MSG_HOST_PREFIX: &str = "CTMsgHost";
+
+/// The usual places Cold Turkey is installed on this machine, most likely first: the app
+/// bundles on macOS, and elsewhere the install folders the environment points to, which
+/// callers try after the default folder
+fn install_dirs() -> Vec<PathBuf> {
+  if cfg!(target_os = "macos") {
+    let mut apps = vec![Path::new("/Applications").join(BLOCKER_APP)];
+    if let Some(home) = dirs::home_dir() {
+      apps.push(home.join("Applications").join(BLOCKER_APP));
+    }
+    apps
+  } else {
+    // Cold Turkey only runs on Windows and macOS, so Windows' folders stand in elsewhere
+    let mut dirs = Vec::new();
+    for (var, dir) in [
+      ("ProgramFiles", "Cold Turkey"),
+      ("ProgramFiles(x86)", "Cold Turkey"),
+      ("LOCALAPPDATA", r"Programs\Cold Turkey"),
+    ] {
+      if let Some(base) = env::var_os(var) {
+        dirs.push(Path::new(&base).join(dir));
+      }
+    }
+    dirs
+  }
+}
+
+/// The first candidate that is there, or the first one if none are
+fn first_found(candidates: Vec<PathBuf>) -> PathBuf {
+  candidates
+    .iter()
+    .find(|candidate| candidate.is_file())
+    .unwrap_or(&candidates[0])
+    .clone()
+}
+
+/// Where Cold Turkey Blocker is installed on this machine: the first of the usual install
+/// folders it is found in, or the usual one if it is not found anywhere
+pub fn default_blocker_path() -> PathBuf {
+  if cfg!(target_os = "macos") {
+    return first_found(
+      install_dirs()
+        .iter()
+        .map(|app| app.join(BLOCKER_APP_EXEC))
+        .collect(),
+    );
+  }
+  let mut candidates = vec![PathBuf::from(CT_EXEC)];
+  candidates.extend(
+    install_dirs()
+      .iter()
+      .map(|dir| dir.join("Cold Turkey Blocker.exe")),
+  );
+  first_found(candidates)
+}
+
+/// Where Cold Turkey's messaging host is installed on this machine, searched for in the same
+/// places as `default_blocker_path`
+pub fn default_msg_host_path() -> PathBuf {
+  if !cfg!(target_os = "macos") {
+    let mut candidates = vec![PathBuf::from(ctsettings::MSG_HOST)];
+    candidates.extend(
+      install_dirs()
+        .iter()
+        .map(|dir| dir.join("CTMsgHostEdge.exe")),
+    );
+    return first_found(candidates);
+  }
+  let bundle_dirs: Vec<PathBuf> = install_dirs()
+    .iter()
+    .flat_map(|app| [app.join("Contents/MacOS"), app.join("Contents/Resources")])
+    .collect();
+  let found = bundle_dirs.iter().find_map(|dir| {
+    let mut hosts: Vec<PathBuf> = fs::read_dir(dir)
+      .ok()?
+      .flatten()
+      .map(|entry| entry.path())
+      .filter(|path| {
+        path.is_file()
+          && path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with(MSG_HOST_PREFIX))
+      })
+      .collect();
+    hosts.sort();
+    hosts.into_iter().next()
+  });
+  found.unwrap_or_else(|| bundle_dirs[0].join(MSG_HOST_PREFIX))
+}
@@ -0,0 +1,121 @@
+use crate::blocker::{self, BlockerCommand, StartLock};
+use crate::config::Config;
+use crate::journal::{self, Event};
+use crate::toast::{self, Button};
+use crate::watch::{lock_for, log};
+use chrono::{Duration, Local};
+use std::thread;
+
+/// How long each part of the pomodoros lasts, in minutes, and how many work intervals to run
+pub struct Plan {
+  pub work: u32,
+  pub break_minutes: u32,
+  pub cycles: u32,
+}
+
+/// Notifications go through Windows, and ring the terminal bell where it cannot show them
+struct Notifier {
+  toasts: bool,
+}
+
+impl Notifier {
+  fn notify(&mut self, title: &str, line: String) {
+    if self.toasts {
+      match toast::show(title, &[line], &[Button::status()]) {
+        Ok(()) => return,
+        Err(err) => {
+          log(&format!(
+            "WARNING: Cannot show a notification, so the terminal bell rings instead: {err}"
+          ));
+          self.toasts = false;
+        }
+      }
+    }
+    eprint!("\x07");
+  }
+}
+
+/// Runs the pomodoros here, starting the block for each work interval and stopping it for
+/// each break. Stopping ctk part way leaves the block as it is at that moment
+pub fn run(config: &Config, block: &str, plan: &Plan) {
+  eprintln!(
+    "Running {} pomodoro(s) of {} minutes of work and {} minutes of break on {block}. Press Ctrl+C to stop.",
+    plan.cycles, plan.work, plan.break_minutes
+  );
+  let mut notifier = Notifier { toasts: true };
+
+  for cycle in 1..=plan.cycles {
+    let work_end = Local::now() + Duration::minutes(plan.work.into());
+    start_block(config, block);
+    log(&format!(
+      "Work {cycle}/{} until {}",
+      plan.cycles,
+      work_end.format("%H:%M")
+    ));
+    notifier.notify(
+      &format!("{block} is on"),
+      format!(
+        "Work {cycle} of {} until {}",
+        plan.cycles,
+        work_end.format("%H:%M")
+      ),
+    );
+    wait(plan.work);
+
+    stop_block(config, block);
+    if cycle == plan.cycles {
+      log(&format!("Done with {} pomodoro(s)", plan.cycles));
+      notifier.notify(
+        &format!("{block} is off"),
+        format!("All {} pomodoros are done", plan.cycles),
+      );
+      return;
+    }
+    let break_end = Local::now() + Duration::minutes(plan.break_minutes.into());
+    log(&format!("Break until {}", break_end.format("%H:%M")));
+    notifier.notify(
+      &format!("{block} is off"),
+      format!("Break until {}", break_end.format("%H:%M")),
+    );
+    wait(plan.break_minutes);
+  }
+}
+
+fn wait(minutes: u32) {
+  thread::sleep(std::time::Duration::from_secs(u64::from(minutes) * 60));
+}
+
+// A failed start or stop only costs one phase, so the timer carries on either way
+
+fn start_block(config: &Config, block: &str) {
+  let Some(_lock) = lock_for(&format!("start {block} for a work interval")) else {
+    return;
+  };
+  match blocker::run(
+    config,
+    BlockerCommand::Start {
+      block,
+      lock: StartLock::None,
+    },
+  ) {
+    Ok(_) => journal::record(Event::Start {
+      block: block.to_string(),
+      lock_minutes: None,
+    }),
+    Err(err) => log(&format!(
+      "ERROR: Cannot start {block} for a work interval: {err}"
+    )),
+  }
+}
+
+fn stop_block(config: &Config, block: &str) {
+  let Some(_lock) = lock_for(&format!("stop {block} for a break")) else {
+    return;
+  };
+  match blocker::run(config, BlockerCommand::Stop { block }) {
+    Ok(_) => journal::record(Event::Stop {
+      block: block.to_string(),
+    }),
+    Err(err) => log(&format!("ERROR: Cannot stop {block} for a break: {err}")),
+  }
+}
@@ -0,0 +1,121 @@
+use std::env;
+use std::io;
+use std::process::Command;
+use url::Url;
+
+pub const SCHEME: &str = "ctk";
+
+const REGISTRY_KEY: &str = r"HKCU\Software\Classes\ctk";
+
+/// Everything a ctk:// link can ask for
+pub const ACTIONS: [&str; 4] = ["add", "extend", "start", "status"];
+
+/// What a ctk:// link asks ctk to do
+#[derive(Debug)]
+pub enum Action {
+  /// ctk://add?url=<url>[&block=<block>], adding to the default block without a block
+  Add { url: String, block: Option<String> },
+  /// ctk://start?block=<block>, starting it without a lock
+  Start { block: String },
+  /// ctk://extend?block=<block>, locking it for toast::EXTEND_MINUTES longer. How long is not
+  /// up to the link, since any web page can open one
+  Extend { block: String },
+  /// ctk://status, showing how every block is doing in a notification
+  Status,
+}
+
+impl Action {
+  /// Its name in ACTIONS
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::Add { .. } => "add",
+      Self::Start { .. } => "start",
+      Self::Extend { .. } => "extend",
+      Self::Status => "status",
+    }
+  }
+}
+
+/// Reads a link like `ctk://add?url=example.com`
+pub fn parse(link: &str) -> Result<Action, String> {
+  let link = Url::parse(link).map_err(|err| format!("{link} is not a link: {err}"))?;
+  if link.scheme() != SCHEME {
+    return Err(format!("{link} is not a {SCHEME}:// link"));
+  }
+  let param = |name: &str| {
+    link
+      .query_pairs()
+      .find(|(key, _)| key == name)
+      .map(|(_, value)| value.trim().to_string())
+      .filter(|value| !value.is_empty())
+  };
+
+  // Browsers differ on whether "ctk://add" ends up as the host or the path
+  let action = link
+    .host_str()
+    .unwrap_or_else(|| link.path())
+    .trim_matches('/');
+  match action {
+    "add" => Ok(Action::Add {
+      url: param("url").ok_or("ctk://add needs the url to add, like ctk://add?url=example.com")?,
+      block: param("block"),
+    }),
+    "start" => Ok(Action::Start {
+      block: param("block")
+        .ok_or("ctk://start needs the block to start, like ctk://start?block=Social")?,
+    }),
+    "extend" => Ok(Action::Extend {
+      block: param("block")
+        .ok_or("ctk://extend needs the block to lock for longer, like ctk://extend?block=Social")?,
+    }),
+    "status" => Ok(Action::Status),
+    _ => Err(format!(
+      "ctk:// links can only add, extend, start or show the status, not {action:?}"
+    )),
+  }
+}
+
+/// Makes Windows open ctk:// links with this ctk
+pub fn register() -> io::Result<()> {
+  windows_only()?;
+  let exe = env::current_exe()?;
+  let command = format!("\"{}\" protocol open \"%1\"", exe.display());
+  reg(&["add", REGISTRY_KEY, "/ve", "/d", "URL:ctk Protocol", "/f"])?;
+  // Windows only offers keys with this empty value to browsers as protocols
+  reg(&["add", REGISTRY_KEY, "/v", "URL Protocol", "/d", "", "/f"])?;
+  reg(&[
+    "add",
+    &format!(r"{REGISTRY_KEY}\shell\open\command"),
+    "/ve",
+    "/d",
+    &command,
+    "/f",
+  ])
+}
+
+pub fn unregister() -> io::Result<()> {
+  windows_only()?;
+  reg(&["delete", REGISTRY_KEY, "/f"])
+}
+
+fn windows_only() -> io::Result<()> {
+  if cfg!(windows) {
+    Ok(())
+  } else {
+    Err(io::Error::new(
+      io::ErrorKind::Unsupported,
+      "only Windows is supported",
+    ))
+  }
+}
+
+fn reg(args: &[&str]) -> io::Result<()> {
+  let output = Command::new("reg").args(args).output()?;
+  if output.status.success() {
+    Ok(())
+  } else {
+    Err(io::Error::other(
+      String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    ))
+  }
+}
@@ -0,0 +1,77 @@
+use crate::snapshot;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Cold Turkey reads an import file as soon as it is handed one, so a day is plenty
+const TEMP_FILE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A file ctk left behind that is no longer needed
+pub struct StaleFile {
+  pub path: PathBuf,
+  /// Like "snapshot", for telling the user what it is
+  pub kind: &'static str,
+}
+
+/// Files in `dir` starting with `prefix` and ending with `suffix` that were last changed
+/// more than `age` ago
+fn old_files(dir: &Path, prefix: &str, suffix: &str, age: Duration) -> Vec<PathBuf> {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return Vec::new();
+  };
+  let now = SystemTime::now();
+  let mut paths: Vec<PathBuf> = entries
+    .flatten()
+    .filter(|entry| {
+      let name = entry.file_name();
+      let name = name.to_string_lossy();
+      name.starts_with(prefix) && name.ends_with(suffix)
+    })
+    .filter(|entry| {
+      entry
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| now.duration_since(modified).is_ok_and(|since| since > age))
+    })
+    .map(|entry| entry.path())
+    .collect();
+  paths.sort_unstable();
+  paths
+}
+
+/// Snapshots older than `keep`, and the import files and browser history copies ctk left
+/// in the temporary directory
+pub fn stale_files(keep: Duration) -> Vec<StaleFile> {
+  let temp_dir = env::temp_dir();
+  let found = [
+    snapshot::snapshot_dir().map(|dir| (old_files(&dir, "", ".json", keep), "snapshot")),
+    Some((
+      old_files(&temp_dir, "ctk_", ".ctbbl", TEMP_FILE_AGE),
+      "import file",
+    )),
+    Some((
+      old_files(&temp_dir, "ctk_history_", "", TEMP_FILE_AGE),
+      "browser history copy",
+    )),
+  ];
+  found
+    .into_iter()
+    .flatten()
+    .flat_map(|(paths, kind)| paths.into_iter().map(move |path| StaleFile { path, kind }))
+    .collect()
+}
+
+/// Deletes the files, giving how many could be deleted
+pub fn delete(files: &[StaleFile]) -> usize {
+  files
+    .iter()
+    .filter(|file| match fs::remove_file(&file.path) {
+      Ok(()) => true,
+      Err(err) => {
+        eprintln!("Cannot delete {}: {err}", file.path.display());
+        false
+      }
+    })
+    .count()
+}
@@ -0,0 +1,95 @@
+use crate::journal::{self, Entry, Event};
+use ctk_common::blocksettings::{BlockSettings, LockMethod};
+use rand::Rng;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// How hard a random text lock should be to get past, for ctk to pick its length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Friction {
+  Light,
+  Moderate,
+  Heavy,
+}
+
+impl Friction {
+  pub const ALL: [Friction; 3] = [Self::Light, Self::Moderate, Self::Heavy];
+
+  fn lengths(self) -> RangeInclusive<u16> {
+    match self {
+      Self::Light => 20..=40,
+      Self::Moderate => 60..=120,
+      Self::Heavy => 200..=400,
+    }
+  }
+
+  /// Picks a length for the random text, so it is not the same one every time
+  pub fn random_length(self) -> u16 {
+    rand::thread_rng().gen_range(self.lengths())
+  }
+}
+
+impl fmt::Display for Friction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let lengths = self.lengths();
+    let name = match self {
+      Self::Light => "Light",
+      Self::Moderate => "Moderate",
+      Self::Heavy => "Heavy",
+    };
+    write!(
+      f,
+      "{name} ({} to {} characters)",
+      lengths.start(),
+      lengths.end()
+    )
+  }
+}
+
+/// Characters a minute when copying random text, which is a lot slower than typing words
+const TYPING_SPEEDS: [(&str, u32); 3] = [("fast", 150), ("average", 90), ("slow", 40)];
+
+/// Spells out how long typing `length` random characters takes, like "about 2 minutes"
+fn describe_typing(length: u16, per_minute: u32) -> String {
+  let seconds = (u32::from(length) * 60).div_ceil(per_minute);
+  match seconds {
+    0..=59 => format!("about {seconds} seconds"),
+    60..=89 => "about 1 minute".to_string(),
+    _ => format!("about {} minutes", (seconds + 30) / 60),
+  }
+}
+
+/// One line per typing speed, like "  average typist: about 2 minutes"
+pub fn typing_estimates(length: u16) -> Vec<String> {
+  TYPING_SPEEDS
+    .iter()
+    .map(|(typist, per_minute)| {
+      format!(
+        "  {typist} typist: {}",
+        describe_typing(length, *per_minute)
+      )
+    })
+    .collect()
+}
+
+/// Records the length of every random text lock in the journal, so `ctk unlock-hint` can
+/// tell how long it takes even when Cold Turkey is not running the block
+pub fn record_locks(blocks: &HashMap<String, BlockSettings>) {
+  for (block, settings) in blocks {
+    if matches!(settings.lock, LockMethod::RandomText) {
+      journal::record(Event::RandomText {
+        block: block.clone(),
+        length: settings.random_text_length,
+      });
+    }
+  }
+}
+
+/// The length of the random text lock last recorded for the block, if there is one
+pub fn recorded_length(entries: &[Entry], block_name: &str) -> Option<u16> {
+  entries.iter().rev().find_map(|entry| match &entry.event {
+    Event::RandomText { block, length } if block == block_name => Some(*length),
+    _ => None,
+  })
+}
@@ -0,0 +1,275 @@
+use crate::journal::{Entry, Event};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The time a report covers, up to now
+#[derive(Debug, Clone, Copy)]
+pub enum Period {
+  /// Since midnight on Monday
+  ThisWeek,
+  /// The last so many days
+  Days(u32),
+}
+
+impl Period {
+  pub fn start(self, now: DateTime<Local>) -> DateTime<Local> {
+    let days_back = match self {
+      Self::ThisWeek => now.weekday().num_days_from_monday(),
+      Self::Days(days) => return now - Duration::days(days.into()),
+    };
+    let monday = now.date_naive() - Duration::days(days_back.into());
+    Local
+      .from_local_datetime(&monday.and_time(NaiveTime::MIN))
+      .earliest()
+      // Midnight was skipped by a clock change, so the week starts a little later
+      .unwrap_or_else(|| now - Duration::days(days_back.into()))
+  }
+
+  /// How many days the report covers, counting today for this week
+  pub fn days(self, now: DateTime<Local>) -> u32 {
+    match self {
+      Self::ThisWeek => now.weekday().num_days_from_monday() + 1,
+      Self::Days(days) => days,
+    }
+  }
+}
+
+/// A stretch of time a block was running, as far as the journal knows
+#[derive(Debug, Clone)]
+pub struct Session {
+  pub block: String,
+  pub start: DateTime<Local>,
+  pub end: DateTime<Local>,
+}
+
+/// Pairs up the starts and stops in the journal. A locked start ends when its lock does
+/// (or earlier, if the block is started or stopped again), an unlocked start ends at the
+/// next start or stop of the same block, and sessions still going end now.
+pub fn sessions(entries: &[Entry], now: DateTime<Local>) -> Vec<Session> {
+  let mut open: HashMap<&str, (DateTime<Local>, Option<DateTime<Local>>)> = HashMap::new();
+  let mut sessions = Vec::new();
+
+  let mut close = |block: &str, start: DateTime<Local>, lock_end: Option<DateTime<Local>>, at| {
+    let end = lock_end.map_or(at, |lock_end| lock_end.min(at));
+    if start < end {
+      sessions.push(Session {
+        block: block.to_string(),
+        start,
+        end,
+      });
+    }
+  };
+
+  for entry in entries {
+    match &entry.event {
+      Event::Start {
+        block,
+        lock_minutes,
+      } => {
+        if let Some((start, lock_end)) = open.remove(block.as_str()) {
+          close(block, start, lock_end, entry.time);
+        }
+        let lock_end = lock_minutes.map(|minutes| entry.time + Duration::minutes(minutes));
+        open.insert(block, (entry.time, lock_end));
+      }
+      Event::Stop { block } => {
+        if let Some((start, lock_end)) = open.remove(block.as_str()) {
+          close(block, start, lock_end, entry.time);
+        }
+      }
+      Event::Add { .. }
+      | Event::Unverified { .. }
+      | Event::Pomodoro { .. }
+      | Event::RandomText { .. }
+      | Event::Snapshot { .. } => {}
+    }
+  }
+
+  for (block, (start, lock_end)) in open {
+    close(block, start, lock_end, now);
+  }
+
+  sessions.sort_by_key(|session| session.start);
+  sessions
+}
+
+/// Total time per block within [from, to)
+pub fn total_per_block(
+  sessions: &[Session],
+  from: DateTime<Local>,
+  to: DateTime<Local>,
+) -> BTreeMap<String, Duration> {
+  let mut totals: BTreeMap<String, Duration> = BTreeMap::new();
+  for session in sessions {
+    let start = session.start.max(from);
+    let end = session.end.min(to);
+    if start < end {
+      *totals
+        .entry(session.block.clone())
+        .or_insert_with(Duration::zero) += end - start;
+    }
+  }
+  totals
+}
+
+/// How long the block ran on each day, splitting sessions that run past midnight
+pub fn daily_totals(sessions: &[Session], block: &str) -> BTreeMap<NaiveDate, Duration> {
+  let mut totals: BTreeMap<NaiveDate, Duration> = BTreeMap::new();
+  for session in sessions.iter().filter(|session| session.block == block) {
+    let mut start = session.start;
+    while start < session.end {
+      let day = start.date_naive();
+      let midnight = day.succ_opt().and_then(|next| {
+        Local
+          .from_local_datetime(&next.and_time(NaiveTime::MIN))
+          .earliest()
+      });
+      let end = midnight.map_or(session.end, |midnight| midnight.min(session.end));
+      if end <= start {
+        break;
+      }
+      *totals.entry(day).or_insert_with(Duration::zero) += end - start;
+      start = end;
+    }
+  }
+  totals
+}
+
+/// The number of days in a row, counting back from today, found in `days`.
+/// A streak that has not reached today yet still counts from yesterday.
+pub fn current_streak(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> u32 {
+  let mut day = if days.contains(&today) {
+    today
+  } else {
+    match today.pred_opt() {
+      Some(yesterday) => yesterday,
+      None => return 0,
+    }
+  };
+
+  let mut streak = 0;
+  while days.contains(&day) {
+    streak += 1;
+    day = match day.pred_opt() {
+      Some(previous) => previous,
+      None => break,
+    };
+  }
+  streak
+}
+
+/// The most days in a row ever found in `days`
+pub fn longest_streak(days: &BTreeSet<NaiveDate>) -> u32 {
+  let mut longest = 0;
+  let mut streak = 0;
+  let mut previous: Option<NaiveDate> = None;
+  for day in days {
+    if previous.and_then(|previous| previous.succ_opt()) == Some(*day) {
+      streak += 1;
+    } else {
+      streak = 1;
+    }
+    longest = longest.max(streak);
+    previous = Some(*day);
+  }
+  longest
+}
+
+pub fn print_report(entries: &[Entry], period: Period) {
+  let now = Local::now();
+  let from = period.start(now);
+  let sessions = sessions(entries, now);
+  let totals = total_per_block(&sessions, from, now);
+
+  println!(
+    "Focus report from {} to {}",
+    from.format("%B %d %Y"),
+    now.format("%B %d %Y")
+  );
+
+  if totals.is_empty() {
+    println!("No blocks were started through ctk in this time.");
+    return;
+  }
+
+  let width = totals
+    .keys()
+    .map(String::len)
+    .max()
+    .unwrap_or(0)
+    .max("Block".len());
+  println!("{:<width$}  {:>6}  {:>6}", "Block", "Hours", "Streak");
+  for (block, total) in &totals {
+    let hours = total.num_minutes() as f64 / 60.0;
+    let days = daily_totals(&sessions, block).into_keys().collect();
+    let streak = current_streak(&days, now.date_naive());
+    println!("{block:<width$}  {hours:>6.1}  {:>6}", format!("{streak}d"));
+  }
+}
+
+/// Whether Cold Turkey's name for a pomodoro phase is a break. Reaching a break means a
+/// focus period was completed
+pub fn is_break_phase(phase: &str) -> bool {
+  phase.to_lowercase().contains("break")
+}
+
+/// Completed pomodoro focus cycles per day and block, since `from`
+pub fn pomodoro_cycles(
+  entries: &[Entry],
+  from: DateTime<Local>,
+) -> BTreeMap<NaiveDate, BTreeMap<String, u32>> {
+  let mut cycles: BTreeMap<NaiveDate, BTreeMap<String, u32>> = BTreeMap::new();
+  for entry in entries.iter().filter(|entry| entry.time >= from) {
+    if let Event::Pomodoro { block, phase } = &entry.event {
+      if is_break_phase(phase) {
+        *cycles
+          .entry(entry.time.date_naive())
+          .or_default()
+          .entry(block.clone())
+          .or_default() += 1;
+      }
+    }
+  }
+  cycles
+}
+
+pub fn print_pomodoro_stats(entries: &[Entry], days: u32) {
+  let now = Local::now();
+  let from = now - Duration::days(days.into());
+  let cycles = pomodoro_cycles(entries, from);
+
+  println!(
+    "Pomodoro cycles from {} to {}",
+    from.format("%B %d %Y"),
+    now.format("%B %d %Y")
+  );
+
+  if cycles.is_empty() {
+    println!(
+      "No pomodoro cycles were seen in this time. They are only counted while `ctk watch` runs."
+    );
+    return;
+  }
+
+  let width = cycles
+    .values()
+    .flat_map(BTreeMap::keys)
+    .map(String::len)
+    .max()
+    .unwrap_or(0)
+    .max("Block".len());
+  println!("{:<10}  {:<width$}  {:>6}", "Date", "Block", "Cycles");
+  let mut totals: BTreeMap<&str, u32> = BTreeMap::new();
+  for (date, blocks) in &cycles {
+    for (block, count) in blocks {
+      println!(
+        "{:<10}  {block:<width$}  {count:>6}",
+        date.format("%Y-%m-%d")
+      );
+      *totals.entry(block).or_default() += count;
+    }
+  }
+  for (block, total) in totals {
+    println!("{:<10}  {block:<width$}  {total:>6}", "Total");
+  }
+}
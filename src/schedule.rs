@@ -0,0 +1,624 @@
+use crate::journal::{Entry, Event};
+use crate::sidecar::{describe_times, parse_time_tuple, Sidecar, DAY_NAMES};
+use crate::suggestdialog::parse_schedule_time;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Timelike, Weekday};
+use ctk_common::blocksettings::{BreakMethod, ScheduleBlock, ScheduleTimeTuple};
+use ctk_common::ctsettings::{BlockInfo, ColdTurkeySettings};
+use serde_json::Value;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+const MINUTES_PER_WEEK: u32 = 7 * MINUTES_PER_DAY;
+// Each cell of the week grid is half an hour, so a day fits in 48 columns
+const CELL_MINUTES: u32 = 30;
+
+/// One entry of a block's schedule, as Cold Turkey reports it
+#[derive(Debug, Clone)]
+pub struct ScheduleEntry {
+  /// In Cold Turkey's "day,hour,minute" form, with Sunday as day 0
+  pub start: String,
+  pub end: String,
+  /// Like "none", "10" for an allowance or "25,5" for pomodoro
+  pub break_type: String,
+}
+
+impl ScheduleEntry {
+  /// Whether the entry covers the given minute of the week, counted from midnight on Sunday
+  pub fn covers(&self, minute: u32) -> bool {
+    let (Some(start), Some(end)) = (minute_of_week(&self.start), minute_of_week(&self.end)) else {
+      return false;
+    };
+    if start <= end {
+      start <= minute && minute < end
+    } else {
+      // Runs past midnight at the end of Saturday
+      start <= minute || minute < end
+    }
+  }
+}
+
+fn minute_of_week(tuple: &str) -> Option<u32> {
+  let (day, hour, minute) = parse_time_tuple(tuple)?;
+  Some(u32::try_from(day).ok()? * MINUTES_PER_DAY + hour * 60 + minute)
+}
+
+/// The entries of a running block's schedule, skipping any ctk cannot read
+pub fn schedule_entries(info: &BlockInfo) -> Vec<ScheduleEntry> {
+  let Some(entries) = info
+    .unknown_fields
+    .get("schedule")
+    .and_then(Value::as_array)
+  else {
+    return Vec::new();
+  };
+  entries
+    .iter()
+    .filter_map(|entry| {
+      let text = |key: &str| entry.get(key).and_then(Value::as_str).map(str::to_string);
+      Some(ScheduleEntry {
+        start: text("startTime")?,
+        end: text("endTime")?,
+        break_type: text("break").unwrap_or_else(|| "none".to_string()),
+      })
+    })
+    .collect()
+}
+
+/// When the timed lock ctk last started the block with ends, if the journal has one that
+/// has not ended yet and the block was not stopped since
+pub fn lock_end(
+  entries: &[Entry],
+  block_name: &str,
+  now: DateTime<Local>,
+) -> Option<DateTime<Local>> {
+  let last = entries.iter().rev().find(|entry| match &entry.event {
+    Event::Start { block, .. } | Event::Stop { block } => block == block_name,
+    _ => false,
+  })?;
+  let Event::Start {
+    lock_minutes: Some(minutes),
+    ..
+  } = last.event
+  else {
+    return None;
+  };
+  Some(last.time + Duration::minutes(minutes)).filter(|end| *end > now)
+}
+
+/// Where each entry starts and ends, in minutes from midnight on Sunday. Entries running
+/// past midnight at the end of Saturday are split in two
+pub fn entry_ranges(entries: &[ScheduleEntry]) -> Vec<(u32, u32)> {
+  let mut ranges = Vec::new();
+  for entry in entries {
+    let (Some(start), Some(end)) = (minute_of_week(&entry.start), minute_of_week(&entry.end))
+    else {
+      continue;
+    };
+    if start <= end {
+      ranges.push((start, end));
+    } else {
+      ranges.push((start, MINUTES_PER_WEEK));
+      ranges.push((0, end));
+    }
+  }
+  ranges
+}
+
+/// Draws the week as a grid of days by hours, filling in the times the ranges cover. Ranges
+/// are in minutes from midnight on Sunday
+pub fn week_grid(ranges: &[(u32, u32)]) -> Vec<String> {
+  let mut lines = grid_lines(|cell_start| match cell_coverage(ranges, cell_start) {
+    0 => '·',
+    CELL_MINUTES => '█',
+    _ => '▒',
+  });
+  lines.push("    █ blocked  ▒ blocked for part of the half hour  · not blocked".to_string());
+  lines
+}
+
+// Letters marking the blocks of a combined grid, in order; any more share the last mark
+const BLOCK_MARKS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ*";
+
+/// Draws the week like `week_grid` for several blocks at once, marking each half hour with
+/// the letter of the block scheduled in it, or + where more than one is. Blocks are lettered
+/// in the order given, with their ranges
+pub fn combined_week_grid(blocks: &[(String, Vec<(u32, u32)>)]) -> Vec<String> {
+  let marks: Vec<char> = BLOCK_MARKS.chars().collect();
+  let mark = |i: usize| marks[i.min(marks.len() - 1)];
+  let mut lines = grid_lines(|cell_start| {
+    let mut scheduled = blocks
+      .iter()
+      .enumerate()
+      .filter(|(_, (_, ranges))| cell_coverage(ranges, cell_start) > 0)
+      .map(|(i, _)| i);
+    match (scheduled.next(), scheduled.next()) {
+      (None, _) => '·',
+      (Some(i), None) => mark(i),
+      (Some(_), Some(_)) => '+',
+    }
+  });
+  for (i, (name, _)) in blocks.iter().enumerate() {
+    lines.push(format!("    {} {name}", mark(i)));
+  }
+  lines.push("    + more than one block  · no block".to_string());
+  lines
+}
+
+/// The hour header and a line for each day, with a character for each half hour from `cell`,
+/// which is given the minute of the week the half hour starts at
+fn grid_lines(cell: impl Fn(u32) -> char) -> Vec<String> {
+  let mut header = String::from("    ");
+  for hour in (0..24).step_by(3) {
+    header.push_str(&format!("{hour:<6}"));
+  }
+  let mut lines = vec![header.trim_end().to_string()];
+
+  for (day, name) in DAY_NAMES.iter().enumerate() {
+    let mut line = format!("{name} ");
+    for i in 0..MINUTES_PER_DAY / CELL_MINUTES {
+      line.push(cell(day as u32 * MINUTES_PER_DAY + i * CELL_MINUTES));
+    }
+    lines.push(line);
+  }
+  lines
+}
+
+/// How many minutes of the half hour starting at `cell_start` the ranges cover
+fn cell_coverage(ranges: &[(u32, u32)], cell_start: u32) -> u32 {
+  let cell_end = cell_start + CELL_MINUTES;
+  let covered: u32 = ranges
+    .iter()
+    .map(|&(start, end)| end.min(cell_end).saturating_sub(start.max(cell_start)))
+    .sum();
+  covered.min(CELL_MINUTES)
+}
+
+/// What a block will be doing at some time, going by what it is doing now
+#[derive(Debug)]
+pub enum Forecast {
+  /// Cold Turkey does not report the settings of blocks that are not running
+  NotRunning,
+  /// Its timed lock turns it off before then
+  LockEnds(DateTime<Local>),
+  Continuous,
+  InSchedule(ScheduleEntry),
+  OutsideSchedule,
+}
+
+pub fn forecast(
+  info: &BlockInfo,
+  lock_end: Option<DateTime<Local>>,
+  when: DateTime<Local>,
+) -> Forecast {
+  if info.is_dormant() {
+    return Forecast::NotRunning;
+  }
+  if let Some(end) = lock_end.filter(|end| *end <= when) {
+    return Forecast::LockEnds(end);
+  }
+  if info.schedule_type.as_deref() != Some("scheduled") {
+    return Forecast::Continuous;
+  }
+
+  let minute =
+    when.weekday().num_days_from_sunday() * MINUTES_PER_DAY + when.hour() * 60 + when.minute();
+  match schedule_entries(info)
+    .into_iter()
+    .find(|entry| entry.covers(minute))
+  {
+    Some(entry) => Forecast::InSchedule(entry),
+    None => Forecast::OutsideSchedule,
+  }
+}
+
+/// Spells out a break like "10" or "25,5"
+pub fn describe_break(break_type: &str) -> String {
+  match break_type.split_once(',') {
+    _ if break_type == "none" || break_type.is_empty() => "no breaks".to_string(),
+    Some((block_minutes, break_minutes)) => {
+      format!("pomodoro, {block_minutes} minutes blocked then a {break_minutes} minute break")
+    }
+    None if break_type.parse::<u32>().is_ok() => format!("{break_type} minute allowance"),
+    None => break_type.to_string(),
+  }
+}
+
+/// Prints whether each block (or only `block_name`) will be blocking at `when`, in
+/// alphabetical order
+pub fn print_simulation(
+  settings: &ColdTurkeySettings,
+  sidecar: &Sidecar,
+  journal: &[Entry],
+  block_name: Option<&str>,
+  now: DateTime<Local>,
+  when: DateTime<Local>,
+) {
+  println!("At {}:", when.format("%H:%M %A %B %d %Y"));
+  if settings.paused {
+    println!("Cold Turkey is paused now, so nothing is blocking until the pause ends");
+  }
+
+  let mut blocks: Vec<(&String, &BlockInfo)> = settings
+    .block_list_info
+    .blocks
+    .iter()
+    .filter(|(name, _)| block_name.is_none_or(|block_name| *name == block_name))
+    .collect();
+  blocks.sort_unstable_by_key(|(name, _)| *name);
+
+  for (name, info) in blocks {
+    let locked_until = lock_end(journal, name, now);
+    match forecast(info, locked_until, when) {
+      Forecast::NotRunning => {
+        println!("{name}: not blocking, unless it is started before then");
+      }
+      Forecast::LockEnds(end) => println!(
+        "{name}: not blocking, since its lock ends at {}",
+        end.format("%H:%M %B %d %Y")
+      ),
+      Forecast::Continuous => {
+        let until = match locked_until {
+          Some(end) => format!("locked until {}", end.format("%H:%M %B %d %Y")),
+          None if !info.password.is_empty() => "locked with a password".to_string(),
+          None if info.random_text_length.is_some() => "locked with random text".to_string(),
+          None => "unless it is stopped before then".to_string(),
+        };
+        println!("{name}: blocking, {until}");
+        if let Some(break_type) = info.unknown_fields.get("break").and_then(Value::as_str) {
+          println!("  Break: {}", describe_break(break_type));
+        }
+      }
+      Forecast::InSchedule(entry) => {
+        let label = sidecar
+          .schedule_labels(name)
+          .iter()
+          .find(|label| label.start == entry.start && label.end == entry.end)
+          .map_or_else(String::new, |label| format!(" ({})", label.label));
+        println!(
+          "{name}: blocking, during {}{label}",
+          describe_times(&entry.start, &entry.end)
+        );
+        println!("  Break: {}", describe_break(&entry.break_type));
+      }
+      Forecast::OutsideSchedule => {
+        println!("{name}: not blocking, since none of its schedule covers then");
+      }
+    }
+  }
+}
+
+/// Schedule times have to end after they start, unless they end at midnight
+pub fn check_times(start: NaiveTime, end: NaiveTime) -> Result<(), &'static str> {
+  if end == NaiveTime::MIN || start < end {
+    Ok(())
+  } else {
+    Err("the end time must be after the start time, or be midnight (00:00 or 24:00)")
+  }
+}
+
+/// Where an entry starts and ends, in minutes from midnight on Sunday. Entries ending at
+/// midnight end on the next day
+fn week_range(entry: &ScheduleBlock) -> (u32, u32) {
+  let minutes = |tuple: &ScheduleTimeTuple| {
+    tuple.day() as u32 * MINUTES_PER_DAY + tuple.hour() * 60 + tuple.minute()
+  };
+  (minutes(&entry.start_time), minutes(&entry.end_time))
+}
+
+/// Where each entry starts and ends, in minutes from midnight on Sunday
+pub fn block_ranges(schedule: &[ScheduleBlock]) -> Vec<(u32, u32)> {
+  schedule.iter().map(week_range).collect()
+}
+
+/// A line for each pair of entries whose times overlap, like "Mon 09:00-12:00 overlaps
+/// Mon 11:00-13:00", including entries given twice
+pub fn describe_overlaps(schedule: &[ScheduleBlock]) -> Vec<String> {
+  let describe = |entry: &ScheduleBlock| {
+    describe_times(&entry.start_time.to_string(), &entry.end_time.to_string())
+  };
+  let mut overlaps = Vec::new();
+  for (i, a) in schedule.iter().enumerate() {
+    for b in &schedule[i + 1..] {
+      let ((a_start, a_end), (b_start, b_end)) = (week_range(a), week_range(b));
+      if a_start < b_end && b_start < a_end {
+        overlaps.push(format!("{} overlaps {}", describe(a), describe(b)));
+      }
+    }
+  }
+  overlaps
+}
+
+/// Joins entries on the same day that overlap or follow straight on from each other, like
+/// 09:00-12:00 and 12:00-17:00, when they have the same break. Entries come out in order
+/// through the week
+pub fn merge_overlapping(mut schedule: Vec<ScheduleBlock>) -> Vec<ScheduleBlock> {
+  schedule.sort_by_key(week_range);
+  let mut merged: Vec<ScheduleBlock> = Vec::new();
+  for entry in schedule {
+    if let Some(last) = merged.last_mut() {
+      let ((_, last_end), (start, end)) = (week_range(last), week_range(&entry));
+      if last.start_time.day() == entry.start_time.day()
+        && start <= last_end
+        && last.break_type == entry.break_type
+      {
+        if end > last_end {
+          last.end_time = entry.end_time;
+        }
+        if last.label.is_none() {
+          last.label = entry.label;
+        }
+        continue;
+      }
+    }
+    merged.push(entry);
+  }
+  for (id, entry) in merged.iter_mut().enumerate() {
+    entry.id = id;
+  }
+  merged
+}
+
+/// Reads a timetable with one "day,start,end,break" line per class, like
+/// "Mon,09:00,10:30,none", into schedule entries. The break can be left out, and is "none",
+/// allowance minutes like "10" or pomodoro minutes like "25,5". Empty lines, # comments and
+/// a header line are skipped. Gives every problem found, by line, if there are any
+pub fn parse_csv(contents: &str) -> Result<Vec<ScheduleBlock>, Vec<String>> {
+  let mut schedule = Vec::new();
+  let mut problems = Vec::new();
+  for (i, line) in contents.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    // The break is the rest of the line, so pomodoro breaks can keep their comma
+    let fields: Vec<&str> = line
+      .splitn(4, ',')
+      .map(|field| field.trim().trim_matches('"').trim())
+      .collect();
+    if fields[0].eq_ignore_ascii_case("day") {
+      continue;
+    }
+    match csv_entry(&fields) {
+      Ok(entry) => schedule.push(entry),
+      Err(why) => problems.push(format!("line {}: {why}", i + 1)),
+    }
+  }
+
+  if !problems.is_empty() {
+    return Err(problems);
+  }
+  if schedule.is_empty() {
+    return Err(vec!["has no times in it".to_string()]);
+  }
+  for (id, entry) in schedule.iter_mut().enumerate() {
+    entry.id = id;
+  }
+  Ok(schedule)
+}
+
+fn csv_entry(fields: &[&str]) -> Result<ScheduleBlock, String> {
+  let [day, start, end, rest @ ..] = fields else {
+    return Err("expected day,start,end or day,start,end,break".to_string());
+  };
+  let day: Weekday = day
+    .parse()
+    .map_err(|_| format!("{day} is not a day of the week"))?;
+  let start_time =
+    parse_schedule_time(start).map_err(|why| format!("start time {start}: {why}"))?;
+  let midnight = NaiveTime::MIN;
+  let end_time = match *end {
+    "24:00" => midnight,
+    end => parse_schedule_time(end).map_err(|why| format!("end time {end}: {why}"))?,
+  };
+  check_times(start_time, end_time).map_err(|why| format!("{start} to {end}: {why}"))?;
+  let break_type = csv_break(rest.first().copied().unwrap_or(""))?;
+
+  let day = day.num_days_from_sunday() as usize;
+  // Times ending at midnight end at the start of the next day
+  let end_day = if end_time == midnight { day + 1 } else { day };
+  Ok(ScheduleBlock {
+    id: 0,
+    start_time: ScheduleTimeTuple::new(day, start_time.hour(), start_time.minute()),
+    end_time: ScheduleTimeTuple::new(end_day, end_time.hour(), end_time.minute()),
+    break_type,
+    label: None,
+  })
+}
+
+fn csv_break(text: &str) -> Result<BreakMethod, String> {
+  let minutes = |text: &str| {
+    text
+      .trim()
+      .parse::<u8>()
+      .ok()
+      .filter(|minutes| *minutes <= 99)
+  };
+  if text.is_empty() || text.eq_ignore_ascii_case("none") {
+    return Ok(BreakMethod::None);
+  }
+  let method = match text.split_once(',') {
+    Some((block_minutes, break_minutes)) => minutes(block_minutes)
+      .zip(minutes(break_minutes))
+      .map(|(block_minutes, break_minutes)| BreakMethod::Pomodoro(block_minutes, break_minutes)),
+    None => minutes(text).map(BreakMethod::Allowance),
+  };
+  method.ok_or_else(|| {
+    format!("break {text} must be none, allowance minutes like 10, or pomodoro minutes like 25,5, each up to 99")
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn times(schedule: &[ScheduleBlock]) -> Vec<String> {
+    schedule
+      .iter()
+      .map(|entry| format!("{}-{}", entry.start_time, entry.end_time))
+      .collect()
+  }
+
+  #[test]
+  fn timetables_become_schedule_entries() {
+    let csv = "day,start,end,break\n\
+               # Lectures\n\
+               Mon,09:00,10:30,none\n\
+               \n\
+               \"Wed\", 13:00 , 15:00, 25,5\n\
+               friday,8am,12:00,10\n";
+    let schedule = parse_csv(csv).unwrap();
+    assert_eq!(
+      times(&schedule),
+      ["1,9,0-1,10,30", "3,13,0-3,15,0", "5,8,0-5,12,0"]
+    );
+    assert_eq!(schedule[0].break_type, BreakMethod::None);
+    assert_eq!(schedule[1].break_type, BreakMethod::Pomodoro(25, 5));
+    assert_eq!(schedule[2].break_type, BreakMethod::Allowance(10));
+    let ids: Vec<usize> = schedule.iter().map(|entry| entry.id).collect();
+    assert_eq!(ids, [0, 1, 2]);
+  }
+
+  #[test]
+  fn days_are_read_by_name() {
+    let schedule = parse_csv("Sun,09:00,10:00\nSaturday,09:00,10:00\nTHU,09:00,10:00").unwrap();
+    assert_eq!(
+      times(&schedule),
+      ["0,9,0-0,10,0", "6,9,0-6,10,0", "4,9,0-4,10,0"]
+    );
+    assert!(parse_csv("Someday,09:00,10:00").is_err());
+  }
+
+  #[test]
+  fn times_ending_at_midnight_end_the_next_day() {
+    let schedule = parse_csv("Sat,22:00,24:00\nMon,20:00,00:00").unwrap();
+    assert_eq!(times(&schedule), ["6,22,0-7,0,0", "1,20,0-2,0,0"]);
+  }
+
+  #[test]
+  fn times_running_past_midnight_are_refused() {
+    let problems = parse_csv("Fri,22:00,02:00").unwrap_err();
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("line 1: 22:00 to 02:00"));
+  }
+
+  #[test]
+  fn every_bad_line_is_reported() {
+    let csv = "Mon,09:00\n\
+               Tue,09:03,10:00\n\
+               Wed,09:00,10:00,lots\n\
+               Thu,09:00,10:00,100\n\
+               Fri,09:00,10:00,none\n\
+               Sat,noon,10:00";
+    let problems = parse_csv(csv).unwrap_err();
+    let lines: Vec<&str> = problems
+      .iter()
+      .map(|problem| problem.split(':').next().unwrap())
+      .collect();
+    assert_eq!(lines, ["line 1", "line 2", "line 3", "line 4", "line 6"]);
+  }
+
+  #[test]
+  fn timetables_without_times_are_refused() {
+    assert!(parse_csv("").is_err());
+    assert!(parse_csv("day,start,end\n# nothing yet\n").is_err());
+  }
+
+  fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+  }
+
+  #[test]
+  fn end_times_must_come_after_start_times() {
+    assert!(check_times(time(9, 0), time(17, 0)).is_ok());
+    assert!(check_times(time(22, 0), NaiveTime::MIN).is_ok());
+    assert!(check_times(time(9, 0), time(9, 0)).is_err());
+    assert!(check_times(time(17, 0), time(9, 0)).is_err());
+  }
+
+  #[test]
+  fn overlapping_and_nested_entries_are_found() {
+    let schedule = parse_csv(
+      "Mon,09:00,12:00
+Mon,10:00,11:00
+Mon,11:30,13:00",
+    )
+    .unwrap();
+    assert_eq!(
+      describe_overlaps(&schedule),
+      [
+        "Mon 09:00-12:00 overlaps Mon 10:00-11:00",
+        "Mon 09:00-12:00 overlaps Mon 11:30-13:00",
+      ]
+    );
+  }
+
+  #[test]
+  fn entries_given_twice_overlap() {
+    let schedule = parse_csv(
+      "Tue,09:00,10:00
+Tue,09:00,10:00",
+    )
+    .unwrap();
+    assert_eq!(
+      describe_overlaps(&schedule),
+      ["Tue 09:00-10:00 overlaps Tue 09:00-10:00"]
+    );
+  }
+
+  #[test]
+  fn entries_that_only_touch_do_not_overlap() {
+    let schedule = parse_csv(
+      "Mon,09:00,12:00
+Mon,12:00,17:00
+Sat,22:00,24:00
+Sun,00:00,01:00",
+    )
+    .unwrap();
+    assert!(describe_overlaps(&schedule).is_empty());
+  }
+
+  #[test]
+  fn overlapping_and_touching_entries_are_joined() {
+    let schedule = parse_csv(
+      "Mon,09:00,12:00
+Mon,12:00,17:00
+Mon,10:00,11:00",
+    )
+    .unwrap();
+    assert_eq!(times(&merge_overlapping(schedule)), ["1,9,0-1,17,0"]);
+  }
+
+  #[test]
+  fn entries_given_twice_are_joined() {
+    let schedule = parse_csv(
+      "Tue,09:00,10:00
+Tue,09:00,10:00",
+    )
+    .unwrap();
+    assert_eq!(times(&merge_overlapping(schedule)), ["2,9,0-2,10,0"]);
+  }
+
+  #[test]
+  fn entries_are_kept_apart_across_days_breaks_and_gaps() {
+    let schedule = parse_csv(
+      "Wed,09:00,10:00
+       Mon,20:00,24:00
+       Tue,00:00,01:00
+       Wed,10:00,11:00,10
+       Wed,11:30,12:00,10",
+    )
+    .unwrap();
+    let merged = merge_overlapping(schedule);
+    assert_eq!(
+      times(&merged),
+      [
+        "1,20,0-2,0,0",
+        "2,0,0-2,1,0",
+        "3,9,0-3,10,0",
+        "3,10,0-3,11,0",
+        "3,11,30-3,12,0"
+      ]
+    );
+    let ids: Vec<usize> = merged.iter().map(|entry| entry.id).collect();
+    assert_eq!(ids, [0, 1, 2, 3, 4]);
+  }
+}
@@ -0,0 +1,98 @@
+use std::fmt;
+use std::process::Command;
+use std::time::Duration;
+
+/// Whether someone is at the computer, as far as ctk can tell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presence {
+  Active,
+  /// No keyboard or mouse input for a while
+  Idle,
+  Locked,
+}
+
+impl Presence {
+  /// Checks the session now. Anything that cannot be checked counts as active, so rules
+  /// still fire on systems ctk cannot ask
+  pub fn now(idle_after: Duration) -> Self {
+    if is_locked() {
+      Self::Locked
+    } else if idle_time().is_some_and(|idle| idle >= idle_after) {
+      Self::Idle
+    } else {
+      Self::Active
+    }
+  }
+}
+
+impl fmt::Display for Presence {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Active => write!(f, "active"),
+      Self::Idle => write!(f, "idle"),
+      Self::Locked => write!(f, "locked"),
+    }
+  }
+}
+
+fn is_locked() -> bool {
+  if cfg!(windows) {
+    // The lock screen runs as LogonUI.exe, which is not running otherwise
+    Command::new("tasklist")
+      .args(["/FI", "IMAGENAME eq LogonUI.exe", "/NH"])
+      .output()
+      .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("LogonUI.exe"))
+  } else if cfg!(target_os = "macos") {
+    Command::new("ioreg")
+      .args(["-n", "Root", "-d1"])
+      .output()
+      .is_ok_and(|output| {
+        String::from_utf8_lossy(&output.stdout).contains("\"CGSSessionScreenIsLocked\"=Yes")
+      })
+  } else {
+    Command::new("sh")
+      .args([
+        "-c",
+        "loginctl show-session \"$XDG_SESSION_ID\" -p LockedHint",
+      ])
+      .output()
+      .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "LockedHint=yes")
+  }
+}
+
+fn idle_time() -> Option<Duration> {
+  if cfg!(windows) {
+    let script = "Add-Type -Namespace Ctk -Name Idle -MemberDefinition '\
+      [StructLayout(LayoutKind.Sequential)] public struct Info { public uint cbSize; public uint dwTime; }\
+      [DllImport(\"user32.dll\")] public static extern bool GetLastInputInfo(ref Info i);';\
+      $i = New-Object Ctk.Idle+Info; $i.cbSize = 8; [void][Ctk.Idle]::GetLastInputInfo([ref]$i);\
+      [Environment]::TickCount - $i.dwTime";
+    let output = Command::new("powershell")
+      .args(["-NoProfile", "-Command", script])
+      .output()
+      .ok()?;
+    let millis: u64 = String::from_utf8_lossy(&output.stdout)
+      .trim()
+      .parse()
+      .ok()?;
+    Some(Duration::from_millis(millis))
+  } else if cfg!(target_os = "macos") {
+    let output = Command::new("ioreg")
+      .args(["-c", "IOHIDSystem", "-d", "4"])
+      .output()
+      .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let nanos: u64 = stdout
+      .lines()
+      .find_map(|line| line.split_once("\"HIDIdleTime\" = "))
+      .and_then(|(_, nanos)| nanos.trim().parse().ok())?;
+    Some(Duration::from_nanos(nanos))
+  } else {
+    let output = Command::new("xprintidle").output().ok()?;
+    let millis: u64 = String::from_utf8_lossy(&output.stdout)
+      .trim()
+      .parse()
+      .ok()?;
+    Some(Duration::from_millis(millis))
+  }
+}
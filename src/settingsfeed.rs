@@ -0,0 +1,37 @@
+use ctk_common::ctsettings::{get_ct_settings, ColdTurkeySettings};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+/// Reads Cold Turkey's settings over and over on a thread of its own, since the messaging
+/// host can take hundreds of milliseconds to answer and a live view should not stall on it
+pub struct SettingsFeed {
+  receiver: Receiver<Option<ColdTurkeySettings>>,
+}
+
+impl SettingsFeed {
+  /// Starts reading, waiting `interval` after each read
+  pub fn start(interval: Duration) -> Self {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || loop {
+      // Nothing is left to read for once the feed is dropped
+      if sender.send(get_ct_settings()).is_err() {
+        return;
+      }
+      thread::sleep(interval);
+    });
+    Self { receiver }
+  }
+
+  /// Waits for the next read, skipping older ones that piled up while the caller was busy
+  /// so only the newest is drawn. None if the reading thread has stopped
+  pub fn next(&self) -> Option<Option<ColdTurkeySettings>> {
+    let first = self.receiver.recv().ok()?;
+    Some(self.receiver.try_iter().last().unwrap_or(first))
+  }
+
+  /// The newest read since the last call, without waiting for one
+  pub fn latest(&self) -> Option<Option<ColdTurkeySettings>> {
+    self.receiver.try_iter().last()
+  }
+}
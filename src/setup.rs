@@ -0,0 +1,183 @@
+use crate::config;
+use clap_complete::Shell;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// One thing `ctk complete-setup` changes on this machine
+pub enum Change {
+  /// Writes a whole file, replacing what was there
+  Write {
+    path: PathBuf,
+    contents: Vec<u8>,
+    what: String,
+  },
+  /// Adds a line to a file, unless it is already there
+  AppendLine {
+    path: PathBuf,
+    line: String,
+    what: String,
+  },
+  /// Adds a folder to the user's PATH on Windows
+  AddToPath(PathBuf),
+}
+
+/// Everything to change for the completions, man page and, if asked, PATH on this machine
+pub fn plan(cmd: &mut clap::Command, add_to_path: bool) -> Vec<Change> {
+  let mut changes = Vec::new();
+  let completions = |shell: Shell, cmd: &mut clap::Command| {
+    let mut contents = Vec::new();
+    clap_complete::generate(shell, cmd, "ctk", &mut contents);
+    contents
+  };
+
+  if cfg!(windows) {
+    if let Some(data_dir) = config::data_dir() {
+      let script = data_dir.join("completions").join("ctk.ps1");
+      changes.push(Change::Write {
+        path: script.clone(),
+        contents: completions(Shell::PowerShell, cmd),
+        what: "PowerShell completions".to_string(),
+      });
+      // PowerShell only loads completions its profile runs
+      if let Some(documents) = dirs::document_dir() {
+        changes.push(Change::AppendLine {
+          path: documents
+            .join("PowerShell")
+            .join("Microsoft.PowerShell_profile.ps1"),
+          line: format!(". \"{}\"", script.display()),
+          what: "a line loading the completions".to_string(),
+        });
+      }
+    }
+  } else if let Some(home) = dirs::home_dir() {
+    let data_dir = dirs::data_dir().unwrap_or_else(|| home.join(".local").join("share"));
+    changes.push(Change::Write {
+      path: data_dir
+        .join("bash-completion")
+        .join("completions")
+        .join("ctk"),
+      contents: completions(Shell::Bash, cmd),
+      what: "bash completions".to_string(),
+    });
+    changes.push(Change::Write {
+      path: home.join(".zfunc").join("_ctk"),
+      contents: completions(Shell::Zsh, cmd),
+      what: "zsh completions".to_string(),
+    });
+    changes.push(Change::Write {
+      path: dirs::config_dir()
+        .unwrap_or_else(|| home.join(".config"))
+        .join("fish")
+        .join("completions")
+        .join("ctk.fish"),
+      contents: completions(Shell::Fish, cmd),
+      what: "fish completions".to_string(),
+    });
+
+    let mut man_page = Vec::new();
+    // Why unwrap? Writing to a Vec cannot fail
+    clap_mangen::Man::new(cmd.clone())
+      .render(&mut man_page)
+      .unwrap();
+    changes.push(Change::Write {
+      // man looks here for users' own pages on Linux and macOS alike
+      path: home
+        .join(".local")
+        .join("share")
+        .join("man")
+        .join("man1")
+        .join("ctk.1"),
+      contents: man_page,
+      what: "the man page".to_string(),
+    });
+  }
+
+  if add_to_path {
+    if let Some(dir) = env::current_exe()
+      .ok()
+      .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+      changes.push(Change::AddToPath(dir));
+    }
+  }
+  changes
+}
+
+impl Change {
+  /// What the change does, to follow "Would" or "Cannot"
+  pub fn describe(&self) -> String {
+    match self {
+      Self::Write { path, what, .. } => format!("write {what} to {}", path.display()),
+      Self::AppendLine { path, what, .. } => format!("add {what} to {}", path.display()),
+      Self::AddToPath(dir) => format!("add {} to your PATH", dir.display()),
+    }
+  }
+
+  /// What the change did, once it is applied
+  pub fn done(&self) -> String {
+    match self {
+      Self::Write { path, what, .. } => format!("Writes {what} to {}", path.display()),
+      Self::AppendLine { path, what, .. } => format!("Adds {what} to {}", path.display()),
+      Self::AddToPath(dir) => format!("Adds {} to your PATH", dir.display()),
+    }
+  }
+
+  pub fn apply(&self) -> io::Result<()> {
+    match self {
+      Self::Write { path, contents, .. } => {
+        create_parent(path)?;
+        fs::write(path, contents)
+      }
+      Self::AppendLine { path, line, .. } => {
+        let existing = fs::read_to_string(path).unwrap_or_default();
+        if existing
+          .lines()
+          .any(|existing_line| existing_line.trim() == line)
+        {
+          return Ok(());
+        }
+        create_parent(path)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if !existing.is_empty() && !existing.ends_with('\n') {
+          writeln!(file)?;
+        }
+        writeln!(file, "{line}")
+      }
+      Self::AddToPath(dir) => add_to_user_path(dir),
+    }
+  }
+}
+
+fn create_parent(path: &Path) -> io::Result<()> {
+  match path.parent() {
+    Some(dir) => fs::create_dir_all(dir),
+    None => Ok(()),
+  }
+}
+
+fn add_to_user_path(dir: &Path) -> io::Result<()> {
+  if !cfg!(windows) {
+    return Err(io::Error::new(
+      io::ErrorKind::Unsupported,
+      "only Windows is supported, add it in your shell's startup file instead",
+    ));
+  }
+  // setx cuts PATH off at 1024 characters, so the user PATH is changed through .NET instead
+  let script = format!(
+    "$dir = '{}'; $path = [Environment]::GetEnvironmentVariable('Path', 'User'); \
+     if (($path -split ';') -notcontains $dir) {{ \
+       [Environment]::SetEnvironmentVariable('Path', (@($path, $dir) -ne '' -join ';'), 'User') }}",
+    dir.display().to_string().replace('\'', "''")
+  );
+  let status = Command::new("powershell")
+    .args(["-NoProfile", "-Command", &script])
+    .status()?;
+  if status.success() {
+    Ok(())
+  } else {
+    Err(io::Error::other(format!("PowerShell exited with {status}")))
+  }
+}
@@ -0,0 +1,353 @@
+use crate::config;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+
+const SIDECAR_FILE_NAME: &str = "blocks.json";
+
+pub const DAY_NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// What ctk remembers about blocks that Cold Turkey itself has no room for
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Sidecar {
+  #[serde(default)]
+  pub blocks: BTreeMap<String, BlockMeta>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BlockMeta {
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub schedule_labels: Vec<ScheduleLabel>,
+  /// Freeform, like "created for exam season, safe to delete after June"
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub note: Option<String>,
+  #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+  pub tags: BTreeSet<String>,
+  /// When a block made by `ctk tmp start` is done with, for `ctk tmp clean` to empty it
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub expires: Option<DateTime<Local>>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub subscriptions: Vec<Subscription>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub categories: Vec<CategorySource>,
+  /// When `ctk stop --when-unlocked` asked for the block to be stopped once its lock ends
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub stop_when_unlocked: Option<DateTime<Local>>,
+}
+
+impl BlockMeta {
+  fn is_empty(&self) -> bool {
+    self.schedule_labels.is_empty()
+      && self.note.is_none()
+      && self.tags.is_empty()
+      && self.expires.is_none()
+      && self.subscriptions.is_empty()
+      && self.categories.is_empty()
+      && self.stop_when_unlocked.is_none()
+  }
+}
+
+/// A blocklist online that `ctk blocklist update` keeps the block in step with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+  pub url: String,
+  /// The websites the list had when it was last applied, so the ones it drops later can be
+  /// taken out of the block without touching the ones added by hand
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub sites: Vec<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub updated: Option<DateTime<Local>>,
+}
+
+/// Where websites in the block came from, when they were added as `@category:<name>`. The
+/// category was expanded to the websites it had then, which later changes to it leave alone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategorySource {
+  pub category: String,
+  pub expanded: DateTime<Local>,
+  pub sites: Vec<String>,
+}
+
+/// A label for one schedule entry, found again by the entry's times since Cold Turkey
+/// renumbers entries freely
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleLabel {
+  /// In Cold Turkey's "day,hour,minute" form
+  pub start: String,
+  pub end: String,
+  pub label: String,
+}
+
+impl ScheduleLabel {
+  /// The entry's times in a readable form, like "Mon 08:00-15:00"
+  pub fn times(&self) -> String {
+    describe_times(&self.start, &self.end)
+  }
+}
+
+/// A schedule entry's "day,hour,minute" times in a readable form, like "Mon 08:00-15:00"
+pub fn describe_times(start: &str, end: &str) -> String {
+  match (parse_time_tuple(start), parse_time_tuple(end)) {
+    (Some((day, start_hour, start_minute)), Some((_, end_hour, end_minute))) => format!(
+      "{} {start_hour:02}:{start_minute:02}-{end_hour:02}:{end_minute:02}",
+      DAY_NAMES[day % 7]
+    ),
+    _ => format!("{start}-{end}"),
+  }
+}
+
+pub fn parse_time_tuple(tuple: &str) -> Option<(usize, u32, u32)> {
+  let mut parts = tuple.split(',').map(str::trim);
+  let day = parts.next()?.parse().ok()?;
+  let hour = parts.next()?.parse().ok()?;
+  let minute = parts.next()?.parse().ok()?;
+  Some((day, hour, minute))
+}
+
+pub fn sidecar_path() -> Option<PathBuf> {
+  config::data_dir().map(|dir| dir.join(SIDECAR_FILE_NAME))
+}
+
+impl Sidecar {
+  /// Reads the sidecar file, or starts an empty one if there is none yet
+  pub fn load() -> Self {
+    sidecar_path()
+      .and_then(|path| fs::read_to_string(path).ok())
+      .and_then(|contents| serde_json::from_str(&contents).ok())
+      .unwrap_or_default()
+  }
+
+  pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+    let path = sidecar_path().ok_or("cannot find your data directory")?;
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(self)?)?;
+    Ok(())
+  }
+
+  pub fn schedule_labels(&self, block: &str) -> &[ScheduleLabel] {
+    self
+      .blocks
+      .get(block)
+      .map_or(&[], |meta| meta.schedule_labels.as_slice())
+  }
+
+  /// Replaces the block's schedule labels, since they describe its whole schedule
+  pub fn set_schedule_labels(&mut self, block: &str, labels: Vec<ScheduleLabel>) {
+    self
+      .blocks
+      .entry(block.to_string())
+      .or_default()
+      .schedule_labels = labels;
+    self.forget_if_empty(block);
+  }
+
+  pub fn note(&self, block: &str) -> Option<&str> {
+    self.blocks.get(block).and_then(|meta| meta.note.as_deref())
+  }
+
+  /// Sets the block's note, or removes it if `note` is None
+  pub fn set_note(&mut self, block: &str, note: Option<String>) {
+    self.blocks.entry(block.to_string()).or_default().note = note;
+    self.forget_if_empty(block);
+  }
+
+  /// Marks the block as temporary until `expires`, or not temporary if it is None
+  pub fn set_expiry(&mut self, block: &str, expires: Option<DateTime<Local>>) {
+    self.blocks.entry(block.to_string()).or_default().expires = expires;
+    self.forget_if_empty(block);
+  }
+
+  /// Has `ctk watch` stop the block once nothing holds it on any more
+  pub fn queue_stop(&mut self, block: &str) {
+    self
+      .blocks
+      .entry(block.to_string())
+      .or_default()
+      .stop_when_unlocked = Some(Local::now());
+  }
+
+  pub fn clear_queued_stop(&mut self, block: &str) {
+    if let Some(meta) = self.blocks.get_mut(block) {
+      meta.stop_when_unlocked = None;
+    }
+    self.forget_if_empty(block);
+  }
+
+  /// The blocks waiting to be stopped once their locks end
+  pub fn queued_stops(&self) -> Vec<String> {
+    self
+      .blocks
+      .iter()
+      .filter(|(_, meta)| meta.stop_when_unlocked.is_some())
+      .map(|(block, _)| block.clone())
+      .collect()
+  }
+
+  /// The blocks made by `ctk tmp start`, with when each expires, soonest first
+  pub fn temporary_blocks(&self) -> Vec<(&str, DateTime<Local>)> {
+    let mut blocks: Vec<(&str, DateTime<Local>)> = self
+      .blocks
+      .iter()
+      .filter_map(|(block, meta)| Some((block.as_str(), meta.expires?)))
+      .collect();
+    blocks.sort_by_key(|(_, expires)| *expires);
+    blocks
+  }
+
+  pub fn subscriptions(&self, block: &str) -> &[Subscription] {
+    self
+      .blocks
+      .get(block)
+      .map_or(&[], |meta| meta.subscriptions.as_slice())
+  }
+
+  /// The blocks that follow a blocklist
+  pub fn subscribed_blocks(&self) -> Vec<String> {
+    self
+      .blocks
+      .iter()
+      .filter(|(_, meta)| !meta.subscriptions.is_empty())
+      .map(|(block, _)| block.clone())
+      .collect()
+  }
+
+  /// Makes the block follow the blocklist at `url`, unless it already does
+  pub fn subscribe(&mut self, block: &str, url: &str) -> bool {
+    let meta = self.blocks.entry(block.to_string()).or_default();
+    if meta
+      .subscriptions
+      .iter()
+      .any(|subscription| subscription.url == url)
+    {
+      return false;
+    }
+    meta.subscriptions.push(Subscription {
+      url: url.to_string(),
+      sites: Vec::new(),
+      updated: None,
+    });
+    true
+  }
+
+  /// Stops the block following the blocklist at `url`, if it did
+  pub fn unsubscribe(&mut self, block: &str, url: &str) -> bool {
+    let Some(meta) = self.blocks.get_mut(block) else {
+      return false;
+    };
+    let before = meta.subscriptions.len();
+    meta
+      .subscriptions
+      .retain(|subscription| subscription.url != url);
+    let removed = meta.subscriptions.len() < before;
+    self.forget_if_empty(block);
+    removed
+  }
+
+  /// Remembers the websites of the blocklist as they were applied to the block just now
+  pub fn set_subscription_sites(&mut self, block: &str, url: &str, sites: Vec<String>) {
+    let subscription = self.blocks.get_mut(block).and_then(|meta| {
+      meta
+        .subscriptions
+        .iter_mut()
+        .find(|subscription| subscription.url == url)
+    });
+    if let Some(subscription) = subscription {
+      subscription.sites = sites;
+      subscription.updated = Some(Local::now());
+    }
+  }
+
+  pub fn categories(&self, block: &str) -> &[CategorySource] {
+    self
+      .blocks
+      .get(block)
+      .map_or(&[], |meta| meta.categories.as_slice())
+  }
+
+  /// Remembers that the category was expanded into the block just now, keeping the websites
+  /// it brought in before as well
+  pub fn record_category(&mut self, block: &str, category: &str, sites: &[String]) {
+    let meta = self.blocks.entry(block.to_string()).or_default();
+    let index = match meta
+      .categories
+      .iter()
+      .position(|source| source.category == category)
+    {
+      Some(index) => index,
+      None => {
+        meta.categories.push(CategorySource {
+          category: category.to_string(),
+          expanded: Local::now(),
+          sites: Vec::new(),
+        });
+        meta.categories.len() - 1
+      }
+    };
+    let source = &mut meta.categories[index];
+    source.expanded = Local::now();
+    for site in sites {
+      if !source.sites.contains(site) {
+        source.sites.push(site.clone());
+      }
+    }
+  }
+
+  /// Drops everything kept about the block, for blocks that are gone
+  pub fn forget(&mut self, block: &str) {
+    self.blocks.remove(block);
+  }
+
+  pub fn tags(&self, block: &str) -> impl Iterator<Item = &str> {
+    self
+      .blocks
+      .get(block)
+      .into_iter()
+      .flat_map(|meta| meta.tags.iter().map(String::as_str))
+  }
+
+  /// Every tag in use, with the blocks that have it
+  pub fn all_tags(&self) -> BTreeMap<&str, Vec<&str>> {
+    let mut tags: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (block, meta) in &self.blocks {
+      for tag in &meta.tags {
+        tags.entry(tag).or_default().push(block);
+      }
+    }
+    tags
+  }
+
+  /// Adds the tags the block does not have yet, returning the ones added
+  pub fn add_tags(&mut self, block: &str, tags: &[String]) -> Vec<String> {
+    let meta = self.blocks.entry(block.to_string()).or_default();
+    tags
+      .iter()
+      .filter(|tag| meta.tags.insert(tag.to_string()))
+      .cloned()
+      .collect()
+  }
+
+  /// Removes the tags the block has, returning the ones removed
+  pub fn remove_tags(&mut self, block: &str, tags: &[String]) -> Vec<String> {
+    let removed = match self.blocks.get_mut(block) {
+      Some(meta) => tags
+        .iter()
+        .filter(|tag| meta.tags.remove(*tag))
+        .cloned()
+        .collect(),
+      None => Vec::new(),
+    };
+    self.forget_if_empty(block);
+    removed
+  }
+
+  /// Keeps the file free of blocks ctk has nothing to remember about
+  fn forget_if_empty(&mut self, block: &str) {
+    if self.blocks.get(block).is_some_and(BlockMeta::is_empty) {
+      self.blocks.remove(block);
+    }
+  }
+}
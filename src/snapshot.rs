@@ -0,0 +1,115 @@
+use crate::bridge;
+use crate::config;
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use ctk_common::blocksettings::BlockSettings;
+use ctk_common::ctsettings::{ColdTurkeySettings, GlobalSetting};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+const SNAPSHOT_DIR_NAME: &str = "snapshots";
+
+/// Cold Turkey's settings as they were just before ctk started some blocks
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot<'a> {
+  taken: DateTime<Local>,
+  blocks_started: &'a [String],
+  lock: &'a str,
+  global_settings: BTreeMap<&'static str, bool>,
+  /// In .ctbbl form, so they can be copied out and imported again
+  running_blocks: BTreeMap<&'a str, BlockSettings>,
+}
+
+pub fn snapshot_dir() -> Option<PathBuf> {
+  config::data_dir().map(|dir| dir.join(SNAPSHOT_DIR_NAME))
+}
+
+/// Saves a snapshot before starting `blocks` with `lock`, returning where it was saved
+pub fn take(
+  settings: &ColdTurkeySettings,
+  blocks: &[String],
+  lock: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+  let taken = Local::now();
+  let snapshot = Snapshot {
+    taken,
+    blocks_started: blocks,
+    lock,
+    global_settings: GlobalSetting::value_variants()
+      .iter()
+      .map(|setting| (setting.key(), settings.global_setting(*setting)))
+      .collect(),
+    running_blocks: settings
+      .block_list_info
+      .blocks
+      .iter()
+      .filter(|(_, info)| !info.is_dormant())
+      .map(|(name, info)| (name.as_str(), bridge::export_block(info)))
+      .collect(),
+  };
+
+  let dir = snapshot_dir().ok_or("cannot find your data directory")?;
+  fs::create_dir_all(&dir)?;
+  let path = dir.join(format!("{}.json", taken.format("%Y%m%d-%H%M%S")));
+  fs::write(&path, serde_json::to_string_pretty(&snapshot)?)?;
+  Ok(path)
+}
+
+/// Prints what can and cannot be changed about the block once it is locked
+pub fn print_rollback_plan(settings: &ColdTurkeySettings, block_name: &str) {
+  let Some(block_info) = settings.block_list_info.blocks.get(block_name) else {
+    return;
+  };
+
+  println!("While {block_name} is locked:");
+  if block_info.is_dormant() {
+    println!("  Cold Turkey only reports the settings of running blocks, so ctk cannot tell");
+    println!("  what {block_name} blocks. Start it without a lock first to see the full plan.");
+  } else {
+    print_list("It keeps blocking", &block_info.block_list);
+    print_list("It keeps blocking windows titled", &block_info.title_list);
+    print_list(
+      "The only exceptions it allows are",
+      &block_info.exception_list,
+    );
+    if block_info.exception_list.is_empty() {
+      println!("  It allows no exceptions, so check that nothing you need is blocked above");
+    }
+  }
+
+  println!("  You can still:");
+  println!("    - add websites to it with `ctk add`, since that only makes it stricter");
+  let mut other_unlocked: Vec<&str> = settings
+    .block_list_info
+    .blocks
+    .iter()
+    .filter(|(name, info)| name.as_str() != block_name && !info.is_locked())
+    .map(|(name, _)| name.as_str())
+    .collect();
+  other_unlocked.sort_unstable();
+  if !other_unlocked.is_empty() {
+    println!(
+      "    - start, stop and change your unlocked blocks: {}",
+      other_unlocked.join(", ")
+    );
+  }
+  println!("    - see what it blocks with `ctk add \"{block_name}\" --list`");
+
+  println!("  You cannot:");
+  println!("    - stop or toggle it until the lock ends");
+  println!("    - remove websites from it or add exceptions to it");
+  println!("    - rename or delete it");
+}
+
+fn print_list(heading: &str, entries: &[String]) {
+  if entries.is_empty() {
+    return;
+  }
+  println!("  {heading}:");
+  for entry in entries {
+    println!("    {entry}");
+  }
+}
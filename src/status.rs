@@ -0,0 +1,233 @@
+use crate::error::{error, EXIT_CHECK_INACTIVE, EXIT_CHECK_UNKNOWN};
+use crate::settingsfeed::SettingsFeed;
+use chrono::{DateTime, Local};
+use ctk_common::ctsettings::{BlockInfo, ColdTurkeySettings};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::time::Duration;
+
+// Moves the cursor to the top left and clears the terminal, to draw the next status over
+// the last one
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+
+/// What `ctk status --json` prints
+#[derive(Serialize)]
+struct Status<'a> {
+  paused: bool,
+  blocks: Vec<BlockStatus<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockStatus<'a> {
+  name: &'a str,
+  active: bool,
+  /// "password", "randomText" or Cold Turkey's own name for the lock, or null if unlocked
+  lock: Option<&'a str>,
+  schedule_type: Option<&'a str>,
+  allowance: Option<u32>,
+  allowance_remaining: Option<u32>,
+  pomodoro_state: Option<&'a str>,
+  pomodoro_remaining: Option<u32>,
+}
+
+/// Every block, or only `block_name`, in alphabetical order
+fn blocks<'a>(
+  settings: &'a ColdTurkeySettings,
+  block_name: Option<&str>,
+) -> Vec<(&'a String, &'a BlockInfo)> {
+  let mut blocks: Vec<(&String, &BlockInfo)> = settings
+    .block_list_info
+    .blocks
+    .iter()
+    .filter(|(name, _)| block_name.is_none_or(|block_name| *name == block_name))
+    .collect();
+  blocks.sort_unstable_by_key(|(name, _)| *name);
+  blocks
+}
+
+/// Prints how each block (or only `block_name`) is doing right now, in alphabetical order
+pub fn print_status(settings: &ColdTurkeySettings, block_name: Option<&str>) {
+  if settings.paused {
+    println!("Cold Turkey is paused, so no block is blocking right now");
+  }
+  for (name, info) in blocks(settings, block_name) {
+    print_block_status(name, info);
+  }
+}
+
+pub fn print_status_json(settings: &ColdTurkeySettings, block_name: Option<&str>) {
+  let status = Status {
+    paused: settings.paused,
+    blocks: blocks(settings, block_name)
+      .into_iter()
+      .map(|(name, info)| BlockStatus {
+        name,
+        active: !info.is_dormant(),
+        lock: lock_kind(info),
+        schedule_type: non_empty(info.schedule_type.as_deref()),
+        allowance: info.allowance,
+        allowance_remaining: info.allowance_remaining,
+        pomodoro_state: non_empty(Some(&info.pomodoro_period_state)),
+        pomodoro_remaining: info.pomodoro_period_remaining,
+      })
+      .collect(),
+  };
+  match serde_json::to_string_pretty(&status) {
+    Ok(json) => println!("{json}"),
+    Err(err) => error!("Cannot write the status as JSON: {err}"),
+  }
+}
+
+/// Shows the status again each time it is read, until killed. With `json`, each status is
+/// printed after the last instead of over it, for scripts that read them as they come
+pub fn watch_status(block_name: Option<&str>, interval: Duration, json: bool) {
+  let feed = SettingsFeed::start(interval);
+  while let Some(settings) = feed.next() {
+    if !json {
+      print!("{CLEAR_SCREEN}");
+    }
+    match settings {
+      Some(settings) if json => print_status_json(&settings, block_name),
+      Some(settings) => print_status(&settings, block_name),
+      None => println!("ctk cannot read the state of your blocks right now, and keeps trying"),
+    }
+    if !json {
+      println!();
+      println!(
+        "Read at {}. Press Ctrl+C to stop.",
+        Local::now().format("%H:%M:%S")
+      );
+    }
+    // A view piped somewhere is only flushed at newlines otherwise
+    let _ = io::stdout().flush();
+  }
+}
+
+fn non_empty(text: Option<&str>) -> Option<&str> {
+  text.filter(|text| !text.is_empty())
+}
+
+/// One line for each block that is blocking, short enough for a notification
+pub fn summary_lines(settings: &ColdTurkeySettings) -> Vec<String> {
+  let mut lines: Vec<String> = blocks(settings, None)
+    .into_iter()
+    .filter(|(_, info)| !info.is_dormant())
+    .map(|(name, info)| format!("{name}: {}", block_summary(info)))
+    .collect();
+  if lines.is_empty() {
+    lines.push("No block is blocking".to_string());
+  }
+  if settings.paused {
+    lines.insert(0, "Cold Turkey is paused".to_string());
+  }
+  lines
+}
+
+/// Like "blocking, locked with a password, scheduled"
+fn block_summary(info: &BlockInfo) -> String {
+  let mut summary = vec!["blocking".to_string(), lock_description(info)];
+  if let Some(schedule_type) = non_empty(info.schedule_type.as_deref()) {
+    summary.push(schedule_type.to_string());
+  }
+  summary.join(", ")
+}
+
+fn print_block_status(name: &str, info: &BlockInfo) {
+  // Cold Turkey only reports the settings of running blocks
+  if info.is_dormant() {
+    println!("{name}: not blocking");
+    return;
+  }
+
+  println!("{name}: {}", block_summary(info));
+
+  match (info.allowance_remaining, info.allowance) {
+    (Some(remaining), Some(allowance)) => {
+      println!("  Allowance: {remaining} of {allowance} minutes left")
+    }
+    (Some(remaining), None) => println!("  Allowance: {remaining} minutes left"),
+    (None, Some(allowance)) => println!("  Allowance: {allowance} minutes a day"),
+    (None, None) => {}
+  }
+
+  if !info.pomodoro_period_state.is_empty() {
+    match info.pomodoro_period_remaining {
+      Some(remaining) => println!(
+        "  Pomodoro: {}, {remaining} minutes left",
+        info.pomodoro_period_state
+      ),
+      None => println!("  Pomodoro: {}", info.pomodoro_period_state),
+    }
+  }
+}
+
+/// Whether the block is blocking and locked, for `ctk check`, giving the exit code. A timed
+/// lock ctk started counts too, since Cold Turkey does not always report it
+pub fn check_block(
+  settings: Option<&ColdTurkeySettings>,
+  locked_until: Option<DateTime<Local>>,
+  block_name: &str,
+  verbose: bool,
+) -> u8 {
+  let Some(settings) = settings else {
+    if verbose {
+      eprintln!("{block_name}: unknown, since ctk cannot read the state of your blocks");
+    }
+    return EXIT_CHECK_UNKNOWN;
+  };
+  let Some(info) = settings.block_list_info.blocks.get(block_name) else {
+    if verbose {
+      eprintln!("{block_name}: unknown, since there is no such block in Cold Turkey");
+    }
+    return EXIT_CHECK_UNKNOWN;
+  };
+
+  if info.is_dormant() {
+    if verbose {
+      println!("{block_name}: not blocking");
+    }
+    return EXIT_CHECK_INACTIVE;
+  }
+  let lock = match locked_until {
+    Some(end) if lock_kind(info).is_none() => {
+      format!("locked until {}", end.format("%H:%M %B %d %Y"))
+    }
+    _ => lock_description(info),
+  };
+  if verbose {
+    println!("{block_name}: blocking, {lock}");
+  }
+  if holds_lock(info, locked_until) {
+    0
+  } else {
+    EXIT_CHECK_INACTIVE
+  }
+}
+
+/// Whether a lock keeps the block on, counting a timed lock ctk started and that ends at
+/// `locked_until`
+pub fn holds_lock(info: &BlockInfo, locked_until: Option<DateTime<Local>>) -> bool {
+  lock_kind(info).is_some() || locked_until.is_some()
+}
+
+fn lock_kind(info: &BlockInfo) -> Option<&str> {
+  if !info.password.is_empty() {
+    return Some("password");
+  }
+  if info.random_text_length.is_some() {
+    return Some("randomText");
+  }
+  non_empty(info.lock.as_deref()).filter(|lock| *lock != "none")
+}
+
+fn lock_description(info: &BlockInfo) -> String {
+  match (lock_kind(info), info.random_text_length) {
+    (None, _) => "unlocked".to_string(),
+    (Some("password"), _) => "locked with a password".to_string(),
+    (Some("randomText"), Some(length)) => {
+      format!("locked with {length} characters of random text")
+    }
+    (Some(lock), _) => format!("locked ({lock})"),
+  }
+}
@@ -0,0 +1,136 @@
+use crate::journal::Entry;
+use crate::report::{current_streak, daily_totals, longest_streak, sessions};
+use chrono::{DateTime, Duration, Local};
+use std::collections::BTreeSet;
+use std::io;
+use std::process::{Command, ExitStatus};
+
+/// Badges earned by reaching a streak of at least this many days
+const BADGES: [(u32, &str); 5] = [
+  (3, "Warming Up"),
+  (7, "One Week"),
+  (30, "One Month"),
+  (100, "Centurion"),
+  (365, "One Year"),
+];
+
+/// How a block's streak stands right now
+pub struct Streak {
+  pub current: u32,
+  pub best: u32,
+  /// How long the block has run so far today
+  pub today: Duration,
+  pub goal: Duration,
+}
+
+impl Streak {
+  /// Works out the streak from the journal. A day counts when the block ran for at least
+  /// `goal` that day.
+  pub fn from_journal(
+    entries: &[Entry],
+    block: &str,
+    goal: Duration,
+    now: DateTime<Local>,
+  ) -> Self {
+    let totals = daily_totals(&sessions(entries, now), block);
+    let days: BTreeSet<_> = totals
+      .iter()
+      .filter(|(_, total)| **total >= goal)
+      .map(|(day, _)| *day)
+      .collect();
+
+    Self {
+      current: current_streak(&days, now.date_naive()),
+      best: longest_streak(&days),
+      today: totals
+        .get(&now.date_naive())
+        .copied()
+        .unwrap_or_else(Duration::zero),
+      goal,
+    }
+  }
+
+  pub fn is_goal_met_today(&self) -> bool {
+    self.today >= self.goal
+  }
+
+  /// The streak ends at midnight unless the block runs a bit longer today
+  pub fn is_at_risk(&self) -> bool {
+    self.current > 0 && !self.is_goal_met_today()
+  }
+
+  pub fn badges(&self) -> impl Iterator<Item = &'static str> + '_ {
+    BADGES
+      .iter()
+      .filter(|(days, _)| self.best >= *days)
+      .map(|(_, name)| *name)
+  }
+}
+
+pub fn print_streak(block: &str, streak: &Streak) {
+  println!(
+    "{block}: {} day streak (best {} days), running at least {} a day",
+    streak.current,
+    streak.best,
+    format_duration(streak.goal)
+  );
+
+  if streak.is_goal_met_today() {
+    println!("Today: {} - goal reached", format_duration(streak.today));
+  } else if streak.is_at_risk() {
+    println!(
+      "Today: {} of {} - streak at risk, {} left to keep it",
+      format_duration(streak.today),
+      format_duration(streak.goal),
+      format_duration(streak.goal - streak.today)
+    );
+  } else {
+    println!(
+      "Today: {} of {}",
+      format_duration(streak.today),
+      format_duration(streak.goal)
+    );
+  }
+
+  let badges: Vec<&str> = streak.badges().collect();
+  if !badges.is_empty() {
+    println!("Badges: {}", badges.join(", "));
+  }
+}
+
+/// Runs the user's notification command through the shell. The command can read the
+/// block and streak from the CTK_STREAK_* environment variables.
+pub fn run_hook(command: &str, block: &str, streak: &Streak) -> io::Result<ExitStatus> {
+  let mut shell = if cfg!(windows) {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C");
+    shell
+  } else {
+    let mut shell = Command::new("sh");
+    shell.arg("-c");
+    shell
+  };
+
+  shell
+    .arg(command)
+    .env("CTK_STREAK_BLOCK", block)
+    .env("CTK_STREAK_DAYS", streak.current.to_string())
+    .env(
+      "CTK_STREAK_MINUTES_LEFT",
+      (streak.goal - streak.today)
+        .num_minutes()
+        .max(0)
+        .to_string(),
+    )
+    .status()
+}
+
+/// Like "45m", "2h" or "1h30m"
+pub fn format_duration(duration: Duration) -> String {
+  let minutes = duration.num_minutes();
+  match (minutes / 60, minutes % 60) {
+    (0, minutes) => format!("{minutes}m"),
+    (hours, 0) => format!("{hours}h"),
+    (hours, minutes) => format!("{hours}h{minutes}m"),
+  }
+}
@@ -1,22 +1,35 @@
-use crate::blocksettings::{AppString, ScheduleBlock, ScheduleTimeTuple};
-use crate::blocksettings::{BlockSettings, BreakMethod, LockMethod, RangeWindow, SchedType};
+use crate::autosave;
+use crate::browserhistory;
+use crate::categories;
+use crate::config::{self, Config};
+use crate::error::error;
+use ctk_common::blocksettings::{self, AppString, ScheduleBlock, ScheduleTimeTuple};
+use ctk_common::blocksettings::{BlockSettings, BreakMethod, LockMethod, RangeWindow, SchedType};
 use ctk_common::convert;
+use ctk_common::ctsettings::get_ct_settings;
+use ctk_common::fuzzy::{self, MatchString, Matcher};
 use crate::historydeque::HistoryDeque;
 use crate::loop_dialoguer::LoopDialogue;
-use crate::matchstring::MatchString;
+use crate::randomtext::{self, Friction};
+use crate::schedule;
+use crate::sidecar::{describe_times, ScheduleLabel, Sidecar};
+use crate::undostack::UndoStack;
+use crate::workspace::{Workspace, Workspaces};
 use chrono::{NaiveTime, Timelike};
 use dialoguer::{Confirm, Input, MultiSelect, Password, Select};
 use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
 use rand::Rng;
 use rayon::prelude::*;
-use std::collections::HashMap;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fmt::Display;
-use std::fs::File;
+use std::fs::{self, File};
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
 use std::time::Instant;
-use sublime_fuzzy::{FuzzySearch, Match, Scoring};
 use walkdir::WalkDir;
 
 const WIN10_APPS: [&str; 99] = [
@@ -131,6 +144,17 @@ const TIMES_OF_WEEK: [&str; 7] = [
   "Saturday",
 ];
 
+const SCHEDULE_ACTIONS: [&str; 6] = [
+  "Add schedule entries",
+  "List the schedule",
+  "Show the week",
+  "Edit an entry",
+  "Delete entries",
+  "Done",
+];
+
+const SCHEDULE_FIELDS: [&str; 4] = ["Day", "Start and end times", "Break", "Label"];
+
 const LOCK_OPTIONS: [&str; 5] = [
   "No Lock",
   "Random Text",
@@ -141,36 +165,457 @@ const LOCK_OPTIONS: [&str; 5] = [
 
 const ALLOWANCE_OPTIONS: [&str; 3] = ["No Breaks", "Allowance", "Pomodoro"];
 
-pub fn suggest() {
-  let mut list_of_blocks: HashMap<String, BlockSettings> = HashMap::new();
-
-  // Loop where we read user input until user does not want to read new blocks anymore
-  let mut continue_settings = true;
-  while continue_settings {
-    let block_name: String = Input::new()
-      .with_prompt("Enter a new Cold Turkey block name")
-      .loop_interact();
+// Ctrl+C stops a running search instead of quitting, so a search of the wrong folder
+// does not lose the blocks entered so far
+static SEARCHING: AtomicBool = AtomicBool::new(false);
+static SEARCH_CANCELLED: AtomicBool = AtomicBool::new(false);
+static CTRLC_HANDLER: Once = Once::new();
+
+// Given to `ls` or `search` in the app shell to look inside symlinks and junctions
+const FOLLOW_LINKS_FLAGS: [&str; 2] = ["-L", "--follow-links"];
+
+// Given to `ls`, `add` or `search` to list the paths a scan could not read, not just count them
+const VERBOSE_ERRORS_FLAG: &str = "--verbose-errors";
+
+const DIR_HISTORY_LEN: usize = 20;
+
+const BLOCKS_SHELL_HELP: [&str; 10] = [
+  "new [name]          make a new block",
+  "rename <old> <new>  rename a block",
+  "copy <block> <new>  make a copy of a block under a new name, to change into a variant",
+  "load <file.ctbbl>   bring in the blocks of a .ctbbl file, to add to and save again",
+  "remove <block>      remove a block",
+  "undo                undo the last change to the blocks",
+  "redo                redo the last change undone",
+  "list                list the blocks so far",
+  "help                show these commands",
+  "done, quit, q       finish, and choose whether to save the blocks",
+];
 
-    if let Some(block_settings) = block_settings_from_stdin() {
-      list_of_blocks.insert(block_name, block_settings);
+pub fn suggest(
+  ask_breaks: bool,
+  target_version: Option<(u32, u32)>,
+  name: Option<&str>,
+  resume: Option<&str>,
+  output_dir: Option<&Path>,
+  from_history: bool,
+) {
+  // Blocks saved by earlier runs of a session are kept as they were written
+  let (workspace, saved_blocks) = match (name, resume) {
+    (_, Some(resume)) => match resume_workspace(resume, output_dir) {
+      Some((workspace, saved_blocks)) => (Some(workspace), saved_blocks),
+      None => return,
+    },
+    (Some(name), None) => match new_workspace(name, output_dir) {
+      Some((workspace, saved_blocks)) => (Some(workspace), saved_blocks),
+      None => return,
+    },
+    (None, None) => (None, Map::new()),
+  };
+  if !saved_blocks.is_empty() {
+    eprintln!("Blocks in this session so far:");
+    for block_name in saved_blocks.keys() {
+      eprintln!("  {block_name}");
     }
-
-    continue_settings = Confirm::new()
-      .with_prompt("Do you want to add new blocks?")
-      .loop_interact();
   }
 
+  let visited = if from_history {
+    browserhistory::unblocked_favorites(get_ct_settings().as_ref(), browserhistory::CANDIDATES)
+  } else {
+    Vec::new()
+  };
+
+  let mut list_of_blocks = restore_autosave().unwrap_or_default();
+  blocks_shell(&mut list_of_blocks, ask_breaks, &visited);
+
   // Save to .ctbbl JSON file
   let save_to_file = Confirm::new()
     .with_prompt("Do you want to save these settings in a .ctbbl file?")
     .loop_interact();
 
   if save_to_file {
-    make_ctbbl_json_file(&list_of_blocks);
+    expand_categories(&mut list_of_blocks);
+    match &workspace {
+      Some(workspace) => {
+        save_to_workspace(workspace, saved_blocks, &list_of_blocks, target_version)
+      }
+      None => make_ctbbl_json_file(&list_of_blocks, target_version, output_dir),
+    }
+    save_schedule_labels(&list_of_blocks);
+    randomtext::record_locks(&list_of_blocks);
+  }
+  autosave::clear();
+}
+
+/// Offers the blocks of a `ctk suggest` that did not finish, like when the terminal was closed
+fn restore_autosave() -> Option<HashMap<String, BlockSettings>> {
+  let (saved_at, blocks) = autosave::load()?;
+  let mut block_names: Vec<&String> = blocks.keys().collect();
+  block_names.sort_unstable();
+  eprintln!(
+    "A ctk suggest from {} did not finish, with these blocks:",
+    saved_at.format("%H:%M %B %d %Y")
+  );
+  for block_name in block_names {
+    eprintln!("  {block_name}");
+  }
+  let restore = Confirm::new()
+    .with_prompt("Do you want to carry on with them?")
+    .loop_interact();
+  if restore {
+    Some(blocks)
+  } else {
+    autosave::clear();
+    None
+  }
+}
+
+/// Reads commands that make, load and list blocks until the user is done. `visited` are the
+/// websites offered for new blocks, from the browser history
+fn blocks_shell(
+  list_of_blocks: &mut HashMap<String, BlockSettings>,
+  ask_breaks: bool,
+  visited: &[(String, u64)],
+) {
+  let mut hist = HistoryDeque::<String>::new();
+  let mut history = UndoStack::new();
+  eprintln!("Type `new` to make a block, `load <file.ctbbl>` to bring in blocks saved before, or `help` for every command.");
+
+  loop {
+    let cmd_result: Result<String, std::io::Error> = Input::new()
+      .with_prompt("blocks>")
+      .history_with(&mut hist)
+      .interact_text();
+    let Ok(cmd) = cmd_result else {
+      eprintln!();
+      continue;
+    };
+    let Some(args) = shlex::split(&cmd) else {
+      eprintln!("Cannot parse the command - please try again.");
+      continue;
+    };
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+      ["new", name @ ..] => {
+        let block_name = if name.is_empty() {
+          Input::new()
+            .with_prompt("Enter a new Cold Turkey block name")
+            .loop_interact()
+        } else {
+          name.join(" ")
+        };
+        if let Some(block_settings) = block_settings_from_stdin(ask_breaks, visited) {
+          history.record(list_of_blocks.clone());
+          list_of_blocks.insert(block_name, block_settings);
+        }
+      }
+      ["rename", old_name, new_name] if can_name(list_of_blocks, old_name, new_name) => {
+        history.record(list_of_blocks.clone());
+        // Why unwrap? can_name checked that the block is there
+        let block_settings = list_of_blocks.remove(*old_name).unwrap();
+        list_of_blocks.insert(new_name.to_string(), block_settings);
+        eprintln!("Renamed block {old_name} to {new_name}");
+      }
+      ["rename", _, _] => {}
+      ["rename", ..] => {
+        eprintln!("Give the block and its new name, like `rename Games \"Games on weekdays\"`.")
+      }
+      ["copy", source, dest] if can_name(list_of_blocks, source, dest) => {
+        history.record(list_of_blocks.clone());
+        let block_settings = list_of_blocks[*source].clone();
+        list_of_blocks.insert(dest.to_string(), block_settings);
+        eprintln!("Copied block {source} to {dest}");
+      }
+      ["copy", _, _] => {}
+      ["copy", ..] => eprintln!(
+        "Give the block and the name of the copy, like `copy Games \"Games on weekends\"`."
+      ),
+      ["remove", block_name] => {
+        let before = list_of_blocks.clone();
+        if list_of_blocks.remove(*block_name).is_some() {
+          history.record(before);
+          eprintln!("Removed block {block_name}. `undo` brings it back");
+        } else {
+          eprintln!("There is no block {block_name} yet. `list` shows the blocks so far.");
+        }
+      }
+      ["remove", ..] => eprintln!("Give the one block to remove, like `remove Games`."),
+      ["undo"] => {
+        if !history.undo(list_of_blocks) {
+          eprintln!("There is nothing to undo.");
+        }
+      }
+      ["redo"] => {
+        if !history.redo(list_of_blocks) {
+          eprintln!("There is nothing to redo.");
+        }
+      }
+      ["load", file] => {
+        let before = list_of_blocks.clone();
+        if load_blocks(Path::new(file), list_of_blocks) {
+          history.record(before);
+        }
+      }
+      ["load", ..] => eprintln!("Give the one .ctbbl file to load, like `load ctk_starter.ctbbl`."),
+      ["list"] => {
+        if list_of_blocks.is_empty() {
+          eprintln!("There are no blocks yet.");
+        }
+        let mut block_names: Vec<&String> = list_of_blocks.keys().collect();
+        block_names.sort_unstable();
+        for block_name in block_names {
+          let block_settings = &list_of_blocks[block_name];
+          eprintln!(
+            "  {block_name}: {} websites, {} apps, {} schedule entries",
+            block_settings.web.len(),
+            block_settings.apps.len(),
+            block_settings.schedule.len()
+          );
+        }
+      }
+      ["help"] => {
+        for line in BLOCKS_SHELL_HELP {
+          eprintln!("  {line}");
+        }
+      }
+      ["done" | "quit" | "q"] => break,
+      [] => {}
+      [other, ..] => eprintln!("There is no command {other}. Type `help` for the commands."),
+    }
+
+    let changes_blocks = matches!(
+      args.first(),
+      Some(&("new" | "rename" | "copy" | "remove" | "load" | "undo" | "redo"))
+    );
+    if changes_blocks {
+      if let Err(err) = autosave::save(list_of_blocks) {
+        eprintln!("WARNING: Cannot autosave the blocks: {err}");
+      }
+    }
+  }
+}
+
+/// Whether the block `from` can be renamed or copied to `to`, saying why not if it cannot
+fn can_name(list_of_blocks: &HashMap<String, BlockSettings>, from: &str, to: &str) -> bool {
+  if !list_of_blocks.contains_key(from) {
+    eprintln!("There is no block {from} yet. `list` shows the blocks so far.");
+    false
+  } else if list_of_blocks.contains_key(to) {
+    eprintln!("There is already a block {to}.");
+    false
+  } else {
+    true
+  }
+}
+
+/// Brings the blocks of a .ctbbl file in, with the schedule labels ctk kept for them, so
+/// they can be added to and saved again. Blocks of the same name are replaced. Gives whether
+/// the file could be loaded
+fn load_blocks(path: &Path, list_of_blocks: &mut HashMap<String, BlockSettings>) -> bool {
+  let loaded = fs::read_to_string(path)
+    .map_err(|err| err.to_string())
+    .and_then(|contents| blocksettings::from_ctbbl(&contents).map_err(|err| err.to_string()));
+  let blocks = match loaded {
+    Ok(blocks) => blocks,
+    Err(why) => {
+      eprintln!("Cannot load {}: {why}", path.display());
+      return false;
+    }
+  };
+
+  let sidecar = Sidecar::load();
+  for (block_name, mut block_settings) in blocks {
+    label_schedule(&sidecar, &block_name, &mut block_settings.schedule);
+    if list_of_blocks
+      .insert(block_name.clone(), block_settings)
+      .is_some()
+    {
+      eprintln!("Loads block {block_name}, replacing the one made earlier");
+    } else {
+      eprintln!("Loads block {block_name}");
+    }
+  }
+  true
+}
+
+/// Starts a named session, refusing to reuse a name so an old session is not overwritten
+fn new_workspace(
+  name: &str,
+  output_dir: Option<&Path>,
+) -> Option<(Workspace, Map<String, Value>)> {
+  let mut workspaces = Workspaces::load();
+  if workspaces.sessions.contains_key(name) {
+    error!("There is already a session called {name}. Reopen it with `ctk suggest --resume {name}`.");
+    return None;
+  }
+  let workspace = Workspace::new(name, output_dir.unwrap_or(Path::new(".")));
+  // A .ctbbl file that is already there is added to rather than overwritten
+  let saved_blocks = read_saved_blocks(&workspace.ctbbl_path())?;
+
+  workspaces
+    .sessions
+    .insert(name.to_string(), workspace.clone());
+  // A session that cannot be remembered can still be saved, just not resumed
+  if let Err(err) = workspaces.save() {
+    eprintln!("WARNING: ctk cannot remember the session {name}, so it cannot be resumed: {err}");
+  }
+  eprintln!("Blocks in this session are saved to {}", workspace.ctbbl_path().display());
+  Some((workspace, saved_blocks))
+}
+
+/// Reopens a named session, moving it to `output_dir` if one is given
+fn resume_workspace(
+  name: &str,
+  output_dir: Option<&Path>,
+) -> Option<(Workspace, Map<String, Value>)> {
+  let mut workspaces = Workspaces::load();
+  let Some(workspace) = workspaces.sessions.get_mut(name) else {
+    error!("There is no session called {name}.");
+    if workspaces.sessions.is_empty() {
+      eprintln!("Start one with `ctk suggest --name <NAME>`.");
+    } else {
+      let names: Vec<&str> = workspaces.sessions.keys().map(String::as_str).collect();
+      eprintln!("The sessions are: {}", names.join(", "));
+    }
+    return None;
+  };
+  let saved_blocks = read_saved_blocks(&workspace.ctbbl_path())?;
+
+  if let Some(output_dir) = output_dir {
+    // The blocks saved so far come along, so the session keeps all of them in one file
+    *workspace = Workspace::new(name, output_dir);
+    let workspace = workspace.clone();
+    if let Err(err) = workspaces.save() {
+      eprintln!("WARNING: ctk cannot remember that {name} moved: {err}");
+    }
+    eprintln!("Blocks in this session are now saved to {}", workspace.ctbbl_path().display());
+    return Some((workspace, saved_blocks));
   }
+  Some((workspace.clone(), saved_blocks))
 }
 
-fn make_ctbbl_json_file(list_of_blocks: &HashMap<String, BlockSettings>) {
+/// The blocks in a session's .ctbbl file, or none if it was not saved yet
+fn read_saved_blocks(path: &Path) -> Option<Map<String, Value>> {
+  let contents = match fs::read_to_string(path) {
+    Ok(contents) => contents,
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Some(Map::new()),
+    Err(err) => {
+      error!("Cannot read {}: {err}", path.display());
+      return None;
+    }
+  };
+  match serde_json::from_str(&contents) {
+    Ok(Value::Object(blocks)) => Some(blocks),
+    _ => {
+      error!("{} is not a .ctbbl file of blocks", path.display());
+      None
+    }
+  }
+}
+
+/// Adds the new blocks to the ones the session already has, replacing any of the same name
+fn save_to_workspace(
+  workspace: &Workspace,
+  mut saved_blocks: Map<String, Value>,
+  list_of_blocks: &HashMap<String, BlockSettings>,
+  target_version: Option<(u32, u32)>,
+) {
+  // Why unwrap? BlockSettings always serializes
+  let mut json = serde_json::to_value(list_of_blocks).unwrap();
+  if let Some(version) = target_version {
+    for warning in blocksettings::adapt_to_version(&mut json, version) {
+      eprintln!("WARNING: {warning}");
+    }
+  }
+  if let Value::Object(new_blocks) = json {
+    for (block_name, block) in new_blocks {
+      if saved_blocks.insert(block_name.clone(), block).is_some() {
+        eprintln!("Replaces the earlier block {block_name} in this session");
+      }
+    }
+  }
+  write_blocks_json(&Value::Object(saved_blocks), &workspace.ctbbl_path());
+}
+
+/// Expands each `@category:<name>` in the blocklists to the websites the category has now,
+/// remembering in the sidecar where they came from. Categories that do not exist are dropped
+fn expand_categories(list_of_blocks: &mut HashMap<String, BlockSettings>) {
+  let mut sidecar = Sidecar::load();
+  let mut expanded_any = false;
+  for (block_name, block_settings) in list_of_blocks.iter_mut() {
+    let mut entries = Vec::new();
+    for entry in &block_settings.web {
+      match categories::expand(std::slice::from_ref(entry)) {
+        Ok((sites, expansions)) => {
+          entries.extend(sites);
+          for expansion in expansions {
+            sidecar.record_category(block_name, &expansion.category, &expansion.sites);
+            expanded_any = true;
+          }
+        }
+        Err(err) => eprintln!("WARNING: {err}, so {entry} is left out of block {block_name}"),
+      }
+    }
+    let mut seen = HashSet::new();
+    entries.retain(|entry| seen.insert(entry.clone()));
+    block_settings.web = entries;
+  }
+  if expanded_any {
+    if let Err(err) = sidecar.save() {
+      eprintln!("WARNING: ctk cannot remember which categories the websites came from: {err}");
+    }
+  }
+}
+
+/// Gives the block's schedule entries back the labels ctk saved for them
+pub fn label_schedule(sidecar: &Sidecar, block_name: &str, schedule: &mut [ScheduleBlock]) {
+  let labels = sidecar.schedule_labels(block_name);
+  for entry in schedule {
+    let (start, end) = (entry.start_time.to_string(), entry.end_time.to_string());
+    entry.label = labels
+      .iter()
+      .find(|label| label.start == start && label.end == end)
+      .map(|label| label.label.clone());
+  }
+}
+
+pub fn save_schedule_labels(list_of_blocks: &HashMap<String, BlockSettings>) {
+  let mut sidecar = Sidecar::load();
+  for (block_name, block_settings) in list_of_blocks {
+    let labels: Vec<ScheduleLabel> = block_settings
+      .schedule
+      .iter()
+      .filter_map(|entry| {
+        entry.label.as_ref().map(|label| ScheduleLabel {
+          start: entry.start_time.to_string(),
+          end: entry.end_time.to_string(),
+          label: label.clone(),
+        })
+      })
+      .collect();
+    if !labels.is_empty() {
+      sidecar.set_schedule_labels(block_name, labels);
+    }
+  }
+  if let Err(err) = sidecar.save() {
+    eprintln!("WARNING: ctk cannot save the schedule labels: {err}");
+  }
+}
+
+fn make_ctbbl_json_file(
+  list_of_blocks: &HashMap<String, BlockSettings>,
+  target_version: Option<(u32, u32)>,
+  output_dir: Option<&Path>,
+) {
+  // Why unwrap? BlockSettings always serializes
+  let mut json = serde_json::to_value(list_of_blocks).unwrap();
+  if let Some(version) = target_version {
+    for warning in blocksettings::adapt_to_version(&mut json, version) {
+      eprintln!("WARNING: {warning}");
+    }
+  }
+
   let file_name: String = Input::new()
     .with_prompt("Enter a new file name [empty string to create random name]")
     .allow_empty(true)
@@ -183,13 +628,26 @@ fn make_ctbbl_json_file(list_of_blocks: &HashMap<String, BlockSettings>) {
     format!("{}.ctbbl", file_name)
   };
 
-  let path = Path::new(&final_file);
+  let path = match output_dir {
+    Some(output_dir) => output_dir.join(&final_file),
+    None => PathBuf::from(&final_file),
+  };
+  write_blocks_json(&json, &path);
+}
+
+fn write_blocks_json(json: &Value, path: &Path) {
   let display = path.display();
+  if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+    if let Err(why) = fs::create_dir_all(dir) {
+      eprintln!("Could not create {}: {}", dir.display(), why);
+      return;
+    }
+  }
 
-  match File::create(&path) {
-    Ok(file) => match serde_json::to_writer_pretty(file, &list_of_blocks) {
+  match File::create(path) {
+    Ok(file) => match serde_json::to_writer_pretty(file, json) {
       Ok(_) => {
-        eprintln!("Successfully saved to {} in current directory", display);
+        eprintln!("Successfully saved to {}", display);
       }
       Err(why) => eprintln!("Could not write to {}: {}", display, why),
     },
@@ -199,12 +657,24 @@ fn make_ctbbl_json_file(list_of_blocks: &HashMap<String, BlockSettings>) {
   }
 }
 
-fn best_match(query: &str, target: &str) -> Option<Match> {
-  let scoring = Scoring::new(50, 0, 20, 0);
-  FuzzySearch::new(query, target)
-    .score_with(&scoring)
-    .case_insensitive()
-    .best_match()
+/// Lets the user pick which of the websites visited most to block
+pub fn sites_from_history(visited: &[(String, u64)]) -> Vec<String> {
+  if visited.is_empty() {
+    return Vec::new();
+  }
+  let items: Vec<String> = visited
+    .iter()
+    .map(|(site, times)| format!("{site} ({times} visits)"))
+    .collect();
+  MultiSelect::new()
+    .with_prompt(
+      "Which of the websites you visit most do you want to block? [press space to select]",
+    )
+    .items(&items)
+    .loop_interact()
+    .into_iter()
+    .map(|i| visited[i].0.clone())
+    .collect()
 }
 
 fn read_string_until_empty<S: Display>(prompt: S) -> Vec<String> {
@@ -281,20 +751,19 @@ fn read_time_from_stdin<S: Into<String>>(prompt: S) -> NaiveTime {
   convert::str_to_time(&time_string).unwrap()
 }
 
+/// Reads a time for a schedule, which Cold Turkey only allows in steps of 5 minutes
+pub fn parse_schedule_time(time_string: &str) -> Result<NaiveTime, &'static str> {
+  match convert::str_to_time(time_string) {
+    Ok(time) if time.minute() % 5 == 0 => Ok(time),
+    Ok(_) => Err("The minute time must be in multiples of 5"),
+    Err(_) => Err("Invalid time format for string"),
+  }
+}
+
 fn read_time_with_divisible_by_5_check<S: Into<String>>(prompt: S) -> NaiveTime {
   let time_string = Input::new()
     .with_prompt(prompt)
-    .validate_with(|time_string: &String| {
-      if let Ok(time) = convert::str_to_time(time_string) {
-        if time.minute() % 5 == 0 {
-          Ok(())
-        } else {
-          Err("The minute time must be in multiples of 5")
-        }
-      } else {
-        Err("Invalid time format for string")
-      }
-    })
+    .validate_with(|time_string: &String| parse_schedule_time(time_string).map(|_| ()))
     .loop_interact();
 
   // We can safely unwrap because convert::str_to_time is already checked to be ok
@@ -302,7 +771,7 @@ fn read_time_with_divisible_by_5_check<S: Into<String>>(prompt: S) -> NaiveTime
   convert::str_to_time(&time_string).unwrap()
 }
 
-fn block_settings_from_stdin() -> Option<BlockSettings> {
+fn block_settings_from_stdin(ask_breaks: bool, visited: &[(String, u64)]) -> Option<BlockSettings> {
   let mut block_settings = BlockSettings::new();
 
   // Ask the user to select a lock option
@@ -321,16 +790,7 @@ fn block_settings_from_stdin() -> Option<BlockSettings> {
 
   match block_settings.lock {
     LockMethod::RandomText => {
-      block_settings.random_text_length = Input::new()
-        .with_prompt("Enter a random string length")
-        .validate_with(|i: &u16| -> Result<(), &str> {
-          if (0..=999).contains(i) {
-            Ok(())
-          } else {
-            Err("Random string length must be between 0 and 999 inclusive.")
-          }
-        })
-        .loop_interact();
+      block_settings.random_text_length = random_text_length_from_stdin();
     }
     LockMethod::Window => {
       block_settings.window = range_window_from_stdin();
@@ -357,7 +817,15 @@ fn block_settings_from_stdin() -> Option<BlockSettings> {
     .loop_interact();
 
   if website_block {
-    block_settings.web = read_string_until_empty("Add a new website");
+    block_settings.web = sites_from_history(visited);
+    eprintln!(
+      "Add a whole category of websites as {}<name>, like {}social",
+      categories::PREFIX,
+      categories::PREFIX
+    );
+    block_settings
+      .web
+      .extend(read_string_until_empty("Add a new website"));
   }
 
   // Ask the user if they want to add websites to the list of exceptions or not
@@ -417,7 +885,7 @@ fn block_settings_from_stdin() -> Option<BlockSettings> {
 
   if schedule_block {
     block_settings.sched_type = SchedType::Scheduled;
-    block_settings.schedule = read_schedule_from_stdin();
+    block_settings.schedule = read_schedule_from_stdin(&block_settings.break_type, ask_breaks);
 
     let lock_by_sched = Confirm::new()
       .with_prompt("Do you want to lock this block during scheduled blocks?")
@@ -433,6 +901,39 @@ fn block_settings_from_stdin() -> Option<BlockSettings> {
   Some(block_settings)
 }
 
+/// Asks for the length of a random text lock, or lets ctk pick one for how much friction
+/// the user wants, showing how long it takes to type
+fn random_text_length_from_stdin() -> u16 {
+  let pick_for_me = Confirm::new()
+    .with_prompt("Do you want ctk to pick a random string length for you?")
+    .loop_interact();
+
+  if !pick_for_me {
+    return Input::new()
+      .with_prompt("Enter a random string length")
+      .validate_with(|i: &u16| -> Result<(), &str> {
+        if (0..=999).contains(i) {
+          Ok(())
+        } else {
+          Err("Random string length must be between 0 and 999 inclusive.")
+        }
+      })
+      .loop_interact();
+  }
+
+  let friction = Select::new()
+    .with_prompt("How hard should it be to unlock?")
+    .items(&Friction::ALL)
+    .default(1)
+    .loop_interact();
+  let length = Friction::ALL[friction].random_length();
+  println!("ctk picked {length} characters. Typing them takes");
+  for estimate in randomtext::typing_estimates(length) {
+    println!("{estimate}");
+  }
+  length
+}
+
 fn range_window_from_stdin() -> RangeWindow {
   let start_time: NaiveTime = read_time_from_stdin("Enter start time");
   let end_time: NaiveTime = read_time_from_stdin("Enter end time");
@@ -459,6 +960,8 @@ fn add_apps_and_folders_from_filesystem() -> Option<Vec<AppString>> {
   };
 
   let mut hist = HistoryDeque::<String>::new();
+  // Directories visited before the current one, oldest first, for `cd -` and `dirs`
+  let mut dir_history: Vec<PathBuf> = Vec::new();
 
   loop {
     if let Ok(current_dir) = env::current_dir() {
@@ -478,19 +981,83 @@ fn add_apps_and_folders_from_filesystem() -> Option<Vec<AppString>> {
           }
         };
 
+        let follow_links = shlex_parse.iter().any(|arg| FOLLOW_LINKS_FLAGS.contains(&arg.as_str()));
+        let verbose_errors = shlex_parse.iter().any(|arg| arg == VERBOSE_ERRORS_FLAG);
+        let shlex_parse: Vec<String> = shlex_parse
+          .into_iter()
+          .filter(|arg| !FOLLOW_LINKS_FLAGS.contains(&arg.as_str()) && arg != VERBOSE_ERRORS_FLAG)
+          .collect();
+
         if &shlex_parse[0] == "cd" {
-          let change_dir_result = if shlex_parse.len() == 2 {
-            let path = PathBuf::from(&shlex_parse[1]);
-            env::set_current_dir(path)
-          } else {
-            env::set_current_dir(".")
+          let target = match shlex_parse.get(1).map(String::as_str) {
+            None => Some(PathBuf::from(".")),
+            Some("-") => {
+              let previous = dir_history.last().cloned();
+              if previous.is_none() {
+                eprintln!("There is no previous directory yet.");
+              }
+              previous
+            }
+            Some(arg) => match arg.strip_prefix('@') {
+              Some(name) => {
+                let bookmark = Config::load()
+                  .bookmarks()
+                  .find(|(bookmark, _)| *bookmark == name)
+                  .map(|(_, path)| PathBuf::from(path));
+                if bookmark.is_none() {
+                  eprintln!("There is no bookmark called {name}. Add one with `bookmark add {name}`.");
+                }
+                bookmark
+              }
+              None => Some(PathBuf::from(arg)),
+            },
           };
 
-          if let Err(err) = change_dir_result {
-            eprintln!("{err}");
+          if let Some(target) = target {
+            match env::set_current_dir(target) {
+              Ok(()) => {
+                dir_history.push(current_dir.clone());
+                if dir_history.len() > DIR_HISTORY_LEN {
+                  dir_history.remove(0);
+                }
+              }
+              Err(err) => eprintln!("{err}"),
+            }
+          }
+        } else if &shlex_parse[0] == "dirs" {
+          eprintln!(" 0  {}", current_dir.display());
+          for (i, dir) in dir_history.iter().rev().enumerate() {
+            eprintln!("{:>2}  {}", i + 1, dir.display());
+          }
+        } else if &shlex_parse[0] == "bookmark" {
+          match (shlex_parse.get(1).map(String::as_str), shlex_parse.get(2)) {
+            (Some("add"), Some(name)) => {
+              let key = format!("bookmarks.{name}");
+              match config::set_user_value(&key, &current_dir.to_string_lossy()) {
+                Ok(_) => eprintln!("Bookmarked {} as @{name}", current_dir.display()),
+                Err(why) => eprintln!("Cannot add the bookmark: {why}"),
+              }
+            }
+            (None | Some("list"), None) => {
+              let config = Config::load();
+              let mut bookmarks = config.bookmarks().peekable();
+              if bookmarks.peek().is_none() {
+                eprintln!("No bookmarks yet. Add the current directory with `bookmark add <name>`.");
+              }
+              for (name, path) in bookmarks {
+                eprintln!("@{name}  {path}");
+              }
+            }
+            _ => eprintln!("Use `bookmark add <name>` or `bookmark list`."),
           }
         } else if &shlex_parse[0] == "ls" {
-          let apps_list: Vec<String> = list_paths_in_current_directory(&current_dir);
+          let Some(patterns) = parse_globs(&shlex_parse[1..]) else {
+            continue;
+          };
+          let apps_list: Vec<String> = list_paths_in_current_directory(&current_dir, follow_links, verbose_errors)
+            .into_iter()
+            .filter(|path| patterns.is_empty() || matches_any_glob(path, &patterns))
+            .collect();
 
           if !apps_list.is_empty() {
             let idxs = MultiSelect::new()
@@ -500,21 +1067,37 @@ fn add_apps_and_folders_from_filesystem() -> Option<Vec<AppString>> {
               .items(&apps_list)
               .loop_interact();
 
-            for i in idxs {
-              let s = apps_list[i].replace('\\', "/");
-              let path = PathBuf::from(&s);
-              if path.is_dir() {
-                apps.push(AppString::Folder(s));
-              } else if path.is_file() {
-                apps.push(AppString::File(s));
-              }
-            }
+            let chosen = idxs.into_iter().filter_map(|i| app_from_path(&apps_list[i]));
+            select_apps(&mut apps, chosen);
+          }
+        } else if &shlex_parse[0] == "add" {
+          let Some(patterns) = parse_globs(&shlex_parse[1..]) else {
+            continue;
+          };
+          if patterns.is_empty() {
+            eprintln!("Give a name or pattern to add, like `add *.exe`.");
+            continue;
+          }
+
+          // The current directory itself is left out, so `add *` only adds what is in it
+          let added: Vec<AppString> = list_paths_in_current_directory(&current_dir, follow_links, verbose_errors)
+            .into_iter()
+            .filter(|path| Path::new(path) != current_dir && matches_any_glob(path, &patterns))
+            .filter_map(|path| app_from_path(&path))
+            .collect();
+
+          if added.is_empty() {
+            eprintln!("Nothing here matches.");
+          }
+          for app in select_apps(&mut apps, added) {
+            eprintln!("Added {}", app_path(&apps[app]));
           }
         } else if &shlex_parse[0] == "search" {
           if shlex_parse.len() == 2 {
             let keyword = &shlex_parse[1];
 
-            let matchstring_vec = fuzzy_search_paths_by_keyword(keyword, &current_dir);
+            let matchstring_vec =
+              fuzzy_search_paths_by_keyword(keyword, &current_dir, follow_links, verbose_errors);
 
             if !matchstring_vec.is_empty() {
               let choose_exes = MultiSelect::new()
@@ -522,17 +1105,38 @@ fn add_apps_and_folders_from_filesystem() -> Option<Vec<AppString>> {
                 .items(&matchstring_vec)
                 .loop_interact();
 
-              for i in choose_exes {
-                let s = matchstring_vec[i].string.replace('\\', "/");
-                let path = PathBuf::from(&s);
-                if path.is_dir() {
-                  apps.push(AppString::Folder(s));
-                } else if path.is_file() {
-                  apps.push(AppString::File(s));
-                }
-              }
+              let chosen = choose_exes
+                .into_iter()
+                .filter_map(|i| app_from_path(&matchstring_vec[i].string));
+              select_apps(&mut apps, chosen);
             }
           }
+        } else if &shlex_parse[0] == "selected" {
+          if apps.is_empty() {
+            eprintln!("Nothing is selected yet.");
+          }
+          for (i, app) in apps.iter().enumerate() {
+            eprintln!("{:>3}  {}", i + 1, app_path(app));
+          }
+        } else if &shlex_parse[0] == "deselect" {
+          let Some(patterns) = parse_globs(&shlex_parse[1..]) else {
+            continue;
+          };
+          if patterns.is_empty() {
+            eprintln!("Give the numbers from `selected` or patterns to deselect, like `deselect 2` or `deselect *.exe`.");
+            continue;
+          }
+
+          let mut number = 0;
+          apps.retain(|app| {
+            number += 1;
+            let is_picked = shlex_parse[1..].contains(&number.to_string())
+              || matches_any_glob(app_path(app), &patterns);
+            if is_picked {
+              eprintln!("Deselected {}", app_path(app));
+            }
+            !is_picked
+          });
         } else if &shlex_parse[0] == "done" || &shlex_parse[0] == "quit" || &shlex_parse[0] == "q" {
           break;
         }
@@ -552,100 +1156,437 @@ fn add_apps_and_folders_from_filesystem() -> Option<Vec<AppString>> {
   Some(apps)
 }
 
-fn list_paths_in_current_directory(current_dir: &PathBuf) -> Vec<String> {
+fn list_paths_in_current_directory(
+  current_dir: &PathBuf,
+  follow_links: bool,
+  verbose_errors: bool,
+) -> Vec<String> {
+  let mut skipped = Vec::new();
   // min_depth(0) is here so if we call ls, we can actually see the current directory and choose it
   // max_depth(1) is here because we only list immediate stuff in the directory
-  WalkDir::new(current_dir)
+  let paths = WalkDir::new(current_dir)
     .min_depth(0)
     .max_depth(1)
+    .follow_links(follow_links)
     .into_iter()
-    .filter_map(std::result::Result::ok)
+    .filter_map(|entry| entry.map_err(|err| skipped.push(err)).ok())
+    .filter(|entry| is_scanned(entry, follow_links))
     .map(walkdir::DirEntry::into_path)
     .filter(|path| path.extension().unwrap_or_default() == "exe" || path.is_dir())
     .filter_map(|path| path.into_os_string().into_string().ok())
+    .collect();
+
+  report_skipped(&skipped, verbose_errors);
+  dedup_by_canonical_path(paths, |path| path)
+}
+
+/// Says how many entries a scan could not read, like folders only an administrator may
+/// open, so a missing executable is not a mystery
+fn report_skipped(skipped: &[walkdir::Error], verbose_errors: bool) {
+  if skipped.is_empty() {
+    return;
+  }
+  let entries = if skipped.len() == 1 { "entry" } else { "entries" };
+  if verbose_errors {
+    eprintln!("Skipped {} inaccessible {entries}:", skipped.len());
+    for err in skipped {
+      eprintln!("  {err}");
+    }
+  } else {
+    eprintln!(
+      "Skipped {} inaccessible {entries}, add {VERBOSE_ERRORS_FLAG} to list them",
+      skipped.len()
+    );
+  }
+}
+
+/// An app entry for an executable or folder, or None if the path is neither
+fn app_from_path(path: &str) -> Option<AppString> {
+  let s = path.replace('\\', "/");
+  let path = PathBuf::from(&s);
+  if path.is_dir() {
+    Some(AppString::Folder(s))
+  } else if path.is_file() {
+    Some(AppString::File(s))
+  } else {
+    None
+  }
+}
+
+/// Adds the apps that are not selected yet, returning the indices of the ones added
+fn select_apps(
+  apps: &mut Vec<AppString>,
+  chosen: impl IntoIterator<Item = AppString>,
+) -> Vec<usize> {
+  let mut added = Vec::new();
+  for app in chosen {
+    if apps.iter().any(|selected| app_path(selected) == app_path(&app)) {
+      eprintln!("{} is already selected", app_path(&app));
+    } else {
+      added.push(apps.len());
+      apps.push(app);
+    }
+  }
+  added
+}
+
+fn app_path(app: &AppString) -> &str {
+  match app {
+    AppString::File(path) | AppString::Folder(path) => path,
+    AppString::Win10(name) | AppString::Title(name) => name,
+  }
+}
+
+/// Parses glob patterns like *.exe, or returns None after saying which one is invalid
+pub fn parse_globs(args: &[String]) -> Option<Vec<glob::Pattern>> {
+  args
+    .iter()
+    .map(|arg| match glob::Pattern::new(arg) {
+      Ok(pattern) => Some(pattern),
+      Err(err) => {
+        eprintln!("{arg} is not a valid pattern: {err}");
+        None
+      }
+    })
+    .collect()
+}
+
+/// Whether the file name of the path matches any of the patterns, ignoring case like Windows does
+pub fn matches_any_glob(path: &str, patterns: &[glob::Pattern]) -> bool {
+  let options = glob::MatchOptions {
+    case_sensitive: false,
+    ..glob::MatchOptions::new()
+  };
+  Path::new(path)
+    .file_name()
+    .and_then(|name| name.to_str())
+    .is_some_and(|name| patterns.iter().any(|pattern| pattern.matches_with(name, options)))
+}
+
+/// Whether a scan should keep an entry. Symlinks are skipped unless followed, which also
+/// skips NTFS junctions since Windows reports them as symlinks too.
+fn is_scanned(entry: &walkdir::DirEntry, follow_links: bool) -> bool {
+  // The folder being scanned is always kept, even if it was reached through a link
+  follow_links || entry.depth() == 0 || !entry.path_is_symlink()
+}
+
+/// Keeps only the first item for each file, since links can make one file show up under
+/// several paths
+fn dedup_by_canonical_path<T>(items: Vec<T>, path_of: impl Fn(&T) -> &str) -> Vec<T> {
+  let mut seen = HashSet::new();
+  items
+    .into_iter()
+    .filter(|item| {
+      let path = path_of(item);
+      seen.insert(fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path)))
+    })
     .collect()
 }
 
-fn fuzzy_search_paths_by_keyword(keyword: &str, current_dir: &PathBuf) -> Vec<MatchString> {
+fn install_ctrlc_handler() {
+  CTRLC_HANDLER.call_once(|| {
+    let result = ctrlc::set_handler(|| {
+      if SEARCHING.load(Ordering::SeqCst) {
+        SEARCH_CANCELLED.store(true, Ordering::SeqCst);
+      } else {
+        // Outside a search, Ctrl+C quits like it normally does
+        std::process::exit(130);
+      }
+    });
+
+    if let Err(err) = result {
+      eprintln!("WARNING: Searches cannot be stopped with Ctrl+C: {err}");
+    }
+  });
+}
+
+fn fuzzy_search_paths_by_keyword(
+  keyword: &str,
+  current_dir: &PathBuf,
+  follow_links: bool,
+  verbose_errors: bool,
+) -> Vec<MatchString> {
+  install_ctrlc_handler();
+  SEARCH_CANCELLED.store(false, Ordering::SeqCst);
+  SEARCHING.store(true, Ordering::SeqCst);
+
   let find_progress_bar = ProgressBar::new(0);
 
   find_progress_bar.set_style(
-    ProgressStyle::default_bar()
-      .template("{spinner} Found {pos} executables and folders - elapsed time: {elapsed}"),
+    ProgressStyle::default_bar().template(
+      "{spinner} Found {pos} executables and folders - elapsed time: {elapsed} - {wide_msg}",
+    ),
   );
+  let dir_progress_bar = find_progress_bar.clone();
 
+  eprintln!("Press Ctrl+C to stop searching and keep what has been found so far");
   let time = Instant::now();
+  let mut skipped = Vec::new();
+  let matcher = Matcher::new(keyword);
+  // Only the best matches are kept as they are found, rather than sorting every one of them
+  let limit = Config::load().search_results();
   let exe_iterable = WalkDir::new(current_dir)
+    .follow_links(follow_links)
     .into_iter()
+    // Once cancelled, no more paths are handed to rayon, so the search winds down quickly
+    .take_while(|_| !SEARCH_CANCELLED.load(Ordering::SeqCst))
+    .inspect(|entry| {
+      if let Ok(entry) = entry {
+        if entry.file_type().is_dir() {
+          dir_progress_bar.set_message(entry.path().display().to_string());
+        }
+      }
+    })
+    // Unreadable entries are set aside here, before the walk is spread over threads
+    .filter_map(|entry| entry.map_err(|err| skipped.push(err)).ok())
     .par_bridge() // paralell find paths to speed things up
-    .filter_map(std::result::Result::ok)
+    .filter(|entry| is_scanned(entry, follow_links))
     .map(walkdir::DirEntry::into_path)
     .filter(|path| path.extension().unwrap_or_default() == "exe" || path.is_dir())
     .filter_map(|path| path.into_os_string().into_string().ok())
-    .filter_map(|path_str| {
-      best_match(keyword, &path_str).map(|m| MatchString {
-        match_object: m,
-        string: path_str,
-      })
-    })
+    .filter_map(|path_str| matcher.match_string(path_str))
     .progress_with(find_progress_bar);
 
-  let mut matchstring_vec: Vec<MatchString> = exe_iterable.collect();
-  matchstring_vec.par_sort_unstable_by(|a, b| b.cmp(a));
-  eprintln!("Searched in {}s", time.elapsed().as_secs_f32());
+  let top_matches = fuzzy::top_matches(exe_iterable, limit);
+  SEARCHING.store(false, Ordering::SeqCst);
+  let found = top_matches.found();
+  let matchstring_vec = dedup_by_canonical_path(top_matches.into_sorted_vec(), |m| &m.string);
+  if found > limit {
+    eprintln!("Showing the best {limit} of {found} matches, try a longer keyword or raise search_results in the config");
+  }
+  if SEARCH_CANCELLED.load(Ordering::SeqCst) {
+    eprintln!(
+      "Search stopped after {}s - showing the {} matches found so far",
+      time.elapsed().as_secs_f32(),
+      matchstring_vec.len()
+    );
+  } else {
+    eprintln!("Searched in {}s", time.elapsed().as_secs_f32());
+  }
+  report_skipped(&skipped, verbose_errors);
 
   matchstring_vec
 }
 
-fn read_schedule_from_stdin() -> Vec<ScheduleBlock> {
+/// Schedule entries take the block's break method, unless `ask_breaks` asks for one each time
+fn read_schedule_from_stdin(block_break: &BreakMethod, ask_breaks: bool) -> Vec<ScheduleBlock> {
   let mut schedule: Vec<ScheduleBlock> = Vec::new();
+  if !ask_breaks {
+    println!(
+      "Schedule blocks use this block's break method. Run `ctk suggest --ask-breaks` to choose one for each."
+    );
+  }
   loop {
-    let add_sched = Confirm::new()
-      .with_prompt("Do you want to add new schedule blocks?")
+    let action = Select::new()
+      .with_prompt("What do you want to do with the schedule?")
+      .items(&SCHEDULE_ACTIONS)
+      .default(0)
       .loop_interact();
 
-    if !add_sched {
-      break;
+    match action {
+      0 => {
+        schedule.extend(schedule_entries_from_stdin(block_break, ask_breaks));
+        resolve_overlaps(&mut schedule);
+      }
+      1 => print_schedule(&schedule),
+      2 => {
+        for line in schedule::week_grid(&schedule::block_ranges(&schedule)) {
+          eprintln!("{line}");
+        }
+      }
+      3 => {
+        edit_schedule_entry(&mut schedule);
+        resolve_overlaps(&mut schedule);
+      }
+      4 => delete_schedule_entries(&mut schedule),
+      _ => break,
     }
+    for (id, entry) in schedule.iter_mut().enumerate() {
+      entry.id = id;
+    }
+  }
 
-    let time_of_week = MultiSelect::new()
-      .with_prompt("Choose the times of the week applied")
-      .items(&TIMES_OF_WEEK)
-      .loop_interact();
+  schedule
+}
 
-    let mut start_time: NaiveTime;
-    let mut end_time: NaiveTime;
+/// Asks for the days, times, break and label of new schedule entries, one for each day
+pub fn schedule_entries_from_stdin(
+  block_break: &BreakMethod,
+  ask_breaks: bool,
+) -> Vec<ScheduleBlock> {
+  let time_of_week = MultiSelect::new()
+    .with_prompt("Choose the times of the week applied")
+    .items(&TIMES_OF_WEEK)
+    .loop_interact();
 
-    let midnight: NaiveTime = NaiveTime::from_hms(0, 0, 0);
+  let (start_time, end_time) = schedule_times_from_stdin();
 
-    loop {
-      start_time = read_time_with_divisible_by_5_check("Enter start time");
+  let break_type = if ask_breaks {
+    break_method_from_stdin()
+  } else {
+    block_break.clone()
+  };
 
-      end_time = read_time_with_divisible_by_5_check("Enter end time");
+  let label: String = Input::new()
+    .with_prompt("Enter a label for these times, like \"school hours\" [empty string to skip]")
+    .allow_empty(true)
+    .loop_interact();
+  let label = Some(label.trim().to_string()).filter(|label| !label.is_empty());
 
-      if end_time == midnight || start_time < end_time {
-        break;
-      }
-      eprintln!("End time must either be after the start time, or end time is midnight");
+  time_of_week
+    .into_iter()
+    .map(|day| ScheduleBlock {
+      id: 0,
+      start_time: ScheduleTimeTuple::new(day, start_time.hour(), start_time.minute()),
+      end_time: schedule_end(day, end_time),
+      break_type: break_type.clone(),
+      label: label.clone(),
+    })
+    .collect()
+}
+
+fn schedule_times_from_stdin() -> (NaiveTime, NaiveTime) {
+  loop {
+    let start_time = read_time_with_divisible_by_5_check("Enter start time");
+    let end_time = read_time_with_divisible_by_5_check("Enter end time");
+
+    match schedule::check_times(start_time, end_time) {
+      Ok(()) => break (start_time, end_time),
+      Err(why) => eprintln!("Cannot use these times: {why}"),
     }
+  }
+}
+
+/// Points out entries whose times overlap, and offers to join the ones that overlap or
+/// follow straight on from each other
+pub fn resolve_overlaps(schedule: &mut Vec<ScheduleBlock>) {
+  let overlaps = schedule::describe_overlaps(schedule);
+  let merged = schedule::merge_overlapping(schedule.clone());
+  if merged.len() == schedule.len() {
+    for overlap in &overlaps {
+      eprintln!("WARNING: {overlap}, and they have different breaks so they cannot be joined");
+    }
+    return;
+  }
+  for overlap in &overlaps {
+    eprintln!("WARNING: {overlap}");
+  }
+  let join = Confirm::new()
+    .with_prompt(format!(
+      "Do you want to join the entries that overlap or follow on from each other, leaving {}?",
+      merged.len()
+    ))
+    .loop_interact();
+  if join {
+    *schedule = merged;
+  }
+}
+
+/// If end_time is midnight, we "go to the next day"
+fn schedule_end(day: usize, end_time: NaiveTime) -> ScheduleTimeTuple {
+  let end_day = if end_time == NaiveTime::MIN {
+    day + 1
+  } else {
+    day
+  };
+  ScheduleTimeTuple::new(end_day, end_time.hour(), end_time.minute())
+}
+
+pub fn describe_schedule_entry(entry: &ScheduleBlock) -> String {
+  let break_type = serde_json::to_value(&entry.break_type)
+    .ok()
+    .and_then(|value| value.as_str().map(str::to_string))
+    .unwrap_or_default();
+  format!(
+    "{:<4}{:<22}{:<48}{}",
+    entry.id,
+    describe_times(&entry.start_time.to_string(), &entry.end_time.to_string()),
+    schedule::describe_break(&break_type),
+    entry.label.as_deref().unwrap_or("")
+  )
+}
 
-    let break_type = break_method_from_stdin();
+pub fn print_schedule(schedule: &[ScheduleBlock]) {
+  if schedule.is_empty() {
+    eprintln!("There are no schedule entries yet.");
+    return;
+  }
+  eprintln!("{:<4}{:<22}{:<48}label", "id", "times", "break");
+  for entry in schedule {
+    eprintln!("{}", describe_schedule_entry(entry));
+  }
+}
 
-    for i in time_of_week {
-      let mut end_day_int = i;
-      // If end_time is midnight, we "go to the next day"
-      if end_time == midnight {
-        end_day_int += 1;
+/// Changes the day, times, break or label of the entry with the id the user gives
+fn edit_schedule_entry(schedule: &mut [ScheduleBlock]) {
+  if schedule.is_empty() {
+    eprintln!("There are no schedule entries to edit yet.");
+    return;
+  }
+  print_schedule(schedule);
+  let last_id = schedule.len() - 1;
+  let id: usize = Input::new()
+    .with_prompt(format!(
+      "Enter the id of the entry to edit [0 to {last_id}]"
+    ))
+    .validate_with(|id: &usize| {
+      if *id <= last_id {
+        Ok(())
+      } else {
+        Err("There is no entry with that id")
       }
+    })
+    .loop_interact();
 
-      schedule.push(ScheduleBlock {
-        id: schedule.len(),
-        start_time: ScheduleTimeTuple::new(i, start_time.hour(), start_time.minute()),
-        end_time: ScheduleTimeTuple::new(end_day_int, end_time.hour(), end_time.minute()),
-        break_type: break_type.clone(),
-      });
+  let entry = &mut schedule[id];
+  let field = Select::new()
+    .with_prompt("What do you want to change?")
+    .items(&SCHEDULE_FIELDS)
+    .loop_interact();
+  match field {
+    0 => {
+      let day = Select::new()
+        .with_prompt("Choose the day")
+        .items(&TIMES_OF_WEEK)
+        .default(entry.start_time.day() % 7)
+        .loop_interact();
+      let (start, end) = (entry.start_time.clone(), entry.end_time.clone());
+      let end_day = day + end.day() - start.day();
+      entry.start_time = ScheduleTimeTuple::new(day, start.hour(), start.minute());
+      entry.end_time = ScheduleTimeTuple::new(end_day, end.hour(), end.minute());
+    }
+    1 => {
+      let day = entry.start_time.day();
+      let (start_time, end_time) = schedule_times_from_stdin();
+      entry.start_time = ScheduleTimeTuple::new(day, start_time.hour(), start_time.minute());
+      entry.end_time = schedule_end(day, end_time);
+    }
+    2 => entry.break_type = break_method_from_stdin(),
+    _ => {
+      let label: String = Input::new()
+        .with_prompt("Enter a new label [empty string to remove it]")
+        .allow_empty(true)
+        .loop_interact();
+      entry.label = Some(label.trim().to_string()).filter(|label| !label.is_empty());
     }
   }
+  eprintln!("{}", describe_schedule_entry(entry));
+}
 
-  schedule
+fn delete_schedule_entries(schedule: &mut Vec<ScheduleBlock>) {
+  if schedule.is_empty() {
+    eprintln!("There are no schedule entries to delete yet.");
+    return;
+  }
+  let items: Vec<String> = schedule.iter().map(describe_schedule_entry).collect();
+  let mut chosen = MultiSelect::new()
+    .with_prompt("Which entries do you want to delete? [press space to select]")
+    .items(&items)
+    .loop_interact();
+  chosen.sort_unstable();
+  // From the back, so the ids left to remove still point at the same entries
+  for i in chosen.iter().rev() {
+    schedule.remove(*i);
+  }
+  eprintln!("Deleted {} schedule entries", chosen.len());
 }
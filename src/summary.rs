@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// How one item of a command on several items went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+  Done,
+  /// Sent to Cold Turkey, but ctk could not read it back to check it
+  Unverified,
+  Failed,
+}
+
+impl Status {
+  fn noun(self) -> &'static str {
+    match self {
+      Self::Done => "ok",
+      Self::Unverified => "not verified",
+      Self::Failed => "failed",
+    }
+  }
+}
+
+struct Row {
+  item: String,
+  status: Status,
+  /// What happened to the item, like "stopped" or "NOT FOUND"
+  label: String,
+  detail: String,
+}
+
+/// A table of how each item of a command went, with a line counting them at the end
+#[derive(Default)]
+pub struct Table {
+  rows: Vec<Row>,
+}
+
+impl Table {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn add(
+    &mut self,
+    item: impl Into<String>,
+    status: Status,
+    label: impl Into<String>,
+    detail: impl Into<String>,
+  ) {
+    self.rows.push(Row {
+      item: item.into(),
+      status,
+      label: label.into(),
+      detail: detail.into(),
+    });
+  }
+
+  /// The rows in aligned columns, then the counts, like "2 ok, 1 failed"
+  pub fn render(&self) -> String {
+    let item_width = self.rows.iter().map(|row| row.item.chars().count()).max();
+    let label_width = self.rows.iter().map(|row| row.label.chars().count()).max();
+    let (item_width, label_width) = (item_width.unwrap_or(0), label_width.unwrap_or(0));
+
+    let mut table = String::new();
+    for row in &self.rows {
+      let line = format!(
+        "  {:item_width$}  {:label_width$}  {}",
+        row.item, row.label, row.detail
+      );
+      table.push_str(line.trim_end());
+      table.push('\n');
+    }
+
+    let counts: Vec<String> = [Status::Done, Status::Unverified, Status::Failed]
+      .into_iter()
+      .map(|status| {
+        let count = self.rows.iter().filter(|row| row.status == status).count();
+        (count, status)
+      })
+      .filter(|(count, status)| *count > 0 || *status == Status::Done)
+      .map(|(count, status)| format!("{count} {}", status.noun()))
+      .collect();
+    table.push_str(&counts.join(", "));
+    table
+  }
+}
+
+/// Runs `f`, leaving out the SUCCESS line of each item when `table` is true, since a table
+/// will show them instead. Errors are still printed as they happen, since they say why an
+/// item failed
+pub fn with_table<R>(table: bool, f: impl FnOnce() -> R) -> R {
+  if !table {
+    return f();
+  }
+  let was_quiet = QUIET.swap(true, Ordering::Relaxed);
+  let result = f();
+  QUIET.store(was_quiet, Ordering::Relaxed);
+  result
+}
+
+pub fn is_quiet() -> bool {
+  QUIET.load(Ordering::Relaxed)
+}
+
+/// Prints that something worked, unless a table of the items will say so instead
+macro_rules! success {
+  ($($arg:tt)*) => {{
+    if !$crate::summary::is_quiet() {
+      eprintln!("SUCCESS: {}", format_args!($($arg)*));
+    }
+  }};
+}
+
+pub(crate) use success;
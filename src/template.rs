@@ -0,0 +1,142 @@
+use crate::config::Config;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// Not turned back into a placeholder when making a template, since a short user name
+// could be part of any website
+const NOT_TEMPLATED: [&str; 1] = ["user"];
+
+/// Values that differ from machine to machine, written as {{name}} in block templates
+pub struct Variables {
+  values: BTreeMap<String, String>,
+}
+
+impl Variables {
+  /// The built-in variables (home, user and, if it can be found, browser_profile), plus
+  /// any set with `ctk config set variables.<name> <value>`, which win over the built-in ones
+  pub fn for_this_machine(config: &Config) -> Self {
+    let mut values = BTreeMap::new();
+    if let Some(home) = dirs::home_dir() {
+      values.insert("home".to_string(), ctbbl_path(&home));
+    }
+    if let Ok(user) = std::env::var("USERNAME").or_else(|_| std::env::var("USER")) {
+      values.insert("user".to_string(), user);
+    }
+    if let Some(profile) = default_browser_profile() {
+      values.insert("browser_profile".to_string(), ctbbl_path(&profile));
+    }
+    for (name, value) in config.variables() {
+      values.insert(name.to_string(), value.replace('\\', "/"));
+    }
+    Self { values }
+  }
+
+  /// Only the given variables, for filling in something other than a block template
+  pub fn new(values: BTreeMap<String, String>) -> Self {
+    Self { values }
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+    self.values.iter()
+  }
+
+  /// Replaces every {{name}} in the text, failing on names without a value
+  pub fn expand(&self, text: &str) -> Result<String, String> {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+      let Some(length) = rest[start..].find("}}") else {
+        break;
+      };
+      let name = rest[start + 2..start + length].trim();
+      let value = self
+        .values
+        .get(name)
+        .ok_or_else(|| format!("{{{{{name}}}}} has no value on this machine"))?;
+      expanded.push_str(&rest[..start]);
+      expanded.push_str(value);
+      rest = &rest[start + length + 2..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+  }
+
+  /// Replaces this machine's values in the text with their {{name}}, longest values first
+  /// so a browser profile inside the home folder becomes {{browser_profile}}
+  pub fn templatize(&self, text: &str) -> String {
+    let mut by_length: Vec<(&String, &String)> = self
+      .values
+      .iter()
+      .filter(|(name, value)| !NOT_TEMPLATED.contains(&name.as_str()) && !value.is_empty())
+      .collect();
+    by_length.sort_by_key(|(_, value)| std::cmp::Reverse(value.len()));
+
+    let mut templated = text.to_string();
+    for (name, value) in by_length {
+      templated = templated.replace(value.as_str(), &format!("{{{{{name}}}}}"));
+    }
+    templated
+  }
+
+  /// Expands every string in a .ctbbl file's JSON
+  pub fn expand_json(&self, value: &mut Value) -> Result<(), String> {
+    match value {
+      Value::String(text) => *text = self.expand(text)?,
+      Value::Array(items) => {
+        for item in items {
+          self.expand_json(item)?;
+        }
+      }
+      Value::Object(fields) => {
+        for field in fields.values_mut() {
+          self.expand_json(field)?;
+        }
+      }
+      _ => {}
+    }
+    Ok(())
+  }
+
+  /// Makes every string in a .ctbbl file's JSON into a template
+  pub fn templatize_json(&self, value: &mut Value) {
+    match value {
+      Value::String(text) => *text = self.templatize(text),
+      Value::Array(items) => {
+        for item in items {
+          self.templatize_json(item);
+        }
+      }
+      Value::Object(fields) => {
+        for field in fields.values_mut() {
+          self.templatize_json(field);
+        }
+      }
+      _ => {}
+    }
+  }
+}
+
+/// .ctbbl files written by ctk use forward slashes in paths
+fn ctbbl_path(path: &Path) -> String {
+  path.to_string_lossy().replace('\\', "/")
+}
+
+/// Chrome's default profile folder, if Chrome is installed
+fn default_browser_profile() -> Option<PathBuf> {
+  let profile = if cfg!(windows) {
+    dirs::data_local_dir()?
+      .join("Google")
+      .join("Chrome")
+      .join("User Data")
+      .join("Default")
+  } else if cfg!(target_os = "macos") {
+    dirs::data_dir()?
+      .join("Google")
+      .join("Chrome")
+      .join("Default")
+  } else {
+    dirs::config_dir()?.join("google-chrome").join("Default")
+  };
+  profile.is_dir().then_some(profile)
+}
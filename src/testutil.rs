@@ -0,0 +1,32 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// A directory of its own for one test, emptied first and removed again once dropped, so
+/// a failing assert doesn't leave it behind
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+  pub fn new(name: &str) -> Self {
+    let dir = env::temp_dir().join(format!("ctk_{name}_{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    Self(dir)
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.0
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.0);
+  }
+}
+
+/// Owned copies of the strings, for functions taking them as the command line gives them
+pub fn strings(strs: &[&str]) -> Vec<String> {
+  strs.iter().map(|s| s.to_string()).collect()
+}
@@ -0,0 +1,114 @@
+use crate::platform::powershell_quote;
+use crate::protocol::SCHEME;
+use std::io;
+use std::process::Command;
+use url::Url;
+
+/// How much longer the "Extend" button locks a block for
+pub const EXTEND_MINUTES: u32 = 30;
+
+// Windows only shows notifications from apps it knows, so they are sent as PowerShell,
+// which every Windows has
+const APP_ID: &str =
+  r"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe";
+
+/// A button on a notification, opening a ctk:// link when clicked
+#[derive(Clone)]
+pub struct Button {
+  pub label: String,
+  pub link: String,
+  /// The link's action, out of protocol::ACTIONS
+  pub action: &'static str,
+}
+
+impl Button {
+  /// Locks the block for EXTEND_MINUTES more through ctk://extend
+  pub fn extend(block: &str) -> Self {
+    Self {
+      label: format!("Extend {EXTEND_MINUTES}m"),
+      link: link("extend", &[("block", block)]),
+      action: "extend",
+    }
+  }
+
+  /// Shows how every block is doing in another notification, through ctk://status
+  pub fn status() -> Self {
+    Self {
+      label: "Show status".to_string(),
+      link: link("status", &[]),
+      action: "status",
+    }
+  }
+}
+
+fn link(action: &str, params: &[(&str, &str)]) -> String {
+  // Why unwrap? The base is always a valid link
+  Url::parse_with_params(&format!("{SCHEME}://{action}"), params)
+    .unwrap()
+    .to_string()
+}
+
+/// Shows a native Windows notification with `lines` under `title`. Clicking a button opens
+/// its link, which only reaches ctk once `ctk protocol register` has been run
+pub fn show(title: &str, lines: &[String], buttons: &[Button]) -> io::Result<()> {
+  if !cfg!(windows) {
+    return Err(io::Error::new(
+      io::ErrorKind::Unsupported,
+      "only Windows is supported",
+    ));
+  }
+
+  let script = format!(
+    "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null; \
+     [Windows.Data.Xml.Dom.XmlDocument, Windows.Data.Xml.Dom.XmlDocument, ContentType = WindowsRuntime] | Out-Null; \
+     $xml = New-Object Windows.Data.Xml.Dom.XmlDocument; \
+     $xml.LoadXml({}); \
+     $toast = New-Object Windows.UI.Notifications.ToastNotification $xml; \
+     [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier({}).Show($toast)",
+    powershell_quote(&toast_xml(title, lines, buttons)),
+    powershell_quote(APP_ID)
+  );
+  let output = Command::new("powershell")
+    .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+    .output()?;
+  if output.status.success() {
+    Ok(())
+  } else {
+    Err(io::Error::other(
+      String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    ))
+  }
+}
+
+fn toast_xml(title: &str, lines: &[String], buttons: &[Button]) -> String {
+  let mut xml = format!(
+    "<toast><visual><binding template=\"ToastGeneric\"><text>{}</text>",
+    xml_escape(title)
+  );
+  if !lines.is_empty() {
+    xml.push_str(&format!("<text>{}</text>", xml_escape(&lines.join("\n"))));
+  }
+  xml.push_str("</binding></visual>");
+  if !buttons.is_empty() {
+    xml.push_str("<actions>");
+    for button in buttons {
+      xml.push_str(&format!(
+        "<action content=\"{}\" activationType=\"protocol\" arguments=\"{}\"/>",
+        xml_escape(&button.label),
+        xml_escape(&button.link)
+      ));
+    }
+    xml.push_str("</actions>");
+  }
+  xml.push_str("</toast>");
+  xml
+}
+
+fn xml_escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&apos;")
+}
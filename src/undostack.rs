@@ -0,0 +1,45 @@
+/// Copies of something from before each change to it, to go back to with `undo` and
+/// forward again with `redo`
+pub struct UndoStack<T> {
+  undo: Vec<T>,
+  redo: Vec<T>,
+}
+
+impl<T: Clone> UndoStack<T> {
+  pub fn new() -> Self {
+    Self {
+      undo: Vec::new(),
+      redo: Vec::new(),
+    }
+  }
+
+  /// Keeps the copy from before a change. A new change cannot be redone past
+  pub fn record(&mut self, previous: T) {
+    self.undo.push(previous);
+    self.redo.clear();
+  }
+
+  /// Puts back the copy from before the last change, giving whether there was one
+  pub fn undo(&mut self, current: &mut T) -> bool {
+    let Some(previous) = self.undo.pop() else {
+      return false;
+    };
+    self.redo.push(std::mem::replace(current, previous));
+    true
+  }
+
+  /// Puts back the last change undone, giving whether there was one
+  pub fn redo(&mut self, current: &mut T) -> bool {
+    let Some(next) = self.redo.pop() else {
+      return false;
+    };
+    self.undo.push(std::mem::replace(current, next));
+    true
+  }
+}
+
+impl<T: Clone> Default for UndoStack<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
@@ -0,0 +1,40 @@
+use crate::config::Config;
+use ctk_common::ctsettings::{get_ct_settings, ColdTurkeySettings};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Reads Cold Turkey's settings until `applied` holds for them, or until the configured
+/// verify_timeout passes, since Cold Turkey applies a command a moment after it is spawned.
+/// Gives the last settings read either way, or nothing if they could never be read
+pub fn settings_until(
+  config: &Config,
+  applied: impl Fn(&ColdTurkeySettings) -> bool,
+) -> Option<ColdTurkeySettings> {
+  settings_within(config, config.verify_timeout(), applied)
+}
+
+/// Like `settings_until`, but waits up to `timeout`, for changes that wait on the user
+pub fn settings_within(
+  config: &Config,
+  timeout: Duration,
+  applied: impl Fn(&ColdTurkeySettings) -> bool,
+) -> Option<ColdTurkeySettings> {
+  let deadline = Instant::now() + timeout;
+  let interval = config.verify_interval();
+  let mut last = None;
+
+  loop {
+    if let Some(settings) = get_ct_settings() {
+      if applied(&settings) {
+        return Some(settings);
+      }
+      last = Some(settings);
+    }
+
+    let now = Instant::now();
+    if now >= deadline {
+      return last;
+    }
+    thread::sleep(interval.min(deadline - now));
+  }
+}
@@ -0,0 +1,966 @@
+use crate::blocker::{self, BlockerCommand, StartLock};
+use crate::config::{self, Config};
+use crate::journal::{self, Event};
+use crate::oplock::OperationLock;
+use crate::platform;
+use crate::schedule;
+use crate::session::Presence;
+use crate::settingsfeed::SettingsFeed;
+use crate::sidecar::Sidecar;
+use crate::status;
+use crate::toast::{self, Button};
+use chrono::{DateTime, Datelike, Duration, Local, LocalResult, NaiveTime, TimeZone, Weekday};
+use ctk_common::convert;
+use ctk_common::ctsettings::{get_ct_settings, ColdTurkeySettings};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+// A running watch holds an OS lock on this file, so it is only locked while one runs
+const RUNNING_FILE_NAME: &str = "watch.lock";
+// The rules the running watch loaded, for `ctk watch --diff`
+const LOADED_FILE_NAME: &str = "watch.json";
+
+// How often the rules are checked. Rules fire once their time has passed, so a slow tick
+// only delays them rather than skipping them
+const TICK: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Everything in the rules file, grouped by kind of rule
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+  #[serde(default)]
+  pub allowance: Vec<AllowanceRule>,
+  #[serde(default)]
+  pub focus: Vec<FocusRule>,
+  #[serde(default)]
+  pub schedule: Vec<ScheduleRule>,
+}
+
+/// A time of day, optionally limited to some days of the week
+#[derive(Debug, Deserialize)]
+pub struct DailyTime {
+  #[serde(deserialize_with = "deserialize_time")]
+  pub at: NaiveTime,
+  /// Every day if empty
+  #[serde(default, deserialize_with = "deserialize_weekdays")]
+  pub days: Vec<Weekday>,
+  #[serde(default)]
+  pub when_away: WhenAway,
+}
+
+/// What a timed rule does when its time comes while the computer is locked or idle
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WhenAway {
+  #[default]
+  Run,
+  Skip,
+  /// Runs once you are back
+  Wait,
+}
+
+/// Stops a running block for a few minutes each day and then starts it again, like
+/// Cold Turkey's allowances but usable by free users
+#[derive(Debug, Deserialize)]
+pub struct AllowanceRule {
+  pub block: String,
+  #[serde(flatten)]
+  pub when: DailyTime,
+  pub minutes: u32,
+}
+
+/// Starts a block once one of the apps has had focus for some minutes in a row, as a
+/// nudge before Cold Turkey's hard blocking takes over
+#[derive(Debug, Deserialize)]
+pub struct FocusRule {
+  pub block: String,
+  /// Process names, like "steam" or "Discord.exe" on Windows
+  pub apps: Vec<String>,
+  pub minutes: u32,
+}
+
+/// Starts or stops a block at the same time every day, on ctk's own schedule rather than
+/// Cold Turkey's
+#[derive(Debug, Deserialize)]
+pub struct ScheduleRule {
+  pub block: String,
+  pub action: ScheduleAction,
+  #[serde(flatten)]
+  pub when: DailyTime,
+  /// Locks the block for this many minutes when starting it
+  #[serde(default)]
+  pub minutes: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleAction {
+  Start,
+  Stop,
+}
+
+impl ScheduleAction {
+  fn verb(self) -> &'static str {
+    match self {
+      Self::Start => "start",
+      Self::Stop => "stop",
+    }
+  }
+}
+
+/// One rule as ctk runs it: when it fires, and the Cold Turkey Blocker command lines it runs
+/// then. `ctk watch --dry-run` shows these, and a running watch saves them for `--diff`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedRule {
+  /// "allowance", "focus" or "schedule"
+  pub kind: String,
+  pub block: String,
+  pub trigger: String,
+  pub days: String,
+  pub commands: Vec<String>,
+}
+
+impl fmt::Display for PlannedRule {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} rule for {}: {}, {}; runs {}",
+      self.kind,
+      self.block,
+      self.trigger,
+      self.days,
+      self.commands.join(", then ")
+    )
+  }
+}
+
+/// The rules a running `ctk watch` loaded, and from where
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoadedRules {
+  pub path: PathBuf,
+  pub loaded: DateTime<Local>,
+  pub rules: Vec<PlannedRule>,
+}
+
+/// How long a focus rule's apps have been in front, and whether it already fired for that
+#[derive(Default)]
+struct FocusState {
+  since: Option<DateTime<Local>>,
+  fired: bool,
+}
+
+impl FocusRule {
+  fn matches(&self, app: &str) -> bool {
+    let app = app_name(app);
+    self.apps.iter().any(|rule_app| app_name(rule_app) == app)
+  }
+}
+
+// Windows reports process names without ".exe", but people tend to write it
+fn app_name(app: &str) -> String {
+  let app = app.to_lowercase();
+  app
+    .strip_suffix(".exe")
+    .map_or_else(|| app.clone(), str::to_string)
+}
+
+enum PendingAction {
+  Restart(String),
+}
+
+impl DailyTime {
+  /// Whether this time happens after `since` and no later than `until`
+  fn happens_between(&self, since: DateTime<Local>, until: DateTime<Local>) -> bool {
+    let mut date = since.date_naive();
+    while date <= until.date_naive() {
+      if self.days.is_empty() || self.days.contains(&date.weekday()) {
+        let time = match Local.from_local_datetime(&date.and_time(self.at)) {
+          LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => Some(time),
+          // The time is skipped by a daylight saving change on this day
+          LocalResult::None => None,
+        };
+        if time.is_some_and(|time| since < time && time <= until) {
+          return true;
+        }
+      }
+
+      date = match date.succ_opt() {
+        Some(next) => next,
+        None => break,
+      };
+    }
+    false
+  }
+}
+
+impl WhenAway {
+  fn describe(self) -> &'static str {
+    match self {
+      Self::Run => "",
+      Self::Skip => " (skipped while you are away)",
+      Self::Wait => " (or once you are back, if you are away)",
+    }
+  }
+}
+
+impl DailyTime {
+  fn trigger(&self) -> String {
+    format!(
+      "at {}{}",
+      self.at.format("%H:%M"),
+      self.when_away.describe()
+    )
+  }
+
+  fn describe_days(&self) -> String {
+    if self.days.is_empty() {
+      return "every day".to_string();
+    }
+    let days: Vec<String> = self.days.iter().map(Weekday::to_string).collect();
+    format!("on {}", days.join(", "))
+  }
+}
+
+/// Every rule as ctk would run it, in the order of the rules file
+pub fn plan(config: &Config, rules: &Rules) -> Vec<PlannedRule> {
+  let start_unlocked = |block| {
+    blocker::command_line(
+      config,
+      &BlockerCommand::Start {
+        block,
+        lock: StartLock::None,
+      },
+    )
+  };
+  let mut planned = Vec::new();
+  for rule in &rules.allowance {
+    planned.push(PlannedRule {
+      kind: "allowance".to_string(),
+      block: rule.block.clone(),
+      trigger: rule.when.trigger(),
+      days: rule.when.describe_days(),
+      commands: vec![
+        blocker::command_line(config, &BlockerCommand::Stop { block: &rule.block }),
+        format!(
+          "{} {} minutes later",
+          start_unlocked(&rule.block),
+          rule.minutes
+        ),
+      ],
+    });
+  }
+  for rule in &rules.focus {
+    planned.push(PlannedRule {
+      kind: "focus".to_string(),
+      block: rule.block.clone(),
+      trigger: format!(
+        "once {} has focus for {} minutes in a row",
+        rule.apps.join(" or "),
+        rule.minutes
+      ),
+      days: "every day".to_string(),
+      commands: vec![start_unlocked(&rule.block)],
+    });
+  }
+  for rule in &rules.schedule {
+    let block = &rule.block;
+    let command = match rule.action {
+      ScheduleAction::Start => BlockerCommand::Start {
+        block,
+        lock: rule.minutes.map_or(StartLock::None, |minutes| {
+          StartLock::Minutes(minutes.into())
+        }),
+      },
+      ScheduleAction::Stop => BlockerCommand::Stop { block },
+    };
+    planned.push(PlannedRule {
+      kind: "schedule".to_string(),
+      block: block.clone(),
+      trigger: rule.when.trigger(),
+      days: rule.when.describe_days(),
+      commands: vec![blocker::command_line(config, &command)],
+    });
+  }
+  planned
+}
+
+/// How the rules a watch loaded differ from the ones in the file: "- " before each rule only
+/// the watch has, and "+ " before each one only the file has
+pub fn diff(loaded: &[PlannedRule], file: &[PlannedRule]) -> Vec<String> {
+  let removed = loaded
+    .iter()
+    .filter(|rule| !file.contains(rule))
+    .map(|rule| format!("- {rule}"));
+  let added = file
+    .iter()
+    .filter(|rule| !loaded.contains(rule))
+    .map(|rule| format!("+ {rule}"));
+  removed.chain(added).collect()
+}
+
+/// Marks this watch as running and saves the rules it loaded. Returns the file whose lock
+/// says so, to be held until the watch ends
+fn record_loaded(path: &Path, rules: Vec<PlannedRule>) -> Result<File, String> {
+  let dir = config::data_dir().ok_or("Cannot find ctk's data folder")?;
+  fs::create_dir_all(&dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+  let running = open_running(&dir).map_err(|err| err.to_string())?;
+  match running.try_lock() {
+    Ok(()) => {}
+    Err(TryLockError::WouldBlock) => return Err("another ctk watch is running".to_string()),
+    Err(TryLockError::Error(err)) => return Err(err.to_string()),
+  }
+
+  let loaded = LoadedRules {
+    path: path.to_path_buf(),
+    loaded: Local::now(),
+    rules,
+  };
+  let json = serde_json::to_string_pretty(&loaded).map_err(|err| err.to_string())?;
+  let loaded_path = dir.join(LOADED_FILE_NAME);
+  fs::write(&loaded_path, json).map_err(|err| format!("{}: {}", loaded_path.display(), err))?;
+  Ok(running)
+}
+
+/// The rules the running `ctk watch` loaded, or None if no watch is running
+pub fn running_rules() -> Result<Option<LoadedRules>, String> {
+  let Some(dir) = config::data_dir() else {
+    return Ok(None);
+  };
+  let running = match open_running(&dir) {
+    Ok(file) => file,
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+    Err(err) => return Err(err.to_string()),
+  };
+  match running.try_lock() {
+    // Nothing holds it, so no watch is running
+    Ok(()) => return Ok(None),
+    Err(TryLockError::WouldBlock) => {}
+    Err(TryLockError::Error(err)) => return Err(err.to_string()),
+  }
+
+  let loaded_path = dir.join(LOADED_FILE_NAME);
+  let json = fs::read_to_string(&loaded_path)
+    .map_err(|err| format!("{}: {}", loaded_path.display(), err))?;
+  serde_json::from_str(&json)
+    .map(Some)
+    .map_err(|err| format!("{}: {}", loaded_path.display(), err))
+}
+
+fn open_running(dir: &Path) -> std::io::Result<File> {
+  OpenOptions::new()
+    .read(true)
+    .write(true)
+    .create(true)
+    .truncate(false)
+    .open(dir.join(RUNNING_FILE_NAME))
+}
+
+pub fn load_rules(path: &Path) -> Result<Rules, String> {
+  let contents = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+  toml::from_str(&contents).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+/// Runs until killed, applying the rules as their times come up
+pub fn watch(config: &Config, rules: &Rules, path: &Path) {
+  let _running = match record_loaded(path, plan(config, rules)) {
+    Ok(running) => Some(running),
+    Err(err) => {
+      log(&format!(
+        "WARNING: `ctk watch --diff` cannot see the rules of this watch: {err}"
+      ));
+      None
+    }
+  };
+  eprintln!(
+    "Watching {} allowance rule(s), {} focus rule(s) and {} schedule rule(s). Press Ctrl+C to stop.",
+    rules.allowance.len(),
+    rules.focus.len(),
+    rules.schedule.len()
+  );
+
+  let mut pending: Vec<(DateTime<Local>, PendingAction)> = Vec::new();
+  let mut focus_states: Vec<FocusState> =
+    rules.focus.iter().map(|_| FocusState::default()).collect();
+  // The last pomodoro phase seen for each block in one
+  let mut pomodoro_phases: HashMap<String, String> = HashMap::new();
+  // Allowance and schedule rules, by index, whose time came while the computer was locked
+  // or idle
+  let mut waiting_for_return: Vec<usize> = Vec::new();
+  let mut schedules_waiting: Vec<usize> = Vec::new();
+  // Pomodoro phases and ended locks only need to be noticed, not checked right away, so
+  // they are read on another thread rather than holding up the rules each tick
+  let settings_feed = SettingsFeed::start(TICK);
+  let mut last_tick = Local::now();
+  loop {
+    thread::sleep(TICK);
+    let now = Local::now();
+
+    // Asked at most once a tick, and only when a rule needs to know
+    let mut presence = None;
+    let mut presence_now = || *presence.get_or_insert_with(|| Presence::now(config.idle_after()));
+
+    if !rules.focus.is_empty() {
+      // Nobody is looking at an app while they are away, whatever is in front
+      let app = match presence_now() {
+        Presence::Active => platform::foreground_app(),
+        _ => None,
+      };
+      for (rule, state) in rules.focus.iter().zip(&mut focus_states) {
+        check_focus(config, rule, state, app.as_deref(), now);
+      }
+    }
+
+    for (index, rule) in rules.allowance.iter().enumerate() {
+      if !rule.when.happens_between(last_tick, now) {
+        continue;
+      }
+      match away(rule.when.when_away, &mut presence_now) {
+        None => run_allowance(config, rule, now, &mut pending),
+        Some((WhenAway::Wait, presence)) => {
+          if !waiting_for_return.contains(&index) {
+            waiting_for_return.push(index);
+            log(&format!(
+              "Waits to start the allowance for {} because the computer is {presence}",
+              rule.block
+            ));
+          }
+        }
+        Some((_, presence)) => log(&format!(
+          "Skips the allowance for {} because the computer is {presence}",
+          rule.block
+        )),
+      }
+    }
+
+    for (index, rule) in rules.schedule.iter().enumerate() {
+      if !rule.when.happens_between(last_tick, now) {
+        continue;
+      }
+      match away(rule.when.when_away, &mut presence_now) {
+        None => run_schedule(config, rule),
+        Some((WhenAway::Wait, presence)) => {
+          if !schedules_waiting.contains(&index) {
+            schedules_waiting.push(index);
+            log(&format!(
+              "Waits to {} {} because the computer is {presence}",
+              rule.action.verb(),
+              rule.block
+            ));
+          }
+        }
+        Some((_, presence)) => log(&format!(
+          "Skips the scheduled {} of {} because the computer is {presence}",
+          rule.action.verb(),
+          rule.block
+        )),
+      }
+    }
+
+    let waiting = !waiting_for_return.is_empty() || !schedules_waiting.is_empty();
+    if waiting && presence_now() == Presence::Active {
+      for index in waiting_for_return.drain(..) {
+        run_allowance(config, &rules.allowance[index], now, &mut pending);
+      }
+      for index in schedules_waiting.drain(..) {
+        run_schedule(config, &rules.schedule[index]);
+      }
+    }
+
+    if let Some(Some(settings)) = settings_feed.latest() {
+      track_pomodoros(&settings, &mut pomodoro_phases);
+      stop_unlocked_blocks(config, &settings);
+    }
+
+    let (due, waiting) = pending.into_iter().partition(|(at, _)| *at <= now);
+    pending = waiting;
+    for (_, action) in due {
+      match action {
+        PendingAction::Restart(block) => restart_block(config, &block),
+      }
+    }
+
+    last_tick = now;
+  }
+}
+
+/// Journals every pomodoro phase change since the settings were last read. The first phase
+/// seen for a block is only remembered, since when it began is unknown
+fn track_pomodoros(settings: &ColdTurkeySettings, phases: &mut HashMap<String, String>) {
+  phases.retain(|block, _| {
+    settings
+      .block_list_info
+      .blocks
+      .get(block)
+      .is_some_and(|info| !info.pomodoro_period_state.is_empty())
+  });
+  for (block, info) in &settings.block_list_info.blocks {
+    let phase = &info.pomodoro_period_state;
+    if phase.is_empty() {
+      continue;
+    }
+    match phases.insert(block.clone(), phase.clone()) {
+      Some(last) if last != *phase => journal::record(Event::Pomodoro {
+        block: block.clone(),
+        phase: phase.clone(),
+      }),
+      _ => {}
+    }
+  }
+}
+
+/// What a timed rule should do instead of running now, if the computer is locked or idle
+fn away(
+  when_away: WhenAway,
+  presence_now: &mut impl FnMut() -> Presence,
+) -> Option<(WhenAway, Presence)> {
+  match when_away {
+    WhenAway::Run => None,
+    when_away => Some(presence_now())
+      .filter(|presence| *presence != Presence::Active)
+      .map(|presence| (when_away, presence)),
+  }
+}
+
+/// Stops the blocks `ctk stop --when-unlocked` left for later, once no lock holds them on
+fn stop_unlocked_blocks(config: &Config, settings: &ColdTurkeySettings) {
+  let mut sidecar = Sidecar::load();
+  let queued = sidecar.queued_stops();
+  if queued.is_empty() {
+    return;
+  }
+
+  let entries = journal::read_entries();
+  let now = Local::now();
+  let mut changed = false;
+  for block in queued {
+    let done = match settings.block_list_info.blocks.get(&block) {
+      None => {
+        log(&format!(
+          "Drops the queued stop of {block} because it cannot be found in your Cold Turkey application"
+        ));
+        true
+      }
+      // Like when a timed lock ends, which turns the block off by itself
+      Some(info) if info.is_dormant() => {
+        log(&format!(
+          "Drops the queued stop of {block} because it already stopped"
+        ));
+        notify(
+          config,
+          &format!("{block} is off"),
+          "Its lock ended".to_string(),
+          &[Button::status()],
+        );
+        true
+      }
+      Some(info) if status::holds_lock(info, schedule::lock_end(&entries, &block, now)) => false,
+      Some(_) => stop_unlocked_block(config, &block),
+    };
+    if done {
+      sidecar.clear_queued_stop(&block);
+      changed = true;
+    }
+  }
+  if changed {
+    if let Err(err) = sidecar.save() {
+      log(&format!(
+        "WARNING: Cannot save which stops are still queued: {err}"
+      ));
+    }
+  }
+}
+
+/// Whether the block was stopped, or should be tried again next time
+fn stop_unlocked_block(config: &Config, block: &str) -> bool {
+  let Some(_lock) = lock_for(&format!("stop {block} now that its lock ended")) else {
+    return false;
+  };
+  match blocker::run(config, BlockerCommand::Stop { block }) {
+    Ok(_) => {
+      log(&format!("SUCCESS: Stops {block} now that its lock ended"));
+      notify(
+        config,
+        &format!("{block} is off"),
+        "Stopped as queued, now that its lock ended".to_string(),
+        &[Button::status()],
+      );
+      journal::record(Event::Stop {
+        block: block.to_string(),
+      });
+      true
+    }
+    Err(err) => {
+      log(&format!(
+        "ERROR: Cannot stop {block} now that its lock ended: {err}"
+      ));
+      false
+    }
+  }
+}
+
+fn run_allowance(
+  config: &Config,
+  rule: &AllowanceRule,
+  now: DateTime<Local>,
+  pending: &mut Vec<(DateTime<Local>, PendingAction)>,
+) {
+  if let Some(restart_at) = start_allowance(config, rule, now) {
+    pending.push((restart_at, PendingAction::Restart(rule.block.clone())));
+  }
+}
+
+fn start_allowance(
+  config: &Config,
+  rule: &AllowanceRule,
+  now: DateTime<Local>,
+) -> Option<DateTime<Local>> {
+  let block = &rule.block;
+  let _lock = lock_for(&format!("stop {block} for its allowance"))?;
+  if let Some(settings) = get_ct_settings() {
+    match settings.block_list_info.blocks.get(block) {
+      Some(info) if info.is_dormant() => {
+        log(&format!(
+          "Skips the allowance for {block} because it is not running"
+        ));
+        return None;
+      }
+      Some(_) => {}
+      None => {
+        log(&format!(
+          "ERROR: Block {block} cannot be found in your Cold Turkey application"
+        ));
+        return None;
+      }
+    }
+  }
+
+  match blocker::run(config, BlockerCommand::Stop { block }) {
+    Ok(_) => {
+      log(&format!(
+        "SUCCESS: Stops {block} for a {} minute allowance",
+        rule.minutes
+      ));
+      notify(
+        config,
+        &format!("{block} is off"),
+        format!("For a {} minute allowance", rule.minutes),
+        &[Button::status()],
+      );
+      journal::record(Event::Stop {
+        block: block.to_string(),
+      });
+      Some(now + Duration::minutes(rule.minutes.into()))
+    }
+    Err(err) => {
+      log(&format!(
+        "ERROR: Cannot stop {block} for its allowance: {err}"
+      ));
+      None
+    }
+  }
+}
+
+fn check_focus(
+  config: &Config,
+  rule: &FocusRule,
+  state: &mut FocusState,
+  app: Option<&str>,
+  now: DateTime<Local>,
+) {
+  let Some(app) = app.filter(|app| rule.matches(app)) else {
+    // The rule is armed again once its apps lose focus
+    *state = FocusState::default();
+    return;
+  };
+  let since = *state.since.get_or_insert(now);
+  if state.fired || now - since < Duration::minutes(rule.minutes.into()) {
+    return;
+  }
+  state.fired = true;
+
+  let block = &rule.block;
+  let Some(_lock) = lock_for(&format!("start {block} after {app} had focus")) else {
+    return;
+  };
+  if let Some(settings) = get_ct_settings() {
+    match settings.block_list_info.blocks.get(block) {
+      Some(info) if !info.is_dormant() => return,
+      Some(_) => {}
+      None => {
+        log(&format!(
+          "ERROR: Block {block} cannot be found in your Cold Turkey application"
+        ));
+        return;
+      }
+    }
+  }
+
+  match blocker::run(
+    config,
+    BlockerCommand::Start {
+      block,
+      lock: StartLock::None,
+    },
+  ) {
+    Ok(_) => {
+      log(&format!(
+        "SUCCESS: Starts {block} after {app} had focus for {} minutes",
+        rule.minutes
+      ));
+      notify(
+        config,
+        &format!("{block} is on"),
+        format!("{app} had focus for {} minutes", rule.minutes),
+        &[Button::extend(block), Button::status()],
+      );
+      journal::record(Event::Start {
+        block: block.to_string(),
+        lock_minutes: None,
+      });
+    }
+    Err(err) => log(&format!(
+      "ERROR: Cannot start {block} after {app} had focus: {err}"
+    )),
+  }
+}
+
+fn run_schedule(config: &Config, rule: &ScheduleRule) {
+  let block = &rule.block;
+  let verb = rule.action.verb();
+  let Some(_lock) = lock_for(&format!("{verb} {block} on schedule")) else {
+    return;
+  };
+  if let Some(settings) = get_ct_settings() {
+    match (settings.block_list_info.blocks.get(block), rule.action) {
+      (Some(info), ScheduleAction::Start) if !info.is_dormant() => {
+        log(&format!(
+          "Skips starting {block} on schedule because it is already running"
+        ));
+        return;
+      }
+      (Some(info), ScheduleAction::Stop) if info.is_dormant() => {
+        log(&format!(
+          "Skips stopping {block} on schedule because it is not running"
+        ));
+        return;
+      }
+      (Some(_), _) => {}
+      (None, _) => {
+        log(&format!(
+          "ERROR: Block {block} cannot be found in your Cold Turkey application"
+        ));
+        return;
+      }
+    }
+  }
+
+  let command = match rule.action {
+    ScheduleAction::Start => BlockerCommand::Start {
+      block,
+      lock: match rule.minutes {
+        Some(minutes) => StartLock::Minutes(minutes.into()),
+        None => StartLock::None,
+      },
+    },
+    ScheduleAction::Stop => BlockerCommand::Stop { block },
+  };
+  match blocker::run(config, command) {
+    Ok(_) => match rule.action {
+      ScheduleAction::Start => {
+        let locked = match rule.minutes {
+          Some(minutes) => format!(", locked for {minutes} minutes"),
+          None => String::new(),
+        };
+        log(&format!("SUCCESS: Starts {block} on schedule{locked}"));
+        notify(
+          config,
+          &format!("{block} is on"),
+          format!("Started on schedule{locked}"),
+          &[Button::extend(block), Button::status()],
+        );
+        journal::record(Event::Start {
+          block: block.to_string(),
+          lock_minutes: rule.minutes.map(i64::from),
+        });
+      }
+      ScheduleAction::Stop => {
+        log(&format!("SUCCESS: Stops {block} on schedule"));
+        notify(
+          config,
+          &format!("{block} is off"),
+          "Stopped on schedule".to_string(),
+          &[Button::status()],
+        );
+        journal::record(Event::Stop {
+          block: block.to_string(),
+        });
+      }
+    },
+    Err(err) => log(&format!("ERROR: Cannot {verb} {block} on schedule: {err}")),
+  }
+}
+
+fn restart_block(config: &Config, block: &str) {
+  let Some(_lock) = lock_for(&format!("start {block} after its allowance")) else {
+    return;
+  };
+  match blocker::run(
+    config,
+    BlockerCommand::Start {
+      block,
+      lock: StartLock::None,
+    },
+  ) {
+    Ok(_) => {
+      log(&format!(
+        "SUCCESS: Starts {block} again after its allowance"
+      ));
+      notify(
+        config,
+        &format!("{block} is on again"),
+        "Its allowance is over".to_string(),
+        &[Button::extend(block), Button::status()],
+      );
+      journal::record(Event::Start {
+        block: block.to_string(),
+        lock_minutes: None,
+      });
+    }
+    Err(err) => log(&format!(
+      "ERROR: Cannot start {block} after its allowance: {err}"
+    )),
+  }
+}
+
+/// Takes the operation lock for one change, logging why not if another ctk holds on to it
+pub fn lock_for(action: &str) -> Option<OperationLock> {
+  match OperationLock::acquire() {
+    Ok(lock) => Some(lock),
+    Err(err) => {
+      log(&format!("ERROR: Cannot {action}: {err}"));
+      None
+    }
+  }
+}
+
+/// Shows what a rule did in a notification, when watch_toasts is set, with the buttons
+/// link_actions lets ctk:// links do
+fn notify(config: &Config, title: &str, line: String, buttons: &[Button]) {
+  if !config.watch_toasts() {
+    return;
+  }
+  let buttons: Vec<Button> = buttons
+    .iter()
+    .filter(|button| config.link_allows_action(button.action))
+    .cloned()
+    .collect();
+  if let Err(err) = toast::show(title, &[line], &buttons) {
+    log(&format!("WARNING: Cannot show a notification: {err}"));
+  }
+}
+
+pub fn log(message: &str) {
+  eprintln!("[{}] {}", Local::now().format("%Y-%m-%d %H:%M"), message);
+}
+
+pub fn default_rules_path() -> Option<PathBuf> {
+  config::config_dir().map(|dir| dir.join("rules.toml"))
+}
+
+fn deserialize_time<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let s: String = Deserialize::deserialize(deserializer)?;
+  convert::str_to_time(&s).map_err(|_| serde::de::Error::custom(format!("invalid time {s}")))
+}
+
+fn deserialize_weekdays<'de, D>(deserializer: D) -> Result<Vec<Weekday>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let days: Vec<String> = Deserialize::deserialize(deserializer)?;
+  days
+    .iter()
+    .map(|day| {
+      day
+        .parse::<Weekday>()
+        .map_err(|_| serde::de::Error::custom(format!("invalid day of the week {day}")))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config() -> Config {
+    Config::layered(Vec::new(), std::iter::empty(), Vec::new())
+  }
+
+  fn planned(toml: &str) -> Vec<PlannedRule> {
+    plan(&config(), &toml::from_str(toml).unwrap())
+  }
+
+  #[test]
+  fn each_rule_shows_when_it_runs_and_the_commands_it_runs() {
+    let rules = planned(
+      r#"
+      [[allowance]]
+      block = "Social"
+      at = "12:00"
+      days = ["Mon", "Fri"]
+      when_away = "skip"
+      minutes = 15
+
+      [[focus]]
+      block = "Games"
+      apps = ["steam", "Discord"]
+      minutes = 10
+
+      [[schedule]]
+      block = "Work"
+      action = "start"
+      at = "9:00"
+      minutes = 60
+      "#,
+    );
+    // Opening Cold Turkey takes no arguments, leaving just the quoted blocker path
+    let blocker = blocker::command_line(&config(), &BlockerCommand::Open);
+    assert_eq!(
+      rules.iter().map(ToString::to_string).collect::<Vec<_>>(),
+      [
+        format!(
+          "allowance rule for Social: at 12:00 (skipped while you are away), on Mon, Fri; runs \
+           {blocker} -stop Social, then {blocker} -start Social 15 minutes later"
+        ),
+        format!(
+          "focus rule for Games: once steam or Discord has focus for 10 minutes in a row, every day; \
+           runs {blocker} -start Games"
+        ),
+        format!("schedule rule for Work: at 09:00, every day; runs {blocker} -start Work -lock 60"),
+      ]
+    );
+  }
+
+  #[test]
+  fn the_diff_shows_rules_only_the_watch_or_only_the_file_has() {
+    let loaded = planned(
+      "[[schedule]]\nblock = \"Work\"\naction = \"start\"\nat = \"9:00\"\n\
+       [[schedule]]\nblock = \"Work\"\naction = \"stop\"\nat = \"17:00\"",
+    );
+    let file = planned(
+      "[[schedule]]\nblock = \"Work\"\naction = \"start\"\nat = \"9:00\"\n\
+       [[schedule]]\nblock = \"Work\"\naction = \"stop\"\nat = \"18:00\"",
+    );
+    assert!(diff(&loaded, &loaded).is_empty());
+    let lines = diff(&loaded, &file);
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("- schedule rule for Work: at 17:00"));
+    assert!(lines[1].starts_with("+ schedule rule for Work: at 18:00"));
+  }
+}
@@ -0,0 +1,195 @@
+use crate::error::error;
+use crate::loop_dialoguer::LoopDialogue;
+use crate::randomtext;
+use crate::suggestdialog;
+use ctk_common::blocksettings::{
+  BlockSettings, LockMethod, SchedType, ScheduleBlock, ScheduleTimeTuple,
+};
+use dialoguer::{Confirm, Input, MultiSelect, Select};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::PathBuf;
+
+/// A block new users commonly want, ready to be made
+struct Starter {
+  name: &'static str,
+  web: &'static [&'static str],
+}
+
+const STARTERS: [Starter; 3] = [
+  Starter {
+    name: "Social Media",
+    web: &[
+      "facebook.com",
+      "instagram.com",
+      "twitter.com",
+      "x.com",
+      "tiktok.com",
+      "reddit.com",
+      "snapchat.com",
+      "pinterest.com",
+    ],
+  },
+  Starter {
+    name: "News",
+    web: &[
+      "news.google.com",
+      "cnn.com",
+      "bbc.com",
+      "nytimes.com",
+      "theguardian.com",
+      "foxnews.com",
+      "news.ycombinator.com",
+    ],
+  },
+  Starter {
+    name: "Video and Games",
+    web: &[
+      "youtube.com",
+      "twitch.tv",
+      "netflix.com",
+      "disneyplus.com",
+      "store.steampowered.com",
+      "poki.com",
+    ],
+  },
+];
+
+/// When the starter blocks run, with days counted from Sunday like Cold Turkey does
+struct SchedulePreset {
+  label: &'static str,
+  days: &'static [usize],
+  start: (u32, u32),
+  end: (u32, u32),
+}
+
+const WEEKDAYS: [usize; 5] = [1, 2, 3, 4, 5];
+
+const SCHEDULE_PRESETS: [SchedulePreset; 2] = [
+  SchedulePreset {
+    label: "work hours",
+    days: &WEEKDAYS,
+    start: (9, 0),
+    end: (17, 0),
+  },
+  SchedulePreset {
+    label: "school hours",
+    days: &WEEKDAYS,
+    start: (8, 0),
+    end: (15, 0),
+  },
+];
+
+const SCHEDULE_OPTIONS: [&str; 3] = [
+  "Work hours (Monday to Friday, 9:00 to 17:00)",
+  "School hours (Monday to Friday, 8:00 to 15:00)",
+  "All the time",
+];
+
+const LOCK_OPTIONS: [&str; 3] = [
+  "Lock during the schedule (recommended)",
+  "Random text: type 30 random characters to stop a block",
+  "No lock",
+];
+
+// Long enough to be a chore to type, short enough not to be hopeless
+const RANDOM_TEXT_LENGTH: u16 = 30;
+
+/// Asks a few questions and builds a set of starter blocks from them
+pub fn starter_blocks() -> Option<HashMap<String, BlockSettings>> {
+  eprintln!("This wizard sets up a few recommended blocks to get you started.");
+  eprintln!("You can change them in Cold Turkey or with ctk afterwards.");
+
+  let names: Vec<&str> = STARTERS.iter().map(|starter| starter.name).collect();
+  let chosen = MultiSelect::new()
+    .with_prompt("Choose the blocks you want (space to select, enter to continue)")
+    .items(&names)
+    .defaults(&[true, true, false])
+    .loop_interact();
+  if chosen.is_empty() {
+    eprintln!("No blocks chosen, so there is nothing to set up.");
+    return None;
+  }
+
+  let schedule_choice = Select::new()
+    .with_prompt("When should they block?")
+    .items(&SCHEDULE_OPTIONS)
+    .default(0)
+    .loop_interact();
+  let preset = SCHEDULE_PRESETS.get(schedule_choice);
+
+  let lock_choice = Select::new()
+    .with_prompt("How should they be locked?")
+    .items(&LOCK_OPTIONS)
+    .default(if preset.is_some() { 0 } else { 1 })
+    .loop_interact();
+
+  let mut blocks = HashMap::new();
+  for index in chosen {
+    let starter = &STARTERS[index];
+    let mut block_settings = BlockSettings::new();
+    block_settings.web = starter.web.iter().map(|site| site.to_string()).collect();
+
+    if let Some(preset) = preset {
+      block_settings.sched_type = SchedType::Scheduled;
+      block_settings.schedule = preset
+        .days
+        .iter()
+        .enumerate()
+        .map(|(id, &day)| ScheduleBlock {
+          id,
+          start_time: ScheduleTimeTuple::new(day, preset.start.0, preset.start.1),
+          end_time: ScheduleTimeTuple::new(day, preset.end.0, preset.end.1),
+          break_type: block_settings.break_type.clone(),
+          label: Some(preset.label.to_string()),
+        })
+        .collect();
+    }
+
+    block_settings.lock = match lock_choice {
+      0 if preset.is_some() => LockMethod::Schedule,
+      // A schedule lock without a schedule would never lock, so random text stands in
+      0 | 1 => LockMethod::RandomText,
+      _ => LockMethod::None,
+    };
+    block_settings.random_text_length = RANDOM_TEXT_LENGTH;
+
+    blocks.insert(starter.name.to_string(), block_settings);
+  }
+
+  eprintln!("These blocks will be made:");
+  for name in blocks.keys() {
+    eprintln!("  {name}");
+  }
+  let go_ahead = Confirm::new()
+    .with_prompt("Save them and import them into Cold Turkey?")
+    .default(true)
+    .loop_interact();
+
+  go_ahead.then_some(blocks)
+}
+
+/// Saves the blocks to a .ctbbl file in the current directory, returning where
+pub fn save(blocks: &HashMap<String, BlockSettings>) -> Option<PathBuf> {
+  let file_name: String = Input::new()
+    .with_prompt("Enter a file name for them")
+    .default("ctk_starter".to_string())
+    .loop_interact();
+  let path = PathBuf::from(format!("{file_name}.ctbbl"));
+
+  let result = File::create(&path)
+    .map_err(|err| err.to_string())
+    .and_then(|file| serde_json::to_writer_pretty(file, blocks).map_err(|err| err.to_string()));
+  match result {
+    Ok(()) => {
+      eprintln!("Saves the blocks to {}", path.display());
+      suggestdialog::save_schedule_labels(blocks);
+      randomtext::record_locks(blocks);
+      Some(path)
+    }
+    Err(why) => {
+      error!("Cannot save the blocks to {}: {why}", path.display());
+      None
+    }
+  }
+}
@@ -0,0 +1,62 @@
+use crate::config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const WORKSPACES_FILE_NAME: &str = "suggest_sessions.json";
+
+/// Named `ctk suggest` sessions, so a set of blocks for one context can be reopened later
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Workspaces {
+  #[serde(default)]
+  pub sessions: BTreeMap<String, Workspace>,
+}
+
+/// Where a session's blocks are saved. The blocks themselves live in that .ctbbl file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+  pub output_dir: PathBuf,
+  pub file_name: String,
+}
+
+impl Workspace {
+  /// A session saving to `<name>.ctbbl` in `output_dir`
+  pub fn new(name: &str, output_dir: &Path) -> Self {
+    Workspace {
+      // Relative folders would change meaning when the session is resumed from elsewhere
+      output_dir: fs::canonicalize(output_dir)
+        .or_else(|_| env::current_dir().map(|dir| dir.join(output_dir)))
+        .unwrap_or_else(|_| output_dir.to_path_buf()),
+      file_name: format!("{name}.ctbbl"),
+    }
+  }
+
+  pub fn ctbbl_path(&self) -> PathBuf {
+    self.output_dir.join(&self.file_name)
+  }
+}
+
+fn workspaces_path() -> Option<PathBuf> {
+  config::data_dir().map(|dir| dir.join(WORKSPACES_FILE_NAME))
+}
+
+impl Workspaces {
+  /// Reads the saved sessions, or starts with none if there are none yet
+  pub fn load() -> Self {
+    workspaces_path()
+      .and_then(|path| fs::read_to_string(path).ok())
+      .and_then(|contents| serde_json::from_str(&contents).ok())
+      .unwrap_or_default()
+  }
+
+  pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+    let path = workspaces_path().ok_or("cannot find your data directory")?;
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(self)?)?;
+    Ok(())
+  }
+}